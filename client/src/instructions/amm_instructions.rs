@@ -528,6 +528,7 @@ pub fn swap_instr(
             tick_array,
             observation_state,
             token_program: spl_token::id(),
+            referrer_token_account: None,
         })
         .accounts(remaining_accounts)
         .args(raydium_instruction::Swap {
@@ -578,6 +579,7 @@ pub fn swap_v2_instr(
             memo_program: spl_memo::id(),
             input_vault_mint,
             output_vault_mint,
+            referrer_token_account: None,
         })
         .accounts(remaining_accounts)
         .args(raydium_instruction::SwapV2 {
@@ -184,6 +184,30 @@ pub fn handle_program_log(
             PoolCreatedEvent::DISCRIMINATOR => {
                 println!("{:#?}", decode_event::<PoolCreatedEvent>(&mut slice)?);
             }
+            VaultShortfallEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<VaultShortfallEvent>(&mut slice)?);
+            }
+            CleanupPositionsEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<CleanupPositionsEvent>(&mut slice)?);
+            }
+            PositionCleanupEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<PositionCleanupEvent>(&mut slice)?);
+            }
+            RouteSelectedEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<RouteSelectedEvent>(&mut slice)?);
+            }
+            CompoundPositionEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<CompoundPositionEvent>(&mut slice)?);
+            }
+            CurrentObservationEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<CurrentObservationEvent>(&mut slice)?);
+            }
+            PoolDeprecatedEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<PoolDeprecatedEvent>(&mut slice)?);
+            }
+            TokenizedPositionEvent::DISCRIMINATOR => {
+                println!("{:#?}", decode_event::<TokenizedPositionEvent>(&mut slice)?);
+            }
             _ => {
                 println!("unknow event: {}", l);
             }
@@ -749,6 +773,33 @@ pub fn handle_program_instruction(
             }
             println!("{:#?}", SwapV2::from(ix));
         }
+        instruction::SwapV2WithLimits::DISCRIMINATOR => {
+            let ix = decode_instruction::<instruction::SwapV2WithLimits>(&mut ix_data).unwrap();
+            #[derive(Debug)]
+            pub struct SwapV2WithLimits {
+                pub amount: u64,
+                pub other_amount_threshold: u64,
+                pub sqrt_price_limit_x64: u128,
+                pub is_base_input: bool,
+                pub forbidden_tick: Option<i32>,
+                pub twap_bound: Option<TwapBound>,
+                pub post_swap_twap_bound: Option<TwapPriceBound>,
+            }
+            impl From<instruction::SwapV2WithLimits> for SwapV2WithLimits {
+                fn from(instr: instruction::SwapV2WithLimits) -> SwapV2WithLimits {
+                    SwapV2WithLimits {
+                        amount: instr.amount,
+                        other_amount_threshold: instr.other_amount_threshold,
+                        sqrt_price_limit_x64: instr.sqrt_price_limit_x64,
+                        is_base_input: instr.is_base_input,
+                        forbidden_tick: instr.forbidden_tick,
+                        twap_bound: instr.twap_bound,
+                        post_swap_twap_bound: instr.post_swap_twap_bound,
+                    }
+                }
+            }
+            println!("{:#?}", SwapV2WithLimits::from(ix));
+        }
         instruction::SwapRouterBaseIn::DISCRIMINATOR => {
             let ix = decode_instruction::<instruction::SwapRouterBaseIn>(&mut ix_data).unwrap();
             #[derive(Debug)]
@@ -471,7 +471,8 @@ fn swap_compute(
             fee,
             is_base_input,
             zero_for_one,
-        );
+        )
+        .map_err(|_| "swap step amount overflow")?;
         state.sqrt_price_x64 = swap_step.sqrt_price_next_x64;
         step.amount_in = swap_step.amount_in;
         step.amount_out = swap_step.amount_out;
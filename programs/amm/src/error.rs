@@ -101,4 +101,96 @@ pub enum ErrorCode {
     MissingTickArrayBitmapExtensionAccount,
     #[msg("Insufficient liquidity for this direction")]
     InsufficientLiquidityForDirection,
+    #[msg("The observation account is in use")]
+    ObservationInUse,
+    #[msg("Input token account balance is insufficient for the swap")]
+    InsufficientInputBalance,
+    #[msg("Output token account already holds the target balance")]
+    TargetBalanceReached,
+    #[msg("The fee tier is disabled for pool creation")]
+    FeeTierDisabled,
+    #[msg("Invalid position")]
+    InvalidPosition,
+    #[msg("Amount out minimum must be set for the pool")]
+    ZeroAmountOutMinimum,
+    #[msg("Referrer token account mint does not match the input token")]
+    InvalidReferrerAccount,
+    #[msg("Input and output vaults must be the two different tokens of the pool")]
+    InvalidVaultPair,
+    #[msg("Secondary recipient accounts are missing or not the ones set for the config")]
+    InvalidSecondaryRecipient,
+    #[msg("Swap amount is below the minimum of the pool")]
+    SwapAmountTooSmall,
+    #[msg("The number of swap inputs is zero, exceeds the max or does not match the pools given")]
+    InvalidSwapInputNum,
+    #[msg("Signer is neither the owner nor the delegate of the input token account")]
+    InputAccountUnauthorized,
+    #[msg("Token vault does not belong to the pool")]
+    InvalidTokenVault,
+    #[msg("The swap crosses out of the given tick arrays, pass the next tick arrays in remaining accounts")]
+    MissingTickArrayAccounts,
+    #[msg("The swap moved the current tick across the forbidden tick")]
+    ForbiddenTickCrossed,
+    #[msg("Position liquidity can't be decreased before the pool's min duration since the last increase")]
+    PositionLocked,
+    #[msg("The pool's spot price deviates from its TWAP by more than the bound")]
+    SpotDeviatesFromTwap,
+    #[msg("The observation index is not the latest written observation")]
+    StaleObservationIndex,
+    #[msg("The gross liquidity of the tick exceeds the max of the fee tier")]
+    TickLiquidityOverflow,
+    #[msg("The accounts to clean up must come in groups of position nft mint, nft account and position")]
+    InvalidCleanupAccounts,
+    #[msg("The fee growth of the swap step is too large for the in range liquidity")]
+    FeeGrowthDeltaTooLarge,
+    #[msg("The token amount overflows u64")]
+    MaxTokenOverflow,
+    #[msg("The pool is not initialized")]
+    PoolNotInitialized,
+    #[msg("The output vault reserve is insufficient for the swap")]
+    InsufficientReserve,
+    #[msg("The pool is deprecated, use its successor pool")]
+    PoolDeprecated,
+    #[msg("The vault must be an empty token account of the pool's mint owned by the pool")]
+    InvalidPoolVault,
+    #[msg("The lower price of the band must be below the upper price")]
+    InvalidPriceBand,
+    #[msg("The trade fee rate charges more than the max fee of a swap")]
+    TradeFeeRateTooHigh,
+    #[msg("The route cursor belongs to another payer or token account")]
+    InvalidRouteCursor,
+    #[msg("The metadata account isn't the metadata of the position nft mint")]
+    InvalidMetadataTarget,
+    #[msg("The initial price leaves no room for a position on one side")]
+    InitialPriceOutOfUsableRange,
+    #[msg("Minting the liquidity takes more than amount_0_max or amount_1_max")]
+    MintSlippage,
+    #[msg("Decreasing the liquidity gives less than amount_0_min or amount_1_min")]
+    DecreaseSlippage,
+    #[msg("Importing the amounts deposits less than amount_0_min or amount_1_min")]
+    ImportSlippage,
+    #[msg("Compounding the fees mints less than liquidity_min")]
+    CompoundSlippage,
+    #[msg("The deadline is further in the future than the pool allows")]
+    DeadlineTooFar,
+    #[msg("The pool's TWAP after the swap is out of the bound")]
+    TwapOutOfBound,
+    #[msg("The tick state in the tick array isn't the position's tick")]
+    TickAccountMismatch,
+    #[msg("Adding the liquidity exceeds the pool's max total liquidity")]
+    PoolLiquidityCapReached,
+    #[msg("The pool's max total liquidity can only be raised, never below its total liquidity")]
+    PoolLiquidityCapLowered,
+    #[msg("The observation index is past the written observations")]
+    ObservationIndexOutOfRange,
+    #[msg("The imported liquidity uses more than the amounts given")]
+    ImportAmountExceeded,
+    #[msg("The claimed fees of the pool exceed its total fees")]
+    ClaimedFeesExceedTotal,
+    #[msg("A route passes through the same pool more than once")]
+    DuplicateRoutePool,
+    #[msg("The unused observation account can't be closed before its grace period ends")]
+    ObservationCloseGracePeriod,
+    #[msg("The swap left the output token account short of the target balance")]
+    TargetBalanceNotReached,
 }
@@ -0,0 +1,274 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseUnusedObservation<'info> {
+    /// The admin, or the creator of the observation account claiming its rent
+    pub signer: Signer<'info>,
+
+    /// CHECK: The observation account allocated off-chain but never initialized by create_pool,
+    /// checked in instruction
+    #[account(mut)]
+    pub observation_state: UncheckedAccount<'info>,
+
+    /// CHECK: The payer of the observation account's creation, the signer when the close is
+    /// scheduled, recorded then and refunded the rent once it's closed
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+/// The account is allocated off-chain before `create_pool` binds it, so its creator isn't known
+/// on chain: whoever schedules the close claims the rent by signing as the payer, and the real
+/// creator has the grace period to create the pool with it, which cancels the close.
+/// Only accounts never bound to a pool are closed, they hold no observation and are outside
+/// any TWAP window. The observation ring of a pool has a fixed `OBSERVATION_NUM` slots, there's
+/// no cardinality to grow or shrink, so there's no bookkeeping of it to keep in step.
+pub fn close_unused_observation(ctx: Context<CloseUnusedObservation>) -> Result<()> {
+    let observation_info = ctx.accounts.observation_state.to_account_info();
+    require!(
+        ObservationState::is_unused(&observation_info)?,
+        ErrorCode::ObservationInUse
+    );
+    let now = Clock::get()?.unix_timestamp;
+    let (payer, scheduled_at) = match ObservationState::scheduled_close(&observation_info)? {
+        Some(scheduled_close) => scheduled_close,
+        None => {
+            // the first call only schedules the close, the creator can still create the pool
+            // with the account within the grace period, which cancels it
+            require_keys_eq!(
+                ctx.accounts.payer.key(),
+                ctx.accounts.signer.key(),
+                ErrorCode::NotApproved
+            );
+            #[cfg(feature = "enable-log")]
+            msg!(
+                "schedule close of unused observation:{}, payer:{}",
+                observation_info.key(),
+                ctx.accounts.payer.key()
+            );
+            return ObservationState::schedule_close(
+                &observation_info,
+                ctx.accounts.payer.key(),
+                now,
+            );
+        }
+    };
+    require_keys_eq!(ctx.accounts.payer.key(), payer, ErrorCode::NotApproved);
+    let signer = ctx.accounts.signer.key();
+    require!(
+        signer == crate::admin::id() || signer == payer,
+        ErrorCode::NotApproved
+    );
+    require_gte!(
+        now,
+        scheduled_at + UNUSED_OBSERVATION_CLOSE_GRACE_PERIOD,
+        ErrorCode::ObservationCloseGracePeriod
+    );
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "close unused observation:{}, refund lamports:{}",
+        observation_info.key(),
+        observation_info.lamports()
+    );
+    let payer_info = ctx.accounts.payer.to_account_info();
+    let payer_lamports = payer_info.lamports();
+    **payer_info.lamports.borrow_mut() = payer_lamports
+        .checked_add(observation_info.lamports())
+        .unwrap();
+    **observation_info.lamports.borrow_mut() = 0;
+
+    observation_info.assign(&System::id());
+    observation_info.realloc(0, false)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod close_unused_observation_test {
+    use super::*;
+    use crate::util::program_test::{
+        process_instruction, program_error, TestAccount, TEST_UNIX_TIMESTAMP,
+    };
+    use anchor_lang::ToAccountMetas;
+
+    /// The admin, the observation and its creator
+    fn build_close_accounts() -> Vec<TestAccount> {
+        vec![
+            TestAccount::wallet(crate::admin::id()),
+            TestAccount::new(
+                Pubkey::new_unique(),
+                crate::id(),
+                vec![0; ObservationState::LEN],
+            ),
+            TestAccount::wallet(Pubkey::new_unique()),
+        ]
+    }
+
+    fn close_metas(signer: Pubkey, observation_state: Pubkey, payer: Pubkey) -> Vec<AccountMeta> {
+        crate::accounts::CloseUnusedObservation {
+            signer,
+            observation_state,
+            payer,
+        }
+        .to_account_metas(None)
+    }
+
+    /// Moves the time the close was scheduled at `seconds` back
+    fn rewind_scheduled_close(observation: &mut TestAccount, seconds: i64) {
+        let key = observation.key;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut observation.lamports,
+            &mut observation.data,
+            &observation.owner,
+            false,
+            0,
+        );
+        let (payer, scheduled_at) = ObservationState::scheduled_close(&info).unwrap().unwrap();
+        ObservationState::schedule_close(&info, payer, scheduled_at - seconds).unwrap();
+    }
+
+    #[test]
+    fn close_unused_observation_grace_period_test() {
+        let mut accounts = build_close_accounts();
+        let (admin, observation, creator) = (accounts[0].key, accounts[1].key, accounts[2].key);
+        let rent = accounts[1].lamports;
+        let creator_lamports = accounts[2].lamports;
+        let other = Pubkey::new_unique();
+        accounts.push(TestAccount::wallet(other));
+
+        // the refund can only be claimed by the payer signing, not for another account
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                close_metas(admin, observation, creator),
+                crate::instruction::CloseUnusedObservation {}
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::NotApproved)
+        );
+        // the first call schedules the close for the creator
+        process_instruction(
+            &mut accounts,
+            close_metas(creator, observation, creator),
+            crate::instruction::CloseUnusedObservation {},
+        )
+        .unwrap();
+        assert_eq!(accounts[1].lamports, rent);
+        assert_eq!(accounts[1].owner, crate::id());
+
+        // within the grace period
+        rewind_scheduled_close(&mut accounts[1], UNUSED_OBSERVATION_CLOSE_GRACE_PERIOD - 1);
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                close_metas(admin, observation, creator),
+                crate::instruction::CloseUnusedObservation {}
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::ObservationCloseGracePeriod)
+        );
+
+        // the rent can't be sent to anyone but the creator recorded
+        rewind_scheduled_close(&mut accounts[1], 1);
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                close_metas(admin, observation, other),
+                crate::instruction::CloseUnusedObservation {}
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::NotApproved)
+        );
+        // nor the close done by anyone but the admin or the creator
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                close_metas(other, observation, creator),
+                crate::instruction::CloseUnusedObservation {}
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::NotApproved)
+        );
+
+        process_instruction(
+            &mut accounts,
+            close_metas(admin, observation, creator),
+            crate::instruction::CloseUnusedObservation {},
+        )
+        .unwrap();
+        assert_eq!(accounts[1].lamports, 0);
+        assert_eq!(accounts[2].lamports, creator_lamports + rent);
+    }
+
+    #[test]
+    fn close_unused_observation_by_creator_test() {
+        let mut accounts = build_close_accounts();
+        let (observation, creator) = (accounts[1].key, accounts[2].key);
+        let rent = accounts[1].lamports;
+        let creator_lamports = accounts[2].lamports;
+        let metas = close_metas(creator, observation, creator);
+
+        process_instruction(
+            &mut accounts,
+            metas.clone(),
+            crate::instruction::CloseUnusedObservation {},
+        )
+        .unwrap();
+        rewind_scheduled_close(&mut accounts[1], UNUSED_OBSERVATION_CLOSE_GRACE_PERIOD);
+        process_instruction(
+            &mut accounts,
+            metas,
+            crate::instruction::CloseUnusedObservation {},
+        )
+        .unwrap();
+        assert_eq!(accounts[1].lamports, 0);
+        assert_eq!(accounts[2].lamports, creator_lamports + rent);
+    }
+
+    #[test]
+    fn close_unused_observation_cancelled_by_create_pool_test() {
+        let mut accounts = build_close_accounts();
+        let (observation, creator) = (accounts[1].key, accounts[2].key);
+        let metas = close_metas(creator, observation, creator);
+        process_instruction(
+            &mut accounts,
+            metas.clone(),
+            crate::instruction::CloseUnusedObservation {},
+        )
+        .unwrap();
+
+        // the creator uses the account within the grace period
+        let observation = &mut accounts[1];
+        let key = observation.key;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut observation.lamports,
+            &mut observation.data,
+            &observation.owner,
+            false,
+            0,
+        );
+        ObservationState::initialize(
+            &info,
+            Pubkey::new_unique(),
+            TEST_UNIX_TIMESTAMP as u32,
+            1 << 64,
+        )
+        .unwrap();
+        assert_eq!(ObservationState::scheduled_close(&info).unwrap(), None);
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                metas,
+                crate::instruction::CloseUnusedObservation {}
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::ObservationInUse)
+        );
+    }
+}
@@ -71,8 +71,17 @@ pub fn collect_fund_fee(
     let amount_1: u64;
     {
         let mut pool_state = ctx.accounts.pool_state.load_mut()?;
-        amount_0 = amount_0_requested.min(pool_state.fund_fees_token_0);
-        amount_1 = amount_1_requested.min(pool_state.fund_fees_token_1);
+        pool_state.check_initialized()?;
+        amount_0 = clamp_collect_amount(
+            ctx.accounts.pool_state.key(),
+            &ctx.accounts.token_vault_0,
+            amount_0_requested.min(pool_state.fund_fees_token_0),
+        );
+        amount_1 = clamp_collect_amount(
+            ctx.accounts.pool_state.key(),
+            &ctx.accounts.token_vault_1,
+            amount_1_requested.min(pool_state.fund_fees_token_1),
+        );
 
         pool_state.fund_fees_token_0 = pool_state.fund_fees_token_0.checked_sub(amount_0).unwrap();
         pool_state.fund_fees_token_1 = pool_state.fund_fees_token_1.checked_sub(amount_1).unwrap();
@@ -109,6 +118,8 @@ pub fn collect_fund_fee(
         recipient_token_account_1: ctx.accounts.recipient_token_account_1.key(),
         amount_0,
         amount_1,
+        secondary_amount_0: 0,
+        secondary_amount_1: 0,
     });
 
     Ok(())
@@ -63,6 +63,20 @@ pub struct CollectProtocolFee<'info> {
 
     /// The SPL program 2022 to perform token transfers
     pub token_program_2022: Program<'info, Token2022>,
+
+    /// The address that receives the secondary share of token_0 protocol fees, required if the config has one
+    #[account(mut)]
+    pub secondary_recipient_token_account_0: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The address that receives the secondary share of token_1 protocol fees, required if the config has one
+    #[account(mut)]
+    pub secondary_recipient_token_account_1: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The config's secondary protocol fee recipient, required if the config has a secondary share
+    #[account(
+        constraint = secondary_fee_recipient.amm_config == amm_config.key() @ ErrorCode::InvalidSecondaryRecipient
+    )]
+    pub secondary_fee_recipient: Option<Account<'info, SecondaryFeeRecipientState>>,
 }
 
 pub fn collect_protocol_fee(
@@ -74,9 +88,19 @@ pub fn collect_protocol_fee(
     let amount_1: u64;
     {
         let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+        pool_state.check_initialized()?;
 
-        amount_0 = amount_0_requested.min(pool_state.protocol_fees_token_0);
-        amount_1 = amount_1_requested.min(pool_state.protocol_fees_token_1);
+        let (protocol_fees_token_0, protocol_fees_token_1) = pool_state.collectable_protocol_fees();
+        amount_0 = clamp_collect_amount(
+            ctx.accounts.pool_state.key(),
+            &ctx.accounts.token_vault_0,
+            amount_0_requested.min(protocol_fees_token_0),
+        );
+        amount_1 = clamp_collect_amount(
+            ctx.accounts.pool_state.key(),
+            &ctx.accounts.token_vault_1,
+            amount_1_requested.min(protocol_fees_token_1),
+        );
 
         pool_state.protocol_fees_token_0 = pool_state
             .protocol_fees_token_0
@@ -87,6 +111,8 @@ pub fn collect_protocol_fee(
             .checked_sub(amount_1)
             .unwrap();
     }
+    let (amount_0, secondary_amount_0) = ctx.accounts.amm_config.split_protocol_fee(amount_0);
+    let (amount_1, secondary_amount_1) = ctx.accounts.amm_config.split_protocol_fee(amount_1);
     transfer_from_pool_vault_to_user(
         &ctx.accounts.pool_state,
         &ctx.accounts.token_vault_0,
@@ -107,6 +133,64 @@ pub fn collect_protocol_fee(
         amount_1,
     )?;
 
+    if ctx.accounts.amm_config.secondary_protocol_fee_bps > 0 {
+        let (
+            secondary_recipient_token_account_0,
+            secondary_recipient_token_account_1,
+            secondary_fee_recipient,
+        ) = match (
+            &ctx.accounts.secondary_recipient_token_account_0,
+            &ctx.accounts.secondary_recipient_token_account_1,
+            &ctx.accounts.secondary_fee_recipient,
+        ) {
+            (Some(account_0), Some(account_1), Some(recipient)) => {
+                (account_0, account_1, recipient)
+            }
+            _ => return err!(ErrorCode::InvalidSecondaryRecipient),
+        };
+        require!(
+            secondary_recipient_token_account_0.mint == ctx.accounts.token_vault_0.mint
+                && secondary_recipient_token_account_1.mint == ctx.accounts.token_vault_1.mint,
+            ErrorCode::InvalidSecondaryRecipient
+        );
+        // the share can only be paid to the recipient the admin set for the config
+        let secondary_recipient = secondary_fee_recipient.recipient;
+        require_keys_neq!(
+            secondary_recipient,
+            Pubkey::default(),
+            ErrorCode::InvalidSecondaryRecipient
+        );
+        require_keys_eq!(
+            secondary_recipient_token_account_0.owner,
+            secondary_recipient,
+            ErrorCode::InvalidSecondaryRecipient
+        );
+        require_keys_eq!(
+            secondary_recipient_token_account_1.owner,
+            secondary_recipient,
+            ErrorCode::InvalidSecondaryRecipient
+        );
+        transfer_from_pool_vault_to_user(
+            &ctx.accounts.pool_state,
+            &ctx.accounts.token_vault_0,
+            secondary_recipient_token_account_0,
+            Some(ctx.accounts.vault_0_mint.clone()),
+            &ctx.accounts.token_program,
+            Some(ctx.accounts.token_program_2022.to_account_info()),
+            secondary_amount_0,
+        )?;
+
+        transfer_from_pool_vault_to_user(
+            &ctx.accounts.pool_state,
+            &ctx.accounts.token_vault_1,
+            secondary_recipient_token_account_1,
+            Some(ctx.accounts.vault_1_mint.clone()),
+            &ctx.accounts.token_program,
+            Some(ctx.accounts.token_program_2022.to_account_info()),
+            secondary_amount_1,
+        )?;
+    }
+
     check_unclaimed_fees_and_vault(
         &ctx.accounts.pool_state,
         &mut ctx.accounts.token_vault_0,
@@ -119,7 +203,192 @@ pub fn collect_protocol_fee(
         recipient_token_account_1: ctx.accounts.recipient_token_account_1.key(),
         amount_0,
         amount_1,
+        secondary_amount_0,
+        secondary_amount_1,
     });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod collect_protocol_fee_test {
+    use super::*;
+    use crate::states::pool_test::{build_pool, build_pool_accounts};
+    use crate::util::program_test::{process_instruction, program_error, TestAccount};
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_2022::spl_token_2022;
+
+    #[test]
+    fn collect_protocol_fee_secondary_recipient_test() {
+        let owner = Pubkey::new_unique();
+        let secondary_recipient = Pubkey::new_unique();
+        let pool_state = build_pool(0, 60, 1 << 64, 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        pool_state.protocol_fees_token_0 = 1_003;
+        pool_state.protocol_fees_token_1 = 2_000;
+        let mut accounts = build_pool_accounts(pool_state, 1_000_000);
+        let amm_config = AmmConfig {
+            owner,
+            secondary_protocol_fee_bps: 2_500,
+            ..Default::default()
+        };
+        accounts[0] = TestAccount::account(pool_state.amm_config, &amm_config, AmmConfig::LEN);
+        // the recipient set for the pool's config, and one set for another config
+        let (secondary_fee_recipient, bump) = Pubkey::find_program_address(
+            &[
+                SECONDARY_FEE_RECIPIENT_SEED.as_bytes(),
+                pool_state.amm_config.as_ref(),
+            ],
+            &crate::id(),
+        );
+        let other_fee_recipient = Pubkey::new_unique();
+        for (key, amm_config) in [
+            (secondary_fee_recipient, pool_state.amm_config),
+            (other_fee_recipient, Pubkey::new_unique()),
+        ] {
+            accounts.push(TestAccount::account(
+                key,
+                &SecondaryFeeRecipientState {
+                    bump,
+                    amm_config,
+                    recipient: secondary_recipient,
+                    ..Default::default()
+                },
+                SecondaryFeeRecipientState::LEN,
+            ));
+        }
+        accounts.push(TestAccount::wallet(owner));
+        accounts.push(TestAccount::program(spl_token::id()));
+        accounts.push(TestAccount::program(spl_token_2022::id()));
+        // stands in for the optional accounts not given
+        accounts.push(TestAccount::program(crate::id()));
+        // the primary recipient accounts, the secondary recipient's, then another wallet's
+        let token_accounts: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        for (i, token_account) in token_accounts.iter().enumerate() {
+            accounts.push(TestAccount::token_account(
+                *token_account,
+                spl_token::id(),
+                if i % 2 == 0 {
+                    pool_state.token_mint_0
+                } else {
+                    pool_state.token_mint_1
+                },
+                match i / 2 {
+                    0 => owner,
+                    1 => secondary_recipient,
+                    _ => Pubkey::new_unique(),
+                },
+                0,
+            ));
+        }
+        let collect_accounts = crate::accounts::CollectProtocolFee {
+            owner,
+            pool_state: pool_state.key(),
+            amm_config: pool_state.amm_config,
+            token_vault_0: pool_state.token_vault_0,
+            token_vault_1: pool_state.token_vault_1,
+            vault_0_mint: pool_state.token_mint_0,
+            vault_1_mint: pool_state.token_mint_1,
+            recipient_token_account_0: token_accounts[0],
+            recipient_token_account_1: token_accounts[1],
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+            secondary_recipient_token_account_0: None,
+            secondary_recipient_token_account_1: None,
+            secondary_fee_recipient: None,
+        };
+        let collect_metas =
+            |secondary_0: Pubkey, secondary_1: Pubkey, fee_recipient: Option<Pubkey>| {
+                crate::accounts::CollectProtocolFee {
+                    secondary_recipient_token_account_0: Some(secondary_0),
+                    secondary_recipient_token_account_1: Some(secondary_1),
+                    secondary_fee_recipient: fee_recipient,
+                    ..collect_accounts
+                }
+                .to_account_metas(None)
+            };
+        let collect = || crate::instruction::CollectProtocolFee {
+            amount_0_requested: u64::MAX,
+            amount_1_requested: u64::MAX,
+        };
+        let fresh_accounts = accounts.clone();
+        let token_amount = |accounts: &[TestAccount], key: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| account.key == key)
+                .unwrap()
+                .token_amount()
+        };
+
+        // token accounts of the right mints, but not owned by the config's secondary recipient
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                collect_metas(
+                    token_accounts[4],
+                    token_accounts[5],
+                    Some(secondary_fee_recipient)
+                ),
+                collect()
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::InvalidSecondaryRecipient)
+        );
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                collect_metas(
+                    token_accounts[2],
+                    token_accounts[5],
+                    Some(secondary_fee_recipient)
+                ),
+                collect()
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::InvalidSecondaryRecipient)
+        );
+
+        // the recipient's token accounts, without the recipient set for the config
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                collect_metas(token_accounts[2], token_accounts[3], None),
+                collect()
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::InvalidSecondaryRecipient)
+        );
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                collect_metas(
+                    token_accounts[2],
+                    token_accounts[3],
+                    Some(other_fee_recipient)
+                ),
+                collect()
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::InvalidSecondaryRecipient)
+        );
+
+        // the runtime would roll the failed attempts back
+        accounts = fresh_accounts;
+        process_instruction(
+            &mut accounts,
+            collect_metas(
+                token_accounts[2],
+                token_accounts[3],
+                Some(secondary_fee_recipient),
+            ),
+            collect(),
+        )
+        .unwrap();
+        // the rounding remainder goes to the primary recipient
+        assert_eq!(token_amount(&accounts, token_accounts[0]), 753);
+        assert_eq!(token_amount(&accounts, token_accounts[1]), 1_500);
+        assert_eq!(token_amount(&accounts, token_accounts[2]), 250);
+        assert_eq!(token_amount(&accounts, token_accounts[3]), 500);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::ErrorCode;
+use crate::libraries::liquidity_math;
 use crate::states::*;
 use anchor_lang::prelude::*;
 use std::ops::DerefMut;
@@ -44,6 +45,7 @@ pub fn create_amm_config(
     amm_config.trade_fee_rate = trade_fee_rate;
     amm_config.protocol_fee_rate = protocol_fee_rate;
     amm_config.tick_spacing = tick_spacing;
+    amm_config.max_liquidity_per_tick = liquidity_math::get_max_liquidity_per_tick(tick_spacing);
     amm_config.fund_fee_rate = fund_fee_rate;
     amm_config.fund_owner = ctx.accounts.owner.key();
 
@@ -55,6 +57,8 @@ pub fn create_amm_config(
         tick_spacing: amm_config.tick_spacing,
         fund_fee_rate: amm_config.fund_fee_rate,
         fund_owner: amm_config.fund_owner,
+        referral_fee_bps: amm_config.referral_fee_bps,
+        secondary_protocol_fee_bps: amm_config.secondary_protocol_fee_bps,
     });
 
     Ok(())
@@ -21,3 +21,9 @@ pub use transfer_reward_owner::*;
 
 pub mod update_pool_status;
 pub use update_pool_status::*;
+
+pub mod close_unused_observation;
+pub use close_unused_observation::*;
+
+pub mod set_secondary_protocol_fee_recipient;
+pub use set_secondary_protocol_fee_recipient::*;
@@ -0,0 +1,46 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetSecondaryProtocolFeeRecipient<'info> {
+    /// The admin, who pays the rent of the recipient account
+    #[account(mut, address = crate::admin::id() @ ErrorCode::NotApproved)]
+    pub owner: Signer<'info>,
+
+    /// The amm config the recipient is set for
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The config's secondary protocol fee recipient, created on first use
+    #[account(
+        init_if_needed,
+        seeds = [
+            SECONDARY_FEE_RECIPIENT_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = SecondaryFeeRecipientState::LEN
+    )]
+    pub secondary_fee_recipient: Account<'info, SecondaryFeeRecipientState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_secondary_protocol_fee_recipient(
+    ctx: Context<SetSecondaryProtocolFeeRecipient>,
+    recipient: Pubkey,
+) -> Result<()> {
+    let secondary_fee_recipient = &mut ctx.accounts.secondary_fee_recipient;
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "amm_config:{}, old_secondary_protocol_fee_recipient:{}, new_secondary_protocol_fee_recipient:{}",
+        ctx.accounts.amm_config.key().to_string(),
+        secondary_fee_recipient.recipient.to_string(),
+        recipient.to_string()
+    );
+    secondary_fee_recipient.bump = ctx.bumps.secondary_fee_recipient;
+    secondary_fee_recipient.amm_config = ctx.accounts.amm_config.key();
+    secondary_fee_recipient.recipient = recipient;
+    Ok(())
+}
@@ -18,6 +18,7 @@ pub fn transfer_reward_owner<'a, 'b, 'c, 'info>(
     new_owner: Pubkey,
 ) -> Result<()> {
     let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
     for reward_info in &mut pool_state.reward_infos {
         reward_info.authority = new_owner;
     }
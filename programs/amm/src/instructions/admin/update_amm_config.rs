@@ -28,6 +28,9 @@ pub fn update_amm_config(ctx: Context<UpdateAmmConfig>, param: u8, value: u32) -
             let new_fund_owner = *ctx.remaining_accounts.iter().next().unwrap().key;
             set_new_fund_owner(amm_config, new_fund_owner);
         }
+        Some(5) => update_status(amm_config, value),
+        Some(6) => update_referral_fee_bps(amm_config, value),
+        Some(7) => update_secondary_protocol_fee_bps(amm_config, value),
         _ => return err!(ErrorCode::InvalidUpdateConfigFlag),
     }
 
@@ -39,6 +42,8 @@ pub fn update_amm_config(ctx: Context<UpdateAmmConfig>, param: u8, value: u32) -
         tick_spacing: amm_config.tick_spacing,
         fund_fee_rate: amm_config.fund_fee_rate,
         fund_owner: amm_config.fund_owner,
+        referral_fee_bps: amm_config.referral_fee_bps,
+        secondary_protocol_fee_bps: amm_config.secondary_protocol_fee_bps,
     });
 
     Ok(())
@@ -80,3 +85,21 @@ fn set_new_fund_owner(amm_config: &mut Account<AmmConfig>, new_fund_owner: Pubke
     );
     amm_config.fund_owner = new_fund_owner;
 }
+
+fn update_status(amm_config: &mut Account<AmmConfig>, status: u32) {
+    assert!(status <= 1);
+    amm_config.status = status as u8;
+}
+
+fn update_referral_fee_bps(amm_config: &mut Account<AmmConfig>, referral_fee_bps: u32) {
+    assert!(referral_fee_bps <= u32::from(MAX_REFERRAL_FEE_BPS));
+    amm_config.referral_fee_bps = referral_fee_bps as u16;
+}
+
+fn update_secondary_protocol_fee_bps(
+    amm_config: &mut Account<AmmConfig>,
+    secondary_protocol_fee_bps: u32,
+) {
+    assert!(secondary_protocol_fee_bps <= u32::from(BIPS_DENOMINATOR_VALUE));
+    amm_config.secondary_protocol_fee_bps = secondary_protocol_fee_bps as u16;
+}
@@ -18,6 +18,55 @@ pub fn update_pool_status(
 ) -> Result<()> {
     require_gte!(255, status);
     let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
     pool_state.set_status(status);
     Ok(())
 }
+
+pub fn deprecate_pool(
+    ctx: Context<UpdatePoolStatus>,
+    successor_pool: Option<Pubkey>,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
+    pool_state.deprecate(successor_pool);
+    emit!(PoolDeprecatedEvent {
+        pool_state: ctx.accounts.pool_state.key(),
+        successor_pool: pool_state.successor_pool,
+    });
+    Ok(())
+}
+
+pub fn update_pool_position_min_duration(
+    ctx: Context<UpdatePoolStatus>,
+    position_min_duration: u64,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
+    pool_state.position_min_duration = position_min_duration;
+    Ok(())
+}
+
+pub fn update_pool_max_deadline_horizon(
+    ctx: Context<UpdatePoolStatus>,
+    max_deadline_horizon: u64,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
+    pool_state.max_deadline_horizon = max_deadline_horizon;
+    Ok(())
+}
+
+pub fn update_pool_protocol_fee_to_lp(
+    ctx: Context<UpdatePoolStatus>,
+    protocol_fee_to_lp: bool,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
+    pool_state.protocol_fee_to_lp = protocol_fee_to_lp;
+    emit!(ProtocolFeeToLpEvent {
+        pool_state: ctx.accounts.pool_state.key(),
+        protocol_fee_to_lp,
+    });
+    Ok(())
+}
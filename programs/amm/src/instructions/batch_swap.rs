@@ -0,0 +1,239 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::swap::{
+    check_amount_out_minimum, check_input_authority, check_input_balance, check_min_swap_amount,
+    check_vault_pair, SwapResult,
+};
+use crate::swap_v2::{exact_internal_v2, SwapSingleV2};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_interface::{Mint, Token2022, TokenAccount},
+};
+
+/// The max number of independent swaps in one batch_swap
+pub const MAX_BATCH_SWAP_NUM: usize = 4;
+
+/// The parameters of one swap in a batch_swap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchSwapSpec {
+    /// Amount of the swap, the input if `is_base_input`, the output otherwise
+    pub amount: u64,
+    /// The minimum output if `is_base_input`, the maximum input otherwise. For slippage.
+    pub other_amount_threshold: u64,
+    /// The Q64.64 sqrt price √P limit of the swap
+    pub sqrt_price_limit_x64: u128,
+    /// Swap base input or base output
+    pub is_base_input: bool,
+}
+
+#[derive(Accounts)]
+pub struct BatchSwap<'info> {
+    /// The user performing the swaps
+    pub payer: Signer<'info>,
+
+    /// SPL program for token transfers
+    pub token_program: Program<'info, Token>,
+    /// SPL program 2022 for token transfers
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK:
+    #[account(
+        address = spl_memo::id()
+    )]
+    pub memo_program: UncheckedAccount<'info>,
+    // remaining accounts, repeated for each swap in the order of swaps
+    // amm_config
+    // pool_state
+    // input_token_account
+    // output_token_account
+    // input_vault
+    // output_vault
+    // input_vault_mint
+    // output_vault_mint
+    // observation_state
+    // tickarray_bitmap_extension: must add account if need regardless the sequence
+    // tick_array_account_...
+}
+
+/// Ensures at least one and at most `MAX_BATCH_SWAP_NUM` swaps are batched
+pub fn check_batch_swap_num(swap_num: usize) -> Result<()> {
+    require!(
+        swap_num > 0 && swap_num <= MAX_BATCH_SWAP_NUM,
+        ErrorCode::InvalidSwapInputNum
+    );
+    Ok(())
+}
+
+/// Checks the result of a swap against its slippage threshold,
+/// the output amount if base input, the input amount otherwise
+pub fn check_other_amount_threshold(
+    is_base_input: bool,
+    amount_result: u64,
+    other_amount_threshold: u64,
+) -> Result<()> {
+    if is_base_input {
+        require_gte!(
+            amount_result,
+            other_amount_threshold,
+            ErrorCode::TooLittleOutputReceived
+        );
+    } else {
+        require_gte!(
+            other_amount_threshold,
+            amount_result,
+            ErrorCode::TooMuchInputPaid
+        );
+    }
+    Ok(())
+}
+
+pub fn batch_swap<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, BatchSwap<'info>>,
+    swaps: Vec<BatchSwapSpec>,
+) -> Result<Vec<SwapResult>> {
+    check_batch_swap_num(swaps.len())?;
+    let mut swap_results = Vec::with_capacity(swaps.len());
+    let mut swaps = swaps.into_iter();
+    let mut accounts: &[AccountInfo] = ctx.remaining_accounts;
+    while !accounts.is_empty() {
+        let mut remaining_accounts = accounts.iter();
+        let account_info = remaining_accounts.next().unwrap();
+        // skip the tick arrays of the previous swap
+        if accounts.len() != ctx.remaining_accounts.len()
+            && account_info.data_len() != AmmConfig::LEN
+        {
+            accounts = remaining_accounts.as_slice();
+            continue;
+        }
+        let spec = swaps.next().ok_or(ErrorCode::InvalidSwapInputNum)?;
+        let amm_config = Box::new(Account::<AmmConfig>::try_from(account_info)?);
+        let pool_state_loader =
+            AccountLoader::<PoolState>::try_from(remaining_accounts.next().unwrap())?;
+        let input_token_account = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let output_token_account = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let input_vault = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let output_vault = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let input_vault_mint = Box::new(InterfaceAccount::<Mint>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let output_vault_mint = Box::new(InterfaceAccount::<Mint>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let observation_state =
+            AccountLoader::<ObservationState>::try_from(remaining_accounts.next().unwrap())?;
+
+        {
+            let pool_state = pool_state_loader.load()?;
+            require_keys_eq!(pool_state.observation_key, observation_state.key());
+            require_keys_eq!(pool_state.amm_config, amm_config.key());
+            check_vault_pair(&pool_state, input_vault.mint, output_vault.mint)?;
+            check_amount_out_minimum(&pool_state, spec.is_base_input, spec.other_amount_threshold)?;
+            check_min_swap_amount(
+                &pool_state,
+                if spec.is_base_input {
+                    input_vault.mint
+                } else {
+                    output_vault.mint
+                },
+                spec.amount,
+            )?;
+        }
+        require_keys_eq!(input_vault_mint.key(), input_vault.mint);
+        require_keys_eq!(output_vault_mint.key(), output_vault.mint);
+        check_input_authority(&input_token_account, ctx.accounts.payer.key())?;
+        if spec.is_base_input {
+            check_input_balance(&input_token_account, spec.amount)?;
+        }
+
+        accounts = remaining_accounts.as_slice();
+        // every swap emits its own SwapEvent and is settled on its own pool
+        let amount_result = exact_internal_v2(
+            &mut SwapSingleV2 {
+                payer: ctx.accounts.payer.clone(),
+                amm_config,
+                input_token_account,
+                pool_state: pool_state_loader.clone(),
+                output_token_account,
+                input_vault,
+                output_vault,
+                input_vault_mint,
+                output_vault_mint,
+                observation_state,
+                token_program: ctx.accounts.token_program.clone(),
+                token_program_2022: ctx.accounts.token_program_2022.clone(),
+                memo_program: ctx.accounts.memo_program.clone(),
+                referrer_token_account: None,
+            },
+            accounts,
+            spec.amount,
+            spec.sqrt_price_limit_x64,
+            spec.is_base_input,
+        )?;
+        // any swap out of its slippage reverts the whole batch
+        check_other_amount_threshold(
+            spec.is_base_input,
+            amount_result,
+            spec.other_amount_threshold,
+        )?;
+
+        let (amount_in, amount_out) = if spec.is_base_input {
+            (spec.amount, amount_result)
+        } else {
+            (amount_result, spec.amount)
+        };
+        swap_results.push(SwapResult::new(
+            &*pool_state_loader.load()?,
+            amount_in,
+            amount_out,
+        ));
+    }
+    // the pool accounts of every swap must be given
+    require!(swaps.next().is_none(), ErrorCode::InvalidSwapInputNum);
+    #[cfg(feature = "enable-log")]
+    msg!("batch swap, swap_results:{:?}", swap_results);
+    Ok(swap_results)
+}
+
+#[cfg(test)]
+mod batch_swap_test {
+    use super::*;
+
+    #[test]
+    fn check_batch_swap_num_test() {
+        assert_eq!(
+            check_batch_swap_num(0).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+        assert!(check_batch_swap_num(1).is_ok());
+        assert!(check_batch_swap_num(MAX_BATCH_SWAP_NUM).is_ok());
+        assert_eq!(
+            check_batch_swap_num(MAX_BATCH_SWAP_NUM + 1).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+    }
+
+    #[test]
+    fn check_other_amount_threshold_test() {
+        // base input, the output must reach the threshold
+        assert!(check_other_amount_threshold(true, 1_000, 1_000).is_ok());
+        assert_eq!(
+            check_other_amount_threshold(true, 999, 1_000).unwrap_err(),
+            ErrorCode::TooLittleOutputReceived.into()
+        );
+        // base output, the input must not exceed the threshold
+        assert!(check_other_amount_threshold(false, 1_000, 1_000).is_ok());
+        assert_eq!(
+            check_other_amount_threshold(false, 1_001, 1_000).unwrap_err(),
+            ErrorCode::TooMuchInputPaid.into()
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use crate::error::ErrorCode;
+use crate::instructions::get_closable_accounts;
+use crate::states::*;
+use crate::util::{burn, close_spl_account};
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// The number of remaining accounts given for each position
+pub const CLEANUP_POSITION_ACCOUNT_NUM: usize = 7;
+
+#[derive(Accounts)]
+pub struct CleanupAccounts<'info> {
+    /// The position nft owner, receives the rent of the closed accounts
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+
+    /// Program to burn the position nfts and close their token accounts
+    pub token_program: Program<'info, Token>,
+    // remaining accounts, repeated for each position
+    // position_nft_mint
+    // position_nft_account
+    // personal_position
+    // pool_state
+    // protocol_position
+    // tick_array_lower
+    // tick_array_upper, may be the lower one
+}
+
+/// Ensures the remaining accounts are whole groups of a position's accounts
+pub fn check_cleanup_account_num(account_num: usize) -> Result<()> {
+    require!(
+        account_num > 0 && account_num % CLEANUP_POSITION_ACCOUNT_NUM == 0,
+        ErrorCode::InvalidCleanupAccounts
+    );
+    Ok(())
+}
+
+/// Close the position, its nft and the nft account if the position is empty and held by the
+/// owner, with the protocol position and tick arrays `get_closable_accounts` finds closable,
+/// `position_accounts` being the position's group of the remaining accounts.
+/// Returns those closed with it, or none without closing anything if it isn't safe to close.
+fn close_empty_position<'info>(
+    nft_owner: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+    position_accounts: &'info [AccountInfo<'info>],
+) -> Result<Option<Vec<Pubkey>>> {
+    let (
+        Ok(position_nft_mint),
+        Ok(position_nft_account),
+        Ok(personal_position),
+        Ok(pool_state),
+        Ok(mut protocol_position),
+        Ok(tick_array_lower),
+        Ok(tick_array_upper),
+    ) = (
+        InterfaceAccount::<Mint>::try_from(&position_accounts[0]),
+        InterfaceAccount::<TokenAccount>::try_from(&position_accounts[1]),
+        Account::<PersonalPositionState>::try_from(&position_accounts[2]),
+        AccountLoader::<PoolState>::try_from(&position_accounts[3]),
+        Account::<ProtocolPositionState>::try_from(&position_accounts[4]),
+        AccountLoader::<TickArrayState>::try_from(&position_accounts[5]),
+        AccountLoader::<TickArrayState>::try_from(&position_accounts[6]),
+    )
+    else {
+        return Ok(None);
+    };
+    if personal_position.nft_mint != position_nft_mint.key()
+        || *position_accounts[0].owner != token_program.key()
+        || position_nft_account.mint != position_nft_mint.key()
+        || position_nft_account.owner != nft_owner.key()
+        || position_nft_account.amount != 1
+        || !personal_position.is_empty()
+        || personal_position
+            .check_position(pool_state.key(), &protocol_position)
+            .is_err()
+    {
+        return Ok(None);
+    }
+    let closed_accounts = {
+        let tick_array_lower_state = tick_array_lower.load()?;
+        let tick_array_upper_state = tick_array_upper.load()?;
+        if tick_array_lower_state.pool_id != pool_state.key()
+            || tick_array_upper_state.pool_id != pool_state.key()
+        {
+            return Ok(None);
+        }
+        // fails for tick arrays not holding the position's ticks
+        let Ok(closed_accounts) = get_closable_accounts(
+            nft_owner.key(),
+            (protocol_position.key(), &protocol_position),
+            (tick_array_lower.key(), &tick_array_lower_state),
+            (tick_array_upper.key(), &tick_array_upper_state),
+            pool_state.load()?.tick_spacing,
+        ) else {
+            return Ok(None);
+        };
+        closed_accounts
+    };
+    protocol_position.update_position_count(false);
+    tick_array_lower.load_mut()?.update_position_count(false);
+    if tick_array_upper.key() != tick_array_lower.key() {
+        tick_array_upper.load_mut()?.update_position_count(false);
+    }
+
+    burn(
+        nft_owner,
+        &position_nft_mint,
+        &position_nft_account,
+        token_program,
+        &[],
+        1,
+    )?;
+    close_spl_account(
+        nft_owner,
+        nft_owner,
+        &position_nft_account,
+        token_program,
+        &[],
+    )?;
+    personal_position.close(nft_owner.to_account_info())?;
+
+    let nft_owner_info = nft_owner.to_account_info();
+    if closed_accounts.contains(&protocol_position.key()) {
+        protocol_position.close(nft_owner_info.clone())?;
+    } else {
+        // a remaining account isn't written back by anchor
+        protocol_position.exit(&crate::id())?;
+    }
+    if closed_accounts.contains(&tick_array_lower.key()) {
+        tick_array_lower.close(nft_owner_info.clone())?;
+    }
+    if tick_array_upper.key() != tick_array_lower.key()
+        && closed_accounts.contains(&tick_array_upper.key())
+    {
+        tick_array_upper.close(nft_owner_info)?;
+    }
+    Ok(Some(closed_accounts))
+}
+
+pub fn cleanup_accounts<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CleanupAccounts<'info>>,
+) -> Result<()> {
+    check_cleanup_account_num(ctx.remaining_accounts.len())?;
+    let mut closed_position_nft_mints = Vec::new();
+    let mut closed_accounts = Vec::new();
+    for position_accounts in ctx
+        .remaining_accounts
+        .chunks_exact(CLEANUP_POSITION_ACCOUNT_NUM)
+    {
+        if let Some(position_closed_accounts) = close_empty_position(
+            &ctx.accounts.nft_owner,
+            &ctx.accounts.token_program,
+            position_accounts,
+        )? {
+            closed_position_nft_mints.push(position_accounts[0].key());
+            closed_accounts.extend(position_closed_accounts);
+        }
+    }
+    emit!(CleanupPositionsEvent {
+        nft_owner: ctx.accounts.nft_owner.key(),
+        closed_position_nft_mints,
+        closed_accounts,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod cleanup_accounts_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::build_pool;
+    use crate::states::protocol_position_test::build_protocol_position;
+    use crate::states::tick_array_test::build_tick_array_with_tick_states;
+    use crate::util::program_test::{process_instruction, TestAccount};
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+
+    #[test]
+    fn check_cleanup_account_num_test() {
+        assert_eq!(
+            check_cleanup_account_num(0).unwrap_err(),
+            ErrorCode::InvalidCleanupAccounts.into()
+        );
+        assert!(check_cleanup_account_num(CLEANUP_POSITION_ACCOUNT_NUM).is_ok());
+        assert!(check_cleanup_account_num(CLEANUP_POSITION_ACCOUNT_NUM * 4).is_ok());
+        assert_eq!(
+            check_cleanup_account_num(CLEANUP_POSITION_ACCOUNT_NUM + 1).unwrap_err(),
+            ErrorCode::InvalidCleanupAccounts.into()
+        );
+    }
+
+    #[test]
+    fn cleanup_accounts_test() {
+        let tick_spacing = 10;
+        let (tick_lower_index, tick_upper_index) = (-100, 1000);
+        let nft_owner = Pubkey::new_unique();
+        let pool_state = build_pool(
+            0,
+            tick_spacing,
+            tick_math::get_sqrt_price_at_tick(0).unwrap(),
+            0,
+        );
+        let pool_state = pool_state.borrow();
+        let pool_id = pool_state.key();
+        // two burned positions of the owner in the same range, which paid for every account
+        let protocol_position = ProtocolPositionState {
+            pool_id,
+            payer: nft_owner,
+            position_count: 2,
+            ..build_protocol_position(tick_lower_index, tick_upper_index, 0)
+        };
+        let tick_array = |start_index: i32| {
+            let tick_array =
+                build_tick_array_with_tick_states(pool_id, start_index, tick_spacing, vec![]);
+            tick_array.borrow_mut().payer = nft_owner;
+            tick_array.borrow_mut().position_count = 2;
+            tick_array.take()
+        };
+        let (protocol_position_key, tick_array_lower_key, tick_array_upper_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut accounts = vec![
+            TestAccount::wallet(nft_owner),
+            TestAccount::program(spl_token::id()),
+            TestAccount::zero_copy(pool_id, &*pool_state),
+            TestAccount::account(
+                protocol_position_key,
+                &protocol_position,
+                ProtocolPositionState::LEN,
+            ),
+            TestAccount::zero_copy(tick_array_lower_key, &tick_array(-600)),
+            TestAccount::zero_copy(tick_array_upper_key, &tick_array(600)),
+        ];
+        let mut position_keys = Vec::new();
+        for _ in 0..2 {
+            let (nft_mint, nft_account, personal_position) = (
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            );
+            let personal_position_state = PersonalPositionState {
+                nft_mint,
+                pool_id,
+                tick_lower_index,
+                tick_upper_index,
+                ..Default::default()
+            };
+            accounts.extend([
+                TestAccount::mint(nft_mint, spl_token::id(), 0),
+                TestAccount::token_account(nft_account, spl_token::id(), nft_mint, nft_owner, 1),
+                TestAccount::account(
+                    personal_position,
+                    &personal_position_state,
+                    PersonalPositionState::LEN,
+                ),
+            ]);
+            position_keys.push((nft_mint, nft_account, personal_position));
+        }
+        let metas = |positions: &[(Pubkey, Pubkey, Pubkey)]| {
+            let mut metas = crate::accounts::CleanupAccounts {
+                nft_owner,
+                token_program: spl_token::id(),
+            }
+            .to_account_metas(None);
+            for (nft_mint, nft_account, personal_position) in positions {
+                metas.extend([
+                    AccountMeta::new(*nft_mint, false),
+                    AccountMeta::new(*nft_account, false),
+                    AccountMeta::new(*personal_position, false),
+                    AccountMeta::new_readonly(pool_id, false),
+                    AccountMeta::new(protocol_position_key, false),
+                    AccountMeta::new(tick_array_lower_key, false),
+                    AccountMeta::new(tick_array_upper_key, false),
+                ]);
+            }
+            metas
+        };
+        let lamports = |accounts: &[TestAccount], key: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| account.key == key)
+                .unwrap()
+                .lamports
+        };
+
+        // the first position is closed, the second is skipped as its nft was sold
+        let (_, sold_nft_account, _) = position_keys[1];
+        let sold_nft_account_index = accounts
+            .iter()
+            .position(|account| account.key == sold_nft_account)
+            .unwrap();
+        accounts[sold_nft_account_index] = TestAccount::token_account(
+            sold_nft_account,
+            spl_token::id(),
+            position_keys[1].0,
+            Pubkey::new_unique(),
+            1,
+        );
+        process_instruction(
+            &mut accounts,
+            metas(&position_keys),
+            crate::instruction::CleanupAccounts {},
+        )
+        .unwrap();
+        assert_eq!(lamports(&accounts, position_keys[0].2), 0);
+        assert!(lamports(&accounts, position_keys[1].2) > 0);
+        // the range's accounts are kept for the other position, which is counted alone
+        let protocol_position_account = accounts
+            .iter()
+            .find(|account| account.key == protocol_position_key)
+            .unwrap();
+        assert_eq!(
+            protocol_position_account
+                .state::<ProtocolPositionState>()
+                .position_count,
+            1
+        );
+        for tick_array_key in [tick_array_lower_key, tick_array_upper_key] {
+            let tick_array_account = accounts
+                .iter()
+                .find(|account| account.key == tick_array_key)
+                .unwrap();
+            assert_eq!(
+                { tick_array_account.load::<TickArrayState>().position_count },
+                1
+            );
+        }
+
+        // the last position of the range closes the range's accounts with it
+        accounts[sold_nft_account_index] = TestAccount::token_account(
+            sold_nft_account,
+            spl_token::id(),
+            position_keys[1].0,
+            nft_owner,
+            1,
+        );
+        let nft_owner_lamports = lamports(&accounts, nft_owner);
+        let rent = [
+            position_keys[1].2,
+            protocol_position_key,
+            tick_array_lower_key,
+            tick_array_upper_key,
+        ]
+        .iter()
+        .map(|key| lamports(&accounts, *key))
+        .sum::<u64>();
+        process_instruction(
+            &mut accounts,
+            metas(&position_keys[1..]),
+            crate::instruction::CleanupAccounts {},
+        )
+        .unwrap();
+        for key in [
+            position_keys[1].2,
+            protocol_position_key,
+            tick_array_lower_key,
+            tick_array_upper_key,
+        ] {
+            assert_eq!(lamports(&accounts, key), 0);
+        }
+        // with the rent of the nft account
+        assert!(lamports(&accounts, nft_owner) > nft_owner_lamports + rent);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::error::ErrorCode;
+use crate::instructions::get_emptied_accounts;
+use crate::states::*;
+use crate::util::{burn, close_spl_account};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ClosePositionComplete<'info> {
+    /// The position nft owner, receives the rent of the position and of every closed account
+    /// it paid for
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+
+    /// Unique token mint address
+    #[account(
+      mut,
+      address = personal_position.nft_mint,
+      mint::token_program = token_program,
+    )]
+    pub position_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token account holding the position NFT
+    #[account(
+        mut,
+        associated_token::mint = position_nft_mint,
+        associated_token::authority = nft_owner,
+        constraint = position_nft_account.amount == 1,
+        token::token_program = token_program,
+    )]
+    pub position_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Metadata for the tokenized position
+    #[account(
+        mut,
+        seeds = [POSITION_SEED.as_bytes(), position_nft_mint.key().as_ref()],
+        bump,
+        close = nft_owner
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    /// The pool of the position
+    #[account(address = personal_position.pool_id)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The protocol position of the position's range, closed if no liquidity is left in it
+    /// and the nft owner paid for it
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &personal_position.tick_lower_index.to_be_bytes(),
+            &personal_position.tick_upper_index.to_be_bytes(),
+        ],
+        bump,
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    /// The tick array holding the position's lower tick, closed if none of its ticks is
+    /// initialized and the nft owner paid for it
+    #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    /// The tick array holding the position's upper tick, may be the lower one
+    #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    /// Program to create the position manager state account
+    pub system_program: Program<'info, System>,
+    /// Program to burn the position nft and close its token account, either token program
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Gets which of an emptied position's accounts can be closed with it: those no other
+/// position refers to, as `get_emptied_accounts` reports them, and that `nft_owner` paid for.
+/// The rent of an account someone else created is theirs, so it's left open, as are the
+/// accounts created before the payer was recorded. A protocol position with fees owed is kept.
+pub fn get_closable_accounts(
+    nft_owner: Pubkey,
+    protocol_position: (Pubkey, &ProtocolPositionState),
+    tick_array_lower: (Pubkey, &TickArrayState),
+    tick_array_upper: (Pubkey, &TickArrayState),
+    tick_spacing: u16,
+) -> Result<Vec<Pubkey>> {
+    let emptied_accounts = get_emptied_accounts(
+        0,
+        protocol_position,
+        tick_array_lower,
+        tick_array_upper,
+        tick_spacing,
+    )?
+    .emptied_accounts;
+    let (protocol_position_key, protocol_position) = protocol_position;
+    let protocol_position_closable = protocol_position.payer == nft_owner
+        && protocol_position.token_fees_owed_0 == 0
+        && protocol_position.token_fees_owed_1 == 0;
+    Ok(emptied_accounts
+        .into_iter()
+        .filter(|key| {
+            if *key == protocol_position_key {
+                protocol_position_closable
+            } else if *key == tick_array_lower.0 {
+                tick_array_lower.1.payer == nft_owner
+            } else {
+                tick_array_upper.1.payer == nft_owner
+            }
+        })
+        .collect())
+}
+
+pub fn close_position_complete<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, ClosePositionComplete<'info>>,
+) -> Result<()> {
+    let personal_position = &ctx.accounts.personal_position;
+    if !personal_position.is_empty() {
+        msg!(
+            "remaing liquidity:{},token_fees_owed_0:{},token_fees_owed_1:{}",
+            personal_position.liquidity,
+            personal_position.token_fees_owed_0,
+            personal_position.token_fees_owed_1
+        );
+        return err!(ErrorCode::ClosePositionErr);
+    }
+    personal_position.check_position(
+        ctx.accounts.pool_state.key(),
+        &ctx.accounts.protocol_position,
+    )?;
+
+    let closed_accounts = {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        let tick_array_lower = ctx.accounts.tick_array_lower.load()?;
+        let tick_array_upper = ctx.accounts.tick_array_upper.load()?;
+        get_closable_accounts(
+            ctx.accounts.nft_owner.key(),
+            (
+                ctx.accounts.protocol_position.key(),
+                &ctx.accounts.protocol_position,
+            ),
+            (ctx.accounts.tick_array_lower.key(), &tick_array_lower),
+            (ctx.accounts.tick_array_upper.key(), &tick_array_upper),
+            pool_state.tick_spacing,
+        )?
+    };
+    ctx.accounts.protocol_position.update_position_count(false);
+    ctx.accounts
+        .tick_array_lower
+        .load_mut()?
+        .update_position_count(false);
+    if ctx.accounts.tick_array_upper.key() != ctx.accounts.tick_array_lower.key() {
+        ctx.accounts
+            .tick_array_upper
+            .load_mut()?
+            .update_position_count(false);
+    }
+
+    burn(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.position_nft_mint,
+        &ctx.accounts.position_nft_account,
+        &ctx.accounts.token_program,
+        &[],
+        1,
+    )?;
+    close_spl_account(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.position_nft_account,
+        &ctx.accounts.token_program,
+        &[],
+    )?;
+
+    let nft_owner_info = ctx.accounts.nft_owner.to_account_info();
+    if closed_accounts.contains(&ctx.accounts.protocol_position.key()) {
+        ctx.accounts
+            .protocol_position
+            .close(nft_owner_info.clone())?;
+    }
+    if closed_accounts.contains(&ctx.accounts.tick_array_lower.key()) {
+        ctx.accounts
+            .tick_array_lower
+            .close(nft_owner_info.clone())?;
+    }
+    if ctx.accounts.tick_array_upper.key() != ctx.accounts.tick_array_lower.key()
+        && closed_accounts.contains(&ctx.accounts.tick_array_upper.key())
+    {
+        ctx.accounts.tick_array_upper.close(nft_owner_info)?;
+    }
+
+    emit!(PositionCleanupEvent {
+        nft_owner: ctx.accounts.nft_owner.key(),
+        position_nft_mint: ctx.accounts.position_nft_mint.key(),
+        closed_accounts,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod close_position_complete_test {
+    use super::*;
+    use crate::instructions::MAX_TEARDOWN_ACCOUNT_NUM;
+    use crate::states::protocol_position_test::build_protocol_position;
+    use crate::states::tick_array_test::{build_tick, build_tick_array_with_tick_states};
+
+    #[test]
+    fn get_closable_accounts_test() {
+        let tick_spacing = 10;
+        let nft_owner = Pubkey::new_unique();
+        let (protocol_position_key, lower_key, upper_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        // the position's liquidity is burned, another position keeps a tick in the upper array
+        let mut protocol_position = build_protocol_position(-100, 1000, 0);
+        protocol_position.payer = nft_owner;
+        let tick_array_lower =
+            build_tick_array_with_tick_states(Pubkey::default(), -600, tick_spacing, vec![]);
+        tick_array_lower.borrow_mut().payer = nft_owner;
+        let tick_array_upper = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            600,
+            tick_spacing,
+            vec![build_tick(1100, 200, 200).take()],
+        );
+        tick_array_upper.borrow_mut().initialized_tick_count = 1;
+        tick_array_upper.borrow_mut().payer = nft_owner;
+
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(closable_accounts, vec![protocol_position_key, lower_key]);
+
+        // another position of the same range keeps the protocol position
+        protocol_position.liquidity = 200;
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(closable_accounts, vec![lower_key]);
+
+        // so do fees not collected yet
+        protocol_position.liquidity = 0;
+        protocol_position.token_fees_owed_1 = 1;
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(closable_accounts, vec![lower_key]);
+
+        // and other positions without liquidity, which still collect with the accounts
+        protocol_position.token_fees_owed_1 = 0;
+        protocol_position.position_count = 2;
+        tick_array_lower.borrow_mut().position_count = 2;
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert!(closable_accounts.is_empty());
+        // the last one closes them
+        protocol_position.position_count = 1;
+        tick_array_lower.borrow_mut().position_count = 1;
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(closable_accounts, vec![protocol_position_key, lower_key]);
+
+        // the tick arrays must hold the position's ticks
+        assert!(get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (upper_key, &tick_array_upper.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn get_closable_accounts_paid_by_others_test() {
+        let tick_spacing = 10;
+        let (nft_owner, other_payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (protocol_position_key, lower_key, upper_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        // all emptied, but only the upper tick array was created by the nft owner
+        let mut protocol_position = build_protocol_position(-100, 1000, 0);
+        protocol_position.payer = other_payer;
+        let tick_array_lower =
+            build_tick_array_with_tick_states(Pubkey::default(), -600, tick_spacing, vec![]);
+        let tick_array_upper =
+            build_tick_array_with_tick_states(Pubkey::default(), 600, tick_spacing, vec![]);
+        tick_array_upper.borrow_mut().payer = nft_owner;
+
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(closable_accounts, vec![upper_key]);
+
+        // the other payer can close the rest
+        tick_array_lower.borrow_mut().payer = other_payer;
+        let closable_accounts = get_closable_accounts(
+            other_payer,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(closable_accounts, vec![protocol_position_key, lower_key]);
+    }
+
+    #[test]
+    fn get_closable_accounts_in_one_tick_array_test() {
+        let tick_spacing = 10;
+        let nft_owner = Pubkey::new_unique();
+        let (protocol_position_key, tick_array_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut protocol_position = build_protocol_position(-100, -50, 0);
+        protocol_position.payer = nft_owner;
+        let tick_array =
+            build_tick_array_with_tick_states(Pubkey::default(), -600, tick_spacing, vec![]);
+        tick_array.borrow_mut().payer = nft_owner;
+
+        let closable_accounts = get_closable_accounts(
+            nft_owner,
+            (protocol_position_key, &protocol_position),
+            (tick_array_key, &tick_array.borrow()),
+            (tick_array_key, &tick_array.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        // closed once
+        assert_eq!(
+            closable_accounts,
+            vec![protocol_position_key, tick_array_key]
+        );
+        assert!(closable_accounts.len() <= MAX_TEARDOWN_ACCOUNT_NUM);
+    }
+}
@@ -76,6 +76,7 @@ fn get_remaining_reward_amount(
 ) -> Result<u64> {
     let current_timestamp = u64::try_from(Clock::get()?.unix_timestamp).unwrap();
     let mut pool_state = pool_state_loader.load_mut()?;
+    pool_state.check_initialized()?;
     pool_state.update_reward_infos(current_timestamp)?;
 
     let reward_info = pool_state.reward_infos[reward_index as usize];
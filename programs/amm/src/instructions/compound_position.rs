@@ -0,0 +1,235 @@
+use super::{burn_liquidity, calculate_latest_token_fees, mint_liquidity};
+use crate::error::ErrorCode;
+use crate::libraries::{liquidity_math, tick_math};
+use crate::states::*;
+use crate::util::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+#[derive(Accounts)]
+pub struct CompoundPosition<'info> {
+    /// The holder of the position nft
+    pub nft_owner: Signer<'info>,
+
+    /// The token account for nft
+    #[account(
+        constraint = nft_account.mint == personal_position.nft_mint
+    )]
+    pub nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &personal_position.tick_lower_index.to_be_bytes(),
+            &personal_position.tick_upper_index.to_be_bytes(),
+        ],
+        bump,
+        constraint = protocol_position.pool_id == pool_state.key(),
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    /// Compound the fees of this position
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    /// Stores init state for the lower tick
+    #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    /// Stores init state for the upper tick
+    #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+    // remaining account
+    // tickarray_bitmap_extension: must add account if the position's tick arrays need it
+}
+
+/// Gets the liquidity the fees owed to a position mint in its range at the current price,
+/// and the amounts of the fees it takes, rounded up as the pool charges them.
+/// The fees the liquidity doesn't take stay owed to the position.
+pub fn get_compound_liquidity(
+    tick_current: i32,
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    token_fees_owed_0: u64,
+    token_fees_owed_1: u64,
+) -> Result<(u128, u64, u64)> {
+    let liquidity = liquidity_math::get_liquidity_from_amounts(
+        sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(tick_lower)?,
+        tick_math::get_sqrt_price_at_tick(tick_upper)?,
+        token_fees_owed_0,
+        token_fees_owed_1,
+    );
+    if liquidity == 0 {
+        return Ok((0, 0, 0));
+    }
+    let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+        tick_current,
+        sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        i128::try_from(liquidity).map_err(|_| ErrorCode::LiquidityAddValueErr)?,
+    )?;
+    require!(
+        amount_0 <= token_fees_owed_0 && amount_1 <= token_fees_owed_1,
+        ErrorCode::LiquidityAddValueErr
+    );
+    Ok((liquidity, amount_0, amount_1))
+}
+
+pub fn compound_position<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CompoundPosition<'info>>,
+    liquidity_min: u128,
+    deadline: i64,
+) -> Result<()> {
+    let personal_position = &mut ctx.accounts.personal_position;
+    let protocol_position = &mut ctx.accounts.protocol_position;
+    personal_position.check_position(ctx.accounts.pool_state.key(), protocol_position)?;
+    let pool_state = &mut ctx.accounts.pool_state.load_mut()?;
+    check_deadline(deadline, pool_state.max_deadline_horizon)?;
+    pool_state.check_initialized()?;
+    pool_state.check_not_deprecated()?;
+    if !pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity)
+        || !pool_state.get_status_by_bit(PoolStatusBitIndex::CollectFee)
+    {
+        return err!(ErrorCode::NotApproved);
+    }
+    let tick_lower = personal_position.tick_lower_index;
+    let tick_upper = personal_position.tick_upper_index;
+    let tick_array_bitmap_extension =
+        if pool_state.is_overflow_default_tickarray_bitmap(vec![tick_lower, tick_upper]) {
+            require_keys_eq!(
+                ctx.remaining_accounts[0].key(),
+                TickArrayBitmapExtension::key(ctx.accounts.pool_state.key())
+            );
+            Some(&ctx.remaining_accounts[0])
+        } else {
+            None
+        };
+
+    // poke the position, burning no liquidity, to bring the fees owed up to date
+    burn_liquidity(
+        pool_state,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        protocol_position,
+        tick_array_bitmap_extension,
+        0,
+    )?;
+    personal_position.token_fees_owed_0 = calculate_latest_token_fees(
+        personal_position.token_fees_owed_0,
+        personal_position.fee_growth_inside_0_last_x64,
+        protocol_position.fee_growth_inside_0_last_x64,
+        personal_position.liquidity,
+    );
+    personal_position.token_fees_owed_1 = calculate_latest_token_fees(
+        personal_position.token_fees_owed_1,
+        personal_position.fee_growth_inside_1_last_x64,
+        protocol_position.fee_growth_inside_1_last_x64,
+        personal_position.liquidity,
+    );
+    personal_position.fee_growth_inside_0_last_x64 = protocol_position.fee_growth_inside_0_last_x64;
+    personal_position.fee_growth_inside_1_last_x64 = protocol_position.fee_growth_inside_1_last_x64;
+    // update rewards, must update before increase liquidity
+    personal_position.update_rewards(protocol_position.reward_growth_inside, true)?;
+
+    let unclaimed_fee_token_0 = pool_state
+        .total_fees_token_0
+        .checked_sub(pool_state.total_fees_claimed_token_0)
+        .ok_or(ErrorCode::ClaimedFeesExceedTotal)?;
+    let unclaimed_fee_token_1 = pool_state
+        .total_fees_token_1
+        .checked_sub(pool_state.total_fees_claimed_token_1)
+        .ok_or(ErrorCode::ClaimedFeesExceedTotal)?;
+    require_gte!(unclaimed_fee_token_0, personal_position.token_fees_owed_0);
+    require_gte!(unclaimed_fee_token_1, personal_position.token_fees_owed_1);
+    let (liquidity, compound_amount_0, compound_amount_1) = get_compound_liquidity(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        personal_position.token_fees_owed_0,
+        personal_position.token_fees_owed_1,
+    )?;
+    require!(liquidity > 0, ErrorCode::ForbidBothZeroForSupplyLiquidity);
+    require_gte!(liquidity, liquidity_min, ErrorCode::CompoundSlippage);
+
+    // the fees are already in the vaults, so the liquidity is minted without a transfer
+    let (amount_0, amount_1) = mint_liquidity(
+        pool_state,
+        &AccountLoad::<TickArrayState>::try_from(&ctx.accounts.tick_array_lower.to_account_info())?,
+        &AccountLoad::<TickArrayState>::try_from(&ctx.accounts.tick_array_upper.to_account_info())?,
+        protocol_position,
+        tick_array_bitmap_extension,
+        liquidity,
+        tick_lower,
+        tick_upper,
+    )?;
+    require!(
+        amount_0 == compound_amount_0 && amount_1 == compound_amount_1,
+        ErrorCode::LiquidityAddValueErr
+    );
+
+    // the compounded fees are claimed from the pool's fees and become the position's liquidity,
+    // they never leave the vaults so they aren't counted in the position's fees_collected
+    personal_position.token_fees_owed_0 -= amount_0;
+    personal_position.token_fees_owed_1 -= amount_1;
+    pool_state.total_fees_claimed_token_0 = pool_state
+        .total_fees_claimed_token_0
+        .checked_add(amount_0)
+        .unwrap();
+    pool_state.total_fees_claimed_token_1 = pool_state
+        .total_fees_claimed_token_1
+        .checked_add(amount_1)
+        .unwrap();
+    personal_position.liquidity = personal_position.liquidity.checked_add(liquidity).unwrap();
+    personal_position.last_increase_timestamp = Clock::get()?.unix_timestamp as u64;
+
+    emit!(CompoundPositionEvent {
+        position_nft_mint: personal_position.nft_mint,
+        liquidity,
+        amount_0,
+        amount_1,
+        token_fees_owed_0: personal_position.token_fees_owed_0,
+        token_fees_owed_1: personal_position.token_fees_owed_1,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod compound_position_test {
+    use super::*;
+
+    #[test]
+    fn get_compound_liquidity_test() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        // in range, the scarcer fee bounds the liquidity and the rest of the other is left owed
+        assert_eq!(
+            get_compound_liquidity(0, sqrt_price_x64, -600, 600, 1_000_000, 2_000_000).unwrap(),
+            (33_837_499, 1_000_000, 1_000_000)
+        );
+
+        // out of range, only the fee of the token the range holds is compounded
+        assert_eq!(
+            get_compound_liquidity(0, sqrt_price_x64, 600, 1200, 1_000_000, 2_000_000).unwrap(),
+            (34_867_952, 1_000_000, 0)
+        );
+        assert_eq!(
+            get_compound_liquidity(0, sqrt_price_x64, -1200, -600, 1_000_000, 2_000_000).unwrap(),
+            (69_735_905, 0, 2_000_000)
+        );
+
+        // no fees of the token the range needs, nothing to compound
+        assert_eq!(
+            get_compound_liquidity(0, sqrt_price_x64, 600, 1200, 0, 2_000_000).unwrap(),
+            (0, 0, 0)
+        );
+    }
+}
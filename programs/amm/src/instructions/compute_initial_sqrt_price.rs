@@ -0,0 +1,123 @@
+use crate::error::ErrorCode;
+use crate::libraries::{big_num::U256, fixed_point_64, tick_math};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ComputeInitialSqrtPrice {}
+
+/// The initial price to create a pool with, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InitialSqrtPrice {
+    /// The Q64.64 sqrt price to pass to create_pool
+    pub sqrt_price_x64: u128,
+    /// The tick the pool will start at
+    pub tick: i32,
+}
+
+/// Convert a human price, token_1 per token_0 in UI units as a Q64.64 value,
+/// to the sqrt price of raw token amounts the pool stores.
+pub fn price_to_sqrt_price_x64(price_x64: u128, decimals_0: u8, decimals_1: u8) -> Result<u128> {
+    let sqrt_price_x64 = price_to_unbounded_sqrt_price_x64(price_x64, decimals_0, decimals_1)?;
+    require!(
+        sqrt_price_x64 >= U256::from(tick_math::MIN_SQRT_PRICE_X64)
+            && sqrt_price_x64 < U256::from(tick_math::MAX_SQRT_PRICE_X64),
+        ErrorCode::SqrtPriceX64
+    );
+    Ok(sqrt_price_x64.as_u128())
+}
+
+/// `price_to_sqrt_price_x64` without checking the sqrt price is within the pool's price range
+pub fn price_to_unbounded_sqrt_price_x64(
+    price_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Result<U256> {
+    let ten = U256::from(10);
+    let multiplier_0 = ten
+        .checked_pow(U256::from(decimals_0))
+        .ok_or(ErrorCode::SqrtPriceX64)?;
+    let multiplier_1 = ten
+        .checked_pow(U256::from(decimals_1))
+        .ok_or(ErrorCode::SqrtPriceX64)?;
+    // sqrt(price * 2^64 * 2^64) = sqrt(price) * 2^64
+    let price_x128 = U256::from(price_x64)
+        .checked_mul(U256::from(fixed_point_64::Q64))
+        .and_then(|v| v.checked_mul(multiplier_1))
+        .ok_or(ErrorCode::SqrtPriceX64)?
+        / multiplier_0;
+    Ok(price_x128.integer_sqrt())
+}
+
+pub fn compute_initial_sqrt_price(
+    _ctx: Context<ComputeInitialSqrtPrice>,
+    price_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Result<InitialSqrtPrice> {
+    let sqrt_price_x64 = price_to_sqrt_price_x64(price_x64, decimals_0, decimals_1)?;
+    let tick = tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?;
+    #[cfg(feature = "enable-log")]
+    msg!("sqrt_price_x64:{}, tick:{}", sqrt_price_x64, tick);
+    Ok(InitialSqrtPrice {
+        sqrt_price_x64,
+        tick,
+    })
+}
+
+#[cfg(test)]
+mod compute_initial_sqrt_price_test {
+    use super::*;
+
+    #[test]
+    fn same_decimals_test() {
+        // price 1 is sqrt price 1
+        let sqrt_price_x64 = price_to_sqrt_price_x64(fixed_point_64::Q64, 6, 6).unwrap();
+        assert_eq!(sqrt_price_x64, fixed_point_64::Q64);
+        assert_eq!(
+            tick_math::get_tick_at_sqrt_price(sqrt_price_x64).unwrap(),
+            0
+        );
+
+        // price 4 is sqrt price 2
+        let sqrt_price_x64 = price_to_sqrt_price_x64(fixed_point_64::Q64 * 4, 9, 9).unwrap();
+        assert_eq!(sqrt_price_x64, fixed_point_64::Q64 * 2);
+    }
+
+    #[test]
+    fn different_decimals_test() {
+        // 1 token_0 with 9 decimals for 100 token_1 with 6 decimals,
+        // the raw price is 100 * 10^6 / 10^9 = 0.1
+        let sqrt_price_x64 = price_to_sqrt_price_x64(fixed_point_64::Q64 * 100, 9, 6).unwrap();
+        let expected = (0.1f64.sqrt() * fixed_point_64::Q64 as f64) as u128;
+        assert!(sqrt_price_x64.abs_diff(expected) < 1 << 12);
+
+        // the reverse pair, the raw price is 100 * 10^9 / 10^6 = 100_000
+        let sqrt_price_x64 = price_to_sqrt_price_x64(fixed_point_64::Q64 * 100, 6, 9).unwrap();
+        let expected = (100_000f64.sqrt() * fixed_point_64::Q64 as f64) as u128;
+        assert!(sqrt_price_x64.abs_diff(expected) < 1 << 20);
+    }
+
+    #[test]
+    fn out_of_range_test() {
+        // zero price
+        assert_eq!(
+            price_to_sqrt_price_x64(0, 6, 6).unwrap_err(),
+            ErrorCode::SqrtPriceX64.into()
+        );
+        // the decimals push the raw price below the min price
+        assert_eq!(
+            price_to_sqrt_price_x64(1, 18, 0).unwrap_err(),
+            ErrorCode::SqrtPriceX64.into()
+        );
+        // the decimals push the raw price above the max price
+        assert_eq!(
+            price_to_sqrt_price_x64(u128::MAX, 0, 18).unwrap_err(),
+            ErrorCode::SqrtPriceX64.into()
+        );
+        // overflow
+        assert_eq!(
+            price_to_sqrt_price_x64(u128::MAX, 0, 255).unwrap_err(),
+            ErrorCode::SqrtPriceX64.into()
+        );
+    }
+}
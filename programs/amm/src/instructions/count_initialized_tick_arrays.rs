@@ -0,0 +1,22 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CountInitializedTickArrays<'info> {
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+pub fn count_initialized_tick_arrays(
+    ctx: Context<CountInitializedTickArrays>,
+    word_pos: u8,
+) -> Result<u32> {
+    let count = ctx
+        .accounts
+        .pool_state
+        .load()?
+        .initialized_tick_array_count(word_pos)?;
+    #[cfg(feature = "enable-log")]
+    msg!("word_pos:{}, initialized tick arrays:{}", word_pos, count);
+    Ok(count)
+}
@@ -2,6 +2,10 @@ use crate::error::ErrorCode;
 use crate::states::*;
 use crate::{libraries::tick_math, util};
 use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+};
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 // use solana_program::{program::invoke_signed, system_instruction};
 #[derive(Accounts)]
@@ -100,54 +104,579 @@ pub struct CreatePool<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn create_pool(ctx: Context<CreatePool>, sqrt_price_x64: u128, open_time: u64) -> Result<()> {
-    if !(util::is_supported_mint(&ctx.accounts.token_mint_0).unwrap()
-        && util::is_supported_mint(&ctx.accounts.token_mint_1).unwrap())
+#[derive(Accounts)]
+pub struct CreatePoolWithVaults<'info> {
+    /// Address paying to create the pool. Can be anyone
+    #[account(mut)]
+    pub pool_creator: Signer<'info>,
+
+    /// Which config the pool belongs to.
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Initialize an account to store the pool state
+    #[account(
+        init,
+        seeds = [
+            POOL_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+            token_mint_0.key().as_ref(),
+            token_mint_1.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        space = PoolState::LEN
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Token_0 mint, the key must grater then token_1 mint.
+    #[account(
+        constraint = token_mint_0.key() < token_mint_1.key(),
+        mint::token_program = token_program_0
+    )]
+    pub token_mint_0: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token_1 mint
+    #[account(
+        mint::token_program = token_program_1
+    )]
+    pub token_mint_1: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token_0 vault for the pool, created by the caller as an empty token_0 account owned by the pool
+    #[account(
+        token::token_program = token_program_0,
+    )]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token_1 vault for the pool, created by the caller as an empty token_1 account owned by the pool
+    #[account(
+        token::token_program = token_program_1,
+    )]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Initialize an account to store oracle observations, the account must be created off-chain, constract will initialzied it
+    #[account(mut)]
+    pub observation_state: UncheckedAccount<'info>,
+
+    /// Initialize an account to store if a tick array is initialized.
+    #[account(
+        init,
+        seeds = [
+            POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        space = TickArrayBitmapExtension::LEN
+    )]
+    pub tick_array_bitmap: AccountLoader<'info, TickArrayBitmapExtension>,
+
+    /// Spl token program or token program 2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+    /// Spl token program or token program 2022
+    pub token_program_1: Interface<'info, TokenInterface>,
+    /// To create a new program account
+    pub system_program: Program<'info, System>,
+    /// Sysvar for program account
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Sets up the pool, its observation and bitmap extension once the vaults exist
+#[allow(clippy::too_many_arguments)]
+fn initialize_pool<'info>(
+    pool_creator: &Signer<'info>,
+    amm_config: &Account<'info, AmmConfig>,
+    pool_state_loader: &AccountLoader<'info, PoolState>,
+    bump: u8,
+    token_mint_0: &InterfaceAccount<'info, Mint>,
+    token_mint_1: &InterfaceAccount<'info, Mint>,
+    token_vault_0: Pubkey,
+    token_vault_1: Pubkey,
+    observation_state: &UncheckedAccount<'info>,
+    tick_array_bitmap: &AccountLoader<'info, TickArrayBitmapExtension>,
+    sqrt_price_x64: u128,
+    open_time: u64,
+) -> Result<()> {
+    amm_config.check_enabled()?;
+    if !(util::is_supported_mint(token_mint_0).unwrap()
+        && util::is_supported_mint(token_mint_1).unwrap())
     {
         return err!(ErrorCode::NotSupportMint);
     }
-    let pool_id = ctx.accounts.pool_state.key();
-    let mut pool_state = ctx.accounts.pool_state.load_init()?;
+    let pool_id = pool_state_loader.key();
+    let mut pool_state = pool_state_loader.load_init()?;
 
     let tick = tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?;
+    check_initial_tick(tick, amm_config.tick_spacing)?;
     #[cfg(feature = "enable-log")]
     msg!(
         "create pool, init_price: {}, init_tick:{}",
         sqrt_price_x64,
         tick
     );
+    let created_at = block_timestamp();
     // init observation
-    ObservationState::initialize(ctx.accounts.observation_state.as_ref(), pool_id)?;
+    ObservationState::initialize(
+        observation_state.as_ref(),
+        pool_id,
+        created_at,
+        sqrt_price_x64,
+    )?;
 
-    let bump = ctx.bumps.pool_state;
     pool_state.initialize(
         bump,
         sqrt_price_x64,
         open_time,
         tick,
-        ctx.accounts.pool_creator.key(),
-        ctx.accounts.token_vault_0.key(),
-        ctx.accounts.token_vault_1.key(),
-        ctx.accounts.amm_config.as_ref(),
-        ctx.accounts.token_mint_0.as_ref(),
-        ctx.accounts.token_mint_1.as_ref(),
-        ctx.accounts.observation_state.key(),
+        pool_creator.key(),
+        token_vault_0,
+        token_vault_1,
+        amm_config,
+        token_mint_0,
+        token_mint_1,
+        observation_state.key(),
+        u64::from(created_at),
     )?;
 
-    ctx.accounts
-        .tick_array_bitmap
-        .load_init()?
-        .initialize(pool_id);
+    tick_array_bitmap.load_init()?.initialize(pool_id);
 
     emit!(PoolCreatedEvent {
-        token_mint_0: ctx.accounts.token_mint_0.key(),
-        token_mint_1: ctx.accounts.token_mint_1.key(),
-        tick_spacing: ctx.accounts.amm_config.tick_spacing,
-        pool_state: ctx.accounts.pool_state.key(),
+        token_mint_0: token_mint_0.key(),
+        token_mint_1: token_mint_1.key(),
+        tick_spacing: amm_config.tick_spacing,
+        pool_state: pool_id,
         sqrt_price_x64,
         tick,
-        token_vault_0: ctx.accounts.token_vault_0.key(),
-        token_vault_1: ctx.accounts.token_vault_1.key(),
+        token_vault_0,
+        token_vault_1,
     });
     Ok(())
 }
+
+pub fn create_pool(ctx: Context<CreatePool>, sqrt_price_x64: u128, open_time: u64) -> Result<()> {
+    initialize_pool(
+        &ctx.accounts.pool_creator,
+        &ctx.accounts.amm_config,
+        &ctx.accounts.pool_state,
+        ctx.bumps.pool_state,
+        &ctx.accounts.token_mint_0,
+        &ctx.accounts.token_mint_1,
+        ctx.accounts.token_vault_0.key(),
+        ctx.accounts.token_vault_1.key(),
+        &ctx.accounts.observation_state,
+        &ctx.accounts.tick_array_bitmap,
+        sqrt_price_x64,
+        open_time,
+    )
+}
+
+/// Ensures a vault created by the caller is an empty token account of `token_mint` owned by the pool,
+/// with no delegate or close authority left by whoever created it. The only extensions allowed are
+/// `ImmutableOwner` and the `TransferFeeAmount` a transfer fee mint requires, others such as
+/// `CpiGuard` or `MemoTransfer` could block the transfers the pool signs.
+pub fn check_pool_vault(
+    token_vault_data: &[u8],
+    token_mint: Pubkey,
+    pool_id: Pubkey,
+) -> Result<()> {
+    let token_vault =
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(token_vault_data)
+            .map_err(|_| ErrorCode::InvalidPoolVault)?;
+    let extension_types = token_vault
+        .get_extension_types()
+        .map_err(|_| ErrorCode::InvalidPoolVault)?;
+    require!(
+        token_vault.base.mint == token_mint
+            && token_vault.base.owner == pool_id
+            && token_vault.base.amount == 0
+            && token_vault.base.delegate.is_none()
+            && token_vault.base.close_authority.is_none()
+            && extension_types.iter().all(|extension_type| {
+                *extension_type == ExtensionType::ImmutableOwner
+                    || *extension_type == ExtensionType::TransferFeeAmount
+            }),
+        ErrorCode::InvalidPoolVault
+    );
+    Ok(())
+}
+
+/// `create_pool` with vaults the caller created, so a program creating the pool by CPI
+/// controls the creation of every account but the pool's own
+pub fn create_pool_with_vaults(
+    ctx: Context<CreatePoolWithVaults>,
+    sqrt_price_x64: u128,
+    open_time: u64,
+) -> Result<()> {
+    let pool_id = ctx.accounts.pool_state.key();
+    check_pool_vault(
+        &ctx.accounts
+            .token_vault_0
+            .to_account_info()
+            .try_borrow_data()?,
+        ctx.accounts.token_mint_0.key(),
+        pool_id,
+    )?;
+    check_pool_vault(
+        &ctx.accounts
+            .token_vault_1
+            .to_account_info()
+            .try_borrow_data()?,
+        ctx.accounts.token_mint_1.key(),
+        pool_id,
+    )?;
+    initialize_pool(
+        &ctx.accounts.pool_creator,
+        &ctx.accounts.amm_config,
+        &ctx.accounts.pool_state,
+        ctx.bumps.pool_state,
+        &ctx.accounts.token_mint_0,
+        &ctx.accounts.token_mint_1,
+        ctx.accounts.token_vault_0.key(),
+        ctx.accounts.token_vault_1.key(),
+        &ctx.accounts.observation_state,
+        &ctx.accounts.tick_array_bitmap,
+        sqrt_price_x64,
+        open_time,
+    )
+}
+
+/// Ensures a pool created at `tick` leaves at least one tick spacing of usable ticks on both sides,
+/// so positions can be opened around its initial price
+pub fn check_initial_tick(tick: i32, tick_spacing: u16) -> Result<()> {
+    let tick_spacing = i32::from(tick_spacing);
+    let max_usable_tick = tick_math::MAX_TICK / tick_spacing * tick_spacing;
+    let min_usable_tick = -max_usable_tick;
+    require!(
+        tick - tick_spacing >= min_usable_tick && tick + tick_spacing <= max_usable_tick,
+        ErrorCode::InitialPriceOutOfUsableRange
+    );
+    Ok(())
+}
+
+/// The sqrt price to create a pool at `tick` with, the tick must be a multiple of `tick_spacing`
+/// within the tick boundaries
+pub fn get_initial_sqrt_price_at_tick(tick: i32, tick_spacing: u16) -> Result<u128> {
+    if TickState::check_is_out_of_boundary(tick) {
+        return err!(ErrorCode::InvaildTickIndex);
+    }
+    require!(
+        tick % i32::from(tick_spacing) == 0,
+        ErrorCode::TickAndSpacingNotMatch
+    );
+    tick_math::get_sqrt_price_at_tick(tick)
+}
+
+pub fn create_pool_at_tick(
+    ctx: Context<CreatePool>,
+    initial_tick: i32,
+    open_time: u64,
+) -> Result<()> {
+    let sqrt_price_x64 =
+        get_initial_sqrt_price_at_tick(initial_tick, ctx.accounts.amm_config.tick_spacing)?;
+    create_pool(ctx, sqrt_price_x64, open_time)
+}
+
+#[cfg(test)]
+mod create_pool_test {
+    use super::*;
+    use crate::util::program_test::{process_instruction, program_error, TestAccount};
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+    use solana_program::{program_option::COption, system_program};
+    use spl_token_2022::extension::{
+        cpi_guard::CpiGuard, immutable_owner::ImmutableOwner, memo_transfer::MemoTransfer,
+        StateWithExtensionsMut,
+    };
+
+    fn build_token_account(
+        mint: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        delegate: Option<Pubkey>,
+        close_authority: Option<Pubkey>,
+        extension_types: &[ExtensionType],
+    ) -> Vec<u8> {
+        let mut data = vec![
+            0u8;
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+                extension_types
+            )
+            .unwrap()
+        ];
+        let mut token_account =
+            StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(
+                &mut data,
+            )
+            .unwrap();
+        token_account.base = spl_token_2022::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: delegate.into(),
+            state: spl_token_2022::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: close_authority.into(),
+        };
+        token_account.pack_base();
+        token_account.init_account_type().unwrap();
+        for extension_type in extension_types {
+            match extension_type {
+                ExtensionType::ImmutableOwner => {
+                    token_account
+                        .init_extension::<ImmutableOwner>(true)
+                        .unwrap();
+                }
+                ExtensionType::CpiGuard => {
+                    token_account
+                        .init_extension::<CpiGuard>(true)
+                        .unwrap()
+                        .lock_cpi = true.into();
+                }
+                ExtensionType::MemoTransfer => {
+                    token_account
+                        .init_extension::<MemoTransfer>(true)
+                        .unwrap()
+                        .require_incoming_transfer_memos = true.into();
+                }
+                _ => token_account
+                    .init_account_extension_from_type(*extension_type)
+                    .unwrap(),
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn check_pool_vault_test() {
+        let pool_id = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        // an empty vault of the mint the caller created for the pool
+        for extension_types in [
+            vec![],
+            vec![ExtensionType::ImmutableOwner],
+            vec![
+                ExtensionType::ImmutableOwner,
+                ExtensionType::TransferFeeAmount,
+            ],
+        ] {
+            let token_vault =
+                build_token_account(token_mint, pool_id, 0, None, None, &extension_types);
+            assert!(check_pool_vault(&token_vault, token_mint, pool_id).is_ok());
+        }
+
+        let other = Pubkey::new_unique();
+        for token_vault in [
+            // the vault of another mint, e.g. the vaults swapped
+            build_token_account(other, pool_id, 0, None, None, &[]),
+            // owned by the caller rather than the pool
+            build_token_account(token_mint, other, 0, None, None, &[]),
+            // already funded
+            build_token_account(token_mint, pool_id, 1, None, None, &[]),
+            // authorities the caller kept before handing the vault to the pool
+            build_token_account(token_mint, pool_id, 0, Some(other), None, &[]),
+            build_token_account(token_mint, pool_id, 0, None, Some(other), &[]),
+            // extensions blocking the transfers the pool signs
+            build_token_account(
+                token_mint,
+                pool_id,
+                0,
+                None,
+                None,
+                &[ExtensionType::CpiGuard],
+            ),
+            build_token_account(
+                token_mint,
+                pool_id,
+                0,
+                None,
+                None,
+                &[ExtensionType::ImmutableOwner, ExtensionType::MemoTransfer],
+            ),
+        ] {
+            assert_eq!(
+                check_pool_vault(&token_vault, token_mint, pool_id).unwrap_err(),
+                ErrorCode::InvalidPoolVault.into()
+            );
+        }
+    }
+
+    #[test]
+    fn create_pool_with_vaults_test() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (token_mint_0, token_mint_1) = (mint_a.min(mint_b), mint_a.max(mint_b));
+        let amm_config = AmmConfig {
+            tick_spacing: 60,
+            ..Default::default()
+        };
+        let amm_config_key = Pubkey::new_unique();
+        let pool_id = Pubkey::find_program_address(
+            &[
+                POOL_SEED.as_bytes(),
+                amm_config_key.as_ref(),
+                token_mint_0.as_ref(),
+                token_mint_1.as_ref(),
+            ],
+            &crate::id(),
+        )
+        .0;
+        let create_accounts = crate::accounts::CreatePoolWithVaults {
+            pool_creator: Pubkey::new_unique(),
+            amm_config: amm_config_key,
+            pool_state: pool_id,
+            token_mint_0,
+            token_mint_1,
+            token_vault_0: Pubkey::new_unique(),
+            token_vault_1: Pubkey::new_unique(),
+            observation_state: Pubkey::new_unique(),
+            tick_array_bitmap: TickArrayBitmapExtension::key(pool_id),
+            token_program_0: spl_token_2022::id(),
+            token_program_1: spl_token::id(),
+            system_program: system_program::id(),
+            rent: solana_program::sysvar::rent::id(),
+        };
+        // the token_0 vault is a token 2022 account with the extensions given
+        let build_accounts = |extension_types: &[ExtensionType]| {
+            vec![
+                TestAccount::wallet(create_accounts.pool_creator),
+                TestAccount::account(amm_config_key, &amm_config, AmmConfig::LEN),
+                TestAccount::uncreated(pool_id, PoolState::LEN),
+                TestAccount::mint(token_mint_0, spl_token_2022::id(), 6),
+                TestAccount::mint(token_mint_1, spl_token::id(), 6),
+                TestAccount::new(
+                    create_accounts.token_vault_0,
+                    spl_token_2022::id(),
+                    build_token_account(token_mint_0, pool_id, 0, None, None, extension_types),
+                ),
+                TestAccount::token_account(
+                    create_accounts.token_vault_1,
+                    spl_token::id(),
+                    token_mint_1,
+                    pool_id,
+                    0,
+                ),
+                TestAccount::new(
+                    create_accounts.observation_state,
+                    crate::id(),
+                    vec![0; ObservationState::LEN],
+                ),
+                TestAccount::uncreated(
+                    create_accounts.tick_array_bitmap,
+                    TickArrayBitmapExtension::LEN,
+                ),
+                TestAccount::program(spl_token_2022::id()),
+                TestAccount::program(spl_token::id()),
+                TestAccount::program(system_program::id()),
+                TestAccount::rent_sysvar(),
+            ]
+        };
+        let metas = create_accounts.to_account_metas(None);
+        let create_pool_with_vaults = || crate::instruction::CreatePoolWithVaults {
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(0).unwrap(),
+            open_time: 0,
+        };
+
+        for extension_types in [
+            vec![ExtensionType::CpiGuard],
+            vec![ExtensionType::ImmutableOwner, ExtensionType::MemoTransfer],
+        ] {
+            let mut accounts = build_accounts(&extension_types);
+            assert_eq!(
+                process_instruction(&mut accounts, metas.clone(), create_pool_with_vaults())
+                    .unwrap_err(),
+                program_error(ErrorCode::InvalidPoolVault)
+            );
+        }
+
+        let mut accounts = build_accounts(&[ExtensionType::ImmutableOwner]);
+        process_instruction(&mut accounts, metas.clone(), create_pool_with_vaults()).unwrap();
+        let pool_state: PoolState = accounts[2].load();
+        assert_eq!(pool_state.token_vault_0, create_accounts.token_vault_0);
+        assert_eq!(pool_state.token_vault_1, create_accounts.token_vault_1);
+
+        // a vault of another token program than its mint's
+        let mut accounts = build_accounts(&[]);
+        accounts[5].owner = spl_token::id();
+        assert_eq!(
+            process_instruction(&mut accounts, metas, create_pool_with_vaults()).unwrap_err(),
+            program_error(anchor_lang::error::ErrorCode::ConstraintTokenTokenProgram)
+        );
+    }
+
+    #[test]
+    fn initial_sqrt_price_at_tick_round_trip_test() {
+        let tick_spacing = 60;
+        for tick in [
+            -443580,
+            -120,
+            -60,
+            0,
+            60,
+            120,
+            443580,
+            tick_math::MIN_TICK / 60 * 60,
+            tick_math::MAX_TICK / 60 * 60,
+        ] {
+            let sqrt_price_x64 = get_initial_sqrt_price_at_tick(tick, tick_spacing).unwrap();
+            assert_eq!(
+                sqrt_price_x64,
+                tick_math::get_sqrt_price_at_tick(tick).unwrap()
+            );
+            // the pool is created exactly at the tick
+            assert_eq!(
+                tick_math::get_tick_at_sqrt_price(sqrt_price_x64).unwrap(),
+                tick
+            );
+        }
+    }
+
+    #[test]
+    fn initial_sqrt_price_at_invalid_tick_test() {
+        assert_eq!(
+            get_initial_sqrt_price_at_tick(61, 60).unwrap_err(),
+            ErrorCode::TickAndSpacingNotMatch.into()
+        );
+        assert_eq!(
+            get_initial_sqrt_price_at_tick(tick_math::MAX_TICK + 1, 1).unwrap_err(),
+            ErrorCode::InvaildTickIndex.into()
+        );
+        assert_eq!(
+            get_initial_sqrt_price_at_tick(tick_math::MIN_TICK - 1, 1).unwrap_err(),
+            ErrorCode::InvaildTickIndex.into()
+        );
+    }
+
+    #[test]
+    fn check_initial_tick_test() {
+        let tick_spacing = 60;
+        let max_usable_tick = tick_math::MAX_TICK / 60 * 60;
+        // just inside, one tick spacing of room on either side
+        for tick in [
+            -max_usable_tick + 60,
+            -max_usable_tick + 61,
+            0,
+            max_usable_tick - 61,
+            max_usable_tick - 60,
+        ] {
+            assert!(check_initial_tick(tick, tick_spacing).is_ok());
+        }
+        // at the boundaries, no position fits on one side
+        for tick in [
+            -max_usable_tick + 59,
+            -max_usable_tick,
+            max_usable_tick - 59,
+            max_usable_tick,
+            tick_math::get_tick_at_sqrt_price(tick_math::MIN_SQRT_PRICE_X64).unwrap(),
+            tick_math::get_tick_at_sqrt_price(tick_math::MAX_SQRT_PRICE_X64 - 1).unwrap(),
+        ] {
+            assert_eq!(
+                check_initial_tick(tick, tick_spacing).unwrap_err(),
+                ErrorCode::InitialPriceOutOfUsableRange.into()
+            );
+        }
+        // the smallest tick spacing
+        assert!(check_initial_tick(tick_math::MIN_TICK + 1, 1).is_ok());
+        assert!(check_initial_tick(tick_math::MIN_TICK, 1).is_err());
+        assert!(check_initial_tick(tick_math::MAX_TICK - 1, 1).is_ok());
+        assert!(check_initial_tick(tick_math::MAX_TICK, 1).is_err());
+    }
+}
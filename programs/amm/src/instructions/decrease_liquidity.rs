@@ -14,7 +14,7 @@ use std::ops::DerefMut;
 pub const DECREASE_MEMO_MSG: &'static [u8] = b"raydium_decrease";
 #[derive(Accounts)]
 pub struct DecreaseLiquidity<'info> {
-    /// The position owner or delegated authority
+    /// The position owner, who holds the position NFT, or the operator of the position
     pub nft_owner: Signer<'info>,
 
     /// The token account for the tokenized position
@@ -95,7 +95,7 @@ pub struct DecreaseLiquidity<'info> {
 
 #[derive(Accounts)]
 pub struct DecreaseLiquidityV2<'info> {
-    /// The position owner or delegated authority
+    /// The position owner, who holds the position NFT, or the operator of the position
     pub nft_owner: Signer<'info>,
 
     /// The token account for the tokenized position
@@ -251,6 +251,19 @@ pub fn decrease_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
     )
 }
 
+/// Slippage check of a decrease, the amounts received net of the transfer fees
+/// must reach the minimums, fails with `DecreaseSlippage`
+pub fn check_decrease_slippage(
+    amount_0: u64,
+    amount_1: u64,
+    amount_0_min: u64,
+    amount_1_min: u64,
+) -> Result<()> {
+    require_gte!(amount_0, amount_0_min, ErrorCode::DecreaseSlippage);
+    require_gte!(amount_1, amount_1_min, ErrorCode::DecreaseSlippage);
+    Ok(())
+}
+
 pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
     pool_state_loader: &'b AccountLoader<'info, PoolState>,
     protocol_position: &'b mut Box<Account<'info, ProtocolPositionState>>,
@@ -284,12 +297,20 @@ pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
     let remaining_collect_accounts = &mut Vec::new();
     {
         let pool_state = pool_state_loader.load()?;
+        pool_state.check_initialized()?;
         if !pool_state.get_status_by_bit(PoolStatusBitIndex::DecreaseLiquidity)
             && !pool_state.get_status_by_bit(PoolStatusBitIndex::CollectFee)
             && !pool_state.get_status_by_bit(PoolStatusBitIndex::CollectReward)
         {
             return err!(ErrorCode::NotApproved);
         }
+        pool_state.check_token_vaults(token_vault_0.key(), token_vault_1.key())?;
+        if liquidity > 0 {
+            personal_position.check_unlocked(
+                Clock::get()?.unix_timestamp as u64,
+                pool_state.position_min_duration,
+            )?;
+        }
         liquidity_before = pool_state.liquidity;
         pool_sqrt_price_x64 = pool_state.sqrt_price_x64;
         pool_tick_current = pool_state.tick_current;
@@ -360,19 +381,35 @@ pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
         latest_fees_owed_1
     );
     if liquidity > 0 {
-        require_gte!(
+        check_decrease_slippage(
             decrease_amount_0 - transfer_fee_0,
-            amount_0_min,
-            ErrorCode::PriceSlippageCheck
-        );
-        require_gte!(
             decrease_amount_1 - transfer_fee_1,
+            amount_0_min,
             amount_1_min,
-            ErrorCode::PriceSlippageCheck
-        );
+        )?;
+    }
+    // A vault short of the owed fees pays what it holds, the rest stays owed to the position
+    // and unclaimed in the pool. The decreased liquidity's amounts are always paid in full.
+    let transfer_amount_0 = util::clamp_collect_amount(
+        pool_state_loader.key(),
+        token_vault_0,
+        decrease_amount_0 + latest_fees_owed_0,
+    )
+    .max(decrease_amount_0);
+    let transfer_amount_1 = util::clamp_collect_amount(
+        pool_state_loader.key(),
+        token_vault_1,
+        decrease_amount_1 + latest_fees_owed_1,
+    )
+    .max(decrease_amount_1);
+    let fees_unpaid_0 = decrease_amount_0 + latest_fees_owed_0 - transfer_amount_0;
+    let fees_unpaid_1 = decrease_amount_1 + latest_fees_owed_1 - transfer_amount_1;
+    if fees_unpaid_0 > 0 || fees_unpaid_1 > 0 {
+        personal_position.keep_fees_owed(fees_unpaid_0, fees_unpaid_1);
+        let mut pool_state = pool_state_loader.load_mut()?;
+        pool_state.total_fees_claimed_token_0 -= fees_unpaid_0;
+        pool_state.total_fees_claimed_token_1 -= fees_unpaid_1;
     }
-    let transfer_amount_0 = decrease_amount_0 + latest_fees_owed_0;
-    let transfer_amount_1 = decrease_amount_1 + latest_fees_owed_1;
 
     let mut token_2022_program_opt: Option<AccountInfo> = None;
     if token_program_2022.is_some() {
@@ -422,8 +459,8 @@ pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
         liquidity,
         decrease_amount_0: decrease_amount_0,
         decrease_amount_1: decrease_amount_1,
-        fee_amount_0: latest_fees_owed_0,
-        fee_amount_1: latest_fees_owed_1,
+        fee_amount_0: latest_fees_owed_0 - fees_unpaid_0,
+        fee_amount_1: latest_fees_owed_1 - fees_unpaid_1,
         reward_amounts,
         transfer_fee_0: transfer_fee_0,
         transfer_fee_1: transfer_fee_1,
@@ -481,20 +518,16 @@ pub fn decrease_liquidity_and_update_position<'a, 'b, 'c: 'info, 'info>(
     let mut latest_fees_owed_0 = 0;
     let mut latest_fees_owed_1 = 0;
     if pool_state.get_status_by_bit(PoolStatusBitIndex::CollectFee) {
-        latest_fees_owed_0 = personal_position.token_fees_owed_0;
-        latest_fees_owed_1 = personal_position.token_fees_owed_1;
-
         require_gte!(
             pool_state.total_fees_token_0 - pool_state.total_fees_claimed_token_0,
-            latest_fees_owed_0
+            personal_position.token_fees_owed_0
         );
         require_gte!(
             pool_state.total_fees_token_1 - pool_state.total_fees_claimed_token_1,
-            latest_fees_owed_1
+            personal_position.token_fees_owed_1
         );
 
-        personal_position.token_fees_owed_0 = 0;
-        personal_position.token_fees_owed_1 = 0;
+        (latest_fees_owed_0, latest_fees_owed_1) = personal_position.collect_fees();
 
         pool_state.total_fees_claimed_token_0 = pool_state
             .total_fees_claimed_token_0
@@ -542,38 +575,60 @@ pub fn burn_liquidity<'c: 'info, 'info>(
         clock.unix_timestamp as u64,
     )?;
 
-    // update tick_state
-    tick_array_lower_loader.load_mut()?.update_tick_state(
-        protocol_position.tick_lower_index,
-        pool_state.tick_spacing,
-        tick_lower_state,
-    )?;
-    tick_array_upper_loader.load_mut()?.update_tick_state(
-        protocol_position.tick_upper_index,
-        pool_state.tick_spacing,
-        tick_upper_state,
-    )?;
+    // the compute units logged before and after the tick update measure the single tick array
+    // path against the two tick arrays one
+    #[cfg(feature = "enable-log")]
+    anchor_lang::solana_program::log::sol_log_compute_units();
+    if tick_array_lower_loader.key() == tick_array_upper_loader.key() {
+        // both ticks in one tick array, common for positions one tick spacing wide
+        let mut tick_array = tick_array_lower_loader.load_mut()?;
+        if tick_array.update_position_tick_states(
+            pool_state.tick_spacing,
+            tick_lower_state,
+            tick_upper_state,
+            flip_tick_lower,
+            flip_tick_upper,
+            false,
+        )? {
+            pool_state
+                .flip_tick_array_bit(tickarray_bitmap_extension, tick_array.start_tick_index)?;
+        }
+    } else {
+        // update tick_state
+        tick_array_lower_loader.load_mut()?.update_tick_state(
+            protocol_position.tick_lower_index,
+            pool_state.tick_spacing,
+            tick_lower_state,
+        )?;
+        tick_array_upper_loader.load_mut()?.update_tick_state(
+            protocol_position.tick_upper_index,
+            pool_state.tick_spacing,
+            tick_upper_state,
+        )?;
 
-    if flip_tick_lower {
-        let mut tick_array_lower = tick_array_lower_loader.load_mut()?;
-        tick_array_lower.update_initialized_tick_count(false)?;
-        if tick_array_lower.initialized_tick_count == 0 {
-            pool_state.flip_tick_array_bit(
-                tickarray_bitmap_extension,
-                tick_array_lower.start_tick_index,
-            )?;
+        if flip_tick_lower {
+            let mut tick_array_lower = tick_array_lower_loader.load_mut()?;
+            tick_array_lower.update_initialized_tick_count(false)?;
+            if tick_array_lower.initialized_tick_count == 0 {
+                pool_state.flip_tick_array_bit(
+                    tickarray_bitmap_extension,
+                    tick_array_lower.start_tick_index,
+                )?;
+            }
         }
-    }
-    if flip_tick_upper {
-        let mut tick_array_upper = tick_array_upper_loader.load_mut()?;
-        tick_array_upper.update_initialized_tick_count(false)?;
-        if tick_array_upper.initialized_tick_count == 0 {
-            pool_state.flip_tick_array_bit(
-                tickarray_bitmap_extension,
-                tick_array_upper.start_tick_index,
-            )?;
+        if flip_tick_upper {
+            let mut tick_array_upper = tick_array_upper_loader.load_mut()?;
+            tick_array_upper.update_initialized_tick_count(false)?;
+            if tick_array_upper.initialized_tick_count == 0 {
+                pool_state.flip_tick_array_bit(
+                    tickarray_bitmap_extension,
+                    tick_array_upper.start_tick_index,
+                )?;
+            }
         }
     }
+    #[cfg(feature = "enable-log")]
+    anchor_lang::solana_program::log::sol_log_compute_units();
 
     emit!(LiquidityChangeEvent {
         pool_state: pool_state.key(),
@@ -720,3 +775,226 @@ pub fn check_unclaimed_fees_and_vault(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod decrease_liquidity_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::{build_pool, build_pool_accounts};
+    use crate::states::tick_array_test::{build_tick, build_tick_array_with_tick_states};
+    use crate::util::program_test::{process_instruction, program_error, TestAccount};
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+
+    /// The accounts of a position from tick -60 to 60 owing `fees_owed_0` of token_0 on a pool
+    /// without fee growth whose vaults hold `vault_amount`, and the accounts of a
+    /// `decrease_liquidity` by the NFT holder
+    fn build_decrease_accounts(
+        fees_owed_0: u64,
+        vault_amount: u64,
+    ) -> (Vec<TestAccount>, crate::accounts::DecreaseLiquidity) {
+        let pool_state = build_pool(0, 60, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        pool_state.fee_growth_global_0_x64 = 0;
+        pool_state.fee_growth_global_1_x64 = 0;
+        pool_state.total_fees_token_0 = fees_owed_0;
+        let mut accounts = build_pool_accounts(pool_state, vault_amount);
+        let pool_id = pool_state.key();
+
+        let personal_position = PersonalPositionState {
+            nft_mint: Pubkey::new_unique(),
+            pool_id,
+            tick_lower_index: -60,
+            tick_upper_index: 60,
+            liquidity: 1_000,
+            token_fees_owed_0: fees_owed_0,
+            ..Default::default()
+        };
+        let protocol_position = ProtocolPositionState {
+            pool_id,
+            tick_lower_index: -60,
+            tick_upper_index: 60,
+            liquidity: 1_000,
+            ..Default::default()
+        };
+        let tick_array_lower = build_tick_array_with_tick_states(
+            pool_id,
+            -3600,
+            60,
+            vec![build_tick(-60, 1_000, 1_000).into_inner()],
+        );
+        let tick_array_upper = build_tick_array_with_tick_states(
+            pool_id,
+            0,
+            60,
+            vec![build_tick(60, 1_000, -1_000).into_inner()],
+        );
+
+        let decrease_accounts = crate::accounts::DecreaseLiquidity {
+            nft_owner: Pubkey::new_unique(),
+            nft_account: Pubkey::new_unique(),
+            personal_position: tokenized_position_pda(personal_position.nft_mint).0,
+            pool_state: pool_id,
+            protocol_position: protocol_position_pda(pool_id, -60, 60).0,
+            token_vault_0: pool_state.token_vault_0,
+            token_vault_1: pool_state.token_vault_1,
+            tick_array_lower: Pubkey::new_unique(),
+            tick_array_upper: Pubkey::new_unique(),
+            recipient_token_account_0: Pubkey::new_unique(),
+            recipient_token_account_1: Pubkey::new_unique(),
+            token_program: spl_token::id(),
+        };
+        accounts.extend([
+            TestAccount::wallet(decrease_accounts.nft_owner),
+            TestAccount::token_account(
+                decrease_accounts.nft_account,
+                spl_token::id(),
+                personal_position.nft_mint,
+                decrease_accounts.nft_owner,
+                1,
+            ),
+            TestAccount::account(
+                decrease_accounts.personal_position,
+                &personal_position,
+                PersonalPositionState::LEN,
+            ),
+            TestAccount::account(
+                decrease_accounts.protocol_position,
+                &protocol_position,
+                ProtocolPositionState::LEN,
+            ),
+            TestAccount::zero_copy(
+                decrease_accounts.tick_array_lower,
+                &*tick_array_lower.borrow(),
+            ),
+            TestAccount::zero_copy(
+                decrease_accounts.tick_array_upper,
+                &*tick_array_upper.borrow(),
+            ),
+            TestAccount::token_account(
+                decrease_accounts.recipient_token_account_0,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                decrease_accounts.nft_owner,
+                0,
+            ),
+            TestAccount::token_account(
+                decrease_accounts.recipient_token_account_1,
+                spl_token::id(),
+                pool_state.token_mint_1,
+                decrease_accounts.nft_owner,
+                0,
+            ),
+            TestAccount::program(spl_token::id()),
+        ]);
+        (accounts, decrease_accounts)
+    }
+
+    fn find_account(accounts: &[TestAccount], key: Pubkey) -> &TestAccount {
+        accounts.iter().find(|account| account.key == key).unwrap()
+    }
+
+    fn collect_fees() -> crate::instruction::DecreaseLiquidity {
+        crate::instruction::DecreaseLiquidity {
+            liquidity: 0,
+            amount_0_min: 0,
+            amount_1_min: 0,
+        }
+    }
+
+    #[test]
+    fn decrease_liquidity_vault_shortfall_test() {
+        let (mut accounts, decrease_accounts) = build_decrease_accounts(1_500, 1_000);
+        let metas = decrease_accounts.to_account_metas(None);
+        process_instruction(&mut accounts, metas.clone(), collect_fees()).unwrap();
+
+        // the vault pays what it holds, the rest stays owed to the position and unclaimed
+        assert_eq!(
+            find_account(&accounts, decrease_accounts.recipient_token_account_0).token_amount(),
+            1_000
+        );
+        let personal_position: PersonalPositionState =
+            find_account(&accounts, decrease_accounts.personal_position).state();
+        assert_eq!(personal_position.token_fees_owed_0, 500);
+        assert_eq!(personal_position.fees_collected_0, 1_000);
+        let pool_state: PoolState = find_account(&accounts, decrease_accounts.pool_state).load();
+        assert_eq!({ pool_state.total_fees_claimed_token_0 }, 1_000);
+
+        // collected in full once the vault holds enough again
+        let vault_0 = accounts
+            .iter_mut()
+            .find(|account| account.key == decrease_accounts.token_vault_0)
+            .unwrap();
+        *vault_0 = TestAccount::token_account(
+            vault_0.key,
+            spl_token::id(),
+            pool_state.token_mint_0,
+            decrease_accounts.pool_state,
+            500,
+        );
+        process_instruction(&mut accounts, metas, collect_fees()).unwrap();
+        assert_eq!(
+            find_account(&accounts, decrease_accounts.recipient_token_account_0).token_amount(),
+            1_500
+        );
+        let personal_position: PersonalPositionState =
+            find_account(&accounts, decrease_accounts.personal_position).state();
+        assert_eq!(personal_position.token_fees_owed_0, 0);
+        assert_eq!(personal_position.fees_collected_0, 1_500);
+        let pool_state: PoolState = find_account(&accounts, decrease_accounts.pool_state).load();
+        assert_eq!({ pool_state.total_fees_claimed_token_0 }, 1_500);
+    }
+
+    #[test]
+    fn check_decrease_slippage_test() {
+        assert!(check_decrease_slippage(1_000, 2_000, 1_000, 2_000).is_ok());
+        for (amount_0, amount_1) in [(999, 2_000), (1_000, 1_999)] {
+            assert_eq!(
+                check_decrease_slippage(amount_0, amount_1, 1_000, 2_000).unwrap_err(),
+                ErrorCode::DecreaseSlippage.into()
+            );
+        }
+    }
+
+    #[test]
+    fn decrease_liquidity_stale_operator_test() {
+        let (mut accounts, mut decrease_accounts) = build_decrease_accounts(0, 0);
+        let (holder, operator) = (decrease_accounts.nft_owner, Pubkey::new_unique());
+        accounts.push(TestAccount::wallet(operator));
+        let set_operator_metas = crate::accounts::SetPositionOperator {
+            nft_owner: holder,
+            nft_account: decrease_accounts.nft_account,
+            personal_position: decrease_accounts.personal_position,
+        }
+        .to_account_metas(None);
+        process_instruction(
+            &mut accounts,
+            set_operator_metas,
+            crate::instruction::SetPositionOperator {
+                operator: Some(operator),
+            },
+        )
+        .unwrap();
+
+        // the operator acts for the holder who approved it
+        decrease_accounts.nft_owner = operator;
+        let metas = decrease_accounts.to_account_metas(None);
+        process_instruction(&mut accounts, metas.clone(), collect_fees()).unwrap();
+
+        // the NFT is transferred, the operator approved by the former holder is revoked
+        let new_holder = Pubkey::new_unique();
+        let nft_mint = find_account(&accounts, decrease_accounts.personal_position)
+            .state::<PersonalPositionState>()
+            .nft_mint;
+        let nft_account = accounts
+            .iter_mut()
+            .find(|account| account.key == decrease_accounts.nft_account)
+            .unwrap();
+        *nft_account =
+            TestAccount::token_account(nft_account.key, spl_token::id(), nft_mint, new_holder, 1);
+        assert_eq!(
+            process_instruction(&mut accounts, metas, collect_fees()).unwrap_err(),
+            program_error(ErrorCode::NotApproved)
+        );
+    }
+}
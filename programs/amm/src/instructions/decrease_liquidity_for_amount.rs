@@ -0,0 +1,173 @@
+use super::{decrease_liquidity, DecreaseLiquidityV2};
+use crate::libraries::{liquidity_math, tick_math};
+use crate::util::check_deadline;
+use anchor_lang::prelude::*;
+
+/// Gets the liquidity of a position to burn for `target_amount_0` of token_0 at the current price,
+/// rounded down so the burn frees at most the target. If the position doesn't hold more token_0
+/// than the target, its whole liquidity.
+pub fn get_liquidity_for_amount_0(
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    position_liquidity: u128,
+    target_amount_0: u64,
+) -> Result<u128> {
+    let sqrt_price_upper_x64 = tick_math::get_sqrt_price_at_tick(tick_upper)?;
+    // the position is all token_1 at or above its range
+    if sqrt_price_x64 >= sqrt_price_upper_x64 {
+        return Ok(position_liquidity);
+    }
+    let liquidity = liquidity_math::get_liquidity_from_single_amount_0(
+        sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(tick_lower)?,
+        sqrt_price_upper_x64,
+        target_amount_0,
+    );
+    Ok(liquidity.min(position_liquidity))
+}
+
+pub fn decrease_liquidity_for_amount_0<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityV2<'info>>,
+    target_amount_0: u64,
+    amount_1_min: u64,
+    deadline: i64,
+) -> Result<u64> {
+    check_deadline(
+        deadline,
+        ctx.accounts.pool_state.load()?.max_deadline_horizon,
+    )?;
+    let personal_position = &ctx.accounts.personal_position;
+    let (liquidity, amount_0) = {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        let liquidity = get_liquidity_for_amount_0(
+            pool_state.sqrt_price_x64,
+            personal_position.tick_lower_index,
+            personal_position.tick_upper_index,
+            personal_position.liquidity,
+            target_amount_0,
+        )?;
+        // the amount of token_0 the burn frees, as decrease_liquidity computes it
+        let (amount_0, _) = liquidity_math::get_delta_amounts_signed(
+            pool_state.tick_current,
+            pool_state.sqrt_price_x64,
+            personal_position.tick_lower_index,
+            personal_position.tick_upper_index,
+            -i128::try_from(liquidity).unwrap(),
+        )?;
+        (liquidity, amount_0)
+    };
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "target_amount_0:{}, liquidity:{}, amount_0:{}",
+        target_amount_0,
+        liquidity,
+        amount_0
+    );
+    decrease_liquidity(
+        &ctx.accounts.pool_state,
+        &mut ctx.accounts.protocol_position,
+        &mut ctx.accounts.personal_position,
+        &mut ctx.accounts.token_vault_0,
+        &mut ctx.accounts.token_vault_1,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &ctx.accounts.recipient_token_account_0,
+        &ctx.accounts.recipient_token_account_1,
+        &ctx.accounts.token_program,
+        Some(ctx.accounts.token_program_2022.clone()),
+        Some(ctx.accounts.memo_program.clone()),
+        Some(ctx.accounts.vault_0_mint.clone()),
+        Some(ctx.accounts.vault_1_mint.clone()),
+        ctx.remaining_accounts,
+        liquidity,
+        0,
+        amount_1_min,
+    )?;
+    Ok(amount_0)
+}
+
+#[cfg(test)]
+mod decrease_liquidity_for_amount_test {
+    use super::*;
+
+    /// The amount of token_0 burning `liquidity` of the position frees at `tick_current`
+    fn amount_0_for_liquidity(
+        tick_current: i32,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> u64 {
+        liquidity_math::get_delta_amounts_signed(
+            tick_current,
+            tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+            tick_lower,
+            tick_upper,
+            -(liquidity as i128),
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn partial_decrease_for_amount_0_test() {
+        let position_liquidity = 1_000_000_000_000;
+        let (tick_lower, tick_upper) = (-6000, 6000);
+        // in range and below the range, the position holds more token_0 than the target
+        for tick_current in [-12000, -6000, 0, 3000] {
+            let position_amount_0 =
+                amount_0_for_liquidity(tick_current, tick_lower, tick_upper, position_liquidity);
+            let target_amount_0 = position_amount_0 / 3;
+            let liquidity = get_liquidity_for_amount_0(
+                tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+                tick_lower,
+                tick_upper,
+                position_liquidity,
+                target_amount_0,
+            )
+            .unwrap();
+            assert!(liquidity < position_liquidity);
+            let amount_0 = amount_0_for_liquidity(tick_current, tick_lower, tick_upper, liquidity);
+            // at most the target, short of it only by rounding
+            assert!(amount_0 <= target_amount_0);
+            assert!(target_amount_0 - amount_0 <= 1);
+        }
+    }
+
+    #[test]
+    fn full_decrease_for_amount_0_test() {
+        let position_liquidity = 1_000_000_000_000;
+        let (tick_lower, tick_upper) = (-6000, 6000);
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let position_amount_0 =
+            amount_0_for_liquidity(0, tick_lower, tick_upper, position_liquidity);
+        // the position holds less token_0 than the target, all of it is withdrawn
+        for target_amount_0 in [position_amount_0 + 1, u64::MAX] {
+            assert_eq!(
+                get_liquidity_for_amount_0(
+                    sqrt_price_x64,
+                    tick_lower,
+                    tick_upper,
+                    position_liquidity,
+                    target_amount_0,
+                )
+                .unwrap(),
+                position_liquidity
+            );
+        }
+        // above the range the position holds only token_1
+        for tick_current in [6000, 12000] {
+            assert_eq!(
+                get_liquidity_for_amount_0(
+                    tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+                    tick_lower,
+                    tick_upper,
+                    position_liquidity,
+                    1,
+                )
+                .unwrap(),
+                position_liquidity
+            );
+        }
+    }
+}
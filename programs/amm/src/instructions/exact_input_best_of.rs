@@ -0,0 +1,226 @@
+use crate::error::ErrorCode;
+use crate::exact_output::{split_route_accounts, ROUTE_POOL_ACCOUNT_NUM};
+use crate::libraries::tick_math;
+use crate::states::*;
+use crate::swap::{check_input_authority, check_input_balance, check_vault_pair, SwapResult};
+use crate::swap::{compute_swap, load_swap_tick_arrays};
+use crate::swap_router_base_in::{route_base_in, SwapRouterBaseIn};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+/// The max number of candidate routes of one exact_input_best_of
+pub const MAX_ROUTE_NUM: usize = 4;
+
+/// A candidate route of exact_input_best_of
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteSpec {
+    /// The number of the accounts after the first `ROUTE_POOL_ACCOUNT_NUM` of each pool of
+    /// the route, its bitmap extension and tick arrays, the accounts of the route are laid out
+    /// as the remaining accounts of swap_router_base_in
+    pub additional_accounts_per_pool: Vec<u8>,
+}
+
+impl RouteSpec {
+    /// The number of remaining accounts of the route
+    pub fn account_num(&self) -> usize {
+        self.additional_accounts_per_pool
+            .iter()
+            .map(|additional_account_num| {
+                ROUTE_POOL_ACCOUNT_NUM + usize::from(*additional_account_num)
+            })
+            .sum()
+    }
+}
+
+/// Ensures at least one and at most `MAX_ROUTE_NUM` routes of at least one pool are given,
+/// and their accounts add up to the remaining accounts
+pub fn check_routes(routes: &[RouteSpec], remaining_account_num: usize) -> Result<()> {
+    require!(
+        !routes.is_empty()
+            && routes.len() <= MAX_ROUTE_NUM
+            && routes
+                .iter()
+                .all(|route| !route.additional_accounts_per_pool.is_empty())
+            && routes.iter().map(RouteSpec::account_num).sum::<usize>() == remaining_account_num,
+        ErrorCode::InvalidSwapInputNum
+    );
+    Ok(())
+}
+
+/// The index and output of the route with the most output, the first one on a tie.
+/// Routes that can't be filled are `None`.
+pub fn select_best_route(amounts_out: &[Option<u64>]) -> Option<(usize, u64)> {
+    amounts_out
+        .iter()
+        .enumerate()
+        .filter_map(|(index, amount_out)| amount_out.map(|amount_out| (index, amount_out)))
+        .fold(None, |best, (index, amount_out)| match best {
+            Some((_, best_amount_out)) if best_amount_out >= amount_out => best,
+            _ => Some((index, amount_out)),
+        })
+}
+
+/// Simulate swapping `amount_in` through the pools of the route without moving any token,
+/// returns the output, or `None` if a pool can't fill its hop with the given tick arrays.
+/// `pool_accounts` are the accounts of each pool as split by `split_route_accounts`.
+pub fn simulate_route_base_in<'c: 'info, 'info>(
+    pool_accounts: &[&'c [AccountInfo<'info>]],
+    mut input_token_mint: Pubkey,
+    amount_in: u64,
+) -> Result<Option<u64>> {
+    let mut amount = amount_in;
+    for accounts in pool_accounts.iter().copied() {
+        // the output token account, output token mint and observation state aren't used
+        // by the simulation
+        let amm_config = Account::<AmmConfig>::try_from(&accounts[0])?;
+        let pool_state_loader = AccountLoader::<PoolState>::try_from(&accounts[1])?;
+        let input_vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[3])?;
+        let output_vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[4])?;
+        let tick_array_accounts = &accounts[ROUTE_POOL_ACCOUNT_NUM..];
+
+        let pool_state = pool_state_loader.load()?;
+        require_keys_eq!(pool_state.amm_config, amm_config.key());
+        check_vault_pair(&pool_state, input_vault.mint, output_vault.mint)?;
+        require_keys_eq!(
+            input_vault.mint,
+            input_token_mint,
+            ErrorCode::InvalidVaultPair
+        );
+        let (tickarray_bitmap_extension, tick_array_loaders) =
+            load_swap_tick_arrays(pool_state_loader.key(), tick_array_accounts)?;
+        let tick_arrays = tick_array_loaders
+            .iter()
+            .map(|loader| loader.load())
+            .collect::<Result<Vec<_>>>()?;
+        let tick_array_states: VecDeque<_> = tick_arrays
+            .iter()
+            .map(|tick_array| tick_array.deref())
+            .collect();
+
+        let zero_for_one = input_vault.mint == pool_state.token_mint_0;
+        let (state, is_completed) = compute_swap(
+            &amm_config,
+            &pool_state,
+            &tick_array_states,
+            &tickarray_bitmap_extension,
+            amount,
+            if zero_for_one {
+                tick_math::MIN_SQRT_PRICE_X64 + 1
+            } else {
+                tick_math::MAX_SQRT_PRICE_X64 - 1
+            },
+            zero_for_one,
+            true,
+        )?;
+        if !is_completed || state.amount_specified_remaining != 0 {
+            return Ok(None);
+        }
+        amount = state.amount_calculated;
+        input_token_mint = output_vault.mint;
+    }
+    Ok(Some(amount))
+}
+
+pub fn exact_input_best_of<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseIn<'info>>,
+    amount_in: u64,
+    amount_out_minimum: u64,
+    routes: Vec<RouteSpec>,
+) -> Result<SwapResult> {
+    check_routes(&routes, ctx.remaining_accounts.len())?;
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    check_input_balance(&ctx.accounts.input_token_account, amount_in)?;
+
+    let mut route_accounts = Vec::with_capacity(routes.len());
+    let mut amounts_out = Vec::with_capacity(routes.len());
+    let mut accounts: &'c [AccountInfo<'info>] = ctx.remaining_accounts;
+    for route in routes.iter() {
+        let (accounts_of_route, next_accounts) = accounts.split_at(route.account_num());
+        accounts = next_accounts;
+        amounts_out.push(simulate_route_base_in(
+            &split_route_accounts(accounts_of_route, &route.additional_accounts_per_pool)?,
+            ctx.accounts.input_token_mint.key(),
+            amount_in,
+        )?);
+        route_accounts.push(accounts_of_route);
+    }
+    let (route_index, amount_out) =
+        select_best_route(&amounts_out).ok_or(ErrorCode::LiquidityInsufficient)?;
+    require_gte!(
+        amount_out,
+        amount_out_minimum,
+        ErrorCode::TooLittleOutputReceived
+    );
+    emit!(RouteSelectedEvent {
+        payer: ctx.accounts.payer.key(),
+        route_index: route_index as u8,
+        amount_in,
+        amount_out,
+    });
+
+    // only the best route is executed, its slippage is checked again on execution
+    route_base_in(
+        ctx.accounts,
+        route_accounts[route_index],
+        amount_in,
+        amount_out_minimum,
+    )
+}
+
+#[cfg(test)]
+mod exact_input_best_of_test {
+    use super::*;
+
+    #[test]
+    fn check_routes_test() {
+        // a route of one pool with two tick arrays and one of two pools, the second one
+        // with the bitmap extension and three tick arrays
+        let routes = vec![
+            RouteSpec {
+                additional_accounts_per_pool: vec![2],
+            },
+            RouteSpec {
+                additional_accounts_per_pool: vec![1, 4],
+            },
+        ];
+        assert_eq!(routes[0].account_num(), 9);
+        assert_eq!(routes[1].account_num(), 19);
+        assert!(check_routes(&routes, 28).is_ok());
+        // the accounts don't add up, e.g. a short slice
+        assert_eq!(
+            check_routes(&routes, 27).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+        assert_eq!(
+            check_routes(&[], 0).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+        // a route without pools
+        assert_eq!(
+            check_routes(&[RouteSpec::default()], 0).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+        let routes = vec![routes[0].clone(); MAX_ROUTE_NUM + 1];
+        assert_eq!(
+            check_routes(&routes, 9 * (MAX_ROUTE_NUM + 1)).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+    }
+
+    #[test]
+    fn select_best_route_test() {
+        assert_eq!(select_best_route(&[]), None);
+        assert_eq!(select_best_route(&[None, None]), None);
+        assert_eq!(
+            select_best_route(&[Some(100), None, Some(120), Some(90)]),
+            Some((2, 120))
+        );
+        // the first one on a tie
+        assert_eq!(
+            select_best_route(&[None, Some(120), Some(120)]),
+            Some((1, 120))
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::swap::{
+    check_input_authority, check_input_balance, check_min_swap_amount, check_vault_pair,
+    compute_swap, get_sqrt_price_limit_x64, load_swap_tick_arrays, SwapResult,
+};
+use crate::swap_v2::{exact_internal_v2, SwapSingleV2};
+use crate::util::check_deadline;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_interface::{Mint, Token2022, TokenAccount},
+};
+use std::collections::{HashSet, VecDeque};
+use std::ops::Deref;
+
+#[derive(Accounts)]
+pub struct SwapRouterBaseOut<'info> {
+    /// The user performing the swap
+    pub payer: Signer<'info>,
+
+    /// The token account that pays input tokens for the swap
+    #[account(mut)]
+    pub input_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The mint of input token
+    #[account(mut)]
+    pub input_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// SPL program for token transfers
+    pub token_program: Program<'info, Token>,
+    /// SPL program 2022 for token transfers
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK:
+    #[account(
+        address = spl_memo::id()
+    )]
+    pub memo_program: UncheckedAccount<'info>,
+}
+
+/// The accounts every pool of an exact output route starts with: the amm config, pool state,
+/// output token account, input vault, output vault, output token mint and observation state.
+/// The pool's bitmap extension and tick arrays follow.
+pub const ROUTE_POOL_ACCOUNT_NUM: usize = 7;
+
+/// Splits the remaining accounts into the accounts of each pool, `ROUTE_POOL_ACCOUNT_NUM`
+/// plus the pool's `additional_accounts_per_pool`, which must add up to all of them
+pub fn split_route_accounts<'a, T>(
+    accounts: &'a [T],
+    additional_accounts_per_pool: &[u8],
+) -> Result<Vec<&'a [T]>> {
+    require!(
+        !additional_accounts_per_pool.is_empty()
+            && additional_accounts_per_pool
+                .iter()
+                .map(|additional_account_num| {
+                    ROUTE_POOL_ACCOUNT_NUM + usize::from(*additional_account_num)
+                })
+                .sum::<usize>()
+                == accounts.len(),
+        ErrorCode::InvalidSwapInputNum
+    );
+    let mut pool_accounts = Vec::with_capacity(additional_accounts_per_pool.len());
+    let mut accounts = accounts;
+    for additional_account_num in additional_accounts_per_pool {
+        let (accounts_of_pool, next_accounts) =
+            accounts.split_at(ROUTE_POOL_ACCOUNT_NUM + usize::from(*additional_account_num));
+        pool_accounts.push(accounts_of_pool);
+        accounts = next_accounts;
+    }
+    Ok(pool_accounts)
+}
+
+/// The input of each of the `pool_num` hops of an exact output route. They are quoted back to
+/// front, the input of a hop is the output the hop before it must give, starting from
+/// `amount_out` at the last hop. Fails without any hop swapped if the route's input exceeds
+/// `amount_in_maximum`.
+pub fn get_route_amounts_in(
+    pool_num: usize,
+    amount_out: u64,
+    amount_in_maximum: u64,
+    mut quote_amount_in: impl FnMut(usize, u64) -> Result<u64>,
+) -> Result<Vec<u64>> {
+    let mut amounts_in = vec![0; pool_num];
+    let mut amount_out_internal = amount_out;
+    for hop in (0..pool_num).rev() {
+        amounts_in[hop] = quote_amount_in(hop, amount_out_internal)?;
+        amount_out_internal = amounts_in[hop];
+    }
+    require_gte!(
+        amount_in_maximum,
+        amount_out_internal,
+        ErrorCode::TooMuchInputPaid
+    );
+    Ok(amounts_in)
+}
+
+/// Ensures the route doesn't pass through a pool twice, a quote of the pool's second hop
+/// would be taken at the price before its first
+pub fn check_route_pools_unique(pool_ids: &[Pubkey]) -> Result<()> {
+    let mut unique_pool_ids = HashSet::with_capacity(pool_ids.len());
+    require!(
+        pool_ids
+            .iter()
+            .all(|pool_id| unique_pool_ids.insert(pool_id)),
+        ErrorCode::DuplicateRoutePool
+    );
+    Ok(())
+}
+
+/// Quote the input a pool takes to give `amount_out` without moving any token, returns the
+/// input and output token mints of the hop with the input
+pub fn quote_hop_amount_in<'c: 'info, 'info>(
+    pool_accounts: &'c [AccountInfo<'info>],
+    amount_out: u64,
+) -> Result<(Pubkey, Pubkey, u64)> {
+    let amm_config = Account::<AmmConfig>::try_from(&pool_accounts[0])?;
+    let pool_state_loader = AccountLoader::<PoolState>::try_from(&pool_accounts[1])?;
+    let input_vault = InterfaceAccount::<TokenAccount>::try_from(&pool_accounts[3])?;
+    let output_vault = InterfaceAccount::<TokenAccount>::try_from(&pool_accounts[4])?;
+
+    let pool_state = pool_state_loader.load()?;
+    require_keys_eq!(pool_state.amm_config, amm_config.key());
+    check_vault_pair(&pool_state, input_vault.mint, output_vault.mint)?;
+    check_min_swap_amount(&pool_state, output_vault.mint, amount_out)?;
+    let (tickarray_bitmap_extension, tick_array_loaders) = load_swap_tick_arrays(
+        pool_state_loader.key(),
+        &pool_accounts[ROUTE_POOL_ACCOUNT_NUM..],
+    )?;
+    let tick_arrays = tick_array_loaders
+        .iter()
+        .map(|loader| loader.load())
+        .collect::<Result<Vec<_>>>()?;
+    let tick_array_states: VecDeque<_> = tick_arrays
+        .iter()
+        .map(|tick_array| tick_array.deref())
+        .collect();
+
+    let zero_for_one = input_vault.mint == pool_state.token_mint_0;
+    let (state, is_completed) = compute_swap(
+        &amm_config,
+        &pool_state,
+        &tick_array_states,
+        &tickarray_bitmap_extension,
+        amount_out,
+        get_sqrt_price_limit_x64(0, zero_for_one),
+        zero_for_one,
+        false,
+    )?;
+    require!(
+        is_completed && state.amount_specified_remaining == 0,
+        ErrorCode::LiquidityInsufficient
+    );
+    Ok((input_vault.mint, output_vault.mint, state.amount_calculated))
+}
+
+pub fn exact_output<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseOut<'info>>,
+    amount_out: u64,
+    amount_in_maximum: u64,
+    additional_accounts_per_pool: Vec<u8>,
+    deadline: i64,
+) -> Result<SwapResult> {
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    let route_accounts: Vec<&'c [AccountInfo<'info>]> =
+        split_route_accounts(ctx.remaining_accounts, &additional_accounts_per_pool)?;
+    // the deadline must be within the horizon of every pool on the route
+    let mut pool_ids = Vec::with_capacity(route_accounts.len());
+    for pool_accounts in route_accounts.iter() {
+        let pool_state_loader = AccountLoader::<PoolState>::try_from(&pool_accounts[1])?;
+        check_deadline(deadline, pool_state_loader.load()?.max_deadline_horizon)?;
+        pool_ids.push(pool_state_loader.key());
+    }
+    check_route_pools_unique(&pool_ids)?;
+
+    // every hop is quoted before any token moves
+    let mut next_input_mint = None;
+    let amounts_in = get_route_amounts_in(
+        route_accounts.len(),
+        amount_out,
+        amount_in_maximum,
+        |hop, amount_out| {
+            let (input_mint, output_mint, amount_in) =
+                quote_hop_amount_in(route_accounts[hop], amount_out)?;
+            if let Some(next_input_mint) = next_input_mint {
+                require_keys_eq!(output_mint, next_input_mint, ErrorCode::InvalidVaultPair);
+            }
+            next_input_mint = Some(input_mint);
+            Ok(amount_in)
+        },
+    )?;
+    require_keys_eq!(
+        next_input_mint.unwrap(),
+        ctx.accounts.input_token_mint.key(),
+        ErrorCode::InvalidVaultPair
+    );
+    check_input_balance(&ctx.accounts.input_token_account, amounts_in[0])?;
+    #[cfg(feature = "enable-log")]
+    msg!("exact output route, amounts_in:{:?}", amounts_in);
+
+    let mut input_token_account = Box::new(ctx.accounts.input_token_account.clone());
+    let mut input_token_mint = Box::new(ctx.accounts.input_token_mint.clone());
+    let mut last_pool_state = None;
+    for (hop, pool_accounts) in route_accounts.iter().enumerate() {
+        let amm_config = Box::new(Account::<AmmConfig>::try_from(&pool_accounts[0])?);
+        let pool_state_loader = AccountLoader::<PoolState>::try_from(&pool_accounts[1])?;
+        let output_token_account = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            &pool_accounts[2],
+        )?);
+        let input_vault = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            &pool_accounts[3],
+        )?);
+        let output_vault = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            &pool_accounts[4],
+        )?);
+        let output_token_mint = Box::new(InterfaceAccount::<Mint>::try_from(&pool_accounts[5])?);
+        let observation_state = AccountLoader::<ObservationState>::try_from(&pool_accounts[6])?;
+        // check observation account is owned by the pool
+        require_keys_eq!(
+            pool_state_loader.load()?.observation_key,
+            observation_state.key()
+        );
+
+        // the output of each hop is the input quoted for the next one
+        let hop_amount_out = amounts_in.get(hop + 1).copied().unwrap_or(amount_out);
+        last_pool_state = Some(pool_state_loader.clone());
+        let amount_in = exact_internal_v2(
+            &mut SwapSingleV2 {
+                payer: ctx.accounts.payer.clone(),
+                amm_config,
+                input_token_account: input_token_account.clone(),
+                pool_state: pool_state_loader,
+                output_token_account: output_token_account.clone(),
+                input_vault,
+                output_vault,
+                input_vault_mint: input_token_mint.clone(),
+                output_vault_mint: output_token_mint.clone(),
+                observation_state,
+                token_program: ctx.accounts.token_program.clone(),
+                token_program_2022: ctx.accounts.token_program_2022.clone(),
+                memo_program: ctx.accounts.memo_program.clone(),
+                referrer_token_account: None,
+            },
+            &pool_accounts[ROUTE_POOL_ACCOUNT_NUM..],
+            hop_amount_out,
+            0,
+            false,
+        )?;
+        // every hop must keep to its quote, the hop before it only gave that much
+        require_gte!(amounts_in[hop], amount_in, ErrorCode::TooMuchInputPaid);
+        // output token is the new swap input token
+        input_token_account = output_token_account;
+        input_token_mint = output_token_mint;
+    }
+
+    let last_pool_state = last_pool_state.ok_or(ErrorCode::InvalidSwapInputNum)?;
+    let swap_result = SwapResult::new(&*last_pool_state.load()?, amounts_in[0], amount_out);
+    Ok(swap_result)
+}
+
+#[cfg(test)]
+mod exact_output_test {
+    use super::*;
+    use crate::states::pool_test::{build_pool_accounts, build_swap_pool};
+    use crate::util::program_test::{
+        process_instruction, program_error, TestAccount, TEST_UNIX_TIMESTAMP,
+    };
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_2022::spl_token_2022;
+    use solana_program::instruction::AccountMeta;
+
+    #[test]
+    fn split_route_accounts_test() {
+        let accounts: Vec<usize> = (0..24).collect();
+        let pool_accounts = split_route_accounts(&accounts, &[1, 0, 2]).unwrap();
+        assert_eq!(pool_accounts.len(), 3);
+        assert_eq!(pool_accounts[0], &accounts[..8]);
+        assert_eq!(pool_accounts[1], &accounts[8..15]);
+        assert_eq!(pool_accounts[2], &accounts[15..]);
+        // the accounts don't add up
+        assert_eq!(
+            split_route_accounts(&accounts, &[1, 0, 1]).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+        assert_eq!(
+            split_route_accounts(&accounts[..0], &[]).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+    }
+
+    #[test]
+    fn get_route_amounts_in_test() {
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+        let pools = [
+            build_swap_pool(1800, 1_000_000_000),
+            build_swap_pool(600, 5_000_000_000),
+            build_swap_pool(3000, 2_000_000_000),
+        ];
+        // every hop sells token_1 for token_0
+        let swap = |hop: usize, amount: u64, is_base_input: bool| {
+            let (pool_state, tick_array) = &pools[hop];
+            let tick_array_ref = tick_array.borrow();
+            let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+            let (state, is_completed) = compute_swap(
+                &amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                amount,
+                get_sqrt_price_limit_x64(0, false),
+                false,
+                is_base_input,
+            )?;
+            require!(
+                is_completed && state.amount_specified_remaining == 0,
+                ErrorCode::LiquidityInsufficient
+            );
+            Ok(state.amount_calculated)
+        };
+        let amount_out = 1_000_000;
+
+        let mut quoted_hops = Vec::new();
+        let amounts_in = get_route_amounts_in(3, amount_out, u64::MAX, |hop, amount_out| {
+            quoted_hops.push(hop);
+            swap(hop, amount_out, false)
+        })
+        .unwrap();
+        // back to front
+        assert_eq!(quoted_hops, vec![2, 1, 0]);
+        assert!(amounts_in.iter().all(|amount_in| *amount_in > 0));
+        // swapping the route's input front to back gives at least the output
+        let mut amount = amounts_in[0];
+        for hop in 0..3 {
+            amount = swap(hop, amount, true).unwrap();
+            assert!(amount >= amounts_in.get(hop + 1).copied().unwrap_or(amount_out));
+        }
+
+        // the input can be capped at exactly the quote
+        assert_eq!(
+            get_route_amounts_in(3, amount_out, amounts_in[0], |hop, amount_out| swap(
+                hop, amount_out, false
+            ))
+            .unwrap(),
+            amounts_in
+        );
+        // an input over the cap fails once quoted, before any hop is swapped
+        assert_eq!(
+            get_route_amounts_in(3, amount_out, amounts_in[0] - 1, |hop, amount_out| swap(
+                hop, amount_out, false
+            ))
+            .unwrap_err(),
+            ErrorCode::TooMuchInputPaid.into()
+        );
+        // so does a hop that can't give the output
+        let mut quoted_hops = Vec::new();
+        assert_eq!(
+            get_route_amounts_in(3, 1_000_000_000, u64::MAX, |hop, amount_out| {
+                quoted_hops.push(hop);
+                swap(hop, amount_out, false)
+            })
+            .unwrap_err(),
+            ErrorCode::LiquidityInsufficient.into()
+        );
+        assert_eq!(quoted_hops, vec![2]);
+    }
+
+    #[test]
+    fn check_route_pools_unique_test() {
+        let (pool_a, pool_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(check_route_pools_unique(&[pool_a]).is_ok());
+        assert!(check_route_pools_unique(&[pool_a, pool_b]).is_ok());
+        for pool_ids in [vec![pool_a, pool_a], vec![pool_a, pool_b, pool_a]] {
+            assert_eq!(
+                check_route_pools_unique(&pool_ids).unwrap_err(),
+                ErrorCode::DuplicateRoutePool.into()
+            );
+        }
+    }
+
+    /// The accounts of a pool selling `input_mint` for `output_mint`, built as `build_swap_pool`,
+    /// and the remaining accounts of its hop paying out to `output_token_account`
+    fn build_hop_accounts(
+        tick: i32,
+        liquidity: u128,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        output_token_account: Pubkey,
+        max_deadline_horizon: u64,
+    ) -> (Vec<TestAccount>, Vec<AccountMeta>) {
+        let (pool_state, tick_array) = build_swap_pool(tick, liquidity);
+        let pool_state = &mut pool_state.borrow_mut();
+        pool_state.token_mint_0 = output_mint;
+        pool_state.token_mint_1 = input_mint;
+        pool_state.bump = [Pubkey::find_program_address(&pool_state.seeds()[..4], &crate::id()).1];
+        pool_state.max_deadline_horizon = max_deadline_horizon;
+        let mut accounts = build_pool_accounts(pool_state, 1_000_000_000);
+        let tick_array_key = Pubkey::new_unique();
+        let mut tick_array = tick_array.borrow_mut();
+        tick_array.pool_id = pool_state.key();
+        accounts.push(TestAccount::zero_copy(tick_array_key, &*tick_array));
+        let metas = vec![
+            AccountMeta::new_readonly(pool_state.amm_config, false),
+            AccountMeta::new(pool_state.key(), false),
+            AccountMeta::new(output_token_account, false),
+            AccountMeta::new(pool_state.token_vault_1, false),
+            AccountMeta::new(pool_state.token_vault_0, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new(pool_state.observation_key, false),
+            AccountMeta::new(tick_array_key, false),
+        ];
+        (accounts, metas)
+    }
+
+    #[test]
+    fn exact_output_route_reverts_test() {
+        let payer = Pubkey::new_unique();
+        let mints: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let token_accounts: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut accounts = vec![
+            TestAccount::wallet(payer),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(spl_token_2022::id()),
+            TestAccount::program(spl_memo::id()),
+        ];
+        for (mint, token_account) in mints.iter().zip(token_accounts.iter()) {
+            accounts.push(TestAccount::token_account(
+                *token_account,
+                spl_token::id(),
+                *mint,
+                payer,
+                if *mint == mints[0] { 1_000_000_000 } else { 0 },
+            ));
+        }
+        // the last pool allows deadlines no more than a minute away
+        let mut hop_metas = Vec::new();
+        for (hop, (tick, liquidity, max_deadline_horizon)) in [
+            (1800, 1_000_000_000, 0),
+            (600, 5_000_000_000, 0),
+            (3000, 2_000_000_000, 60),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let (pool_accounts, metas) = build_hop_accounts(
+                tick,
+                liquidity,
+                mints[hop],
+                mints[hop + 1],
+                token_accounts[hop + 1],
+                max_deadline_horizon,
+            );
+            accounts.extend(pool_accounts);
+            hop_metas.push(metas);
+        }
+        let router_metas = crate::accounts::SwapRouterBaseOut {
+            payer,
+            input_token_account: token_accounts[0],
+            input_token_mint: mints[0],
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+            memo_program: spl_memo::id(),
+        }
+        .to_account_metas(None);
+        let route_metas = |hops: &[usize]| {
+            let mut metas = router_metas.clone();
+            for hop in hops {
+                metas.extend(hop_metas[*hop].clone());
+            }
+            metas
+        };
+        let exact_output = |amount_in_maximum, deadline| crate::instruction::ExactOutput {
+            amount_out: 1_000_000,
+            amount_in_maximum,
+            additional_accounts_per_pool: vec![1, 1, 1],
+            deadline,
+        };
+        let token_balances = |accounts: &[TestAccount]| -> Vec<u64> {
+            token_accounts
+                .iter()
+                .map(|key| {
+                    accounts
+                        .iter()
+                        .find(|account| account.key == *key)
+                        .unwrap()
+                        .token_amount()
+                })
+                .collect()
+        };
+        let balances_before = token_balances(&accounts);
+
+        // the route's input is over the cap, it fails once every hop is quoted
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                route_metas(&[0, 1, 2]),
+                exact_output(1, TEST_UNIX_TIMESTAMP + 30)
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::TooMuchInputPaid)
+        );
+        assert_eq!(token_balances(&accounts), balances_before);
+        // the deadline is within the first pools' horizon but not the last one's
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                route_metas(&[0, 1, 2]),
+                exact_output(u64::MAX, TEST_UNIX_TIMESTAMP + 3_600)
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::DeadlineTooFar)
+        );
+        assert_eq!(token_balances(&accounts), balances_before);
+        // a pool passed through twice
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                route_metas(&[0, 1, 1]),
+                exact_output(u64::MAX, TEST_UNIX_TIMESTAMP + 30)
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::DuplicateRoutePool)
+        );
+        assert_eq!(token_balances(&accounts), balances_before);
+
+        process_instruction(
+            &mut accounts,
+            route_metas(&[0, 1, 2]),
+            exact_output(u64::MAX, TEST_UNIX_TIMESTAMP + 30),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,365 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::swap::{
+    check_input_authority, check_min_swap_amount, check_vault_pair, compute_swap, exact_internal,
+    get_output_reserve, get_sqrt_price_limit_x64, load_swap_tick_arrays, SwapAccounts, SwapResult,
+    SwapSingle,
+};
+use crate::util::check_deadline;
+use anchor_lang::prelude::*;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+pub fn exact_output_single<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapSingle<'info>>,
+    amount_out: u64,
+    amount_in_maximum: u64,
+    deadline: i64,
+    sqrt_price_limit_x64: u128,
+) -> Result<SwapResult> {
+    let zero_for_one = {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        check_deadline(deadline, pool_state.max_deadline_horizon)?;
+        check_vault_pair(
+            &pool_state,
+            ctx.accounts.input_vault.mint,
+            ctx.accounts.output_vault.mint,
+        )?;
+        check_min_swap_amount(&pool_state, ctx.accounts.output_vault.mint, amount_out)?;
+        ctx.accounts.input_vault.mint == pool_state.token_mint_0
+    };
+    // the referral fee is only paid by `swap`
+    require!(
+        ctx.accounts.referrer_token_account.is_none(),
+        ErrorCode::InvalidReferrerAccount
+    );
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    get_output_reserve(ctx.accounts.output_vault.amount, amount_out)?;
+    let sqrt_price_limit_x64 = get_sqrt_price_limit_x64(sqrt_price_limit_x64, zero_for_one);
+
+    let input_balance_before = ctx.accounts.input_token_account.amount;
+    let output_balance_before = ctx.accounts.output_token_account.amount;
+    exact_internal(
+        &mut SwapAccounts {
+            signer: ctx.accounts.payer.clone(),
+            amm_config: &ctx.accounts.amm_config,
+            input_token_account: ctx.accounts.input_token_account.clone(),
+            output_token_account: ctx.accounts.output_token_account.clone(),
+            input_vault: ctx.accounts.input_vault.clone(),
+            output_vault: ctx.accounts.output_vault.clone(),
+            token_program: ctx.accounts.token_program.clone(),
+            pool_state: &mut ctx.accounts.pool_state,
+            tick_array_state: &mut ctx.accounts.tick_array,
+            observation_state: &mut ctx.accounts.observation_state,
+        },
+        ctx.remaining_accounts,
+        amount_out,
+        sqrt_price_limit_x64,
+        false,
+    )?;
+    // the amounts are what the swap moved between the user's token accounts
+    ctx.accounts.input_token_account.reload()?;
+    ctx.accounts.output_token_account.reload()?;
+    let amount_in = input_balance_before - ctx.accounts.input_token_account.amount;
+    let amount_out = ctx.accounts.output_token_account.amount - output_balance_before;
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "exact output single, amount_out:{}, amount_in:{}, amount_in_maximum:{}",
+        amount_out,
+        amount_in,
+        amount_in_maximum
+    );
+    require_gte!(amount_in_maximum, amount_in, ErrorCode::TooMuchInputPaid);
+
+    Ok(SwapResult::new(
+        &*ctx.accounts.pool_state.load()?,
+        amount_in,
+        amount_out,
+    ))
+}
+
+/// The input cap `amount_in_maximum_bps` over `quoted_amount_in`, rounded down and
+/// saturating at `u64::MAX`
+pub fn get_amount_in_maximum(quoted_amount_in: u64, amount_in_maximum_bps: u16) -> u64 {
+    let amount_in_maximum = u128::from(quoted_amount_in)
+        * (u128::from(BIPS_DENOMINATOR_VALUE) + u128::from(amount_in_maximum_bps))
+        / u128::from(BIPS_DENOMINATOR_VALUE);
+    u64::try_from(amount_in_maximum).unwrap_or(u64::MAX)
+}
+
+/// The input a base output swap of `amount_out` takes from the pool as it is, fee included,
+/// quoted by running `compute_swap` over the swap's tick arrays without changing the pool
+fn quote_amount_in<'c: 'info, 'info>(
+    ctx: &Context<'_, '_, 'c, 'info, SwapSingle<'info>>,
+    amount_out: u64,
+    sqrt_price_limit_x64: u128,
+) -> Result<u64> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let zero_for_one = ctx.accounts.input_vault.mint == pool_state.token_mint_0;
+    let (tickarray_bitmap_extension, tick_array_loaders) =
+        load_swap_tick_arrays(ctx.accounts.pool_state.key(), ctx.remaining_accounts)?;
+    let first_tick_array = ctx.accounts.tick_array.load()?;
+    let tick_arrays = tick_array_loaders
+        .iter()
+        .map(|loader| loader.load())
+        .collect::<Result<Vec<_>>>()?;
+    let mut tick_array_states = VecDeque::from([first_tick_array.deref()]);
+    tick_array_states.extend(tick_arrays.iter().map(|tick_array| tick_array.deref()));
+
+    let (state, is_completed) = compute_swap(
+        &ctx.accounts.amm_config,
+        &pool_state,
+        &tick_array_states,
+        &tickarray_bitmap_extension,
+        amount_out,
+        get_sqrt_price_limit_x64(sqrt_price_limit_x64, zero_for_one),
+        zero_for_one,
+        false,
+    )?;
+    require!(is_completed, ErrorCode::LiquidityInsufficient);
+    Ok(state.amount_calculated)
+}
+
+/// `exact_output_single` with the input cap given as `amount_in_maximum_bps` over the input
+/// quoted for `amount_out` on chain, by running the swap over the pool's current state
+pub fn exact_output_single_bps<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapSingle<'info>>,
+    amount_out: u64,
+    amount_in_maximum_bps: u16,
+    deadline: i64,
+    sqrt_price_limit_x64: u128,
+) -> Result<SwapResult> {
+    let quoted_amount_in = quote_amount_in(&ctx, amount_out, sqrt_price_limit_x64)?;
+    let amount_in_maximum = get_amount_in_maximum(quoted_amount_in, amount_in_maximum_bps);
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "quoted_amount_in:{}, amount_in_maximum_bps:{}, amount_in_maximum:{}",
+        quoted_amount_in,
+        amount_in_maximum_bps,
+        amount_in_maximum
+    );
+    exact_output_single(
+        ctx,
+        amount_out,
+        amount_in_maximum,
+        deadline,
+        sqrt_price_limit_x64,
+    )
+}
+
+#[cfg(test)]
+mod exact_output_single_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::{build_pool_accounts, build_swap_pool};
+    use crate::swap::swap_internal;
+    use crate::util::program_test::{
+        process_instruction, program_error, return_data, TestAccount, TEST_UNIX_TIMESTAMP,
+    };
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+
+    #[test]
+    fn get_sqrt_price_limit_x64_test() {
+        assert_eq!(
+            get_sqrt_price_limit_x64(0, true),
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        );
+        assert_eq!(
+            get_sqrt_price_limit_x64(0, false),
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        );
+        let sqrt_price_limit_x64 = tick_math::get_sqrt_price_at_tick(100).unwrap();
+        assert_eq!(
+            get_sqrt_price_limit_x64(sqrt_price_limit_x64, true),
+            sqrt_price_limit_x64
+        );
+        assert_eq!(
+            get_sqrt_price_limit_x64(sqrt_price_limit_x64, false),
+            sqrt_price_limit_x64
+        );
+    }
+
+    #[test]
+    fn exact_output_exhausts_liquidity_test() {
+        let (pool_state, tick_array) = build_swap_pool(1800, 1_000_000_000);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig::default();
+        let observation_state = ObservationState::default();
+        // buys token_0 with a zero price limit
+        let sqrt_price_limit_x64 = get_sqrt_price_limit_x64(0, false);
+        let swap = |amount_out: u64| {
+            swap_internal(
+                &amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &observation_state,
+                &None,
+                u64::MAX,
+                amount_out,
+                sqrt_price_limit_x64,
+                false,
+                false,
+                0,
+            )
+        };
+
+        // the range holds less than 80_000_000 of token_0 above the current price
+        let (amount_0, amount_1) = swap(1_000_000).unwrap();
+        assert_eq!(amount_0, 1_000_000);
+        assert!(amount_1 > 0);
+        assert_eq!(
+            swap(80_000_000).unwrap_err(),
+            ErrorCode::LiquidityInsufficient.into()
+        );
+
+        // the swap stops after taking all the range's liquidity, short of the output
+        let (state, is_completed) = compute_swap(
+            &amm_config,
+            &pool_state.borrow(),
+            &tick_array_states,
+            &None,
+            80_000_000,
+            sqrt_price_limit_x64,
+            false,
+            false,
+        )
+        .unwrap();
+        assert!(!is_completed);
+        assert!(state.amount_specified_remaining > 0);
+        assert_eq!(state.liquidity, 0);
+        assert_eq!(
+            state.sqrt_price_x64,
+            tick_math::get_sqrt_price_at_tick(3540).unwrap()
+        );
+    }
+
+    #[test]
+    fn exact_output_near_price_limit_test() {
+        let (pool_state, tick_array) = build_swap_pool(1800, 1_000_000_000);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+        let swap = |amount_out: u64, sqrt_price_limit_x64: u128| {
+            compute_swap(
+                &amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                amount_out,
+                get_sqrt_price_limit_x64(sqrt_price_limit_x64, false),
+                false,
+                false,
+            )
+            .unwrap()
+        };
+
+        for amount_out in [1, 7, 999_999, 1_000_001, 50_000_000] {
+            // without a limit the whole output is paid out, down to the last token
+            let (state, is_completed) = swap(amount_out, 0);
+            assert!(is_completed);
+            assert_eq!(state.amount_specified_remaining, 0);
+            let sqrt_price_x64 = state.sqrt_price_x64;
+            assert!(sqrt_price_x64 > pool_state.borrow().sqrt_price_x64);
+
+            // a limit at the price the output ends at still fills it exactly, for the same input
+            let (limited_state, is_completed) = swap(amount_out, sqrt_price_x64);
+            assert!(is_completed);
+            assert_eq!(limited_state.amount_specified_remaining, 0);
+            assert_eq!(limited_state.amount_calculated, state.amount_calculated);
+
+            // a limit just short of it stops there, short of the last token
+            if sqrt_price_x64 - 1 > pool_state.borrow().sqrt_price_x64 {
+                let (limited_state, is_completed) = swap(amount_out, sqrt_price_x64 - 1);
+                assert!(is_completed);
+                assert_eq!(limited_state.sqrt_price_x64, sqrt_price_x64 - 1);
+                assert_eq!(limited_state.amount_specified_remaining, 1);
+                assert!(limited_state.amount_calculated <= state.amount_calculated);
+            }
+        }
+    }
+
+    #[test]
+    fn get_amount_in_maximum_test() {
+        assert_eq!(get_amount_in_maximum(10_000, 0), 10_000);
+        assert_eq!(get_amount_in_maximum(10_000, 50), 10_050);
+        // rounded down, never below the quote
+        assert_eq!(get_amount_in_maximum(999, 5), 999);
+        assert_eq!(get_amount_in_maximum(1_000, u16::MAX), 7_553);
+        assert_eq!(get_amount_in_maximum(u64::MAX, 1), u64::MAX);
+    }
+
+    #[test]
+    fn exact_output_single_bps_quote_test() {
+        let (pool_state, tick_array_state) = build_swap_pool(1800, 1_000_000_000);
+        let pool_state = &mut pool_state.borrow_mut();
+        let mut accounts = build_pool_accounts(pool_state, 1_000_000_000);
+        tick_array_state.borrow_mut().pool_id = pool_state.key();
+        let payer = Pubkey::new_unique();
+        let (input_token_account, output_token_account, tick_array) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        accounts.extend([
+            TestAccount::wallet(payer),
+            TestAccount::token_account(
+                input_token_account,
+                spl_token::id(),
+                pool_state.token_mint_1,
+                payer,
+                1_000_000_000,
+            ),
+            TestAccount::token_account(
+                output_token_account,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                payer,
+                0,
+            ),
+            TestAccount::zero_copy(tick_array, &*tick_array_state.borrow()),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(crate::id()),
+        ]);
+        // buys token_0
+        let metas = crate::accounts::SwapSingle {
+            payer,
+            amm_config: pool_state.amm_config,
+            pool_state: pool_state.key(),
+            input_token_account,
+            output_token_account,
+            input_vault: pool_state.token_vault_1,
+            output_vault: pool_state.token_vault_0,
+            observation_state: pool_state.observation_key,
+            token_program: spl_token::id(),
+            tick_array,
+            referrer_token_account: None,
+        }
+        .to_account_metas(Some(true));
+        let mut exact_output_single_bps = |amount_out: u64| {
+            process_instruction(
+                &mut accounts,
+                metas.clone(),
+                crate::instruction::ExactOutputSingleBps {
+                    amount_out,
+                    amount_in_maximum_bps: 50,
+                    deadline: TEST_UNIX_TIMESTAMP + 60,
+                    sqrt_price_limit_x64: 0,
+                },
+            )
+        };
+
+        // the input is quoted by running the swap over the pool's tick arrays
+        exact_output_single_bps(1_000_000).unwrap();
+        assert_eq!(return_data::<SwapResult>().amount_out, 0);
+        // the range holds less than 80_000_000 of token_0 above the current price
+        assert_eq!(
+            exact_output_single_bps(80_000_000).unwrap_err(),
+            program_error(ErrorCode::LiquidityInsufficient)
+        );
+    }
+}
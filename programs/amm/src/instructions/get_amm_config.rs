@@ -0,0 +1,111 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetAmmConfig<'info> {
+    /// The amm config to be read
+    pub amm_config: Account<'info, AmmConfig>,
+}
+
+/// The version of `AmmConfigInfo`, bumped when fields are appended
+pub const AMM_CONFIG_INFO_VERSION: u8 = 1;
+
+/// The amm config fields, set as the instruction's return data.
+/// Borsh serialized in the field order: the version byte, then the fields as laid out below,
+/// new fields are only ever appended, so a client can read the fields of the versions it knows.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AmmConfigInfo {
+    /// The layout version, so consumers can tell the fields apart
+    pub version: u8,
+    /// The index of the config
+    pub index: u16,
+    /// Address of the protocol owner
+    pub owner: Pubkey,
+    /// The protocol fee, denominated in hundredths of a bip (10^-6) of the trade fee
+    pub protocol_fee_rate: u32,
+    /// The trade fee, denominated in hundredths of a bip (10^-6)
+    pub trade_fee_rate: u32,
+    /// The tick spacing of the config's pools
+    pub tick_spacing: u16,
+    /// The fund fee, denominated in hundredths of a bip (10^-6) of the trade fee
+    pub fund_fee_rate: u32,
+    /// The receiver of the fund fee
+    pub fund_owner: Pubkey,
+    /// Whether pools can be created with the config, 0: enable, 1: disable
+    pub status: u8,
+    /// The share of swap input paid to the referrer, denominated in bips (10^-4)
+    pub referral_fee_bps: u16,
+    /// The share of collected protocol fees sent to the secondary recipient, denominated in bips (10^-4)
+    pub secondary_protocol_fee_bps: u16,
+    /// The max gross liquidity of a tick, zero for configs created before the cap
+    pub max_liquidity_per_tick: u128,
+}
+
+impl From<&AmmConfig> for AmmConfigInfo {
+    fn from(amm_config: &AmmConfig) -> Self {
+        Self {
+            version: AMM_CONFIG_INFO_VERSION,
+            index: amm_config.index,
+            owner: amm_config.owner,
+            protocol_fee_rate: amm_config.protocol_fee_rate,
+            trade_fee_rate: amm_config.trade_fee_rate,
+            tick_spacing: amm_config.tick_spacing,
+            fund_fee_rate: amm_config.fund_fee_rate,
+            fund_owner: amm_config.fund_owner,
+            status: amm_config.status,
+            referral_fee_bps: amm_config.referral_fee_bps,
+            secondary_protocol_fee_bps: amm_config.secondary_protocol_fee_bps,
+            max_liquidity_per_tick: amm_config.max_liquidity_per_tick,
+        }
+    }
+}
+
+pub fn get_amm_config(ctx: Context<GetAmmConfig>) -> Result<AmmConfigInfo> {
+    Ok(AmmConfigInfo::from(&*ctx.accounts.amm_config))
+}
+
+#[cfg(test)]
+mod get_amm_config_test {
+    use super::*;
+
+    #[test]
+    fn amm_config_info_test() {
+        let amm_config = AmmConfig {
+            index: 3,
+            owner: Pubkey::new_unique(),
+            trade_fee_rate: 2500,
+            protocol_fee_rate: 120_000,
+            fund_fee_rate: 40_000,
+            tick_spacing: 60,
+            fund_owner: Pubkey::new_unique(),
+            status: 1,
+            referral_fee_bps: 10,
+            secondary_protocol_fee_bps: 2_000,
+            max_liquidity_per_tick: 1 << 100,
+            ..Default::default()
+        };
+
+        let amm_config_info = AmmConfigInfo::from(&amm_config);
+        assert_eq!(amm_config_info.version, AMM_CONFIG_INFO_VERSION);
+        assert_eq!(amm_config_info.index, 3);
+        assert_eq!(amm_config_info.owner, amm_config.owner);
+        assert_eq!(amm_config_info.trade_fee_rate, 2500);
+        assert_eq!(amm_config_info.protocol_fee_rate, 120_000);
+        assert_eq!(amm_config_info.fund_fee_rate, 40_000);
+        assert_eq!(amm_config_info.tick_spacing, 60);
+        assert_eq!(amm_config_info.fund_owner, amm_config.fund_owner);
+        assert_eq!(amm_config_info.status, 1);
+        assert_eq!(amm_config_info.referral_fee_bps, 10);
+        assert_eq!(amm_config_info.secondary_protocol_fee_bps, 2_000);
+        assert_eq!(amm_config_info.max_liquidity_per_tick, 1 << 100);
+
+        // the version leads the return data, followed by the index
+        let data = amm_config_info.try_to_vec().unwrap();
+        assert_eq!(data[0], AMM_CONFIG_INFO_VERSION);
+        assert_eq!(data[1..3], 3u16.to_le_bytes());
+        assert_eq!(
+            AmmConfigInfo::try_from_slice(&data).unwrap(),
+            amm_config_info
+        );
+    }
+}
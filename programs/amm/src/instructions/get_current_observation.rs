@@ -0,0 +1,89 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetCurrentObservation<'info> {
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The observation account of the pool
+    #[account(address = pool_state.load()?.observation_key)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+}
+
+/// Where the pool's next observation is written, set as the instruction's return data.
+/// The ring is allocated at full size when the pool is created, so a swap only ever
+/// needs the one `observation_key` account, however far the ring has been written.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CurrentObservation {
+    /// The observation account to pass to a swap
+    pub observation_key: Pubkey,
+    /// The most recently written index of the observations
+    pub observation_index: u16,
+    /// The number of observations that have been written
+    pub observation_cardinality: u16,
+    /// The number of observations the ring holds before it wraps, always `OBSERVATION_NUM`
+    pub observation_cardinality_next: u16,
+}
+
+impl CurrentObservation {
+    pub fn new(
+        observation_key: Pubkey,
+        observation_index: u16,
+        observation_state: &ObservationState,
+    ) -> Self {
+        Self {
+            observation_key,
+            observation_index,
+            observation_cardinality: observation_state.cardinality(),
+            observation_cardinality_next: OBSERVATION_NUM as u16,
+        }
+    }
+}
+
+pub fn get_current_observation(ctx: Context<GetCurrentObservation>) -> Result<CurrentObservation> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let current_observation = CurrentObservation::new(
+        pool_state.observation_key,
+        pool_state.observation_index,
+        &*ctx.accounts.observation_state.load()?,
+    );
+    emit!(CurrentObservationEvent {
+        pool_state: ctx.accounts.pool_state.key(),
+        observation_key: current_observation.observation_key,
+        observation_index: current_observation.observation_index,
+        observation_cardinality: current_observation.observation_cardinality,
+        observation_cardinality_next: current_observation.observation_cardinality_next,
+    });
+    Ok(current_observation)
+}
+
+#[cfg(test)]
+mod get_current_observation_test {
+    use super::*;
+
+    #[test]
+    fn current_observation_test() {
+        let observation_key = Pubkey::new_unique();
+        let mut observation_state = ObservationState::default();
+        let current_observation = CurrentObservation::new(observation_key, 0, &observation_state);
+        assert_eq!(current_observation.observation_cardinality, 0);
+        assert_eq!(
+            current_observation.observation_cardinality_next,
+            OBSERVATION_NUM as u16
+        );
+
+        for index in 0..3 {
+            observation_state.observations[index].block_timestamp = 100 + index as u32;
+        }
+        assert_eq!(
+            CurrentObservation::new(observation_key, 2, &observation_state),
+            CurrentObservation {
+                observation_key,
+                observation_index: 2,
+                observation_cardinality: 3,
+                observation_cardinality_next: OBSERVATION_NUM as u16,
+            }
+        );
+    }
+}
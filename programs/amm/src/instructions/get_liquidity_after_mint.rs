@@ -0,0 +1,129 @@
+use crate::error::ErrorCode;
+use crate::libraries::{liquidity_math, tick_math};
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetLiquidityAfterMint<'info> {
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+/// The pool's price and in range liquidity after a proposed mint, set as the return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LiquidityAfterMint {
+    /// The current price of the pool, a mint never moves it
+    pub sqrt_price_x64: u128,
+    /// Whether the range holds the current tick, so the mint adds to the in range liquidity
+    pub is_in_range: bool,
+    /// The in range liquidity of the pool after the mint
+    pub liquidity: u128,
+}
+
+/// Gets the pool's state after minting `liquidity` in `[tick_lower, tick_upper)` without changing
+/// the pool. The in range liquidity only grows if the range holds the current tick, as
+/// `modify_position` does, and the price stays where it is.
+pub fn compute_liquidity_after_mint(
+    pool_state: &PoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<LiquidityAfterMint> {
+    check_ticks_order(tick_lower, tick_upper)?;
+    require!(
+        tick_lower >= tick_math::MIN_TICK,
+        ErrorCode::TickLowerOverflow
+    );
+    require!(
+        tick_upper <= tick_math::MAX_TICK,
+        ErrorCode::TickUpperOverflow
+    );
+    let tick_spacing = i32::from(pool_state.tick_spacing);
+    require!(
+        tick_lower % tick_spacing == 0 && tick_upper % tick_spacing == 0,
+        ErrorCode::TickAndSpacingNotMatch
+    );
+
+    let is_in_range = pool_state.tick_current >= tick_lower && pool_state.tick_current < tick_upper;
+    let liquidity = if is_in_range {
+        liquidity_math::add_delta(
+            pool_state.liquidity,
+            i128::try_from(liquidity).map_err(|_| ErrorCode::LiquidityAddValueErr)?,
+        )?
+    } else {
+        pool_state.liquidity
+    };
+    Ok(LiquidityAfterMint {
+        sqrt_price_x64: pool_state.sqrt_price_x64,
+        is_in_range,
+        liquidity,
+    })
+}
+
+pub fn get_liquidity_after_mint(
+    ctx: Context<GetLiquidityAfterMint>,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<LiquidityAfterMint> {
+    let liquidity_after_mint = compute_liquidity_after_mint(
+        &*ctx.accounts.pool_state.load()?,
+        tick_lower,
+        tick_upper,
+        liquidity,
+    )?;
+    #[cfg(feature = "enable-log")]
+    msg!("liquidity after mint:{:?}", liquidity_after_mint);
+    Ok(liquidity_after_mint)
+}
+
+#[cfg(test)]
+mod get_liquidity_after_mint_test {
+    use super::*;
+    use crate::states::pool_test::build_pool;
+
+    #[test]
+    fn compute_liquidity_after_mint_test() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(1800).unwrap();
+        let pool_state = build_pool(1800, 60, sqrt_price_x64, 1_000_000_000);
+        let pool_state = pool_state.borrow();
+
+        // in range, including a range starting at the current tick
+        for (tick_lower, tick_upper) in [(-600, 6000), (1800, 1860)] {
+            assert_eq!(
+                compute_liquidity_after_mint(&pool_state, tick_lower, tick_upper, 500).unwrap(),
+                LiquidityAfterMint {
+                    sqrt_price_x64,
+                    is_in_range: true,
+                    liquidity: 1_000_000_500,
+                }
+            );
+        }
+        // out of range, including a range ending at the current tick
+        for (tick_lower, tick_upper) in [(1860, 6000), (-600, 1800)] {
+            assert_eq!(
+                compute_liquidity_after_mint(&pool_state, tick_lower, tick_upper, 500).unwrap(),
+                LiquidityAfterMint {
+                    sqrt_price_x64,
+                    is_in_range: false,
+                    liquidity: 1_000_000_000,
+                }
+            );
+        }
+
+        // mis-specified ranges
+        assert_eq!(
+            compute_liquidity_after_mint(&pool_state, 6000, -600, 500).unwrap_err(),
+            ErrorCode::TickInvaildOrder.into()
+        );
+        assert_eq!(
+            compute_liquidity_after_mint(&pool_state, -600, 6001, 500).unwrap_err(),
+            ErrorCode::TickAndSpacingNotMatch.into()
+        );
+        assert_eq!(
+            compute_liquidity_after_mint(&pool_state, tick_math::MIN_TICK - 60, 600, 500)
+                .unwrap_err(),
+            ErrorCode::TickLowerOverflow.into()
+        );
+    }
+}
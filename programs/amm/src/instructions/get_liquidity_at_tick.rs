@@ -0,0 +1,211 @@
+use crate::error::ErrorCode;
+use crate::libraries::liquidity_math;
+use crate::states::*;
+use crate::swap::load_swap_tick_arrays;
+use anchor_lang::prelude::*;
+use std::ops::Deref;
+
+#[derive(Accounts)]
+pub struct GetLiquidityAtTick<'info> {
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+    // remaining accounts
+    // tickarray_bitmap_extension: must add account if need regardless the sequence
+    // the initialized tick arrays between the current tick and the target tick, in any order
+}
+
+/// Gets the in range liquidity of the pool if its price moved to `tick`, by crossing the
+/// initialized ticks between the current tick and `tick` as a swap would.
+/// Every initialized tick array in between must be in `tick_array_states`.
+pub fn compute_liquidity_at_tick(
+    pool_state: &PoolState,
+    tick_array_states: &[&TickArrayState],
+    tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
+    tick: i32,
+) -> Result<u128> {
+    require!(
+        !TickState::check_is_out_of_boundary(tick),
+        ErrorCode::InvaildTickIndex
+    );
+    let tick_current = pool_state.tick_current;
+    if tick == tick_current {
+        return Ok(pool_state.liquidity);
+    }
+    let zero_for_one = tick < tick_current;
+    // moving up crosses the ticks in (tick_current, tick], moving down those in (tick, tick_current]
+    let (tick_lower, tick_upper) = if zero_for_one {
+        (tick, tick_current)
+    } else {
+        (tick_current, tick)
+    };
+    let tick_count = TickArrayState::tick_count(pool_state.tick_spacing);
+    let mut start_index = match pool_state
+        .get_first_initialized_tick_array(tickarray_bitmap_extension, zero_for_one)
+    {
+        Ok((_, start_index)) => Some(start_index),
+        // no initialized tick in the direction, nothing to cross
+        Err(error) if error == ErrorCode::InsufficientLiquidityForDirection.into() => None,
+        Err(error) => return Err(error),
+    };
+    let mut liquidity_delta: i128 = 0;
+    while let Some(tick_array_start_index) = start_index {
+        // the tick array is beyond the target tick
+        if tick_array_start_index > tick_upper || tick_array_start_index + tick_count <= tick_lower
+        {
+            break;
+        }
+        let tick_array = tick_array_states
+            .iter()
+            .find(|tick_array| tick_array.start_tick_index == tick_array_start_index)
+            .ok_or(ErrorCode::MissingTickArrayAccounts)?;
+        for tick_state in tick_array.ticks.iter() {
+            if tick_state.is_initialized()
+                && tick_state.tick > tick_lower
+                && tick_state.tick <= tick_upper
+            {
+                liquidity_delta = liquidity_delta
+                    .checked_add(tick_state.liquidity_net)
+                    .ok_or(ErrorCode::LiquidityAddValueErr)?;
+            }
+        }
+        start_index = pool_state.next_initialized_tick_array_start_index(
+            tickarray_bitmap_extension,
+            tick_array_start_index,
+            zero_for_one,
+        )?;
+    }
+    // crossing a tick downwards removes its liquidity_net
+    if zero_for_one {
+        liquidity_delta = -liquidity_delta;
+    }
+    liquidity_math::add_delta(pool_state.liquidity, liquidity_delta)
+}
+
+pub fn get_liquidity_at_tick<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, GetLiquidityAtTick<'info>>,
+    tick: i32,
+) -> Result<u128> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let (tickarray_bitmap_extension, tick_array_loaders) =
+        load_swap_tick_arrays(ctx.accounts.pool_state.key(), ctx.remaining_accounts)?;
+    let tick_arrays = tick_array_loaders
+        .iter()
+        .map(|loader| loader.load())
+        .collect::<Result<Vec<_>>>()?;
+    let tick_array_states = tick_arrays
+        .iter()
+        .map(|tick_array| tick_array.deref())
+        .collect::<Vec<_>>();
+
+    let liquidity = compute_liquidity_at_tick(
+        &pool_state,
+        &tick_array_states,
+        &tickarray_bitmap_extension,
+        tick,
+    )?;
+    #[cfg(feature = "enable-log")]
+    msg!("tick:{}, liquidity:{}", tick, liquidity);
+    Ok(liquidity)
+}
+
+#[cfg(test)]
+mod get_liquidity_at_tick_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::build_pool;
+    use crate::states::tick_array_test::{build_tick, build_tick_array_with_tick_states};
+
+    #[test]
+    fn compute_liquidity_at_tick_test() {
+        let tick_spacing = 60;
+        // positions [-3540, 3540) with 100, [60, 120) with 10 and [-4200, -60) with 1,
+        // the ticks in the tick arrays [-7200, -3600), [-3600, 0) and [0, 3600)
+        let pool_state = build_pool(
+            0,
+            tick_spacing,
+            tick_math::get_sqrt_price_at_tick(0).unwrap(),
+            100,
+        );
+        let tick_arrays = [
+            build_tick_array_with_tick_states(
+                Pubkey::default(),
+                -7200,
+                tick_spacing,
+                vec![build_tick(-4200, 1, 1).take()],
+            ),
+            build_tick_array_with_tick_states(
+                Pubkey::default(),
+                -3600,
+                tick_spacing,
+                vec![
+                    build_tick(-3540, 100, 100).take(),
+                    build_tick(-60, 1, -1).take(),
+                ],
+            ),
+            build_tick_array_with_tick_states(
+                Pubkey::default(),
+                0,
+                tick_spacing,
+                vec![
+                    build_tick(60, 10, 10).take(),
+                    build_tick(120, 10, -10).take(),
+                    build_tick(3540, 100, -100).take(),
+                ],
+            ),
+        ];
+        for start_index in [-7200, -3600, 0] {
+            pool_state
+                .borrow_mut()
+                .flip_tick_array_bit(None, start_index)
+                .unwrap();
+        }
+        let tick_array_refs = tick_arrays
+            .iter()
+            .map(|tick_array| tick_array.borrow())
+            .collect::<Vec<_>>();
+        let tick_array_states = tick_array_refs
+            .iter()
+            .map(|tick_array| tick_array.deref())
+            .collect::<Vec<_>>();
+        let liquidity_at_tick = |tick: i32| {
+            compute_liquidity_at_tick(&pool_state.borrow(), &tick_array_states, &None, tick)
+                .unwrap()
+        };
+
+        // upwards from the current tick
+        assert_eq!(liquidity_at_tick(0), 100);
+        assert_eq!(liquidity_at_tick(59), 100);
+        assert_eq!(liquidity_at_tick(60), 110);
+        assert_eq!(liquidity_at_tick(119), 110);
+        assert_eq!(liquidity_at_tick(120), 100);
+        assert_eq!(liquidity_at_tick(3539), 100);
+        assert_eq!(liquidity_at_tick(3540), 0);
+        assert_eq!(liquidity_at_tick(tick_math::MAX_TICK), 0);
+        // downwards from the current tick
+        assert_eq!(liquidity_at_tick(-1), 100);
+        assert_eq!(liquidity_at_tick(-60), 100);
+        assert_eq!(liquidity_at_tick(-61), 101);
+        assert_eq!(liquidity_at_tick(-3540), 101);
+        assert_eq!(liquidity_at_tick(-3541), 1);
+        assert_eq!(liquidity_at_tick(-4200), 1);
+        assert_eq!(liquidity_at_tick(-4201), 0);
+        assert_eq!(liquidity_at_tick(tick_math::MIN_TICK), 0);
+
+        // an initialized tick array in between isn't given
+        assert_eq!(
+            compute_liquidity_at_tick(&pool_state.borrow(), &tick_array_states[1..], &None, -5000)
+                .unwrap_err(),
+            ErrorCode::MissingTickArrayAccounts.into()
+        );
+        assert_eq!(
+            compute_liquidity_at_tick(
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                tick_math::MAX_TICK + 1
+            )
+            .unwrap_err(),
+            ErrorCode::InvaildTickIndex.into()
+        );
+    }
+}
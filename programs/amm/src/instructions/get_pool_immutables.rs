@@ -0,0 +1,91 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetPoolImmutables<'info> {
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+/// The facts of a pool fixed when it was created, set as the instruction's return data.
+/// Unlike `PoolInfo` nothing here changes with trading, so a client can cache it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolImmutables {
+    /// Which config the pool belongs
+    pub amm_config: Pubkey,
+    /// Token pair of the pool, where token_mint_0 address < token_mint_1 address
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    /// mint0 and mint1 decimals
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    /// Token pair vault
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    /// Copied from the amm config when the pool created
+    pub tick_spacing: u16,
+    /// The tick of the initial price
+    pub genesis_tick: i32,
+    /// The creator of the pool, default for pools created before it was stored
+    pub creator: Pubkey,
+    /// The block timestamp when the pool created, zero for pools created before it was stored
+    pub created_at: u64,
+}
+
+impl PoolImmutables {
+    pub fn new(pool_state: &PoolState) -> Self {
+        Self {
+            amm_config: pool_state.amm_config,
+            token_mint_0: pool_state.token_mint_0,
+            token_mint_1: pool_state.token_mint_1,
+            mint_decimals_0: pool_state.mint_decimals_0,
+            mint_decimals_1: pool_state.mint_decimals_1,
+            token_vault_0: pool_state.token_vault_0,
+            token_vault_1: pool_state.token_vault_1,
+            tick_spacing: pool_state.tick_spacing,
+            genesis_tick: pool_state.genesis_tick,
+            creator: pool_state.creator,
+            created_at: pool_state.created_at,
+        }
+    }
+}
+
+pub fn get_pool_immutables(ctx: Context<GetPoolImmutables>) -> Result<PoolImmutables> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let pool_immutables = PoolImmutables::new(&pool_state);
+    #[cfg(feature = "enable-log")]
+    msg!("pool immutables:{:?}", pool_immutables);
+    Ok(pool_immutables)
+}
+
+#[cfg(test)]
+mod get_pool_immutables_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::build_pool;
+
+    #[test]
+    fn pool_immutables_test() {
+        let pool_state = build_pool(100, 10, tick_math::get_sqrt_price_at_tick(100).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        pool_state.genesis_tick = 60;
+        pool_state.creator = Pubkey::new_unique();
+        pool_state.created_at = 1_700_000_000;
+
+        let pool_immutables = PoolImmutables::new(pool_state);
+        assert_eq!(pool_immutables.amm_config, pool_state.amm_config);
+        assert_eq!(pool_immutables.token_mint_0, pool_state.token_mint_0);
+        assert_eq!(pool_immutables.token_mint_1, pool_state.token_mint_1);
+        assert_eq!(pool_immutables.tick_spacing, 10);
+        assert_eq!(pool_immutables.genesis_tick, 60);
+        assert_eq!(pool_immutables.creator, pool_state.creator);
+        assert_eq!(pool_immutables.created_at, 1_700_000_000);
+
+        // trading and a transfer of the reward owner leave it unchanged
+        pool_state.tick_current = -100;
+        pool_state.sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(-100).unwrap();
+        pool_state.liquidity = 1_000;
+        pool_state.owner = Pubkey::new_unique();
+        assert_eq!(PoolImmutables::new(pool_state), pool_immutables);
+    }
+}
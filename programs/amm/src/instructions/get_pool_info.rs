@@ -0,0 +1,62 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetPoolInfo<'info> {
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+/// The pool fields a client needs to quote and build instructions,
+/// so it doesn't have to fetch the amm config as well.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolInfo {
+    /// Which config the pool belongs
+    pub amm_config: Pubkey,
+    /// Token pair of the pool
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    /// Copied from the amm config when the pool created, never changes
+    pub tick_spacing: u16,
+    /// The current tick of the pool
+    pub tick_current: i32,
+    /// The current price of the pool as a sqrt(token_1/token_0) Q64.64 value
+    pub sqrt_price_x64: u128,
+    /// The currently in range liquidity
+    pub liquidity: u128,
+    /// The tick of the initial price when the pool created
+    pub genesis_tick: i32,
+}
+
+impl From<&PoolState> for PoolInfo {
+    fn from(pool_state: &PoolState) -> Self {
+        Self {
+            amm_config: pool_state.amm_config,
+            token_mint_0: pool_state.token_mint_0,
+            token_mint_1: pool_state.token_mint_1,
+            tick_spacing: pool_state.tick_spacing,
+            tick_current: pool_state.tick_current,
+            sqrt_price_x64: pool_state.sqrt_price_x64,
+            liquidity: pool_state.liquidity,
+            genesis_tick: pool_state.genesis_tick,
+        }
+    }
+}
+
+pub fn get_pool_info(ctx: Context<GetPoolInfo>) -> Result<PoolInfo> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    Ok(PoolInfo::from(&*pool_state))
+}
+
+pub fn get_fee_growth_global(ctx: Context<GetPoolInfo>) -> Result<()> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    emit!(FeeGrowthGlobalEvent {
+        pool_state: ctx.accounts.pool_state.key(),
+        fee_growth_global_0_x64: pool_state.fee_growth_global_0_x64,
+        fee_growth_global_1_x64: pool_state.fee_growth_global_1_x64,
+        liquidity: pool_state.liquidity,
+        tick_current: pool_state.tick_current,
+        timestamp: Clock::get()?.unix_timestamp as u64,
+    });
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use crate::libraries::{big_num::U256, fixed_point_64, full_math::MulDiv, liquidity_math};
+use crate::states::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+#[derive(Accounts)]
+pub struct GetPositionFeeApr<'info> {
+    /// The pool the position belongs to
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The position to estimate
+    #[account(constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+}
+
+/// Value token_0 and token_1 amounts in token_1 at the given price
+fn value_in_token_1(sqrt_price_x64: u128, amount_0: u64, amount_1: u64) -> U256 {
+    U256::from(amount_0)
+        .mul_div_floor(U256::from(sqrt_price_x64), U256::from(fixed_point_64::Q64))
+        .unwrap()
+        .mul_div_floor(U256::from(sqrt_price_x64), U256::from(fixed_point_64::Q64))
+        .unwrap()
+        + U256::from(amount_1)
+}
+
+/// Estimate the annualized fee yield of a position in bps.
+///
+/// The position earns `position_liquidity / pool_liquidity` of the LP fees while in range,
+/// and the fees of the window are extrapolated to a year as if the volume stays constant.
+/// Fees and principal are both valued in token_1 at the current price.
+pub fn estimate_fee_apr_bps(
+    sqrt_price_x64: u128,
+    pool_liquidity: u128,
+    position_liquidity: u128,
+    fees_in_window: (u64, u64),
+    position_amounts: (u64, u64),
+    lookback_seconds: u64,
+) -> u64 {
+    if pool_liquidity == 0 || position_liquidity == 0 || lookback_seconds == 0 {
+        return 0;
+    }
+    let position_value = value_in_token_1(sqrt_price_x64, position_amounts.0, position_amounts.1);
+    if position_value.is_zero() {
+        return 0;
+    }
+    let position_fees_value = value_in_token_1(sqrt_price_x64, fees_in_window.0, fees_in_window.1)
+        .mul_div_floor(U256::from(position_liquidity), U256::from(pool_liquidity))
+        .unwrap();
+    let apr_bps = position_fees_value
+        .mul_div_floor(
+            U256::from(SECONDS_PER_YEAR) * U256::from(BIPS_DENOMINATOR_VALUE),
+            U256::from(lookback_seconds) * position_value,
+        )
+        .unwrap();
+    if apr_bps > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        apr_bps.as_u64()
+    }
+}
+
+pub fn get_position_fee_apr(
+    ctx: Context<GetPositionFeeApr>,
+    lookback_seconds: u64,
+    total_fees_token_0_before: u64,
+    total_fees_token_1_before: u64,
+) -> Result<()> {
+    require_gt!(lookback_seconds, 0);
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let personal_position = &ctx.accounts.personal_position;
+
+    let fees_in_window = (
+        pool_state
+            .total_fees_token_0
+            .saturating_sub(total_fees_token_0_before),
+        pool_state
+            .total_fees_token_1
+            .saturating_sub(total_fees_token_1_before),
+    );
+    // only the position in range shares the fees
+    let in_range = pool_state.tick_current >= personal_position.tick_lower_index
+        && pool_state.tick_current < personal_position.tick_upper_index;
+    let position_amounts = liquidity_math::get_delta_amounts_signed(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        personal_position.tick_lower_index,
+        personal_position.tick_upper_index,
+        -i128::try_from(personal_position.liquidity).unwrap(),
+    )?;
+
+    let apr_bps = if in_range {
+        estimate_fee_apr_bps(
+            pool_state.sqrt_price_x64,
+            pool_state.liquidity,
+            personal_position.liquidity,
+            fees_in_window,
+            position_amounts,
+            lookback_seconds,
+        )
+    } else {
+        0
+    };
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "fees_in_window:{:?}, position_amounts:{:?}, apr_bps:{}",
+        fees_in_window,
+        position_amounts,
+        apr_bps
+    );
+    set_return_data(&apr_bps.to_le_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod get_position_fee_apr_test {
+    use super::*;
+
+    #[test]
+    fn estimate_fee_apr_bps_test() {
+        // price is 1
+        let sqrt_price_x64 = fixed_point_64::Q64;
+        // position owns half of the pool liquidity, principal worth 2_000_000 token_1,
+        // pool earns 1_000 token_1 fees in one day, the position gets 500 a day
+        let apr_bps = estimate_fee_apr_bps(
+            sqrt_price_x64,
+            200,
+            100,
+            (500, 500),
+            (1_000_000, 1_000_000),
+            24 * 60 * 60,
+        );
+        // 500 * 365 / 2_000_000 = 9.125%
+        assert_eq!(apr_bps, 912);
+    }
+
+    #[test]
+    fn estimate_fee_apr_bps_zero_test() {
+        let sqrt_price_x64 = fixed_point_64::Q64;
+        assert_eq!(
+            estimate_fee_apr_bps(sqrt_price_x64, 0, 100, (500, 500), (1_000, 1_000), 60),
+            0
+        );
+        assert_eq!(
+            estimate_fee_apr_bps(sqrt_price_x64, 200, 100, (500, 500), (0, 0), 60),
+            0
+        );
+        assert_eq!(
+            estimate_fee_apr_bps(sqrt_price_x64, 200, 100, (0, 0), (1_000, 1_000), 60),
+            0
+        );
+    }
+}
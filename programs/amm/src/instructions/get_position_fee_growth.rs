@@ -0,0 +1,140 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetPositionFeeGrowth<'info> {
+    /// The pool the position belongs to
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The position to be read
+    #[account(constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    /// Stores the state of the position's lower tick
+    #[account(constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    /// Stores the state of the position's upper tick
+    #[account(constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+}
+
+/// The fee growth checkpoint of a position, set as the instruction's return data.
+/// The fees earned since the last update are
+/// `(fee_growth_inside_x64 - fee_growth_inside_last_x64) * liquidity / 2^64` per token.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionFeeGrowth {
+    /// The liquidity of the position
+    pub liquidity: u128,
+    /// The token_0 fee growth inside the range as of the position's last update
+    pub fee_growth_inside_0_last_x64: u128,
+    /// The token_1 fee growth inside the range as of the position's last update
+    pub fee_growth_inside_1_last_x64: u128,
+    /// The current token_0 fee growth inside the range
+    pub fee_growth_inside_0_x64: u128,
+    /// The current token_1 fee growth inside the range
+    pub fee_growth_inside_1_x64: u128,
+}
+
+pub fn compute_position_fee_growth(
+    personal_position: &PersonalPositionState,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+    tick_current: i32,
+    fee_growth_global_0_x64: u128,
+    fee_growth_global_1_x64: u128,
+) -> PositionFeeGrowth {
+    let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = get_fee_growth_inside(
+        tick_lower,
+        tick_upper,
+        tick_current,
+        fee_growth_global_0_x64,
+        fee_growth_global_1_x64,
+    );
+    PositionFeeGrowth {
+        liquidity: personal_position.liquidity,
+        fee_growth_inside_0_last_x64: personal_position.fee_growth_inside_0_last_x64,
+        fee_growth_inside_1_last_x64: personal_position.fee_growth_inside_1_last_x64,
+        fee_growth_inside_0_x64,
+        fee_growth_inside_1_x64,
+    }
+}
+
+pub fn get_position_fee_growth(ctx: Context<GetPositionFeeGrowth>) -> Result<PositionFeeGrowth> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let personal_position = &ctx.accounts.personal_position;
+    let tick_array_lower = ctx.accounts.tick_array_lower.load()?;
+    let tick_array_upper = ctx.accounts.tick_array_upper.load()?;
+    let tick_lower = tick_array_lower
+        .get_tick_state(personal_position.tick_lower_index, pool_state.tick_spacing)?;
+    let tick_upper = tick_array_upper
+        .get_tick_state(personal_position.tick_upper_index, pool_state.tick_spacing)?;
+
+    let fee_growth = compute_position_fee_growth(
+        personal_position,
+        tick_lower,
+        tick_upper,
+        pool_state.tick_current,
+        pool_state.fee_growth_global_0_x64,
+        pool_state.fee_growth_global_1_x64,
+    );
+    #[cfg(feature = "enable-log")]
+    msg!("position fee growth:{:?}", fee_growth);
+    Ok(fee_growth)
+}
+
+#[cfg(test)]
+mod get_position_fee_growth_test {
+    use super::*;
+
+    #[test]
+    fn compute_position_fee_growth_test() {
+        let personal_position = PersonalPositionState {
+            tick_lower_index: -100,
+            tick_upper_index: 100,
+            liquidity: 1_000_000,
+            fee_growth_inside_0_last_x64: 10,
+            fee_growth_inside_1_last_x64: 20,
+            ..Default::default()
+        };
+
+        let tick_lower = TickState {
+            tick: -100,
+            fee_growth_outside_0_x64: 5,
+            fee_growth_outside_1_x64: 6,
+            ..Default::default()
+        };
+        let tick_upper = TickState {
+            tick: 100,
+            fee_growth_outside_0_x64: 7,
+            fee_growth_outside_1_x64: 8,
+            ..Default::default()
+        };
+
+        // in range, inside = global - below - above
+        let fee_growth =
+            compute_position_fee_growth(&personal_position, &tick_lower, &tick_upper, 0, 100, 200);
+        assert_eq!(
+            fee_growth,
+            PositionFeeGrowth {
+                liquidity: 1_000_000,
+                fee_growth_inside_0_last_x64: 10,
+                fee_growth_inside_1_last_x64: 20,
+                fee_growth_inside_0_x64: 100 - 5 - 7,
+                fee_growth_inside_1_x64: 200 - 6 - 8,
+            }
+        );
+
+        // above the range, inside = upper.outside - lower.outside
+        let fee_growth = compute_position_fee_growth(
+            &personal_position,
+            &tick_lower,
+            &tick_upper,
+            200,
+            100,
+            200,
+        );
+        assert_eq!(fee_growth.fee_growth_inside_0_x64, 7 - 5);
+        assert_eq!(fee_growth.fee_growth_inside_1_x64, 8 - 6);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetPositionFees<'info> {
+    /// The position to be read
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+}
+
+/// The fees a position earned over its lifetime, as of its last update
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionFees {
+    /// The fees of token_0 owed to the position owner and not collected yet
+    pub fees_owed_0: u64,
+    /// The fees of token_1 owed to the position owner and not collected yet
+    pub fees_owed_1: u64,
+    /// The fees of token_0 already collected
+    pub fees_collected_0: u64,
+    /// The fees of token_1 already collected
+    pub fees_collected_1: u64,
+}
+
+impl From<&PersonalPositionState> for PositionFees {
+    fn from(personal_position: &PersonalPositionState) -> Self {
+        Self {
+            fees_owed_0: personal_position.token_fees_owed_0,
+            fees_owed_1: personal_position.token_fees_owed_1,
+            fees_collected_0: personal_position.fees_collected_0,
+            fees_collected_1: personal_position.fees_collected_1,
+        }
+    }
+}
+
+pub fn get_position_fees(ctx: Context<GetPositionFees>) -> Result<PositionFees> {
+    let personal_position: &PersonalPositionState = &ctx.accounts.personal_position;
+    Ok(PositionFees::from(personal_position))
+}
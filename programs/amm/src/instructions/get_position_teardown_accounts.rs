@@ -0,0 +1,288 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+/// The most accounts `get_position_teardown_accounts` reports, the protocol position and
+/// the two tick arrays
+pub const MAX_TEARDOWN_ACCOUNT_NUM: usize = 3;
+
+#[derive(Accounts)]
+pub struct GetPositionTeardownAccounts<'info> {
+    /// The position to be read
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    /// The pool of the position
+    #[account(address = personal_position.pool_id)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The protocol position of the position's range
+    #[account(
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &personal_position.tick_lower_index.to_be_bytes(),
+            &personal_position.tick_upper_index.to_be_bytes(),
+        ],
+        bump,
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    /// The tick array holding the position's lower tick
+    #[account(constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    /// The tick array holding the position's upper tick, may be the lower one
+    #[account(constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+}
+
+/// The accounts left empty once a position is burned, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionTeardownAccounts {
+    /// The protocol position and tick arrays no other position refers to, at most
+    /// `MAX_TEARDOWN_ACCOUNT_NUM`. The tick array bitmap extension is shared by the
+    /// whole pool and never reported.
+    pub emptied_accounts: Vec<Pubkey>,
+}
+
+/// Gets which of the accounts a position touches hold no liquidity once the position's
+/// `liquidity` is burned: the protocol position if the position is its only liquidity,
+/// and each tick array whose initialized ticks are all uninitialized by the burn.
+/// A position already burned, with no `liquidity` left, gets the accounts already empty.
+/// Other positions may hold no liquidity too but still need the accounts to collect their
+/// fees and rewards, so an account counting any position but this one isn't reported.
+pub fn get_emptied_accounts(
+    liquidity: u128,
+    protocol_position: (Pubkey, &ProtocolPositionState),
+    tick_array_lower: (Pubkey, &TickArrayState),
+    tick_array_upper: (Pubkey, &TickArrayState),
+    tick_spacing: u16,
+) -> Result<PositionTeardownAccounts> {
+    let (protocol_position_key, protocol_position) = protocol_position;
+    let tick_lower_index = protocol_position.tick_lower_index;
+    let tick_upper_index = protocol_position.tick_upper_index;
+    check_tick_array_start_index(
+        tick_array_lower.1.start_tick_index,
+        tick_lower_index,
+        tick_spacing,
+    )?;
+    check_tick_array_start_index(
+        tick_array_upper.1.start_tick_index,
+        tick_upper_index,
+        tick_spacing,
+    )?;
+
+    let mut emptied_accounts = Vec::with_capacity(MAX_TEARDOWN_ACCOUNT_NUM);
+    if protocol_position.liquidity == liquidity && protocol_position.position_count <= 1 {
+        emptied_accounts.push(protocol_position_key);
+    }
+    // a tick is uninitialized once the burn takes its whole gross liquidity,
+    // one already uninitialized isn't counted by its tick array
+    let flipped_ticks = |tick_array: &TickArrayState, tick_index: i32| -> Result<u8> {
+        let liquidity_gross = tick_array
+            .get_tick_state(tick_index, tick_spacing)?
+            .liquidity_gross;
+        Ok(u8::from(
+            liquidity_gross > 0 && liquidity_gross == liquidity,
+        ))
+    };
+    let flip_tick_lower = flipped_ticks(tick_array_lower.1, tick_lower_index)?;
+    let flip_tick_upper = flipped_ticks(tick_array_upper.1, tick_upper_index)?;
+    if tick_array_lower.0 == tick_array_upper.0 {
+        if tick_array_lower.1.initialized_tick_count == flip_tick_lower + flip_tick_upper
+            && tick_array_lower.1.position_count <= 1
+        {
+            emptied_accounts.push(tick_array_lower.0);
+        }
+    } else {
+        for (flipped, (tick_array_key, tick_array)) in [
+            (flip_tick_lower, tick_array_lower),
+            (flip_tick_upper, tick_array_upper),
+        ] {
+            if tick_array.initialized_tick_count == flipped && tick_array.position_count <= 1 {
+                emptied_accounts.push(tick_array_key);
+            }
+        }
+    }
+    Ok(PositionTeardownAccounts { emptied_accounts })
+}
+
+pub fn get_position_teardown_accounts(
+    ctx: Context<GetPositionTeardownAccounts>,
+) -> Result<PositionTeardownAccounts> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let personal_position = &ctx.accounts.personal_position;
+    personal_position.check_position(
+        ctx.accounts.pool_state.key(),
+        &ctx.accounts.protocol_position,
+    )?;
+    let tick_array_lower = ctx.accounts.tick_array_lower.load()?;
+    let tick_array_upper = ctx.accounts.tick_array_upper.load()?;
+    get_emptied_accounts(
+        personal_position.liquidity,
+        (
+            ctx.accounts.protocol_position.key(),
+            &ctx.accounts.protocol_position,
+        ),
+        (ctx.accounts.tick_array_lower.key(), &tick_array_lower),
+        (ctx.accounts.tick_array_upper.key(), &tick_array_upper),
+        pool_state.tick_spacing,
+    )
+}
+
+#[cfg(test)]
+mod get_position_teardown_accounts_test {
+    use super::*;
+    use crate::states::protocol_position_test::build_protocol_position;
+    use crate::states::tick_array_test::{build_tick, build_tick_array_with_tick_states};
+
+    #[test]
+    fn get_emptied_accounts_in_separate_tick_arrays_test() {
+        let tick_spacing = 10;
+        let (protocol_position_key, lower_key, upper_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        // the only position in [-100, 1000), 1000 is in the next tick array
+        let protocol_position = build_protocol_position(-100, 1000, 500);
+        let tick_array_lower = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            -600,
+            tick_spacing,
+            vec![build_tick(-100, 500, 500).take()],
+        );
+        tick_array_lower.borrow_mut().initialized_tick_count = 1;
+        let tick_array_upper = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            600,
+            tick_spacing,
+            vec![
+                build_tick(1000, 500, -500).take(),
+                build_tick(1100, 200, 200).take(),
+            ],
+        );
+        tick_array_upper.borrow_mut().initialized_tick_count = 2;
+
+        let teardown = get_emptied_accounts(
+            500,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        // the upper tick array still holds another position's tick
+        assert_eq!(
+            teardown.emptied_accounts,
+            vec![protocol_position_key, lower_key]
+        );
+
+        // another position of the same range keeps everything
+        let teardown = get_emptied_accounts(
+            200,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert!(teardown.emptied_accounts.is_empty());
+
+        // once burned, the accounts left empty
+        let mut protocol_position = build_protocol_position(-100, 1000, 0);
+        tick_array_lower.borrow_mut().ticks[50] = build_tick(-100, 0, 0).take();
+        tick_array_lower.borrow_mut().initialized_tick_count = 0;
+        tick_array_upper.borrow_mut().ticks[40] = build_tick(1000, 0, 0).take();
+        tick_array_upper.borrow_mut().initialized_tick_count = 1;
+        let teardown = get_emptied_accounts(
+            0,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(
+            teardown.emptied_accounts,
+            vec![protocol_position_key, lower_key]
+        );
+        // unless another position of the range is left
+        protocol_position.liquidity = 200;
+        let teardown = get_emptied_accounts(
+            0,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(teardown.emptied_accounts, vec![lower_key]);
+        // or another position of the range holding no liquidity
+        protocol_position.liquidity = 0;
+        protocol_position.position_count = 2;
+        tick_array_lower.borrow_mut().position_count = 2;
+        let teardown = get_emptied_accounts(
+            0,
+            (protocol_position_key, &protocol_position),
+            (lower_key, &tick_array_lower.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert!(teardown.emptied_accounts.is_empty());
+
+        // the tick arrays must hold the position's ticks
+        assert!(get_emptied_accounts(
+            500,
+            (protocol_position_key, &protocol_position),
+            (upper_key, &tick_array_upper.borrow()),
+            (upper_key, &tick_array_upper.borrow()),
+            tick_spacing,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn get_emptied_accounts_in_one_tick_array_test() {
+        let tick_spacing = 10;
+        let (protocol_position_key, tick_array_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let protocol_position = build_protocol_position(-100, -50, 500);
+        let tick_array = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            -600,
+            tick_spacing,
+            vec![
+                build_tick(-100, 500, 500).take(),
+                build_tick(-50, 500, -500).take(),
+            ],
+        );
+        tick_array.borrow_mut().initialized_tick_count = 2;
+
+        let teardown = get_emptied_accounts(
+            500,
+            (protocol_position_key, &protocol_position),
+            (tick_array_key, &tick_array.borrow()),
+            (tick_array_key, &tick_array.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        // reported once
+        assert_eq!(
+            teardown.emptied_accounts,
+            vec![protocol_position_key, tick_array_key]
+        );
+        assert!(teardown.emptied_accounts.len() <= MAX_TEARDOWN_ACCOUNT_NUM);
+
+        // another initialized tick keeps the tick array
+        tick_array.borrow_mut().initialized_tick_count = 3;
+        let teardown = get_emptied_accounts(
+            500,
+            (protocol_position_key, &protocol_position),
+            (tick_array_key, &tick_array.borrow()),
+            (tick_array_key, &tick_array.borrow()),
+            tick_spacing,
+        )
+        .unwrap();
+        assert_eq!(teardown.emptied_accounts, vec![protocol_position_key]);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::libraries::{big_num::U256, full_math::MulDiv, swap_math, tick_math};
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+/// The tightest slippage suggested, even for the deepest pools of the lowest fee tier
+pub const MIN_SUGGESTED_SLIPPAGE_BPS: u16 = 10;
+/// The widest slippage suggested, for pools too shallow for the amount to trade at all
+pub const MAX_SUGGESTED_SLIPPAGE_BPS: u16 = 5_000;
+
+#[derive(Accounts)]
+pub struct GetSuggestedSlippage<'info> {
+    /// The factory state to read the fee tier
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+/// A default slippage for a front-end to offer, set as the instruction's return data.
+/// It is advisory only, no swap checks it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SuggestedSlippage {
+    /// The suggested slippage, `fee_tier_bps + price_impact_bps` within the min and max
+    pub slippage_bps: u16,
+    /// The part for the volatility of the pair, twice the trade fee rate in bips
+    pub fee_tier_bps: u16,
+    /// The part for the depth of the pool, the price impact of the amount at the in range liquidity
+    pub price_impact_bps: u16,
+}
+
+/// Suggest a slippage for swapping `amount_in` in a pool. The heuristic:
+/// * Higher fee tiers hold more volatile pairs, so the price moves more between the quote and
+///   the swap. That part is twice the trade fee rate, at least `MIN_SUGGESTED_SLIPPAGE_BPS`.
+/// * A trade of the same size landing first moves the price about as much as this one, so the
+///   price impact of `amount_in` at the in range liquidity is added. It ignores the liquidity of
+///   the ticks crossed, so it widens quickly for shallow pools. A pool without in range
+///   liquidity gets the max.
+pub fn compute_suggested_slippage(
+    trade_fee_rate: u32,
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<SuggestedSlippage> {
+    let fee_tier_bps = (u64::from(trade_fee_rate) * 2 / 100).clamp(
+        u64::from(MIN_SUGGESTED_SLIPPAGE_BPS),
+        u64::from(MAX_SUGGESTED_SLIPPAGE_BPS),
+    ) as u16;
+    let price_impact_bps = if liquidity == 0 {
+        MAX_SUGGESTED_SLIPPAGE_BPS
+    } else {
+        let step = swap_math::compute_swap_step(
+            sqrt_price_x64,
+            if zero_for_one {
+                tick_math::MIN_SQRT_PRICE_X64 + 1
+            } else {
+                tick_math::MAX_SQRT_PRICE_X64 - 1
+            },
+            liquidity,
+            amount_in,
+            trade_fee_rate,
+            true,
+            zero_for_one,
+        )?;
+        // the price after the amount over the current price, in bips,
+        // rounded towards the current price so a negligible amount has no impact
+        let sqrt_price_next_x64 = U256::from(step.sqrt_price_next_x64);
+        let price_next = sqrt_price_next_x64 * sqrt_price_next_x64;
+        let price = U256::from(sqrt_price_x64) * U256::from(sqrt_price_x64);
+        let bips = U256::from(BIPS_DENOMINATOR_VALUE);
+        let price_ratio_bps = if zero_for_one {
+            price_next.mul_div_ceil(bips, price)
+        } else {
+            price_next.mul_div_floor(bips, price)
+        }
+        .map(|ratio| ratio.min(U256::from(u128::MAX)).as_u128())
+        .unwrap_or(u128::MAX);
+        price_ratio_bps
+            .abs_diff(u128::from(BIPS_DENOMINATOR_VALUE))
+            .min(u128::from(MAX_SUGGESTED_SLIPPAGE_BPS)) as u16
+    };
+    Ok(SuggestedSlippage {
+        slippage_bps: fee_tier_bps
+            .saturating_add(price_impact_bps)
+            .min(MAX_SUGGESTED_SLIPPAGE_BPS),
+        fee_tier_bps,
+        price_impact_bps,
+    })
+}
+
+pub fn get_suggested_slippage(
+    ctx: Context<GetSuggestedSlippage>,
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<SuggestedSlippage> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let suggested_slippage = compute_suggested_slippage(
+        ctx.accounts.amm_config.trade_fee_rate,
+        pool_state.sqrt_price_x64,
+        pool_state.liquidity,
+        amount_in,
+        zero_for_one,
+    )?;
+    #[cfg(feature = "enable-log")]
+    msg!("suggested slippage:{:?}", suggested_slippage);
+    Ok(suggested_slippage)
+}
+
+#[cfg(test)]
+mod get_suggested_slippage_test {
+    use super::*;
+
+    #[test]
+    fn compute_suggested_slippage_test() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let deep_liquidity = 1_000_000_000_000_000;
+        let shallow_liquidity = 1_000_000_000;
+
+        // a deep pool of the lowest fee tier gets the min
+        let suggested_slippage =
+            compute_suggested_slippage(100, sqrt_price_x64, deep_liquidity, 1_000_000, true)
+                .unwrap();
+        assert_eq!(suggested_slippage.fee_tier_bps, MIN_SUGGESTED_SLIPPAGE_BPS);
+        assert_eq!(suggested_slippage.price_impact_bps, 0);
+        assert_eq!(suggested_slippage.slippage_bps, MIN_SUGGESTED_SLIPPAGE_BPS);
+
+        // a higher fee tier is wider
+        let suggested_slippage =
+            compute_suggested_slippage(2500, sqrt_price_x64, deep_liquidity, 1_000_000, true)
+                .unwrap();
+        assert_eq!(suggested_slippage.fee_tier_bps, 50);
+        assert_eq!(suggested_slippage.slippage_bps, 50);
+
+        // the same amount in a shallow pool adds its price impact, in either direction
+        for zero_for_one in [true, false] {
+            let suggested_slippage = compute_suggested_slippage(
+                2500,
+                sqrt_price_x64,
+                shallow_liquidity,
+                10_000_000,
+                zero_for_one,
+            )
+            .unwrap();
+            // 1% of the liquidity moves the price by about 2%
+            assert!(
+                suggested_slippage.price_impact_bps > 180
+                    && suggested_slippage.price_impact_bps <= 201
+            );
+            assert_eq!(
+                suggested_slippage.slippage_bps,
+                50 + suggested_slippage.price_impact_bps
+            );
+        }
+
+        // no liquidity in range, or an amount draining it, gets the max
+        for (liquidity, amount_in) in [(0, 1_000), (shallow_liquidity, u64::MAX)] {
+            let suggested_slippage =
+                compute_suggested_slippage(2500, sqrt_price_x64, liquidity, amount_in, false)
+                    .unwrap();
+            assert_eq!(
+                suggested_slippage.price_impact_bps,
+                MAX_SUGGESTED_SLIPPAGE_BPS
+            );
+            assert_eq!(suggested_slippage.slippage_bps, MAX_SUGGESTED_SLIPPAGE_BPS);
+        }
+    }
+}
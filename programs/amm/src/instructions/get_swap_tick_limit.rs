@@ -0,0 +1,149 @@
+use crate::libraries::tick_math;
+use crate::states::*;
+use crate::swap::{compute_swap, load_swap_tick_arrays};
+use anchor_lang::prelude::*;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+#[derive(Accounts)]
+pub struct GetSwapTickLimit<'info> {
+    /// The factory state to read the trade fee rate
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// The pool to be read
+    pub pool_state: AccountLoader<'info, PoolState>,
+    // remaining accounts
+    // tickarray_bitmap_extension: must add account if need regardless the sequence
+    // tick_array_account_1
+    // tick_array_account_2
+    // tick_array_account_...
+}
+
+/// Whether the initialized ticks of a pool cover a swap, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwapTickLimit {
+    /// Whether the swap completes with the given tick arrays
+    pub is_sufficient: bool,
+    /// The current tick after the swap, or the tick it stops at if not sufficient
+    pub tick_limit: i32,
+    /// The amount of the swap left unfilled, zero if sufficient
+    pub amount_remaining: u64,
+}
+
+/// Run the swap over the given tick arrays without a price limit and report where it stops
+pub fn compute_swap_tick_limit(
+    amm_config: &AmmConfig,
+    pool_state: &PoolState,
+    tick_array_states: &VecDeque<&TickArrayState>,
+    tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
+    amount_specified: u64,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> Result<SwapTickLimit> {
+    let sqrt_price_limit_x64 = if zero_for_one {
+        tick_math::MIN_SQRT_PRICE_X64 + 1
+    } else {
+        tick_math::MAX_SQRT_PRICE_X64 - 1
+    };
+    let (state, is_completed) = compute_swap(
+        amm_config,
+        pool_state,
+        tick_array_states,
+        tickarray_bitmap_extension,
+        amount_specified,
+        sqrt_price_limit_x64,
+        zero_for_one,
+        is_base_input,
+    )?;
+    Ok(SwapTickLimit {
+        // a swap reaching the price boundary ends with the amount unfilled
+        is_sufficient: is_completed && state.amount_specified_remaining == 0,
+        tick_limit: state.tick,
+        amount_remaining: state.amount_specified_remaining,
+    })
+}
+
+pub fn get_swap_tick_limit<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, GetSwapTickLimit<'info>>,
+    amount: u64,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> Result<SwapTickLimit> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let (tickarray_bitmap_extension, tick_array_loaders) =
+        load_swap_tick_arrays(ctx.accounts.pool_state.key(), ctx.remaining_accounts)?;
+    let tick_arrays = tick_array_loaders
+        .iter()
+        .map(|loader| loader.load())
+        .collect::<Result<Vec<_>>>()?;
+    let tick_array_states = tick_arrays
+        .iter()
+        .map(|tick_array| tick_array.deref())
+        .collect();
+
+    let swap_tick_limit = compute_swap_tick_limit(
+        &ctx.accounts.amm_config,
+        &pool_state,
+        &tick_array_states,
+        &tickarray_bitmap_extension,
+        amount,
+        zero_for_one,
+        is_base_input,
+    )?;
+    #[cfg(feature = "enable-log")]
+    msg!("swap tick limit:{:?}", swap_tick_limit);
+    Ok(swap_tick_limit)
+}
+
+#[cfg(test)]
+mod get_swap_tick_limit_test {
+    use super::*;
+    use crate::states::pool_test::build_swap_pool;
+
+    #[test]
+    fn compute_swap_tick_limit_test() {
+        // the default bitmap covers the whole tick range, no bitmap extension needed
+        let liquidity = 1_000_000_000;
+        // the only initialized tick array, [0, 3600), holds the whole liquidity in [60, 3540)
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+
+        // a small swap completes inside the range
+        let swap_tick_limit = compute_swap_tick_limit(
+            &amm_config,
+            &pool_state.borrow(),
+            &tick_array_states,
+            &None,
+            1_000_000,
+            true,
+            true,
+        )
+        .unwrap();
+        assert!(swap_tick_limit.is_sufficient);
+        assert_eq!(swap_tick_limit.amount_remaining, 0);
+        assert!(swap_tick_limit.tick_limit < 1800 && swap_tick_limit.tick_limit > 60);
+
+        // a large swap runs out of initialized ticks past the range in either direction
+        for (zero_for_one, tick_limit) in [(true, 59), (false, 3540)] {
+            let swap_tick_limit = compute_swap_tick_limit(
+                &amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                1_000_000_000,
+                zero_for_one,
+                true,
+            )
+            .unwrap();
+            assert!(!swap_tick_limit.is_sufficient);
+            assert_eq!(swap_tick_limit.tick_limit, tick_limit);
+            assert!(swap_tick_limit.amount_remaining > 0);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetTokenizedPosition<'info> {
+    /// CHECK: The mint of the position nft, only its key is used to derive the position
+    pub position_nft_mint: UncheckedAccount<'info>,
+
+    /// The position tokenized by the nft mint
+    #[account(
+        seeds = [POSITION_SEED.as_bytes(), position_nft_mint.key().as_ref()],
+        bump,
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+}
+
+pub fn get_tokenized_position(ctx: Context<GetTokenizedPosition>) -> Result<()> {
+    let personal_position = &ctx.accounts.personal_position;
+    emit!(TokenizedPositionEvent {
+        position_nft_mint: ctx.accounts.position_nft_mint.key(),
+        personal_position: personal_position.key(),
+        pool_id: personal_position.pool_id,
+        tick_lower_index: personal_position.tick_lower_index,
+        tick_upper_index: personal_position.tick_upper_index,
+        liquidity: personal_position.liquidity,
+        token_fees_owed_0: personal_position.token_fees_owed_0,
+        token_fees_owed_1: personal_position.token_fees_owed_1,
+    });
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use crate::error::ErrorCode;
+use crate::libraries::{liquidity_math, tick_math};
+use crate::open_position::{open_position, OpenPositionV2};
+use crate::states::*;
+use crate::util::*;
+use anchor_lang::prelude::*;
+
+/// The result of a liquidity import, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportLiquidityResult {
+    /// The liquidity minted to the new position
+    pub liquidity: u128,
+    /// The amount of token_0 deposited into the pool, excluding transfer fee
+    pub amount_0: u64,
+    /// The amount of token_1 deposited into the pool, excluding transfer fee
+    pub amount_1: u64,
+    /// The amount of token_0 left in the payer's token account
+    pub remainder_0: u64,
+    /// The amount of token_1 left in the payer's token account
+    pub remainder_1: u64,
+}
+
+impl ImportLiquidityResult {
+    /// Slippage check of an import, the amounts deposited must reach the minimums,
+    /// fails with `ImportSlippage`
+    pub fn check_slippage(&self, amount_0_min: u64, amount_1_min: u64) -> Result<()> {
+        require_gte!(self.amount_0, amount_0_min, ErrorCode::ImportSlippage);
+        require_gte!(self.amount_1, amount_1_min, ErrorCode::ImportSlippage);
+        Ok(())
+    }
+}
+
+/// Calculate the most liquidity that both `amount_0` and `amount_1` can pay for at the current price,
+/// and how much of each amount is left over.
+/// Funds withdrawn from a constant product pool rarely match the concentrated range ratio exactly,
+/// so the side in excess is returned rather than failing the import.
+pub fn calculate_import_amounts(
+    sqrt_price_x64: u128,
+    tick_current: i32,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    amount_0: u64,
+    amount_1: u64,
+) -> Result<ImportLiquidityResult> {
+    let liquidity = liquidity_math::get_liquidity_from_amounts(
+        sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(tick_lower_index)?,
+        tick_math::get_sqrt_price_at_tick(tick_upper_index)?,
+        amount_0,
+        amount_1,
+    );
+    let (used_amount_0, used_amount_1) = liquidity_math::get_delta_amounts_signed(
+        tick_current,
+        sqrt_price_x64,
+        tick_lower_index,
+        tick_upper_index,
+        i128::try_from(liquidity).unwrap(),
+    )?;
+    Ok(ImportLiquidityResult {
+        liquidity,
+        amount_0: used_amount_0,
+        amount_1: used_amount_1,
+        remainder_0: amount_0
+            .checked_sub(used_amount_0)
+            .ok_or(ErrorCode::ImportAmountExceeded)?,
+        remainder_1: amount_1
+            .checked_sub(used_amount_1)
+            .ok_or(ErrorCode::ImportAmountExceeded)?,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn import_liquidity<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, OpenPositionV2<'info>>,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+    amount_0: u64,
+    amount_1: u64,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    deadline: i64,
+) -> Result<ImportLiquidityResult> {
+    check_deadline(
+        deadline,
+        ctx.accounts.pool_state.load()?.max_deadline_horizon,
+    )?;
+    check_ticks_order(tick_lower_index, tick_upper_index)?;
+
+    // must deduct transfer fee before calculate liquidity
+    let amount_0_transfer_fee = get_transfer_fee(ctx.accounts.vault_0_mint.clone(), amount_0)?;
+    let amount_1_transfer_fee = get_transfer_fee(ctx.accounts.vault_1_mint.clone(), amount_1)?;
+    let mut result = {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        calculate_import_amounts(
+            pool_state.sqrt_price_x64,
+            pool_state.tick_current,
+            tick_lower_index,
+            tick_upper_index,
+            amount_0.checked_sub(amount_0_transfer_fee).unwrap(),
+            amount_1.checked_sub(amount_1_transfer_fee).unwrap(),
+        )?
+    };
+    result.check_slippage(amount_0_min, amount_1_min)?;
+
+    // the remainder is what is actually left to the payer after the deposit and its transfer fee
+    let amount_0_deposit_fee =
+        get_transfer_inverse_fee(ctx.accounts.vault_0_mint.clone(), result.amount_0)?;
+    result.remainder_0 = amount_0
+        .checked_sub(result.amount_0)
+        .and_then(|remainder| remainder.checked_sub(amount_0_deposit_fee))
+        .ok_or(ErrorCode::ImportAmountExceeded)?;
+    let amount_1_deposit_fee =
+        get_transfer_inverse_fee(ctx.accounts.vault_1_mint.clone(), result.amount_1)?;
+    result.remainder_1 = amount_1
+        .checked_sub(result.amount_1)
+        .and_then(|remainder| remainder.checked_sub(amount_1_deposit_fee))
+        .ok_or(ErrorCode::ImportAmountExceeded)?;
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "import liquidity:{}, amount_0:{}, amount_1:{}, remainder_0:{}, remainder_1:{}",
+        result.liquidity,
+        result.amount_0,
+        result.amount_1,
+        result.remainder_0,
+        result.remainder_1
+    );
+
+    open_position(
+        &ctx.accounts.payer,
+        &ctx.accounts.position_nft_owner,
+        &ctx.accounts.position_nft_mint,
+        &ctx.accounts.position_nft_account,
+        &ctx.accounts.metadata_account,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &mut ctx.accounts.protocol_position,
+        &mut ctx.accounts.personal_position,
+        &ctx.accounts.token_account_0,
+        &ctx.accounts.token_account_1,
+        &ctx.accounts.token_vault_0,
+        &ctx.accounts.token_vault_1,
+        &ctx.accounts.rent,
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+        &ctx.accounts.metadata_program,
+        Some(ctx.accounts.token_program_2022.clone()),
+        Some(ctx.accounts.vault_0_mint.clone()),
+        Some(ctx.accounts.vault_1_mint.clone()),
+        ctx.remaining_accounts,
+        ctx.bumps.protocol_position,
+        ctx.bumps.personal_position,
+        result.liquidity,
+        amount_0,
+        amount_1,
+        tick_lower_index,
+        tick_upper_index,
+        tick_array_lower_start_index,
+        tick_array_upper_start_index,
+        true,
+        None,
+    )?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod import_liquidity_test {
+    use super::*;
+
+    #[test]
+    fn imbalanced_token_0_remainder_test() {
+        let tick_current = 0;
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick_current).unwrap();
+        // a symmetric range around price 1 needs equal amounts, token_0 is over supplied
+        let result = calculate_import_amounts(
+            sqrt_price_x64,
+            tick_current,
+            -1000,
+            1000,
+            1_500_000,
+            1_000_000,
+        )
+        .unwrap();
+        assert!(result.liquidity > 0);
+        assert_eq!(result.amount_0 + result.remainder_0, 1_500_000);
+        assert_eq!(result.amount_1 + result.remainder_1, 1_000_000);
+        assert!(result.remainder_0 >= 499_000);
+        assert!(result.remainder_1 <= 1);
+    }
+
+    #[test]
+    fn imbalanced_token_1_remainder_test() {
+        let tick_current = 0;
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick_current).unwrap();
+        let result = calculate_import_amounts(
+            sqrt_price_x64,
+            tick_current,
+            -1000,
+            1000,
+            1_000_000,
+            1_200_000,
+        )
+        .unwrap();
+        assert!(result.remainder_0 <= 1);
+        assert!(result.remainder_1 >= 199_000);
+        assert_eq!(result.amount_1 + result.remainder_1, 1_200_000);
+    }
+
+    #[test]
+    fn out_of_range_remainder_test() {
+        // price below the range, only token_0 is deposited and all token_1 is returned
+        let tick_current = -2000;
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick_current).unwrap();
+        let result = calculate_import_amounts(
+            sqrt_price_x64,
+            tick_current,
+            -1000,
+            1000,
+            1_000_000,
+            500_000,
+        )
+        .unwrap();
+        assert_eq!(result.amount_1, 0);
+        assert_eq!(result.remainder_1, 500_000);
+        assert!(result.remainder_0 <= 1);
+    }
+
+    #[test]
+    fn import_slippage_test() {
+        let tick_current = 0;
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick_current).unwrap();
+        let result = calculate_import_amounts(
+            sqrt_price_x64,
+            tick_current,
+            -1000,
+            1000,
+            1_500_000,
+            1_000_000,
+        )
+        .unwrap();
+        assert!(result
+            .check_slippage(result.amount_0, result.amount_1)
+            .is_ok());
+        // the excess token_0 is returned, so a minimum of the whole amount fails
+        assert_eq!(
+            result.check_slippage(1_500_000, 0).unwrap_err(),
+            ErrorCode::ImportSlippage.into()
+        );
+        assert_eq!(
+            result
+                .check_slippage(result.amount_0, result.amount_1 + 1)
+                .unwrap_err(),
+            ErrorCode::ImportSlippage.into()
+        );
+    }
+}
@@ -264,11 +264,15 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
     amount_1_max: u64,
     base_flag: Option<bool>,
 ) -> Result<()> {
+    personal_position.check_position(pool_state_loader.key(), protocol_position)?;
     let mut liquidity = liquidity;
     let pool_state = &mut pool_state_loader.load_mut()?;
+    pool_state.check_initialized()?;
+    pool_state.check_not_deprecated()?;
     if !pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity) {
         return err!(ErrorCode::NotApproved);
     }
+    pool_state.check_token_vaults(token_vault_0.key(), token_vault_1.key())?;
     let tick_lower = personal_position.tick_lower_index;
     let tick_upper = personal_position.tick_upper_index;
 
@@ -325,6 +329,7 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
     // update rewards, must update before increase liquidity
     personal_position.update_rewards(protocol_position.reward_growth_inside, true)?;
     personal_position.liquidity = personal_position.liquidity.checked_add(liquidity).unwrap();
+    personal_position.last_increase_timestamp = Clock::get()?.unix_timestamp as u64;
 
     emit!(IncreaseLiquidityEvent {
         position_nft_mint: personal_position.nft_mint,
@@ -0,0 +1,166 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::util::AccountLoad;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(tick_lower_index: i32, tick_upper_index: i32,tick_array_lower_start_index:i32,tick_array_upper_start_index:i32)]
+pub struct InitPositionAccounts<'info> {
+    /// Pays to create the accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The pool the position will be opened in
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Store the information of market marking in range
+    #[account(
+        init_if_needed,
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_lower_index.to_be_bytes(),
+            &tick_upper_index.to_be_bytes(),
+        ],
+        bump,
+        payer = payer,
+        space = ProtocolPositionState::LEN
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    /// CHECK: Account to store data for the position's lower tick
+    #[account(
+        mut,
+        seeds = [
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_array_lower_start_index.to_be_bytes(),
+        ],
+        bump,
+    )]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// CHECK: Account to store data for the position's upper tick
+    #[account(
+        mut,
+        seeds = [
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_array_upper_start_index.to_be_bytes(),
+        ],
+        bump,
+    )]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    /// Program to create the accounts
+    pub system_program: Program<'info, System>,
+}
+
+/// Ensures the position's ticks are ordered, aligned to the tick spacing, within the tick boundaries,
+/// and the tick array start indexes are the arrays holding them
+pub fn check_position_range(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+    tick_spacing: u16,
+) -> Result<()> {
+    check_ticks_order(tick_lower_index, tick_upper_index)?;
+    check_tick_array_start_index(tick_array_lower_start_index, tick_lower_index, tick_spacing)?;
+    check_tick_array_start_index(tick_array_upper_start_index, tick_upper_index, tick_spacing)?;
+    Ok(())
+}
+
+/// Create the tick arrays and the protocol position of a range ahead of opening a position in it,
+/// so the liquidity add doesn't pay for them. The tick array bitmap bits are only flipped once the
+/// ticks hold liquidity, so there is nothing to create for them here.
+pub fn init_position_accounts(
+    ctx: Context<InitPositionAccounts>,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+) -> Result<()> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    pool_state.check_initialized()?;
+    pool_state.check_not_deprecated()?;
+    if !pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity) {
+        return err!(ErrorCode::NotApproved);
+    }
+    check_position_range(
+        tick_lower_index,
+        tick_upper_index,
+        tick_array_lower_start_index,
+        tick_array_upper_start_index,
+        pool_state.tick_spacing,
+    )?;
+
+    let tick_array_lower_loader = TickArrayState::get_or_create_tick_array(
+        ctx.accounts.payer.to_account_info(),
+        ctx.accounts.tick_array_lower.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.pool_state,
+        tick_array_lower_start_index,
+        pool_state.tick_spacing,
+    )?;
+    // the two ticks may be in the same tick array
+    let tick_array_upper_loader = if tick_array_lower_start_index == tick_array_upper_start_index {
+        AccountLoad::<TickArrayState>::try_from(&ctx.accounts.tick_array_upper.to_account_info())?
+    } else {
+        TickArrayState::get_or_create_tick_array(
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.tick_array_upper.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.pool_state,
+            tick_array_upper_start_index,
+            pool_state.tick_spacing,
+        )?
+    };
+
+    let protocol_position = &mut ctx.accounts.protocol_position;
+    if protocol_position.pool_id == Pubkey::default() {
+        protocol_position.bump = ctx.bumps.protocol_position;
+        protocol_position.pool_id = ctx.accounts.pool_state.key();
+        protocol_position.payer = ctx.accounts.payer.key();
+        protocol_position.tick_lower_index = tick_lower_index;
+        protocol_position.tick_upper_index = tick_upper_index;
+        tick_array_lower_loader
+            .load_mut()?
+            .get_tick_state_mut(tick_lower_index, pool_state.tick_spacing)?
+            .tick = tick_lower_index;
+        tick_array_upper_loader
+            .load_mut()?
+            .get_tick_state_mut(tick_upper_index, pool_state.tick_spacing)?
+            .tick = tick_upper_index;
+    }
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "init position accounts, tick_lower_index:{}, tick_upper_index:{}",
+        tick_lower_index,
+        tick_upper_index
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod init_position_accounts_test {
+    use super::*;
+
+    #[test]
+    fn check_position_range_test() {
+        let tick_spacing = 10;
+        let tick_array_span = TICK_ARRAY_SIZE * i32::from(tick_spacing);
+        assert!(check_position_range(-100, 100, -tick_array_span, 0, tick_spacing).is_ok());
+        // both ticks in the same tick array
+        assert!(check_position_range(10, 100, 0, 0, tick_spacing).is_ok());
+
+        assert_eq!(
+            check_position_range(100, -100, 0, -tick_array_span, tick_spacing).unwrap_err(),
+            ErrorCode::TickInvaildOrder.into()
+        );
+        // the tick isn't a multiple of the tick spacing
+        assert!(check_position_range(-100, 105, -tick_array_span, 0, tick_spacing).is_err());
+        // the tick array doesn't hold the tick
+        assert!(check_position_range(-100, 100, 0, 0, tick_spacing).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// The pool which may have been created before its initialized flag was added
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    if pool_state.initialized {
+        // already migrated or created with the flag
+        return Ok(());
+    }
+    #[cfg(feature = "enable-log")]
+    msg!("migrate pool:{}", ctx.accounts.pool_state.key());
+    pool_state.migrate_initialized()
+}
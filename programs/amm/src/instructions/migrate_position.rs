@@ -0,0 +1,64 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::util::is_authorized_for_token;
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token_interface::TokenAccount;
+
+#[derive(Accounts)]
+pub struct MigratePosition<'info> {
+    /// The position owner, who holds the position NFT and pays the rent of the grown account
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+
+    /// The token account for the tokenized position
+    pub nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: The position account which may be of an older layout, deserialized in instruction
+    #[account(mut, owner = crate::id())]
+    pub personal_position: UncheckedAccount<'info>,
+
+    /// Program to pay the rent of the grown account
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_position(ctx: Context<MigratePosition>) -> Result<()> {
+    let position_info = ctx.accounts.personal_position.to_account_info();
+    let personal_position =
+        PersonalPositionState::try_deserialize_legacy(&position_info.try_borrow_data()?)?;
+    require_keys_eq!(
+        ctx.accounts.nft_account.mint,
+        personal_position.nft_mint,
+        ErrorCode::NotApproved
+    );
+    is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account)?;
+    if position_info.data_len() == PersonalPositionState::LEN {
+        // already the current layout
+        return Ok(());
+    }
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "migrate position:{}, from len:{} to len:{}",
+        position_info.key(),
+        position_info.data_len(),
+        PersonalPositionState::LEN
+    );
+
+    let required_lamports = Rent::get()?
+        .minimum_balance(PersonalPositionState::LEN)
+        .saturating_sub(position_info.lamports());
+    if required_lamports > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.nft_owner.to_account_info(),
+                    to: position_info.clone(),
+                },
+            ),
+            required_lamports,
+        )?;
+    }
+    position_info.realloc(PersonalPositionState::LEN, false)?;
+    personal_position.try_serialize(&mut &mut position_info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
@@ -1,18 +1,51 @@
 pub mod create_pool;
 pub use create_pool::*;
 
+pub mod compute_initial_sqrt_price;
+pub use compute_initial_sqrt_price::*;
+
+pub mod price_band_to_ticks;
+pub use price_band_to_ticks::*;
+
 pub mod open_position;
 pub use open_position::*;
 
+pub mod init_position_accounts;
+pub use init_position_accounts::*;
+
+pub mod import_liquidity;
+pub use import_liquidity::*;
+
 pub mod close_position;
 pub use close_position::*;
 
+pub mod cleanup_accounts;
+pub use cleanup_accounts::*;
+
+pub mod close_position_complete;
+pub use close_position_complete::*;
+
+pub mod migrate_position;
+pub use migrate_position::*;
+
+pub mod migrate_pool;
+pub use migrate_pool::*;
+
+pub mod set_position_operator;
+pub use set_position_operator::*;
+
 pub mod increase_liquidity;
 pub use increase_liquidity::*;
 
 pub mod decrease_liquidity;
 pub use decrease_liquidity::*;
 
+pub mod decrease_liquidity_for_amount;
+pub use decrease_liquidity_for_amount::*;
+
+pub mod compound_position;
+pub use compound_position::*;
+
 pub mod swap;
 pub use swap::*;
 
@@ -21,6 +54,74 @@ pub use swap_v2::*;
 
 pub mod swap_router_base_in;
 pub use swap_router_base_in::*;
+pub mod swap_router_base_in_resumable;
+pub use swap_router_base_in_resumable::*;
+
+pub mod exact_input_best_of;
+pub use exact_input_best_of::*;
+
+pub mod exact_output_single;
+pub use exact_output_single::*;
+
+pub mod exact_output;
+pub use exact_output::*;
+
+pub mod swap_multi_input;
+pub use swap_multi_input::*;
+
+pub mod batch_swap;
+pub use batch_swap::*;
+
+pub mod swap_to_balance;
+pub use swap_to_balance::*;
+
+pub mod get_pool_info;
+pub use get_pool_info::*;
+
+pub mod get_pool_immutables;
+pub use get_pool_immutables::*;
+
+pub mod get_amm_config;
+pub use get_amm_config::*;
+
+pub mod get_current_observation;
+pub use get_current_observation::*;
+
+pub mod count_initialized_tick_arrays;
+pub use count_initialized_tick_arrays::*;
+
+pub mod validate_swap_accounts;
+pub use validate_swap_accounts::*;
+
+pub mod get_swap_tick_limit;
+pub use get_swap_tick_limit::*;
+
+pub mod quote_exact_input_single;
+pub use quote_exact_input_single::*;
+
+pub mod get_suggested_slippage;
+pub use get_suggested_slippage::*;
+
+pub mod get_liquidity_after_mint;
+pub use get_liquidity_after_mint::*;
+
+pub mod get_liquidity_at_tick;
+pub use get_liquidity_at_tick::*;
+
+pub mod get_position_fee_apr;
+pub use get_position_fee_apr::*;
+
+pub mod get_position_fees;
+pub use get_position_fees::*;
+
+pub mod get_position_teardown_accounts;
+pub use get_position_teardown_accounts::*;
+
+pub mod get_tokenized_position;
+pub use get_tokenized_position::*;
+
+pub mod get_position_fee_growth;
+pub use get_position_fee_growth::*;
 
 pub mod update_reward_info;
 pub use update_reward_info::*;
@@ -31,6 +132,12 @@ pub use initialize_reward::*;
 pub mod set_reward_params;
 pub use set_reward_params::*;
 
+pub mod set_pool_max_total_liquidity;
+pub use set_pool_max_total_liquidity::*;
+
+pub mod set_pool_min_swap_amount;
+pub use set_pool_min_swap_amount::*;
+
 pub mod collect_remaining_rewards;
 pub use collect_remaining_rewards::*;
 
@@ -450,12 +450,22 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
     with_matedata: bool,
     base_flag: Option<bool>,
 ) -> Result<()> {
+    if with_matedata {
+        check_metadata_target(
+            metadata_account.key(),
+            position_nft_mint.key(),
+            personal_position.key(),
+        )?;
+    }
     let mut liquidity = liquidity;
     {
         let pool_state = &mut pool_state_loader.load_mut()?;
+        pool_state.check_initialized()?;
+        pool_state.check_not_deprecated()?;
         if !pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity) {
             return err!(ErrorCode::NotApproved);
         }
+        pool_state.check_token_vaults(token_vault_0.key(), token_vault_1.key())?;
         check_ticks_order(tick_lower_index, tick_upper_index)?;
         check_tick_array_start_index(
             tick_array_lower_start_index,
@@ -499,6 +509,7 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
         if protocol_position.pool_id == Pubkey::default() {
             protocol_position.bump = protocol_position_bump;
             protocol_position.pool_id = pool_state_loader.key();
+            protocol_position.payer = payer.key();
             protocol_position.tick_lower_index = tick_lower_index;
             protocol_position.tick_upper_index = tick_upper_index;
             tick_array_lower_loader
@@ -510,6 +521,15 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
                 .get_tick_state_mut(tick_upper_index, pool_state.tick_spacing)?
                 .tick = tick_upper_index;
         }
+        protocol_position.update_position_count(true);
+        tick_array_lower_loader
+            .load_mut()?
+            .update_position_count(true);
+        if tick_array_lower_start_index != tick_array_upper_start_index {
+            tick_array_upper_loader
+                .load_mut()?
+                .update_position_count(true);
+        }
 
         let use_tickarray_bitmap_extension = pool_state.is_overflow_default_tickarray_bitmap(vec![
             tick_array_lower_start_index,
@@ -562,6 +582,7 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
         // update rewards, must update before update liquidity
         personal_position.update_rewards(protocol_position.reward_growth_inside, false)?;
         personal_position.liquidity = liquidity;
+        personal_position.last_increase_timestamp = Clock::get()?.unix_timestamp as u64;
 
         emit!(CreatePersonalPositionEvent {
             pool_state: pool_state_loader.key(),
@@ -592,6 +613,40 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
     Ok(())
 }
 
+/// Slippage check of a mint, the amounts paid including the transfer fees
+/// must be within the maximums, fails with `MintSlippage`
+pub fn check_mint_slippage(
+    amount_0: u64,
+    amount_1: u64,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Result<()> {
+    require_gte!(amount_0_max, amount_0, ErrorCode::MintSlippage);
+    require_gte!(amount_1_max, amount_1, ErrorCode::MintSlippage);
+    Ok(())
+}
+
+/// Get a copy of the tick state a position's liquidity is recorded on,
+/// a tick not used before takes the position's tick index.
+/// Fails with `TickAccountMismatch` if the state holds another tick,
+/// so the liquidity is never recorded on the wrong tick.
+pub fn get_position_tick_state(
+    tick_array: &mut TickArrayState,
+    tick_index: i32,
+    tick_spacing: u16,
+) -> Result<TickState> {
+    let mut tick_state = *tick_array.get_tick_state_mut(tick_index, tick_spacing)?;
+    if tick_state.tick == 0 {
+        tick_state.tick = tick_index;
+    }
+    require_eq!(
+        { tick_state.tick },
+        tick_index,
+        ErrorCode::TickAccountMismatch
+    );
+    Ok(tick_state)
+}
+
 /// Add liquidity to an initialized pool
 pub fn add_liquidity<'b, 'c: 'info, 'info>(
     payer: &'b Signer<'info>,
@@ -660,68 +715,16 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
     }
     assert!(*liquidity > 0);
     let liquidity_before = pool_state.liquidity;
-    require_keys_eq!(tick_array_lower_loader.load()?.pool_id, pool_state.key());
-    require_keys_eq!(tick_array_upper_loader.load()?.pool_id, pool_state.key());
-
-    // get tick_state
-    let mut tick_lower_state = *tick_array_lower_loader
-        .load_mut()?
-        .get_tick_state_mut(tick_lower_index, pool_state.tick_spacing)?;
-    let mut tick_upper_state = *tick_array_upper_loader
-        .load_mut()?
-        .get_tick_state_mut(tick_upper_index, pool_state.tick_spacing)?;
-    if tick_lower_state.tick == 0 {
-        tick_lower_state.tick = tick_lower_index;
-    }
-    if tick_upper_state.tick == 0 {
-        tick_upper_state.tick = tick_upper_index;
-    }
-    let clock = Clock::get()?;
-    let (amount_0, amount_1, flip_tick_lower, flip_tick_upper) = modify_position(
-        i128::try_from(*liquidity).unwrap(),
+    let (amount_0, amount_1) = mint_liquidity(
         pool_state,
+        tick_array_lower_loader,
+        tick_array_upper_loader,
         protocol_position,
-        &mut tick_lower_state,
-        &mut tick_upper_state,
-        clock.unix_timestamp as u64,
-    )?;
-
-    // update tick_state
-    tick_array_lower_loader.load_mut()?.update_tick_state(
+        tick_array_bitmap_extension,
+        *liquidity,
         tick_lower_index,
-        pool_state.tick_spacing,
-        tick_lower_state,
-    )?;
-    tick_array_upper_loader.load_mut()?.update_tick_state(
         tick_upper_index,
-        pool_state.tick_spacing,
-        tick_upper_state,
     )?;
-
-    if flip_tick_lower {
-        let mut tick_array_lower = tick_array_lower_loader.load_mut()?;
-        let before_init_tick_count = tick_array_lower.initialized_tick_count;
-        tick_array_lower.update_initialized_tick_count(true)?;
-
-        if before_init_tick_count == 0 {
-            pool_state.flip_tick_array_bit(
-                tick_array_bitmap_extension,
-                tick_array_lower.start_tick_index,
-            )?;
-        }
-    }
-    if flip_tick_upper {
-        let mut tick_array_upper = tick_array_upper_loader.load_mut()?;
-        let before_init_tick_count = tick_array_upper.initialized_tick_count;
-        tick_array_upper.update_initialized_tick_count(true)?;
-
-        if before_init_tick_count == 0 {
-            pool_state.flip_tick_array_bit(
-                tick_array_bitmap_extension,
-                tick_array_upper.start_tick_index,
-            )?;
-        }
-    }
     require!(
         amount_0 > 0 || amount_1 > 0,
         ErrorCode::ForbidBothZeroForSupplyLiquidity
@@ -756,16 +759,12 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
         amount_1,
         amount_1_transfer_fee
     );
-    require_gte!(
-        amount_0_max,
+    check_mint_slippage(
         amount_0 + amount_0_transfer_fee,
-        ErrorCode::PriceSlippageCheck
-    );
-    require_gte!(
-        amount_1_max,
         amount_1 + amount_1_transfer_fee,
-        ErrorCode::PriceSlippageCheck
-    );
+        amount_0_max,
+        amount_1_max,
+    )?;
     let mut token_2022_program_opt: Option<AccountInfo> = None;
     if token_program_2022.is_some() {
         token_2022_program_opt = Some(token_program_2022.clone().unwrap().to_account_info());
@@ -805,6 +804,104 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
     ))
 }
 
+/// Adds `liquidity` to the position's ticks and the pool, returning the amounts it takes
+/// without moving any tokens
+#[allow(clippy::too_many_arguments)]
+pub fn mint_liquidity<'c: 'info, 'info>(
+    pool_state: &mut RefMut<PoolState>,
+    tick_array_lower_loader: &AccountLoad<'info, TickArrayState>,
+    tick_array_upper_loader: &AccountLoad<'info, TickArrayState>,
+    protocol_position: &mut ProtocolPositionState,
+    tick_array_bitmap_extension: Option<&'c AccountInfo<'info>>,
+    liquidity: u128,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> Result<(u64, u64)> {
+    require_keys_eq!(tick_array_lower_loader.load()?.pool_id, pool_state.key());
+    require_keys_eq!(tick_array_upper_loader.load()?.pool_id, pool_state.key());
+
+    // get tick_state
+    let mut tick_lower_state = get_position_tick_state(
+        &mut *tick_array_lower_loader.load_mut()?,
+        tick_lower_index,
+        pool_state.tick_spacing,
+    )?;
+    let mut tick_upper_state = get_position_tick_state(
+        &mut *tick_array_upper_loader.load_mut()?,
+        tick_upper_index,
+        pool_state.tick_spacing,
+    )?;
+    let clock = Clock::get()?;
+    let (amount_0, amount_1, flip_tick_lower, flip_tick_upper) = modify_position(
+        i128::try_from(liquidity).unwrap(),
+        pool_state,
+        protocol_position,
+        &mut tick_lower_state,
+        &mut tick_upper_state,
+        clock.unix_timestamp as u64,
+    )?;
+
+    // the compute units logged before and after the tick update measure the single tick array
+    // path against the two tick arrays one
+    #[cfg(feature = "enable-log")]
+    anchor_lang::solana_program::log::sol_log_compute_units();
+    if tick_array_lower_loader.key() == tick_array_upper_loader.key() {
+        // both ticks in one tick array, common for positions one tick spacing wide
+        let mut tick_array = tick_array_lower_loader.load_mut()?;
+        if tick_array.update_position_tick_states(
+            pool_state.tick_spacing,
+            tick_lower_state,
+            tick_upper_state,
+            flip_tick_lower,
+            flip_tick_upper,
+            true,
+        )? {
+            pool_state
+                .flip_tick_array_bit(tick_array_bitmap_extension, tick_array.start_tick_index)?;
+        }
+    } else {
+        // update tick_state
+        tick_array_lower_loader.load_mut()?.update_tick_state(
+            tick_lower_index,
+            pool_state.tick_spacing,
+            tick_lower_state,
+        )?;
+        tick_array_upper_loader.load_mut()?.update_tick_state(
+            tick_upper_index,
+            pool_state.tick_spacing,
+            tick_upper_state,
+        )?;
+
+        if flip_tick_lower {
+            let mut tick_array_lower = tick_array_lower_loader.load_mut()?;
+            let before_init_tick_count = tick_array_lower.initialized_tick_count;
+            tick_array_lower.update_initialized_tick_count(true)?;
+
+            if before_init_tick_count == 0 {
+                pool_state.flip_tick_array_bit(
+                    tick_array_bitmap_extension,
+                    tick_array_lower.start_tick_index,
+                )?;
+            }
+        }
+        if flip_tick_upper {
+            let mut tick_array_upper = tick_array_upper_loader.load_mut()?;
+            let before_init_tick_count = tick_array_upper.initialized_tick_count;
+            tick_array_upper.update_initialized_tick_count(true)?;
+
+            if before_init_tick_count == 0 {
+                pool_state.flip_tick_array_bit(
+                    tick_array_bitmap_extension,
+                    tick_array_upper.start_tick_index,
+                )?;
+            }
+        }
+    }
+    #[cfg(feature = "enable-log")]
+    anchor_lang::solana_program::log::sol_log_compute_units();
+    Ok((amount_0, amount_1))
+}
+
 pub fn modify_position(
     liquidity_delta: i128,
     pool_state: &mut RefMut<PoolState>,
@@ -825,6 +922,7 @@ pub fn modify_position(
     let mut amount_1 = 0;
 
     if liquidity_delta != 0 {
+        pool_state.update_total_liquidity(liquidity_delta)?;
         (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
             pool_state.tick_current,
             pool_state.sqrt_price_x64,
@@ -876,6 +974,10 @@ pub fn update_position(
             true,
             &updated_reward_infos,
         )?;
+        if liquidity_delta > 0 {
+            pool_state.check_tick_liquidity(tick_lower_state.liquidity_gross)?;
+            pool_state.check_tick_liquidity(tick_upper_state.liquidity_gross)?;
+        }
         #[cfg(feature = "enable-log")]
         msg!(
             "tick_upper.reward_growths_outside_x64:{:?}, tick_lower.reward_growths_outside_x64:{:?}",
@@ -920,6 +1022,21 @@ pub fn update_position(
     Ok((flipped_lower, flipped_upper))
 }
 
+/// Ensures the metadata account is the metaplex metadata PDA of the position nft mint,
+/// and the personal position is the position tokenized by that mint
+pub fn check_metadata_target(
+    metadata_account: Pubkey,
+    position_nft_mint: Pubkey,
+    personal_position: Pubkey,
+) -> Result<()> {
+    require!(
+        metadata_account == mpl_token_metadata::accounts::Metadata::find_pda(&position_nft_mint).0
+            && personal_position == tokenized_position_pda(position_nft_mint).0,
+        ErrorCode::InvalidMetadataTarget
+    );
+    Ok(())
+}
+
 const METADATA_URI: &str =
     "https://cloudflare-ipfs.com/ipfs/QmbzJafuKY3B4t25eq9zdKZMgXiMeW4jHLzf6KE6ZmHWn1/02.json";
 
@@ -1004,13 +1121,17 @@ fn create_nft_with_metadata<'info>(
 
 #[cfg(test)]
 mod modify_position_test {
-    use super::modify_position;
+    use super::{
+        check_metadata_target, check_mint_slippage, get_position_tick_state, modify_position,
+    };
     use crate::error::ErrorCode;
     use crate::libraries::tick_math;
     use crate::states::oracle::block_timestamp_mock;
     use crate::states::pool_test::build_pool;
     use crate::states::protocol_position::*;
-    use crate::states::tick_array_test::build_tick;
+    use crate::states::tick_array_test::{build_tick, build_tick_array};
+    use crate::states::tokenized_position_pda;
+    use anchor_lang::prelude::Pubkey;
 
     #[test]
     fn liquidity_delta_zero_empty_liquidity_not_allowed_test() {
@@ -1229,4 +1350,175 @@ mod modify_position_test {
 
         // check protocol position state
     }
+
+    /// The amounts minting `liquidity` in [tick_lower, tick_upper) takes at `tick_current`,
+    /// from the liquidity formulas in floating point
+    fn reference_amounts(
+        liquidity: f64,
+        tick_current: i32,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> (f64, f64) {
+        let sqrt_price = |tick: i32| 1.0001f64.powf(f64::from(tick) / 2.0);
+        let sqrt_price_lower = sqrt_price(tick_lower);
+        let sqrt_price_upper = sqrt_price(tick_upper);
+        let sqrt_price_current = sqrt_price(tick_current).clamp(sqrt_price_lower, sqrt_price_upper);
+        (
+            liquidity * (1.0 / sqrt_price_current - 1.0 / sqrt_price_upper),
+            liquidity * (sqrt_price_current - sqrt_price_lower),
+        )
+    }
+
+    fn mint_amounts(
+        liquidity: i128,
+        tick_current: i32,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> (u64, u64) {
+        let pool_state_ref = build_pool(
+            tick_current,
+            60,
+            tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+            0,
+        );
+        let (amount_0, amount_1, _, _) = modify_position(
+            liquidity,
+            &mut pool_state_ref.borrow_mut(),
+            &mut ProtocolPositionState::default(),
+            &mut build_tick(tick_lower, 0, 0).take(),
+            &mut build_tick(tick_upper, 0, 0).take(),
+            block_timestamp_mock(),
+        )
+        .unwrap();
+        (amount_0, amount_1)
+    }
+
+    fn assert_close(amount: u64, reference: f64) {
+        // the amounts are rounded up, within the precision of the sqrt prices
+        let tolerance = reference * 1e-9 + 1.0;
+        assert!(
+            (amount as f64 - reference).abs() <= tolerance,
+            "amount:{}, reference:{}",
+            amount,
+            reference
+        );
+    }
+
+    #[test]
+    fn mint_straddling_range_ratio_test() {
+        let liquidity = 1_000_000_000_000;
+        let (tick_lower, tick_upper) = (-6000, 6000);
+        let mut last_ratio = 0.0;
+        for tick_current in [-6000, -5999, -3000, -60, 0, 1, 2999, 5940, 5999] {
+            let (amount_0, amount_1) =
+                mint_amounts(liquidity, tick_current, tick_lower, tick_upper);
+            let (reference_0, reference_1) =
+                reference_amounts(liquidity as f64, tick_current, tick_lower, tick_upper);
+            assert_close(amount_0, reference_0);
+            assert_close(amount_1, reference_1);
+            assert!(amount_0 > 0);
+            // the further the price is into the range, the more token_1 per token_0
+            let ratio = amount_1 as f64 / amount_0 as f64;
+            assert!(ratio >= last_ratio);
+            last_ratio = ratio;
+        }
+        // at the lower tick the range is in range but holds only token_0
+        assert_eq!(
+            mint_amounts(liquidity, tick_lower, tick_lower, tick_upper).1,
+            0
+        );
+        // symmetric around price 1, the amounts only differ by rounding
+        let (amount_0, amount_1) = mint_amounts(liquidity, 0, tick_lower, tick_upper);
+        assert!(amount_0.abs_diff(amount_1) <= 1);
+    }
+
+    #[test]
+    fn mint_single_sided_range_test() {
+        let liquidity = 1_000_000_000_000;
+        let (tick_lower, tick_upper) = (-6000, 6000);
+        // below the range, only token_0 for the whole range
+        let (reference_0, _) =
+            reference_amounts(liquidity as f64, tick_lower, tick_lower, tick_upper);
+        for tick_current in [-6001, -6060, -60000] {
+            let (amount_0, amount_1) =
+                mint_amounts(liquidity, tick_current, tick_lower, tick_upper);
+            assert_close(amount_0, reference_0);
+            assert_eq!(amount_1, 0);
+        }
+        // at or above the upper tick, only token_1 for the whole range
+        let (_, reference_1) =
+            reference_amounts(liquidity as f64, tick_upper, tick_lower, tick_upper);
+        for tick_current in [6000, 6060, 60000] {
+            let (amount_0, amount_1) =
+                mint_amounts(liquidity, tick_current, tick_lower, tick_upper);
+            assert_eq!(amount_0, 0);
+            assert_close(amount_1, reference_1);
+        }
+    }
+
+    #[test]
+    fn check_metadata_target_test() {
+        let position_nft_mint = Pubkey::new_unique();
+        let metadata_account =
+            mpl_token_metadata::accounts::Metadata::find_pda(&position_nft_mint).0;
+        let personal_position = tokenized_position_pda(position_nft_mint).0;
+        assert!(
+            check_metadata_target(metadata_account, position_nft_mint, personal_position).is_ok()
+        );
+
+        // the metadata of another mint
+        let other_nft_mint = Pubkey::new_unique();
+        assert_eq!(
+            check_metadata_target(
+                mpl_token_metadata::accounts::Metadata::find_pda(&other_nft_mint).0,
+                position_nft_mint,
+                personal_position
+            )
+            .unwrap_err(),
+            ErrorCode::InvalidMetadataTarget.into()
+        );
+        // a position of another mint
+        assert_eq!(
+            check_metadata_target(
+                metadata_account,
+                position_nft_mint,
+                tokenized_position_pda(other_nft_mint).0
+            )
+            .unwrap_err(),
+            ErrorCode::InvalidMetadataTarget.into()
+        );
+    }
+
+    #[test]
+    fn check_mint_slippage_test() {
+        assert!(check_mint_slippage(1_000, 2_000, 1_000, 2_000).is_ok());
+        for (amount_0, amount_1) in [(1_001, 2_000), (1_000, 2_001)] {
+            assert_eq!(
+                check_mint_slippage(amount_0, amount_1, 1_000, 2_000).unwrap_err(),
+                ErrorCode::MintSlippage.into()
+            );
+        }
+    }
+
+    #[test]
+    fn get_position_tick_state_test() {
+        let tick_spacing = 10;
+        // ticks 0 and 20 initialized in the tick array [0, 600)
+        let tick_array = build_tick_array(0, tick_spacing, vec![0, 2]);
+        let tick_array = &mut tick_array.borrow_mut();
+        let tick_state = get_position_tick_state(tick_array, 20, tick_spacing).unwrap();
+        assert_eq!({ tick_state.tick }, 20);
+        // a tick not used before takes the position's tick
+        let tick_state = get_position_tick_state(tick_array, 50, tick_spacing).unwrap();
+        assert_eq!({ tick_state.tick }, 50);
+
+        // a state recorded for another tick is rejected
+        tick_array.ticks[5].tick = 60;
+        assert_eq!(
+            get_position_tick_state(tick_array, 50, tick_spacing).unwrap_err(),
+            ErrorCode::TickAccountMismatch.into()
+        );
+        // a tick array that doesn't hold the tick
+        assert!(get_position_tick_state(tick_array, 600, tick_spacing).is_err());
+    }
 }
@@ -0,0 +1,184 @@
+use super::price_to_unbounded_sqrt_price_x64;
+use crate::error::ErrorCode;
+use crate::libraries::{big_num::U256, tick_math};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct PriceBandToTicks {}
+
+/// The range covering a price band, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PriceBandTicks {
+    /// The lower tick of the range, at or below the lower price
+    pub tick_lower: i32,
+    /// The upper tick of the range, at or above the upper price
+    pub tick_upper: i32,
+}
+
+/// The tick of a human price, a price beyond the pool's price range is clamped to the boundary tick
+fn price_to_tick(price_x64: u128, decimals_0: u8, decimals_1: u8) -> Result<i32> {
+    let sqrt_price_x64 = price_to_unbounded_sqrt_price_x64(price_x64, decimals_0, decimals_1)?;
+    if sqrt_price_x64 < U256::from(tick_math::MIN_SQRT_PRICE_X64) {
+        return Ok(tick_math::MIN_TICK);
+    }
+    if sqrt_price_x64 >= U256::from(tick_math::MAX_SQRT_PRICE_X64) {
+        return Ok(tick_math::MAX_TICK);
+    }
+    tick_math::get_tick_at_sqrt_price(sqrt_price_x64.as_u128())
+}
+
+/// Gets the range of ticks aligned to `tick_spacing` covering the band between two human prices,
+/// the lower price rounded down to a tick and the upper rounded up, within the usable ticks.
+pub fn get_price_band_ticks(
+    price_lower_x64: u128,
+    price_upper_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+    tick_spacing: u16,
+) -> Result<PriceBandTicks> {
+    require!(tick_spacing > 0, ErrorCode::InvaildTickIndex);
+    require_gt!(
+        price_upper_x64,
+        price_lower_x64,
+        ErrorCode::InvalidPriceBand
+    );
+    let tick_spacing = i32::from(tick_spacing);
+    let max_usable_tick = tick_math::MAX_TICK / tick_spacing * tick_spacing;
+    let min_usable_tick = -max_usable_tick;
+
+    let tick_lower = price_to_tick(price_lower_x64, decimals_0, decimals_1)?;
+    let mut tick_upper = price_to_tick(price_upper_x64, decimals_0, decimals_1)?;
+    // the price of the floor tick is below the upper price unless it is exactly on the tick
+    if tick_upper < tick_math::MAX_TICK
+        && U256::from(tick_math::get_sqrt_price_at_tick(tick_upper)?)
+            < price_to_unbounded_sqrt_price_x64(price_upper_x64, decimals_0, decimals_1)?
+    {
+        tick_upper += 1;
+    }
+    let mut tick_lower = (tick_lower.div_euclid(tick_spacing) * tick_spacing)
+        .clamp(min_usable_tick, max_usable_tick);
+    let mut tick_upper = ((tick_upper + tick_spacing - 1).div_euclid(tick_spacing) * tick_spacing)
+        .clamp(min_usable_tick, max_usable_tick);
+    // a band within a single tick, or clamped to a boundary, still needs a range one spacing wide
+    if tick_lower == tick_upper {
+        if tick_upper < max_usable_tick {
+            tick_upper += tick_spacing;
+        } else {
+            tick_lower -= tick_spacing;
+        }
+    }
+    Ok(PriceBandTicks {
+        tick_lower,
+        tick_upper,
+    })
+}
+
+pub fn price_band_to_ticks(
+    _ctx: Context<PriceBandToTicks>,
+    price_lower_x64: u128,
+    price_upper_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+    tick_spacing: u16,
+) -> Result<PriceBandTicks> {
+    let price_band_ticks = get_price_band_ticks(
+        price_lower_x64,
+        price_upper_x64,
+        decimals_0,
+        decimals_1,
+        tick_spacing,
+    )?;
+    #[cfg(feature = "enable-log")]
+    msg!("price band ticks:{:?}", price_band_ticks);
+    Ok(price_band_ticks)
+}
+
+#[cfg(test)]
+mod price_band_to_ticks_test {
+    use super::*;
+    use crate::libraries::fixed_point_64;
+
+    #[test]
+    fn price_band_ticks_test() {
+        let price_at_tick = |tick: i32| {
+            let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick).unwrap();
+            (U256::from(sqrt_price_x64) * U256::from(sqrt_price_x64)
+                / U256::from(fixed_point_64::Q64))
+            .as_u128()
+        };
+        // the band between ticks -600 and 600 at spacing 60, rounded outwards
+        let band = get_price_band_ticks(price_at_tick(-590), price_at_tick(590), 6, 6, 60).unwrap();
+        assert_eq!(
+            band,
+            PriceBandTicks {
+                tick_lower: -600,
+                tick_upper: 600,
+            }
+        );
+        // the band is always covered by the range
+        for (tick_lower, tick_upper, tick_spacing) in [
+            (-1000, 1000, 10),
+            (-1, 1, 1),
+            (5, 7, 60),
+            (-7, -5, 60),
+            (1234, 98765, 200),
+        ] {
+            let band = get_price_band_ticks(
+                price_at_tick(tick_lower),
+                price_at_tick(tick_upper),
+                9,
+                9,
+                tick_spacing,
+            )
+            .unwrap();
+            assert!(band.tick_lower <= tick_lower && band.tick_upper >= tick_upper);
+            assert!(
+                band.tick_upper - band.tick_lower
+                    <= tick_upper - tick_lower + 2 * i32::from(tick_spacing)
+            );
+            assert_eq!(band.tick_lower % i32::from(tick_spacing), 0);
+            assert_eq!(band.tick_upper % i32::from(tick_spacing), 0);
+        }
+
+        // the decimals shift the band like the initial price, 1 token_0 of 9 decimals
+        // for 1 token_1 of 6 decimals is a raw price of 0.001, about tick -69078
+        let band =
+            get_price_band_ticks(fixed_point_64::Q64, fixed_point_64::Q64 * 2, 9, 6, 60).unwrap();
+        assert_eq!(band.tick_lower, -69120);
+        assert!(band.tick_upper > band.tick_lower);
+    }
+
+    #[test]
+    fn price_band_clamped_test() {
+        let max_usable_tick = tick_math::MAX_TICK / 60 * 60;
+        // the widest band is clamped to the usable ticks
+        assert_eq!(
+            get_price_band_ticks(0, u128::MAX, 0, 0, 60).unwrap(),
+            PriceBandTicks {
+                tick_lower: -max_usable_tick,
+                tick_upper: max_usable_tick,
+            }
+        );
+        // a band entirely above the price range is one spacing at the top
+        assert_eq!(
+            get_price_band_ticks(u128::MAX - 1, u128::MAX, 0, 18, 60).unwrap(),
+            PriceBandTicks {
+                tick_lower: max_usable_tick - 60,
+                tick_upper: max_usable_tick,
+            }
+        );
+    }
+
+    #[test]
+    fn inverted_price_band_test() {
+        for (price_lower_x64, price_upper_x64) in [
+            (fixed_point_64::Q64 * 2, fixed_point_64::Q64),
+            (fixed_point_64::Q64, fixed_point_64::Q64),
+        ] {
+            assert_eq!(
+                get_price_band_ticks(price_lower_x64, price_upper_x64, 6, 6, 60).unwrap_err(),
+                ErrorCode::InvalidPriceBand.into()
+            );
+        }
+    }
+}
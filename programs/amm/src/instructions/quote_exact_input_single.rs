@@ -0,0 +1,180 @@
+use crate::libraries::tick_math;
+use crate::states::*;
+use crate::swap::{compute_swap, load_swap_tick_arrays};
+use anchor_lang::prelude::*;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+#[derive(Accounts)]
+pub struct QuoteExactInputSingle<'info> {
+    /// The factory state to read the trade, protocol and fund fee rates
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// The pool to be quoted
+    pub pool_state: AccountLoader<'info, PoolState>,
+    // remaining accounts
+    // tickarray_bitmap_extension: must add account if need regardless the sequence
+    // tick_array_account_1
+    // tick_array_account_2
+    // tick_array_account_...
+}
+
+/// The amounts and fees of a prospective base input swap, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// The amount of input token the swap takes, including the fee
+    pub amount_in: u64,
+    /// The amount of output token the swap gives
+    pub amount_out: u64,
+    /// The whole trade fee charged in input token, the LPs', protocol's and fund's shares
+    pub fee_amount: u64,
+    /// The protocol's share of `fee_amount`
+    pub protocol_fee: u64,
+    /// The fund's share of `fee_amount`
+    pub fund_fee: u64,
+}
+
+/// Run a base input swap of `amount_in` over the given tick arrays without changing the pool,
+/// the fees are accumulated by the swap loop exactly as a swap charges them
+pub fn compute_swap_quote(
+    amm_config: &AmmConfig,
+    pool_state: &PoolState,
+    tick_array_states: &VecDeque<&TickArrayState>,
+    tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
+    amount_in: u64,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+) -> Result<SwapQuote> {
+    let sqrt_price_limit_x64 = if sqrt_price_limit_x64 == 0 {
+        if zero_for_one {
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        } else {
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        }
+    } else {
+        sqrt_price_limit_x64
+    };
+    let (state, _) = compute_swap(
+        amm_config,
+        pool_state,
+        tick_array_states,
+        tickarray_bitmap_extension,
+        amount_in,
+        sqrt_price_limit_x64,
+        zero_for_one,
+        true,
+    )?;
+    Ok(SwapQuote {
+        amount_in: amount_in - state.amount_specified_remaining,
+        amount_out: state.amount_calculated,
+        fee_amount: state.fee_amount + state.protocol_fee + state.fund_fee,
+        protocol_fee: state.protocol_fee,
+        fund_fee: state.fund_fee,
+    })
+}
+
+pub fn quote_exact_input_single<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, QuoteExactInputSingle<'info>>,
+    amount_in: u64,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+) -> Result<SwapQuote> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let (tickarray_bitmap_extension, tick_array_loaders) =
+        load_swap_tick_arrays(ctx.accounts.pool_state.key(), ctx.remaining_accounts)?;
+    let tick_arrays = tick_array_loaders
+        .iter()
+        .map(|loader| loader.load())
+        .collect::<Result<Vec<_>>>()?;
+    let tick_array_states = tick_arrays
+        .iter()
+        .map(|tick_array| tick_array.deref())
+        .collect();
+
+    let swap_quote = compute_swap_quote(
+        &ctx.accounts.amm_config,
+        &pool_state,
+        &tick_array_states,
+        &tickarray_bitmap_extension,
+        amount_in,
+        sqrt_price_limit_x64,
+        zero_for_one,
+    )?;
+    #[cfg(feature = "enable-log")]
+    msg!("swap quote:{:?}", swap_quote);
+    Ok(swap_quote)
+}
+
+#[cfg(test)]
+mod quote_exact_input_single_test {
+    use super::*;
+    use crate::libraries::{fixed_point_64, swap_math};
+    use crate::states::pool_test::build_swap_pool;
+
+    #[test]
+    fn compute_swap_quote_fee_test() {
+        let liquidity = 1_000_000_000;
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(1800).unwrap();
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            protocol_fee_rate: 120000,
+            fund_fee_rate: 40000,
+            ..Default::default()
+        };
+
+        let amount_in = 1_000_000;
+        let swap_quote = compute_swap_quote(
+            &amm_config,
+            &pool_state.borrow(),
+            &tick_array_states,
+            &None,
+            amount_in,
+            0,
+            true,
+        )
+        .unwrap();
+        assert_eq!(swap_quote.amount_in, amount_in);
+
+        // a swap inside the range is a single step, charged the step's fee
+        let swap_step = swap_math::compute_swap_step(
+            sqrt_price_x64,
+            tick_math::get_sqrt_price_at_tick(60).unwrap(),
+            liquidity,
+            amount_in,
+            amm_config.trade_fee_rate,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_eq!(swap_quote.amount_out, swap_step.amount_out);
+        assert_eq!(swap_quote.fee_amount, swap_step.fee_amount);
+        let (lp_fee, protocol_fee, fund_fee) = amm_config.split_trade_fee(swap_step.fee_amount);
+        assert_eq!(swap_quote.protocol_fee, protocol_fee);
+        assert_eq!(swap_quote.fund_fee, fund_fee);
+
+        // the LPs' share matches the fee growth the swap accrues to the in range liquidity
+        let (state, _) = compute_swap(
+            &amm_config,
+            &pool_state.borrow(),
+            &tick_array_states,
+            &None,
+            amount_in,
+            tick_math::MIN_SQRT_PRICE_X64 + 1,
+            true,
+            true,
+        )
+        .unwrap();
+        let fee_growth_delta_x64 =
+            state.fee_growth_global_x64 - pool_state.borrow().fee_growth_global_0_x64;
+        let accrued_lp_fee = fee_growth_delta_x64 * liquidity / fixed_point_64::Q64;
+        assert!(accrued_lp_fee <= u128::from(lp_fee) && accrued_lp_fee + 1 >= u128::from(lp_fee));
+        assert_eq!(
+            swap_quote.fee_amount - swap_quote.protocol_fee - swap_quote.fund_fee,
+            lp_fee
+        );
+    }
+}
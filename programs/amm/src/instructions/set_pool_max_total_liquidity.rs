@@ -0,0 +1,26 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPoolMaxTotalLiquidity<'info> {
+    /// The pool owner or the admin
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+pub fn set_pool_max_total_liquidity(
+    ctx: Context<SetPoolMaxTotalLiquidity>,
+    max_total_liquidity: u128,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
+    let authority = ctx.accounts.authority.key();
+    if authority != pool_state.owner && authority != crate::admin::id() {
+        return err!(ErrorCode::NotApproved);
+    }
+    pool_state.set_max_total_liquidity(max_total_liquidity)?;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPoolMinSwapAmount<'info> {
+    /// The pool owner
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+pub fn set_pool_min_swap_amount(
+    ctx: Context<SetPoolMinSwapAmount>,
+    min_swap_amount_0: u64,
+    min_swap_amount_1: u64,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
+    require_keys_eq!(
+        ctx.accounts.owner.key(),
+        pool_state.owner,
+        ErrorCode::NotApproved
+    );
+    pool_state.min_swap_amount_0 = min_swap_amount_0;
+    pool_state.min_swap_amount_1 = min_swap_amount_1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod set_pool_min_swap_amount_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::{build_pool, build_pool_accounts};
+    use crate::util::program_test::{process_instruction, program_error, TestAccount};
+    use anchor_lang::ToAccountMetas;
+
+    #[test]
+    fn set_pool_min_swap_amount_owner_test() {
+        let pool_state = build_pool(0, 10, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        let (owner, admin) = (Pubkey::new_unique(), crate::admin::id());
+        pool_state.owner = owner;
+        let mut accounts = build_pool_accounts(pool_state, 0);
+        accounts.extend([TestAccount::wallet(owner), TestAccount::wallet(admin)]);
+        let set_pool_min_swap_amount = || crate::instruction::SetPoolMinSwapAmount {
+            min_swap_amount_0: 1_000,
+            min_swap_amount_1: 10,
+        };
+
+        // not even the admin can set it
+        let metas = crate::accounts::SetPoolMinSwapAmount {
+            owner: admin,
+            pool_state: pool_state.key(),
+        }
+        .to_account_metas(None);
+        assert_eq!(
+            process_instruction(&mut accounts, metas, set_pool_min_swap_amount()).unwrap_err(),
+            program_error(ErrorCode::NotApproved)
+        );
+
+        let metas = crate::accounts::SetPoolMinSwapAmount {
+            owner,
+            pool_state: pool_state.key(),
+        }
+        .to_account_metas(None);
+        process_instruction(&mut accounts, metas, set_pool_min_swap_amount()).unwrap();
+        let pool_state = accounts[1].load::<PoolState>();
+        assert_eq!({ pool_state.min_swap_amount_0 }, 1_000);
+        assert_eq!({ pool_state.min_swap_amount_1 }, 10);
+    }
+}
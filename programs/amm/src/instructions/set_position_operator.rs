@@ -0,0 +1,36 @@
+use crate::states::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+#[derive(Accounts)]
+pub struct SetPositionOperator<'info> {
+    /// The position owner, who holds the position NFT
+    pub nft_owner: Signer<'info>,
+
+    /// The token account for the tokenized position
+    #[account(
+        constraint = nft_account.mint == personal_position.nft_mint
+    )]
+    pub nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The position to set the operator of
+    #[account(mut)]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+}
+
+pub fn set_position_operator(
+    ctx: Context<SetPositionOperator>,
+    operator: Option<Pubkey>,
+) -> Result<()> {
+    let personal_position = &mut ctx.accounts.personal_position;
+    personal_position.operator_approval = match operator {
+        Some(operator) => operator_approval(operator, ctx.accounts.nft_owner.key()),
+        None => [0; 32],
+    };
+    emit!(PositionOperatorEvent {
+        position_nft_mint: personal_position.nft_mint,
+        nft_owner: ctx.accounts.nft_owner.key(),
+        operator: operator.unwrap_or_default(),
+    });
+    Ok(())
+}
@@ -57,6 +57,7 @@ pub fn set_reward_params<'a, 'b, 'c: 'info, 'info>(
     require_gt!(open_time, current_timestamp);
 
     let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
 
     if !admin_operator {
         require_keys_eq!(ctx.accounts.authority.key(), pool_state.owner);
@@ -1,6 +1,9 @@
 use crate::error::ErrorCode;
 use crate::libraries::{
-    big_num::U128, fixed_point_64, full_math::MulDiv, liquidity_math, swap_math, tick_math,
+    big_num::{U128, U256},
+    fixed_point_64,
+    full_math::MulDiv,
+    liquidity_math, swap_math, tick_math,
 };
 use crate::states::*;
 use crate::util::*;
@@ -63,6 +66,13 @@ pub struct SwapSingle<'info> {
 
     #[account(mut, constraint = tick_array.load()?.pool_id == pool_state.key())]
     pub tick_array: AccountLoader<'info, TickArrayState>,
+
+    /// The referrer token account for input token, receives the referral fee if given
+    #[account(
+        mut,
+        token::token_program = token_program,
+    )]
+    pub referrer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 }
 
 pub struct SwapAccounts<'b, 'info> {
@@ -118,6 +128,19 @@ pub struct SwapState {
     pub fund_fee: u64,
     // the current liquidity in range
     pub liquidity: u128,
+    // the initialized ticks crossed, in the order of the swap
+    pub tick_crosses: Vec<TickCross>,
+}
+
+/// An initialized tick crossed by a swap, emitted as a `TickCrossEvent` once the swap is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickCross {
+    /// The tick which is crossed
+    pub tick: i32,
+    /// The net liquidity applied to the pool when crossed, already signed by the swap direction
+    pub liquidity_net: i128,
+    /// The liquidity of the pool after crossing
+    pub liquidity_after: u128,
 }
 
 #[derive(Default)]
@@ -138,18 +161,51 @@ struct StepComputations {
     fee_amount: u64,
 }
 
-pub fn swap_internal<'b, 'info>(
+/// Load the pool's tick arrays to run `compute_swap` over from `tick_array_accounts`,
+/// the bitmap extension is picked out of them if given
+pub fn load_swap_tick_arrays<'info>(
+    pool_id: Pubkey,
+    tick_array_accounts: &'info [AccountInfo<'info>],
+) -> Result<(
+    Option<TickArrayBitmapExtension>,
+    Vec<AccountLoader<'info, TickArrayState>>,
+)> {
+    let mut tickarray_bitmap_extension = None;
+    let mut tick_array_loaders = Vec::new();
+    for account_info in tick_array_accounts.iter() {
+        if account_info
+            .key()
+            .eq(&TickArrayBitmapExtension::key(pool_id))
+        {
+            tickarray_bitmap_extension = Some(
+                *(AccountLoader::<TickArrayBitmapExtension>::try_from(account_info)?
+                    .load()?
+                    .deref()),
+            );
+            continue;
+        }
+        let tick_array_loader = AccountLoader::<TickArrayState>::try_from(account_info)?;
+        require_keys_eq!(tick_array_loader.load()?.pool_id, pool_id);
+        tick_array_loaders.push(tick_array_loader);
+    }
+    Ok((tickarray_bitmap_extension, tick_array_loaders))
+}
+
+/// Run a swap over the given tick arrays without updating the pool, returns the final state and
+/// whether the swap ran to its end rather than out of initialized ticks in the tick arrays
+#[allow(clippy::too_many_arguments)]
+pub fn compute_swap(
     amm_config: &AmmConfig,
     pool_state: &PoolState,
     tick_array_states: &VecDeque<&TickArrayState>,
-    _observation_state: &ObservationState,
     tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
     amount_specified: u64,
     sqrt_price_limit_x64: u128,
     zero_for_one: bool,
     is_base_input: bool,
-    _block_timestamp: u32,
-) -> Result<(u64, u64)> {
+) -> Result<(SwapState, bool)> {
+    pool_state.check_initialized()?;
+    pool_state.check_not_deprecated()?;
     require!(amount_specified != 0, ErrorCode::InvaildSwapAmountSpecified);
     if !pool_state.get_status_by_bit(PoolStatusBitIndex::Swap) {
         return err!(ErrorCode::NotApproved);
@@ -183,6 +239,7 @@ pub fn swap_internal<'b, 'info>(
         protocol_fee: 0,
         fund_fee: 0,
         liquidity: liquidity_start,
+        tick_crosses: Vec::new(),
     };
 
     // check observation account is owned by the pool
@@ -194,7 +251,7 @@ pub fn swap_internal<'b, 'info>(
 
     let mut tick_array_current = tick_array_states
         .get(0)
-        .expect(&tickarray_bitmap_extension.unwrap().pool_id.to_string());
+        .ok_or(ErrorCode::NotEnoughTickArrayAccount)?;
     let mut used_tick_array_index = 0;
     // find the first active tick array account
     // for _ in 0..tick_array_states.len() {
@@ -272,7 +329,7 @@ pub fn swap_internal<'b, 'info>(
                 //     zero_for_one,
                 //     is_base_input
                 // );
-                return err!(ErrorCode::LiquidityInsufficient);
+                return Ok((state, false));
             }
             while tick_array_current.start_tick_index != next_initialized_tickarray_index.unwrap() {
                 used_tick_array_index += 1;
@@ -289,7 +346,7 @@ pub fn swap_internal<'b, 'info>(
                         //     zero_for_one,
                         //     is_base_input
                         // );
-                        return err!(ErrorCode::LiquidityInsufficient);
+                        return Ok((state, false));
                     }
                 };
             }
@@ -344,7 +401,7 @@ pub fn swap_internal<'b, 'info>(
             amm_config.trade_fee_rate,
             is_base_input,
             zero_for_one,
-        );
+        )?;
         #[cfg(feature = "enable-log")]
         msg!("{:#?}", swap_step);
         if zero_for_one {
@@ -371,56 +428,49 @@ pub fn swap_internal<'b, 'info>(
                 .amount_specified_remaining
                 .checked_sub(step.amount_out)
                 .unwrap();
+            // the input of an exact output swap may not fit in u64
             state.amount_calculated = state
                 .amount_calculated
-                .checked_add(step.amount_in + step.fee_amount)
-                .unwrap();
+                .checked_add(step.amount_in)
+                .and_then(|amount| amount.checked_add(step.fee_amount))
+                .ok_or(ErrorCode::MaxTokenOverflow)?;
         }
 
         let step_fee_amount = step.fee_amount;
         // if the protocol fee is on, calculate how much is owed, decrement fee_amount, and increment protocol_fee
-        // if amm_config.protocol_fee_rate > 0 {
-        //     let delta = U128::from(step_fee_amount)
-        //         .checked_mul(amm_config.protocol_fee_rate.into())
-        //         .unwrap()
-        //         .checked_div(FEE_RATE_DENOMINATOR_VALUE.into())
-        //         .unwrap()
-        //         .as_u64();
-        //     step.fee_amount = step.fee_amount.checked_sub(delta).unwrap();
-        //     state.protocol_fee = state.protocol_fee.checked_add(delta).unwrap();
-        // }
+        // the protocol fee is rounded down, so the dust stays with the LPs, see AmmConfig::split_trade_fee
+        // when the pool returns the protocol fee to the LPs, the share stays in fee_amount
+        if amm_config.protocol_fee_rate > 0 && !pool_state.protocol_fee_to_lp {
+            let delta = amm_config.protocol_fee(step_fee_amount);
+            step.fee_amount = step.fee_amount.checked_sub(delta).unwrap();
+            state.protocol_fee = state.protocol_fee.checked_add(delta).unwrap();
+        }
         // if the fund fee is on, calculate how much is owed, decrement fee_amount, and increment fund_fee
-        // if amm_config.fund_fee_rate > 0 {
-        //     let delta = U128::from(step_fee_amount)
-        //         .checked_mul(amm_config.fund_fee_rate.into())
-        //         .unwrap()
-        //         .checked_div(FEE_RATE_DENOMINATOR_VALUE.into())
-        //         .unwrap()
-        //         .as_u64();
-        //     step.fee_amount = step.fee_amount.checked_sub(delta).unwrap();
-        //     state.fund_fee = state.fund_fee.checked_add(delta).unwrap();
-        // }
+        if amm_config.fund_fee_rate > 0 {
+            let delta = amm_config.fund_fee(step_fee_amount);
+            step.fee_amount = step.fee_amount.checked_sub(delta).unwrap();
+            state.fund_fee = state.fund_fee.checked_add(delta).unwrap();
+        }
 
         // update global fee tracker
-        // if state.liquidity > 0 {
-        //     let fee_growth_global_x64_delta = U128::from(step.fee_amount)
-        //         .mul_div_floor(U128::from(fixed_point_64::Q64), U128::from(state.liquidity))
-        //         .unwrap()
-        //         .as_u128();
-
-        //     state.fee_growth_global_x64 = state
-        //         .fee_growth_global_x64
-        //         .checked_add(fee_growth_global_x64_delta)
-        //         .unwrap();
-        //     state.fee_amount = state.fee_amount.checked_add(step.fee_amount).unwrap();
-        //     #[cfg(feature = "enable-log")]
-        //     msg!(
-        //         "fee_growth_global_x64_delta:{}, state.fee_growth_global_x64:{}, state.liquidity:{}, step.fee_amount:{}, state.fee_amount:{}",
-        //         fee_growth_global_x64_delta,
-        //         state.fee_growth_global_x64, state.liquidity, step.fee_amount, state.fee_amount
-        //     );
-        // }
-        // shift tick if we reached the next price
+        if state.liquidity > 0 {
+            let fee_growth_global_x64_delta =
+                get_fee_growth_delta_x64(step.fee_amount, state.liquidity)?;
+
+            state.fee_growth_global_x64 = state
+                .fee_growth_global_x64
+                .checked_add(fee_growth_global_x64_delta)
+                .unwrap();
+            state.fee_amount = state.fee_amount.checked_add(step.fee_amount).unwrap();
+            #[cfg(feature = "enable-log")]
+            msg!(
+                "fee_growth_global_x64_delta:{}, state.fee_growth_global_x64:{}, state.liquidity:{}, step.fee_amount:{}, state.fee_amount:{}",
+                fee_growth_global_x64_delta,
+                state.fee_growth_global_x64, state.liquidity, step.fee_amount, state.fee_amount
+            );
+        }
+        // shift tick if we reached the next price, landing exactly on an initialized tick crosses it,
+        // so a zero for one swap ends below the tick and a one for zero swap at it
         if state.sqrt_price_x64 == step.sqrt_price_next_x64 {
             // if the tick is initialized, run the tick transition
             if step.initialized {
@@ -447,10 +497,18 @@ pub fn swap_internal<'b, 'info>(
                 //     *next_initialized_tick,
                 // )?;
 
+                // liquidity_net is added crossing a tick left to right, as the price rises,
+                // so a zero for one swap crossing it right to left subtracts it
                 if zero_for_one {
                     liquidity_net = liquidity_net.neg();
                 }
                 state.liquidity = liquidity_math::add_delta(state.liquidity, liquidity_net)?;
+
+                state.tick_crosses.push(TickCross {
+                    tick: step.tick_next,
+                    liquidity_net,
+                    liquidity_after: state.liquidity,
+                });
             }
 
             state.tick = if zero_for_one {
@@ -493,6 +551,48 @@ pub fn swap_internal<'b, 'info>(
         //     zero_for_one,
         // });
     }
+    Ok((state, true))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn swap_internal(
+    amm_config: &AmmConfig,
+    pool_state: &PoolState,
+    tick_array_states: &VecDeque<&TickArrayState>,
+    _observation_state: &ObservationState,
+    tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
+    output_vault_amount: u64,
+    amount_specified: u64,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+    is_base_input: bool,
+    _block_timestamp: u32,
+) -> Result<(u64, u64)> {
+    let (state, is_completed) = compute_swap(
+        amm_config,
+        pool_state,
+        tick_array_states,
+        tickarray_bitmap_extension,
+        amount_specified,
+        sqrt_price_limit_x64,
+        zero_for_one,
+        is_base_input,
+    )?;
+    // the swap ran out of initialized ticks before its end
+    require!(is_completed, ErrorCode::LiquidityInsufficient);
+    // only an executed swap reports its crossings, a quote running `compute_swap` doesn't
+    if !state.tick_crosses.is_empty() {
+        let pool_id = pool_state.key();
+        for tick_cross in state.tick_crosses.iter() {
+            emit!(TickCrossEvent {
+                pool_state: pool_id,
+                tick: tick_cross.tick,
+                liquidity_net: tick_cross.liquidity_net,
+                liquidity_after: tick_cross.liquidity_after,
+                zero_for_one,
+            });
+        }
+    }
     // update tick
     // if state.tick != pool_state.tick_current {
     //     pool_state.tick_current = state.tick;
@@ -531,6 +631,9 @@ pub fn swap_internal<'b, 'info>(
                 .unwrap(),
         )
     };
+    // the output vault must cover the output before it is transferred
+    let amount_out = if zero_for_one { amount_1 } else { amount_0 };
+    get_output_reserve(output_vault_amount, amount_out)?;
 
     // if zero_for_one {
     //     pool_state.fee_growth_global_0_x64 = state.fee_growth_global_x64;
@@ -591,26 +694,452 @@ pub fn swap_internal<'b, 'info>(
     Ok((amount_0, amount_1))
 }
 
+/// The version of `SwapResult`, bumped when fields are appended
+pub const SWAP_RESULT_VERSION: u8 = 4;
+
+/// The result of a swap, set as the return data of every swap instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    /// The layout version, so consumers can tell the fields apart
+    pub version: u8,
+    /// The amount of input token paid by the user, including the referral fee
+    pub amount_in: u64,
+    /// The amount of output token received by the user
+    pub amount_out: u64,
+    /// The current tick of the pool after the swap, the last pool for a multi-pool swap
+    pub tick_current: i32,
+    /// The current price of the pool after the swap as a sqrt(token_1/token_0) Q64.64 value
+    pub sqrt_price_x64: u128,
+    /// The in range liquidity of the pool after the swap
+    pub liquidity: u128,
+    /// The average price the swap executed at, the input paid per output received as a Q64.64
+    pub average_price_x64: u128,
+    /// The amount of input token actually paid, including the referral fee.
+    /// Less than `amount_in` only when a base input swap stopped at its `sqrt_price_limit_x64`
+    /// and was partially filled, the rest of `amount_in` stays with the user.
+    pub amount_in_consumed: u64,
+    /// Set when a partially filled swap stopped at the caller's `sqrt_price_limit_x64`,
+    /// the pool can still trade further at a worse price
+    pub price_limit_reached: bool,
+    /// Set when a partially filled swap drained the liquidity in its direction and the price
+    /// reached the boundary, nothing more can be traded that way whatever the price limit
+    pub pool_exhausted: bool,
+}
+
+impl SwapResult {
+    pub fn new(pool_state: &PoolState, amount_in: u64, amount_out: u64) -> Self {
+        Self::new_partial(pool_state, amount_in, amount_in, amount_out)
+    }
+
+    /// The result of a swap that may have consumed less than the specified `amount_in`
+    pub fn new_partial(
+        pool_state: &PoolState,
+        amount_in: u64,
+        amount_in_consumed: u64,
+        amount_out: u64,
+    ) -> Self {
+        Self {
+            version: SWAP_RESULT_VERSION,
+            amount_in,
+            amount_out,
+            tick_current: pool_state.tick_current,
+            sqrt_price_x64: pool_state.sqrt_price_x64,
+            liquidity: pool_state.liquidity,
+            average_price_x64: get_average_price_x64(amount_in_consumed, amount_out),
+            amount_in_consumed,
+            price_limit_reached: false,
+            pool_exhausted: false,
+        }
+    }
+
+    /// Sets why a partially filled swap stopped, from the price the pool ended at.
+    /// Reaching the price boundary means the pool is exhausted, even if it is the price limit too.
+    pub fn with_partial_fill_reason(
+        mut self,
+        sqrt_price_limit_x64: u128,
+        zero_for_one: bool,
+    ) -> Self {
+        if self.amount_in_consumed >= self.amount_in {
+            return self;
+        }
+        let boundary_sqrt_price_x64 = if zero_for_one {
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        } else {
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        };
+        self.pool_exhausted = if zero_for_one {
+            self.sqrt_price_x64 <= boundary_sqrt_price_x64
+        } else {
+            self.sqrt_price_x64 >= boundary_sqrt_price_x64
+        };
+        self.price_limit_reached =
+            !self.pool_exhausted && self.sqrt_price_x64 == sqrt_price_limit_x64;
+        self
+    }
+}
+
+/// The average execution price `amount_in / amount_out` as a Q64.64, rounded down.
+/// Unlike the pool's spot price it includes the fees and the price impact, zero without output.
+pub fn get_average_price_x64(amount_in: u64, amount_out: u64) -> u128 {
+    if amount_out == 0 {
+        return 0;
+    }
+    (u128::from(amount_in) << fixed_point_64::RESOLUTION) / u128::from(amount_out)
+}
+
+/// An optional bound on how far the pool's spot price may deviate from its own TWAP for a swap
+/// to execute, to avoid trading into a manipulated price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TwapBound {
+    /// The seconds the TWAP is averaged over
+    pub window_seconds: u32,
+    /// The max deviation of the spot price from the TWAP, denominated in bips (10^-4)
+    pub max_deviation_bps: u16,
+}
+
+/// Ensures the spot price doesn't deviate from the TWAP by more than `max_deviation_bps`
+pub fn check_twap_deviation(
+    sqrt_price_x64: u128,
+    twap_price_x64: u128,
+    max_deviation_bps: u16,
+) -> Result<()> {
+    let spot_price_x64 = U128::from(sqrt_price_x64)
+        .mul_div_floor(U128::from(sqrt_price_x64), U128::from(fixed_point_64::Q64))
+        .unwrap()
+        .as_u128();
+    let deviation_x64 = spot_price_x64.abs_diff(twap_price_x64);
+    // deviation / twap <= max_deviation_bps / 10000
+    require!(
+        U256::from(deviation_x64) * U256::from(BIPS_DENOMINATOR_VALUE)
+            <= U256::from(twap_price_x64) * U256::from(max_deviation_bps),
+        ErrorCode::SpotDeviatesFromTwap
+    );
+    Ok(())
+}
+
+/// An optional bound on the pool's TWAP once a swap has written its observation, for protocols
+/// that both trade in the pool and read its oracle, so their own swap can't push the TWAP out
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TwapPriceBound {
+    /// The seconds the TWAP is averaged over
+    pub window_seconds: u32,
+    /// The lowest the TWAP may be after the swap, as a token_1/token_0 Q64.64 price
+    pub min_price_x64: u128,
+    /// The highest the TWAP may be after the swap, as a token_1/token_0 Q64.64 price
+    pub max_price_x64: u128,
+}
+
+/// Ensures the TWAP is within the bound's prices, inclusive
+pub fn check_twap_price_bound(
+    twap_price_x64: u128,
+    twap_price_bound: &TwapPriceBound,
+) -> Result<()> {
+    require!(
+        twap_price_x64 >= twap_price_bound.min_price_x64
+            && twap_price_x64 <= twap_price_bound.max_price_x64,
+        ErrorCode::TwapOutOfBound
+    );
+    Ok(())
+}
+
+/// The max fee growth a swap step may add, a fee of 2^32 tokens per unit of in range liquidity.
+/// It's only reached when the liquidity is dust compared to the fee, where the fee growth
+/// would otherwise run towards overflowing the fee growth and the owed fees of positions.
+pub const MAX_FEE_GROWTH_DELTA_X64: u128 = 1 << 96;
+
+/// The fee growth per unit of liquidity the LPs' `fee_amount` adds, as a Q64.64
+pub fn get_fee_growth_delta_x64(fee_amount: u64, liquidity: u128) -> Result<u128> {
+    let fee_growth_delta_x64 = U128::from(fee_amount)
+        .mul_div_floor(U128::from(fixed_point_64::Q64), U128::from(liquidity))
+        .unwrap()
+        .as_u128();
+    require_gte!(
+        MAX_FEE_GROWTH_DELTA_X64,
+        fee_growth_delta_x64,
+        ErrorCode::FeeGrowthDeltaTooLarge
+    );
+    Ok(fee_growth_delta_x64)
+}
+
+/// Gets the output vault's reserve left after paying `amount_out`, so a swap paying out more
+/// than the vault holds reverts with a clear error rather than wrapping the reserve
+pub fn get_output_reserve(output_vault_amount: u64, amount_out: u64) -> Result<u64> {
+    output_vault_amount
+        .checked_sub(amount_out)
+        .ok_or(error!(ErrorCode::InsufficientReserve))
+}
+
+/// Check the input token account holds enough to pay `amount_in`, so the swap fails
+/// before any computation rather than inside the token transfer
+pub fn check_input_balance(input_token_account: &TokenAccount, amount_in: u64) -> Result<()> {
+    require_gte!(
+        input_token_account.amount,
+        amount_in,
+        ErrorCode::InsufficientInputBalance
+    );
+    Ok(())
+}
+
+/// Ensures the signer owns or is delegated on the input token account, so the swap fails
+/// with a clear error rather than inside the token transfer
+pub fn check_input_authority(input_token_account: &TokenAccount, signer: Pubkey) -> Result<()> {
+    check_token_authority(
+        input_token_account.owner,
+        input_token_account.delegate.into(),
+        signer,
+    )
+}
+
+/// Authority check of `check_input_authority` on the token account's owner and delegate
+pub fn check_token_authority(
+    owner: Pubkey,
+    delegate: Option<Pubkey>,
+    signer: Pubkey,
+) -> Result<()> {
+    require!(
+        owner == signer || delegate == Some(signer),
+        ErrorCode::InputAccountUnauthorized
+    );
+    Ok(())
+}
+
+/// Reject swap base input without slippage protection if the pool opts in
+pub fn check_amount_out_minimum(
+    pool_state: &PoolState,
+    is_base_input: bool,
+    amount_out_minimum: u64,
+) -> Result<()> {
+    if is_base_input
+        && amount_out_minimum == 0
+        && !pool_state.get_status_by_bit(PoolStatusBitIndex::SwapWithoutOutputMinimum)
+    {
+        return err!(ErrorCode::ZeroAmountOutMinimum);
+    }
+    Ok(())
+}
+
+/// Reject dust swaps below the pool's minimum of the token `amount` is in, `amount` is the
+/// input of swap base input or the output of swap base output
+pub fn check_min_swap_amount(
+    pool_state: &PoolState,
+    token_mint: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let min_swap_amount = if token_mint == pool_state.token_mint_0 {
+        pool_state.min_swap_amount_0
+    } else {
+        pool_state.min_swap_amount_1
+    };
+    require_gte!(amount, min_swap_amount, ErrorCode::SwapAmountTooSmall);
+    Ok(())
+}
+
+/// Ensures the swap didn't move the current tick across `forbidden_tick`, in either direction.
+/// Unlike the price limit, which ends the swap with a partial fill, crossing reverts the swap.
+pub fn check_forbidden_tick(
+    tick_before: i32,
+    tick_after: i32,
+    forbidden_tick: Option<i32>,
+) -> Result<()> {
+    if let Some(forbidden_tick) = forbidden_tick {
+        require!(
+            (tick_before >= forbidden_tick) == (tick_after >= forbidden_tick),
+            ErrorCode::ForbiddenTickCrossed
+        );
+    }
+    Ok(())
+}
+
+/// Ensures the input and output vaults hold the pool's two tokens, one each
+pub fn check_vault_pair(
+    pool_state: &PoolState,
+    input_vault_mint: Pubkey,
+    output_vault_mint: Pubkey,
+) -> Result<()> {
+    require!(
+        input_vault_mint != output_vault_mint
+            && ((input_vault_mint == pool_state.token_mint_0
+                && output_vault_mint == pool_state.token_mint_1)
+                || (input_vault_mint == pool_state.token_mint_1
+                    && output_vault_mint == pool_state.token_mint_0)),
+        ErrorCode::InvalidVaultPair
+    );
+    Ok(())
+}
+
+/// The price limit of a swap, a zero `sqrt_price_limit_x64` is the furthest price in the
+/// swap's direction
+pub fn get_sqrt_price_limit_x64(sqrt_price_limit_x64: u128, zero_for_one: bool) -> u128 {
+    if sqrt_price_limit_x64 != 0 {
+        sqrt_price_limit_x64
+    } else if zero_for_one {
+        tick_math::MIN_SQRT_PRICE_X64 + 1
+    } else {
+        tick_math::MAX_SQRT_PRICE_X64 - 1
+    }
+}
+
+/// Estimate whether a swap on the current liquidity moves the price out of the tick array
+/// holding the current tick, so it needs the next tick arrays in the remaining accounts.
+/// The liquidity change of the ticks crossed inside the tick array is not accounted for.
+pub fn swap_leaves_current_tick_array(
+    pool_state: &PoolState,
+    fee_rate: u32,
+    amount_specified: u64,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> Result<bool> {
+    if pool_state.liquidity == 0 {
+        return Ok(false);
+    }
+    let start_index =
+        TickArrayState::get_array_start_index(pool_state.tick_current, pool_state.tick_spacing);
+    let boundary_tick = if zero_for_one {
+        start_index.max(tick_math::MIN_TICK)
+    } else {
+        (start_index + TickArrayState::tick_count(pool_state.tick_spacing)).min(tick_math::MAX_TICK)
+    };
+    let boundary_sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(boundary_tick)?;
+    let sqrt_price_limit_x64 = get_sqrt_price_limit_x64(sqrt_price_limit_x64, zero_for_one);
+    // the swap stops at the price limit inside the tick array
+    if (zero_for_one && sqrt_price_limit_x64 >= boundary_sqrt_price_x64)
+        || (!zero_for_one && sqrt_price_limit_x64 <= boundary_sqrt_price_x64)
+    {
+        return Ok(false);
+    }
+    let step = swap_math::compute_swap_step(
+        pool_state.sqrt_price_x64,
+        boundary_sqrt_price_x64,
+        pool_state.liquidity,
+        amount_specified,
+        fee_rate,
+        is_base_input,
+        zero_for_one,
+    )?;
+    let amount_used = if is_base_input {
+        step.amount_in.saturating_add(step.fee_amount)
+    } else {
+        step.amount_out
+    };
+    Ok(step.sqrt_price_next_x64 == boundary_sqrt_price_x64 && amount_specified > amount_used)
+}
+
+/// Calculate the referral fee the swapper pays on top of the trade fee for `amount_in`,
+/// zero without a referrer
+pub fn get_referral_fee(
+    amm_config: &AmmConfig,
+    referrer_token_mint: Option<Pubkey>,
+    input_token_mint: Pubkey,
+    amount_in: u64,
+) -> Result<u64> {
+    match referrer_token_mint {
+        Some(referrer_token_mint) => {
+            require_keys_eq!(
+                referrer_token_mint,
+                input_token_mint,
+                ErrorCode::InvalidReferrerAccount
+            );
+            Ok(amm_config.referral_fee(amount_in))
+        }
+        None => Ok(0),
+    }
+}
+
 /// Performs a single exact input/output swap
 /// if is_base_input = true, return vaule is the max_amount_out, otherwise is min_amount_in
 pub fn exact_internal<'b, 'c: 'info, 'info>(
-    _ctx: &mut SwapAccounts<'b, 'info>,
+    ctx: &mut SwapAccounts<'b, 'info>,
     _remaining_accounts: &'c [AccountInfo<'info>],
     _amount_specified: u64,
     _sqrt_price_limit_x64: u128,
     _is_base_input: bool,
 ) -> Result<u64> {
+    let pool_state = ctx.pool_state.load()?;
+    pool_state.check_initialized()?;
+    pool_state.check_not_deprecated()?;
     Ok(0)
 }
 
+/// Transfer the referral fee from the swapper to the referrer, nothing without a referrer
+fn transfer_referral_fee(accounts: &SwapSingle, referral_fee: u64) -> Result<()> {
+    if let Some(referrer_token_account) = &accounts.referrer_token_account {
+        transfer_from_user_to_pool_vault(
+            &accounts.payer,
+            &accounts.input_token_account,
+            referrer_token_account,
+            None,
+            &accounts.token_program,
+            None,
+            referral_fee,
+        )?;
+    }
+    Ok(())
+}
+
 pub fn swap<'a, 'b, 'c: 'info, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, SwapSingle<'info>>,
     amount: u64,
     other_amount_threshold: u64,
     sqrt_price_limit_x64: u128,
     is_base_input: bool,
-) -> Result<()> {
-    let amount = exact_internal(
+) -> Result<SwapResult> {
+    {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        check_vault_pair(
+            &pool_state,
+            ctx.accounts.input_vault.mint,
+            ctx.accounts.output_vault.mint,
+        )?;
+        check_amount_out_minimum(&pool_state, is_base_input, other_amount_threshold)?;
+        check_min_swap_amount(
+            &pool_state,
+            if is_base_input {
+                ctx.accounts.input_vault.mint
+            } else {
+                ctx.accounts.output_vault.mint
+            },
+            amount,
+        )?;
+    }
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    let referrer_token_account = ctx.accounts.referrer_token_account.as_deref();
+    let mut amount_specified = amount;
+    let mut referral_fee = 0;
+    if is_base_input {
+        check_input_balance(&ctx.accounts.input_token_account, amount)?;
+        // the amount includes the referral fee, it's skimmed before the swap,
+        // which takes exactly the rest
+        referral_fee = get_referral_fee(
+            &ctx.accounts.amm_config,
+            referrer_token_account.map(|account| account.mint),
+            ctx.accounts.input_token_account.mint,
+            amount,
+        )?;
+        amount_specified = amount - referral_fee;
+    } else {
+        // the output of swap base output is known up front
+        get_output_reserve(ctx.accounts.output_vault.amount, amount)?;
+    }
+    if ctx.remaining_accounts.is_empty() {
+        // only the tick array in the accounts can be crossed
+        let pool_state = ctx.accounts.pool_state.load()?;
+        require!(
+            !swap_leaves_current_tick_array(
+                &pool_state,
+                ctx.accounts.amm_config.trade_fee_rate,
+                amount_specified,
+                sqrt_price_limit_x64,
+                ctx.accounts.input_vault.mint == pool_state.token_mint_0,
+                is_base_input,
+            )?,
+            ErrorCode::MissingTickArrayAccounts
+        );
+    }
+    let input_balance_before = ctx.accounts.input_token_account.amount;
+    let output_balance_before = ctx.accounts.output_token_account.amount;
+    transfer_referral_fee(ctx.accounts, referral_fee)?;
+    exact_internal(
         &mut SwapAccounts {
             signer: ctx.accounts.payer.clone(),
             amm_config: &ctx.accounts.amm_config,
@@ -624,21 +1153,1239 @@ pub fn swap<'a, 'b, 'c: 'info, 'info>(
             observation_state: &mut ctx.accounts.observation_state,
         },
         ctx.remaining_accounts,
-        amount,
+        amount_specified,
         sqrt_price_limit_x64,
         is_base_input,
     )?;
+    // the amounts are what the swap moved between the user's token accounts, a swap stopped
+    // by the price limit takes less than the amount specified, the input paid includes the
+    // referral fee skimmed for a base input swap
+    ctx.accounts.input_token_account.reload()?;
+    ctx.accounts.output_token_account.reload()?;
+    let amount_paid_in = input_balance_before - ctx.accounts.input_token_account.amount;
+    let amount_out = ctx.accounts.output_token_account.amount - output_balance_before;
     if is_base_input {
         require!(
-            amount >= other_amount_threshold,
+            amount_out >= other_amount_threshold,
             ErrorCode::TooLittleOutputReceived
         );
     } else {
+        // the input of swap base output is only known after the swap, so is its referral fee
+        referral_fee = get_referral_fee(
+            &ctx.accounts.amm_config,
+            referrer_token_account.map(|account| account.mint),
+            ctx.accounts.input_token_account.mint,
+            amount_paid_in,
+        )?;
         require!(
-            amount <= other_amount_threshold,
+            amount_paid_in + referral_fee <= other_amount_threshold,
             ErrorCode::TooMuchInputPaid
         );
+        check_input_balance(&ctx.accounts.input_token_account, referral_fee)?;
+        transfer_referral_fee(ctx.accounts, referral_fee)?;
     }
 
-    Ok(())
+    let (amount_in, amount_in_consumed) = if is_base_input {
+        (amount, amount_paid_in)
+    } else {
+        (amount_paid_in + referral_fee, amount_paid_in + referral_fee)
+    };
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let zero_for_one = ctx.accounts.input_vault.mint == pool_state.token_mint_0;
+    Ok(
+        SwapResult::new_partial(&pool_state, amount_in, amount_in_consumed, amount_out)
+            .with_partial_fill_reason(sqrt_price_limit_x64, zero_for_one),
+    )
+}
+
+#[cfg(test)]
+mod swap_test {
+    use super::*;
+    use crate::states::pool_test::{build_pool, build_pool_accounts, build_swap_pool};
+    use crate::states::tick_array_test::{build_tick, build_tick_array_with_tick_states};
+    use crate::util::program_test::{process_instruction, program_error, return_data, TestAccount};
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+    use std::convert::identity;
+
+    /// The LPs' share of a step fee and the fee growth it adds, as the swap accounts them
+    fn lp_fee_growth(amm_config: &AmmConfig, fee_amount: u64, liquidity: u128) -> (u64, u128) {
+        let (lp_fee, protocol_fee, fund_fee) = amm_config.split_trade_fee(fee_amount);
+        assert_eq!(lp_fee + protocol_fee + fund_fee, fee_amount);
+        let fee_growth_global_x64_delta = get_fee_growth_delta_x64(lp_fee, liquidity).unwrap();
+        (lp_fee, fee_growth_global_x64_delta)
+    }
+
+    #[test]
+    fn minimal_protocol_fee_test() {
+        let mut amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+        // the smallest nonzero protocol share
+        amm_config.protocol_fee_rate = 1;
+        let liquidity = 1u128 << 20;
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+
+        // the smallest nonzero swap can't move the price, the whole input is the fee
+        let step = swap_math::compute_swap_step(
+            sqrt_price_x64,
+            tick_math::get_sqrt_price_at_tick(-10).unwrap(),
+            liquidity,
+            1,
+            amm_config.trade_fee_rate,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_eq!(step.amount_in + step.fee_amount, 1);
+        assert_eq!(step.fee_amount, 1);
+
+        // the protocol share rounds to zero and the LPs get the full fee
+        let (lp_fee, fee_growth_global_x64_delta) =
+            lp_fee_growth(&amm_config, step.fee_amount, liquidity);
+        assert_eq!(lp_fee, 1);
+        assert_eq!(fee_growth_global_x64_delta, fixed_point_64::Q64 / liquidity);
+
+        // the fee growth accounts for every unit of the LPs' fee
+        for fee_amount in [1, 2, 999, 1_000_000] {
+            let (lp_fee, fee_growth_global_x64_delta) =
+                lp_fee_growth(&amm_config, fee_amount, liquidity);
+            assert_eq!(lp_fee, fee_amount - amm_config.protocol_fee(fee_amount));
+            assert_eq!(
+                U128::from(fee_growth_global_x64_delta)
+                    .mul_div_floor(U128::from(liquidity), U128::from(fixed_point_64::Q64))
+                    .unwrap()
+                    .as_u64(),
+                lp_fee
+            );
+        }
+
+        // no protocol or fund fee at all
+        amm_config.protocol_fee_rate = 0;
+        assert_eq!(amm_config.split_trade_fee(1), (1, 0, 0));
+        assert_eq!(amm_config.split_trade_fee(u64::MAX), (u64::MAX, 0, 0));
+    }
+
+    #[test]
+    fn swap_fee_shares_test() {
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let swap = |amm_config: &AmmConfig, amount: u64| {
+            let (state, is_completed) = compute_swap(
+                amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                amount,
+                tick_math::MIN_SQRT_PRICE_X64 + 1,
+                true,
+                true,
+            )
+            .unwrap();
+            assert!(is_completed);
+            state
+        };
+        let mut amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            protocol_fee_rate: 120_000,
+            fund_fee_rate: 40_000,
+            ..Default::default()
+        };
+
+        // a swap within the range is one step, its fee is split as split_trade_fee does it
+        let state = swap(&amm_config, 1_000_000);
+        let trade_fee = state.fee_amount + state.protocol_fee + state.fund_fee;
+        assert_eq!(trade_fee, 2500);
+        assert_eq!(
+            amm_config.split_trade_fee(trade_fee),
+            (state.fee_amount, state.protocol_fee, state.fund_fee)
+        );
+        assert_eq!((state.protocol_fee, state.fund_fee), (300, 100));
+
+        // the shares of the smallest swap round down to zero and the LPs get the whole fee
+        amm_config.protocol_fee_rate = 1;
+        amm_config.fund_fee_rate = 1;
+        let state = swap(&amm_config, 1);
+        assert_eq!(state.amount_calculated, 0);
+        assert_eq!(
+            (state.fee_amount, state.protocol_fee, state.fund_fee),
+            (1, 0, 0)
+        );
+
+        // no shares at all
+        amm_config.protocol_fee_rate = 0;
+        amm_config.fund_fee_rate = 0;
+        let state = swap(&amm_config, 1_000_000);
+        assert_eq!(
+            (state.fee_amount, state.protocol_fee, state.fund_fee),
+            (2500, 0, 0)
+        );
+    }
+
+    #[test]
+    fn protocol_fee_to_lp_swap_test() {
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            protocol_fee_rate: 120_000,
+            fund_fee_rate: 40_000,
+            ..Default::default()
+        };
+
+        let swap = |pool_state: &PoolState| {
+            let (state, is_completed) = compute_swap(
+                &amm_config,
+                pool_state,
+                &tick_array_states,
+                &None,
+                1_000_000,
+                tick_math::MIN_SQRT_PRICE_X64 + 1,
+                true,
+                true,
+            )
+            .unwrap();
+            assert!(is_completed);
+            state
+        };
+        let state = swap(&pool_state.borrow());
+        assert!(state.protocol_fee > 0);
+
+        pool_state.borrow_mut().protocol_fee_to_lp = true;
+        let redirected_state = swap(&pool_state.borrow());
+        // the protocol share goes to the LPs, the fund share and the swap amounts don't change
+        assert_eq!(redirected_state.protocol_fee, 0);
+        assert_eq!(redirected_state.fund_fee, state.fund_fee);
+        assert_eq!(
+            redirected_state.fee_amount,
+            state.fee_amount + state.protocol_fee
+        );
+        assert!(redirected_state.fee_growth_global_x64 > state.fee_growth_global_x64);
+        assert_eq!(redirected_state.amount_calculated, state.amount_calculated);
+        assert_eq!(redirected_state.sqrt_price_x64, state.sqrt_price_x64);
+
+        // nothing can be collected while redirected, the fees accrued before stay owed
+        pool_state.borrow_mut().protocol_fees_token_0 = 100;
+        assert_eq!(pool_state.borrow().collectable_protocol_fees(), (0, 0));
+        pool_state.borrow_mut().protocol_fee_to_lp = false;
+        assert_eq!(pool_state.borrow().collectable_protocol_fees(), (100, 0));
+    }
+
+    #[test]
+    fn zero_fee_swap_test() {
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let zero_fee_config = AmmConfig {
+            trade_fee_rate: 0,
+            protocol_fee_rate: 120_000,
+            fund_fee_rate: 40_000,
+            ..Default::default()
+        };
+        let mut fee_config = zero_fee_config.clone();
+        fee_config.trade_fee_rate = 2500;
+
+        for zero_for_one in [true, false] {
+            let sqrt_price_limit_x64 = if zero_for_one {
+                tick_math::MIN_SQRT_PRICE_X64 + 1
+            } else {
+                tick_math::MAX_SQRT_PRICE_X64 - 1
+            };
+            for is_base_input in [true, false] {
+                let swap_amount = |amm_config: &AmmConfig| {
+                    let (state, is_completed) = compute_swap(
+                        amm_config,
+                        &pool_state.borrow(),
+                        &tick_array_states,
+                        &None,
+                        1_000_000,
+                        sqrt_price_limit_x64,
+                        zero_for_one,
+                        is_base_input,
+                    )
+                    .unwrap();
+                    assert!(is_completed);
+                    assert_eq!(state.amount_specified_remaining, 0);
+                    state.amount_calculated
+                };
+                let amount_calculated = swap_amount(&zero_fee_config);
+                let amount_calculated_with_fee = swap_amount(&fee_config);
+                assert!(amount_calculated > 0);
+                // the fee lowers the output or raises the input
+                if is_base_input {
+                    assert!(amount_calculated > amount_calculated_with_fee);
+                } else {
+                    assert!(amount_calculated < amount_calculated_with_fee);
+                }
+            }
+
+            // a step reaching its target charges no fee, and a zero fee splits into nothing
+            let sqrt_price_x64 = pool_state.borrow().sqrt_price_x64;
+            let step = swap_math::compute_swap_step(
+                sqrt_price_x64,
+                tick_math::get_sqrt_price_at_tick(if zero_for_one { 1200 } else { 2400 }).unwrap(),
+                liquidity,
+                u64::MAX,
+                zero_fee_config.trade_fee_rate,
+                true,
+                zero_for_one,
+            )
+            .unwrap();
+            assert!(step.amount_in > 0 && step.amount_out > 0);
+            assert_eq!(step.fee_amount, 0);
+            assert_eq!(zero_fee_config.split_trade_fee(step.fee_amount), (0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn exact_output_overflow_test() {
+        // deep enough that the range holds more than u64::MAX of either token
+        let liquidity = 1u128 << 70;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+        let swap_base_output = |amount: u64| {
+            compute_swap(
+                &amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                amount,
+                tick_math::MAX_SQRT_PRICE_X64 - 1,
+                false,
+                false,
+            )
+        };
+
+        // the amount to the next tick overflows u64, the output still fits
+        let (state, is_completed) = swap_base_output(i64::MAX as u64).unwrap();
+        assert!(is_completed);
+        assert_eq!(state.amount_specified_remaining, 0);
+        assert!(state.amount_calculated > i64::MAX as u64);
+
+        // a token_0 output near u64::MAX costs more token_1 than u64 holds
+        for amount in [u64::MAX - u64::MAX / 10, u64::MAX - 1, u64::MAX] {
+            assert_eq!(
+                swap_base_output(amount).unwrap_err(),
+                ErrorCode::MaxTokenOverflow.into()
+            );
+        }
+    }
+
+    #[test]
+    fn swap_uninitialized_pool_test() {
+        // a zeroed pool account, never set up by create_pool
+        let pool_state = PoolState::default();
+        assert_eq!(
+            compute_swap(
+                &AmmConfig::default(),
+                &pool_state,
+                &VecDeque::new(),
+                &None,
+                1_000_000,
+                tick_math::MIN_SQRT_PRICE_X64 + 1,
+                true,
+                true,
+            )
+            .unwrap_err(),
+            ErrorCode::PoolNotInitialized.into()
+        );
+
+        // a zeroed pool account can't be migrated into an initialized one
+        let mut pool_state = PoolState::default();
+        assert_eq!(
+            pool_state.migrate_initialized().unwrap_err(),
+            ErrorCode::PoolNotInitialized.into()
+        );
+    }
+
+    #[test]
+    fn swap_legacy_pool_test() {
+        // a pool created before the initialized flag was added
+        let pool_state = build_pool(0, 10, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        pool_state.initialized = false;
+        let mut accounts = build_pool_accounts(pool_state, 1_000_000);
+        let payer = Pubkey::new_unique();
+        let (input_token_account, output_token_account, tick_array) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let tick_array_state = TickArrayState {
+            pool_id: pool_state.key(),
+            ..Default::default()
+        };
+        accounts.extend([
+            TestAccount::wallet(payer),
+            TestAccount::token_account(
+                input_token_account,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                payer,
+                1_000_000,
+            ),
+            TestAccount::token_account(
+                output_token_account,
+                spl_token::id(),
+                pool_state.token_mint_1,
+                payer,
+                0,
+            ),
+            TestAccount::zero_copy(tick_array, &tick_array_state),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(crate::id()),
+        ]);
+        let metas = crate::accounts::SwapSingle {
+            payer,
+            amm_config: pool_state.amm_config,
+            pool_state: pool_state.key(),
+            input_token_account,
+            output_token_account,
+            input_vault: pool_state.token_vault_0,
+            output_vault: pool_state.token_vault_1,
+            observation_state: pool_state.observation_key,
+            token_program: spl_token::id(),
+            tick_array,
+            referrer_token_account: None,
+        }
+        .to_account_metas(Some(true));
+        let swap = || crate::instruction::Swap {
+            amount: 1_000_000,
+            other_amount_threshold: 0,
+            sqrt_price_limit_x64: 0,
+            is_base_input: true,
+        };
+
+        assert_eq!(
+            process_instruction(&mut accounts, metas.clone(), swap()).unwrap_err(),
+            program_error(ErrorCode::PoolNotInitialized)
+        );
+        // the pool swaps once migrated
+        process_instruction(
+            &mut accounts,
+            crate::accounts::MigratePool {
+                pool_state: pool_state.key(),
+            }
+            .to_account_metas(None),
+            crate::instruction::MigratePool {},
+        )
+        .unwrap();
+        assert!(accounts[1].load::<PoolState>().initialized);
+        process_instruction(&mut accounts, metas, swap()).unwrap();
+    }
+
+    #[test]
+    fn cross_tick_round_trip_liquidity_test() {
+        let tick_spacing = 60;
+        let liquidity = 1_000_000_000;
+        let liquidity_inner = 400_000_000;
+        let pool_state = build_pool(
+            1800,
+            tick_spacing,
+            tick_math::get_sqrt_price_at_tick(1800).unwrap(),
+            liquidity,
+        );
+        pool_state
+            .borrow_mut()
+            .flip_tick_array_bit(None, 0)
+            .unwrap();
+        // a wide range holding the price and a narrower one above it, in the tick array [0, 3600)
+        let tick_array = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            0,
+            tick_spacing,
+            vec![
+                build_tick(60, liquidity, liquidity as i128).take(),
+                build_tick(1860, liquidity_inner, liquidity_inner as i128).take(),
+                build_tick(3000, liquidity_inner, -(liquidity_inner as i128)).take(),
+                build_tick(3540, liquidity, -(liquidity as i128)).take(),
+            ],
+        );
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+        // swap up to a price and move the pool there, as a swap does
+        let swap_to = |tick: i32, zero_for_one: bool| {
+            let (state, _) = compute_swap(
+                &amm_config,
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                u64::MAX / 2,
+                tick_math::get_sqrt_price_at_tick(tick).unwrap(),
+                zero_for_one,
+                true,
+            )
+            .unwrap();
+            let mut pool_state = pool_state.borrow_mut();
+            pool_state.sqrt_price_x64 = state.sqrt_price_x64;
+            pool_state.tick_current = state.tick;
+            pool_state.liquidity = state.liquidity;
+            state.tick_crosses
+        };
+
+        // crossing the lower tick of the inner range upward adds its liquidity
+        assert_eq!(
+            swap_to(2400, false),
+            vec![TickCross {
+                tick: 1860,
+                liquidity_net: liquidity_inner as i128,
+                liquidity_after: liquidity + liquidity_inner,
+            }]
+        );
+        assert_eq!(identity(pool_state.borrow().tick_current), 2400);
+        assert_eq!(
+            identity(pool_state.borrow().liquidity),
+            liquidity + liquidity_inner
+        );
+        // crossing its upper tick upward removes it
+        swap_to(3300, false);
+        assert_eq!(identity(pool_state.borrow().liquidity), liquidity);
+
+        // crossing both back downward restores the liquidity at each step
+        assert_eq!(
+            swap_to(2400, true),
+            vec![TickCross {
+                tick: 3000,
+                liquidity_net: liquidity_inner as i128,
+                liquidity_after: liquidity + liquidity_inner,
+            }]
+        );
+        assert_eq!(
+            identity(pool_state.borrow().liquidity),
+            liquidity + liquidity_inner
+        );
+        swap_to(1800, true);
+        assert_eq!(identity(pool_state.borrow().tick_current), 1800);
+        assert_eq!(identity(pool_state.borrow().liquidity), liquidity);
+    }
+
+    #[test]
+    fn swap_exactly_to_tick_boundary_test() {
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let sqrt_price_x64 = pool_state.borrow().sqrt_price_x64;
+        let swap_base_output = |amount_out: u64, zero_for_one: bool| {
+            let (state, is_completed) = compute_swap(
+                &AmmConfig::default(),
+                &pool_state.borrow(),
+                &tick_array_states,
+                &None,
+                amount_out,
+                if zero_for_one {
+                    tick_math::MIN_SQRT_PRICE_X64 + 1
+                } else {
+                    tick_math::MAX_SQRT_PRICE_X64 - 1
+                },
+                zero_for_one,
+                false,
+            )
+            .unwrap();
+            assert!(is_completed);
+            assert_eq!(state.amount_specified_remaining, 0);
+            state
+        };
+
+        // the output of the whole range below the price lands exactly on the lower tick,
+        // reaching the tick crosses it, so the tick is below it and the range is out
+        let sqrt_price_lower_x64 = tick_math::get_sqrt_price_at_tick(60).unwrap();
+        let amount_out = liquidity_math::get_delta_amount_1_unsigned(
+            sqrt_price_lower_x64,
+            sqrt_price_x64,
+            liquidity,
+            false,
+        )
+        .unwrap();
+        let state = swap_base_output(amount_out, true);
+        assert_eq!(state.sqrt_price_x64, sqrt_price_lower_x64);
+        assert_eq!(state.tick, 59);
+        assert_eq!(state.liquidity, 0);
+        // just short of the tick, it isn't crossed
+        let state = swap_base_output(amount_out - 1, true);
+        assert!(state.sqrt_price_x64 > sqrt_price_lower_x64);
+        assert_eq!(state.tick, 60);
+        assert_eq!(state.liquidity, liquidity);
+
+        // the output of the whole range above the price lands exactly on the upper tick,
+        // which is the current tick once crossed, out of the range
+        let sqrt_price_upper_x64 = tick_math::get_sqrt_price_at_tick(3540).unwrap();
+        let amount_out = liquidity_math::get_delta_amount_0_unsigned(
+            sqrt_price_x64,
+            sqrt_price_upper_x64,
+            liquidity,
+            false,
+        )
+        .unwrap();
+        let state = swap_base_output(amount_out, false);
+        assert_eq!(state.sqrt_price_x64, sqrt_price_upper_x64);
+        assert_eq!(state.tick, 3540);
+        assert_eq!(state.liquidity, 0);
+        let state = swap_base_output(amount_out - 1, false);
+        assert!(state.sqrt_price_x64 < sqrt_price_upper_x64);
+        assert_eq!(state.tick, 3539);
+        assert_eq!(state.liquidity, liquidity);
+    }
+
+    #[test]
+    fn swap_max_trade_fee_rate_test() {
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        // a broken fee tier, charging 90% of the input
+        let amm_config = AmmConfig {
+            trade_fee_rate: 900_000,
+            ..Default::default()
+        };
+        for is_base_input in [true, false] {
+            assert_eq!(
+                compute_swap(
+                    &amm_config,
+                    &pool_state.borrow(),
+                    &VecDeque::from([tick_array_ref.deref()]),
+                    &None,
+                    1_000_000,
+                    tick_math::MIN_SQRT_PRICE_X64 + 1,
+                    true,
+                    is_base_input,
+                )
+                .unwrap_err(),
+                ErrorCode::TradeFeeRateTooHigh.into()
+            );
+        }
+    }
+
+    #[test]
+    fn swap_insufficient_reserve_test() {
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        let tick_array_ref = tick_array.borrow();
+        let tick_array_states = VecDeque::from([tick_array_ref.deref()]);
+        let observation_state = ObservationState::default();
+        let swap = |output_vault_amount: u64, is_base_input: bool| {
+            swap_internal(
+                &AmmConfig::default(),
+                &pool_state.borrow(),
+                &tick_array_states,
+                &observation_state,
+                &None,
+                output_vault_amount,
+                1_000_000,
+                tick_math::MIN_SQRT_PRICE_X64 + 1,
+                true,
+                is_base_input,
+                0,
+            )
+        };
+
+        let (_, amount_out) = swap(u64::MAX, true).unwrap();
+        assert!(amount_out > 0);
+        // the vault holds exactly the output
+        assert_eq!(swap(amount_out, true).unwrap().1, amount_out);
+        // a swap paying out more than the vault holds reverts rather than wraps
+        assert_eq!(
+            swap(amount_out - 1, true).unwrap_err(),
+            ErrorCode::InsufficientReserve.into()
+        );
+        assert_eq!(
+            swap(999_999, false).unwrap_err(),
+            ErrorCode::InsufficientReserve.into()
+        );
+
+        assert_eq!(get_output_reserve(100, 40).unwrap(), 60);
+        assert_eq!(get_output_reserve(100, 100).unwrap(), 0);
+        assert_eq!(
+            get_output_reserve(0, 1).unwrap_err(),
+            ErrorCode::InsufficientReserve.into()
+        );
+    }
+
+    #[test]
+    fn get_fee_growth_delta_x64_test() {
+        assert_eq!(get_fee_growth_delta_x64(0, 1).unwrap(), 0);
+        assert_eq!(
+            get_fee_growth_delta_x64(1_000, 1_000).unwrap(),
+            fixed_point_64::Q64
+        );
+        // a normal pool never gets close to the bound
+        assert!(
+            get_fee_growth_delta_x64(u64::MAX, 1_000_000_000_000).unwrap()
+                < MAX_FEE_GROWTH_DELTA_X64
+        );
+
+        // a fee of 2^32 per unit of liquidity is the most a step can add
+        assert_eq!(
+            get_fee_growth_delta_x64(1 << 32, 1).unwrap(),
+            MAX_FEE_GROWTH_DELTA_X64
+        );
+        assert_eq!(
+            get_fee_growth_delta_x64((1 << 32) + 1, 1).unwrap_err(),
+            ErrorCode::FeeGrowthDeltaTooLarge.into()
+        );
+        assert_eq!(
+            get_fee_growth_delta_x64(u64::MAX, 1_000).unwrap_err(),
+            ErrorCode::FeeGrowthDeltaTooLarge.into()
+        );
+    }
+
+    #[test]
+    fn swap_fee_growth_test() {
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            protocol_fee_rate: 120_000,
+            ..Default::default()
+        };
+        let liquidity = 1_000_000_000;
+        let (pool_state, tick_array) = build_swap_pool(1800, liquidity);
+        pool_state.borrow_mut().fee_growth_global_0_x64 = 0;
+        let tick_array_ref = tick_array.borrow();
+        let (state, _) = compute_swap(
+            &amm_config,
+            &pool_state.borrow(),
+            &VecDeque::from([tick_array_ref.deref()]),
+            &None,
+            1_000_000,
+            tick_math::MIN_SQRT_PRICE_X64 + 1,
+            true,
+            true,
+        )
+        .unwrap();
+        // the LPs' share of the fee, not the protocol's, grows the fee
+        assert!(state.protocol_fee > 0);
+        assert_eq!(
+            state.fee_growth_global_x64,
+            get_fee_growth_delta_x64(state.fee_amount, liquidity).unwrap()
+        );
+
+        // the fee cap keeps a step under the bound, even the highest fee on dust liquidity
+        // over a tick array near the max price, where a unit of liquidity trades the most
+        let amm_config = AmmConfig {
+            trade_fee_rate: MAX_TRADE_FEE_RATE,
+            ..Default::default()
+        };
+        let tick_spacing = 60;
+        let pool_state = build_pool(
+            439_260,
+            tick_spacing,
+            tick_math::get_sqrt_price_at_tick(439_260).unwrap(),
+            1,
+        );
+        pool_state.borrow_mut().fee_growth_global_1_x64 = 0;
+        pool_state
+            .borrow_mut()
+            .flip_tick_array_bit(None, 439_200)
+            .unwrap();
+        let tick_array = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            439_200,
+            tick_spacing,
+            vec![
+                build_tick(439_260, 1, 1).take(),
+                build_tick(442_740, 1, -1).take(),
+            ],
+        );
+        let tick_array_ref = tick_array.borrow();
+        let (state, _) = compute_swap(
+            &amm_config,
+            &pool_state.borrow(),
+            &VecDeque::from([tick_array_ref.deref()]),
+            &None,
+            10_000_000_000,
+            tick_math::MAX_SQRT_PRICE_X64 - 1,
+            false,
+            true,
+        )
+        .unwrap();
+        assert!(state.fee_amount > 0);
+        assert_eq!(
+            state.fee_growth_global_x64,
+            get_fee_growth_delta_x64(state.fee_amount, 1).unwrap()
+        );
+        assert!(state.fee_growth_global_x64 < MAX_FEE_GROWTH_DELTA_X64);
+    }
+
+    #[test]
+    fn check_twap_deviation_test() {
+        // spot price 4
+        let sqrt_price_x64 = fixed_point_64::Q64 * 2;
+        let twap_price_x64 = fixed_point_64::Q64 * 4;
+        assert!(check_twap_deviation(sqrt_price_x64, twap_price_x64, 0).is_ok());
+
+        // the spot is 100% above the TWAP
+        let twap_price_x64 = fixed_point_64::Q64 * 2;
+        assert!(check_twap_deviation(sqrt_price_x64, twap_price_x64, 10_000).is_ok());
+        assert_eq!(
+            check_twap_deviation(sqrt_price_x64, twap_price_x64, 9_999).unwrap_err(),
+            ErrorCode::SpotDeviatesFromTwap.into()
+        );
+
+        // the spot is 20% below the TWAP
+        let twap_price_x64 = fixed_point_64::Q64 * 5;
+        assert!(check_twap_deviation(sqrt_price_x64, twap_price_x64, 2_000).is_ok());
+        assert_eq!(
+            check_twap_deviation(sqrt_price_x64, twap_price_x64, 1_999).unwrap_err(),
+            ErrorCode::SpotDeviatesFromTwap.into()
+        );
+    }
+
+    #[test]
+    fn check_twap_price_bound_test() {
+        let twap_price_bound = TwapPriceBound {
+            window_seconds: 60,
+            min_price_x64: fixed_point_64::Q64,
+            max_price_x64: fixed_point_64::Q64 * 2,
+        };
+        for twap_price_x64 in [fixed_point_64::Q64, fixed_point_64::Q64 * 2] {
+            assert!(check_twap_price_bound(twap_price_x64, &twap_price_bound).is_ok());
+        }
+        for twap_price_x64 in [fixed_point_64::Q64 - 1, fixed_point_64::Q64 * 2 + 1] {
+            assert_eq!(
+                check_twap_price_bound(twap_price_x64, &twap_price_bound).unwrap_err(),
+                ErrorCode::TwapOutOfBound.into()
+            );
+        }
+    }
+
+    #[test]
+    fn check_token_authority_test() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        assert!(check_token_authority(owner, None, owner).is_ok());
+        assert!(check_token_authority(owner, Some(delegate), owner).is_ok());
+        assert!(check_token_authority(owner, Some(delegate), delegate).is_ok());
+
+        // the signer doesn't control the input account
+        let other = Pubkey::new_unique();
+        assert_eq!(
+            check_token_authority(owner, None, other).unwrap_err(),
+            ErrorCode::InputAccountUnauthorized.into()
+        );
+        assert_eq!(
+            check_token_authority(owner, Some(delegate), other).unwrap_err(),
+            ErrorCode::InputAccountUnauthorized.into()
+        );
+    }
+
+    #[test]
+    fn swap_leaves_current_tick_array_test() {
+        let mut pool_state = PoolState {
+            tick_spacing: 10,
+            tick_current: 300,
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(300).unwrap(),
+            liquidity: 1_000_000_000,
+            ..Default::default()
+        };
+        let fee_rate = 2500;
+
+        // the tick array is [0, 600), each boundary is about 1.5% away from the sqrt price,
+        // it takes about 15_000_000 of either token to reach
+        for (zero_for_one, is_base_input) in
+            [(true, true), (true, false), (false, true), (false, false)]
+        {
+            assert!(!swap_leaves_current_tick_array(
+                &pool_state,
+                fee_rate,
+                1_000_000,
+                0,
+                zero_for_one,
+                is_base_input
+            )
+            .unwrap());
+            assert!(swap_leaves_current_tick_array(
+                &pool_state,
+                fee_rate,
+                20_000_000,
+                0,
+                zero_for_one,
+                is_base_input
+            )
+            .unwrap());
+        }
+
+        // the price limit is inside the tick array
+        assert!(!swap_leaves_current_tick_array(
+            &pool_state,
+            fee_rate,
+            20_000_000,
+            tick_math::get_sqrt_price_at_tick(450).unwrap(),
+            false,
+            true
+        )
+        .unwrap());
+        assert!(!swap_leaves_current_tick_array(
+            &pool_state,
+            fee_rate,
+            20_000_000,
+            tick_math::get_sqrt_price_at_tick(0).unwrap(),
+            true,
+            true
+        )
+        .unwrap());
+
+        // unknown without liquidity
+        pool_state.liquidity = 0;
+        assert!(
+            !swap_leaves_current_tick_array(&pool_state, fee_rate, 20_000_000, 0, false, true)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn swap_result_test() {
+        let pool_state = PoolState {
+            tick_current: -100,
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(-100).unwrap(),
+            liquidity: 1_000,
+            ..Default::default()
+        };
+        let swap_result = SwapResult::new(&pool_state, 10, 9);
+        assert_eq!(swap_result.version, SWAP_RESULT_VERSION);
+        assert_eq!(swap_result.amount_in, 10);
+        assert_eq!(swap_result.amount_out, 9);
+        assert_eq!(swap_result.tick_current, -100);
+        assert_eq!(
+            swap_result.sqrt_price_x64,
+            identity(pool_state.sqrt_price_x64)
+        );
+        assert_eq!(swap_result.liquidity, 1_000);
+        // the average price times the output gives back the input
+        assert_eq!(
+            U128::from(swap_result.average_price_x64)
+                .mul_div_ceil(U128::from(9u64), U128::from(fixed_point_64::Q64))
+                .unwrap()
+                .as_u64(),
+            10
+        );
+
+        // the version leads the return data
+        assert_eq!(swap_result.try_to_vec().unwrap()[0], SWAP_RESULT_VERSION);
+
+        // a full fill consumes the whole input
+        assert_eq!(swap_result.amount_in_consumed, 10);
+        // a swap capped by the price limit consumes less, and its price is over the consumed input
+        let swap_result = SwapResult::new_partial(&pool_state, 10, 6, 3);
+        assert_eq!(swap_result.amount_in, 10);
+        assert!(swap_result.amount_in_consumed < swap_result.amount_in);
+        assert_eq!(swap_result.average_price_x64, get_average_price_x64(6, 3));
+    }
+
+    #[test]
+    fn partial_fill_reason_test() {
+        let mut pool_state = PoolState {
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(-100).unwrap(),
+            ..Default::default()
+        };
+        let sqrt_price_limit_x64 = pool_state.sqrt_price_x64;
+
+        // a full fill has no reason to report
+        let swap_result = SwapResult::new(&pool_state, 10, 9)
+            .with_partial_fill_reason(sqrt_price_limit_x64, true);
+        assert!(!swap_result.price_limit_reached && !swap_result.pool_exhausted);
+
+        // stopped at the caller's limit
+        let swap_result = SwapResult::new_partial(&pool_state, 10, 6, 3)
+            .with_partial_fill_reason(sqrt_price_limit_x64, true);
+        assert!(swap_result.price_limit_reached);
+        assert!(!swap_result.pool_exhausted);
+
+        // drained to the boundary, with or without a limit there
+        for (zero_for_one, sqrt_price_x64) in [
+            (true, tick_math::MIN_SQRT_PRICE_X64 + 1),
+            (false, tick_math::MAX_SQRT_PRICE_X64 - 1),
+        ] {
+            pool_state.sqrt_price_x64 = sqrt_price_x64;
+            pool_state.liquidity = 0;
+            for sqrt_price_limit_x64 in [sqrt_price_x64, 0] {
+                let swap_result = SwapResult::new_partial(&pool_state, 10, 6, 3)
+                    .with_partial_fill_reason(sqrt_price_limit_x64, zero_for_one);
+                assert!(swap_result.pool_exhausted);
+                assert!(!swap_result.price_limit_reached);
+            }
+        }
+    }
+
+    #[test]
+    fn get_average_price_x64_test() {
+        assert_eq!(get_average_price_x64(1_000, 1_000), fixed_point_64::Q64);
+        assert_eq!(get_average_price_x64(2_000, 1_000), fixed_point_64::Q64 * 2);
+        assert_eq!(get_average_price_x64(1_000, 4_000), fixed_point_64::Q64 / 4);
+        assert_eq!(
+            get_average_price_x64(u64::MAX, 1),
+            u128::from(u64::MAX) << 64
+        );
+        assert_eq!(get_average_price_x64(1_000, 0), 0);
+
+        // matches the ratio of the amounts of a swap
+        let (amount_in, amount_out) = (1_000_000u64, 996_503u64);
+        let average_price_x64 = get_average_price_x64(amount_in, amount_out);
+        assert!(
+            U256::from(average_price_x64) * U256::from(amount_out) <= U256::from(amount_in) << 64
+        );
+        assert!(
+            (U256::from(average_price_x64) + 1) * U256::from(amount_out)
+                > U256::from(amount_in) << 64
+        );
+    }
+
+    #[test]
+    fn check_forbidden_tick_test() {
+        assert!(check_forbidden_tick(-100, 100, None).is_ok());
+
+        // price up across the tick
+        assert_eq!(
+            check_forbidden_tick(-100, 100, Some(0)).unwrap_err(),
+            ErrorCode::ForbiddenTickCrossed.into()
+        );
+        assert_eq!(
+            check_forbidden_tick(-100, 0, Some(0)).unwrap_err(),
+            ErrorCode::ForbiddenTickCrossed.into()
+        );
+        assert!(check_forbidden_tick(-100, -1, Some(0)).is_ok());
+
+        // price down across the tick
+        assert_eq!(
+            check_forbidden_tick(100, -1, Some(0)).unwrap_err(),
+            ErrorCode::ForbiddenTickCrossed.into()
+        );
+        assert!(check_forbidden_tick(100, 0, Some(0)).is_ok());
+
+        // moves on one side of the tick
+        assert!(check_forbidden_tick(100, 200, Some(0)).is_ok());
+        assert!(check_forbidden_tick(-100, -200, Some(0)).is_ok());
+    }
+
+    #[test]
+    fn check_min_swap_amount_test() {
+        let mut pool_state = PoolState {
+            token_mint_0: Pubkey::new_unique(),
+            token_mint_1: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let (token_mint_0, token_mint_1) = (pool_state.token_mint_0, pool_state.token_mint_1);
+        // no minimum by default
+        assert!(check_min_swap_amount(&pool_state, token_mint_0, 0).is_ok());
+        assert!(check_min_swap_amount(&pool_state, token_mint_1, 0).is_ok());
+
+        // each token has its own minimum, as the tokens' units differ
+        pool_state.min_swap_amount_0 = 1_000;
+        pool_state.min_swap_amount_1 = 10;
+        assert_eq!(
+            check_min_swap_amount(&pool_state, token_mint_0, 999).unwrap_err(),
+            ErrorCode::SwapAmountTooSmall.into()
+        );
+        assert!(check_min_swap_amount(&pool_state, token_mint_0, 1_000).is_ok());
+        assert!(check_min_swap_amount(&pool_state, token_mint_0, 1_001).is_ok());
+        assert_eq!(
+            check_min_swap_amount(&pool_state, token_mint_1, 9).unwrap_err(),
+            ErrorCode::SwapAmountTooSmall.into()
+        );
+        assert!(check_min_swap_amount(&pool_state, token_mint_1, 10).is_ok());
+    }
+
+    #[test]
+    fn check_amount_out_minimum_test() {
+        let mut pool_state = PoolState::default();
+        // guard off by default
+        assert!(check_amount_out_minimum(&pool_state, true, 0).is_ok());
+
+        pool_state.set_status_by_bit(
+            PoolStatusBitIndex::SwapWithoutOutputMinimum,
+            PoolStatusBitFlag::Disable,
+        );
+        assert_eq!(
+            check_amount_out_minimum(&pool_state, true, 0).unwrap_err(),
+            ErrorCode::ZeroAmountOutMinimum.into()
+        );
+        assert!(check_amount_out_minimum(&pool_state, true, 1).is_ok());
+        // the threshold of swap base output is amount_in_maximum
+        assert!(check_amount_out_minimum(&pool_state, false, 0).is_ok());
+    }
+
+    #[test]
+    fn check_vault_pair_test() {
+        let pool_state = PoolState {
+            token_mint_0: Pubkey::new_unique(),
+            token_mint_1: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let token_mint_0 = pool_state.token_mint_0;
+        let token_mint_1 = pool_state.token_mint_1;
+        assert!(check_vault_pair(&pool_state, token_mint_0, token_mint_1).is_ok());
+        assert!(check_vault_pair(&pool_state, token_mint_1, token_mint_0).is_ok());
+
+        // both vaults of the same mint
+        assert_eq!(
+            check_vault_pair(&pool_state, token_mint_0, token_mint_0).unwrap_err(),
+            ErrorCode::InvalidVaultPair.into()
+        );
+        assert_eq!(
+            check_vault_pair(&pool_state, token_mint_1, token_mint_1).unwrap_err(),
+            ErrorCode::InvalidVaultPair.into()
+        );
+        // vault of a token not in the pool
+        assert_eq!(
+            check_vault_pair(&pool_state, token_mint_0, Pubkey::new_unique()).unwrap_err(),
+            ErrorCode::InvalidVaultPair.into()
+        );
+    }
+
+    #[test]
+    fn get_referral_fee_test() {
+        let amm_config = AmmConfig {
+            referral_fee_bps: 30,
+            ..Default::default()
+        };
+        let input_token_mint = Pubkey::new_unique();
+
+        // no referrer, no referral fee
+        assert_eq!(
+            get_referral_fee(&amm_config, None, input_token_mint, 1_000_000).unwrap(),
+            0
+        );
+        assert_eq!(
+            get_referral_fee(
+                &amm_config,
+                Some(input_token_mint),
+                input_token_mint,
+                1_000_000
+            )
+            .unwrap(),
+            3_000
+        );
+        // referrer account of another token
+        assert_eq!(
+            get_referral_fee(
+                &amm_config,
+                Some(input_token_mint),
+                Pubkey::new_unique(),
+                1_000_000
+            )
+            .unwrap_err(),
+            ErrorCode::InvalidReferrerAccount.into()
+        );
+    }
+
+    #[test]
+    fn swap_referral_fee_test() {
+        let pool_state = build_pool(0, 10, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        let mut accounts = build_pool_accounts(pool_state, 1_000_000);
+        let amm_config = AmmConfig {
+            tick_spacing: pool_state.tick_spacing,
+            referral_fee_bps: 30,
+            ..Default::default()
+        };
+        accounts[0] = TestAccount::account(pool_state.amm_config, &amm_config, AmmConfig::LEN);
+        let payer = Pubkey::new_unique();
+        let (input_token_account, output_token_account, referrer_token_account, tick_array) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let tick_array_state = TickArrayState {
+            pool_id: pool_state.key(),
+            ..Default::default()
+        };
+        accounts.extend([
+            TestAccount::wallet(payer),
+            TestAccount::token_account(
+                input_token_account,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                payer,
+                1_000_000,
+            ),
+            TestAccount::token_account(
+                output_token_account,
+                spl_token::id(),
+                pool_state.token_mint_1,
+                payer,
+                0,
+            ),
+            TestAccount::token_account(
+                referrer_token_account,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                Pubkey::new_unique(),
+                0,
+            ),
+            TestAccount::zero_copy(tick_array, &tick_array_state),
+            TestAccount::program(spl_token::id()),
+        ]);
+        let metas = crate::accounts::SwapSingle {
+            payer,
+            amm_config: pool_state.amm_config,
+            pool_state: pool_state.key(),
+            input_token_account,
+            output_token_account,
+            input_vault: pool_state.token_vault_0,
+            output_vault: pool_state.token_vault_1,
+            observation_state: pool_state.observation_key,
+            token_program: spl_token::id(),
+            tick_array,
+            referrer_token_account: Some(referrer_token_account),
+        }
+        .to_account_metas(Some(true));
+        let token_amount = |accounts: &[TestAccount], key: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| account.key == key)
+                .unwrap()
+                .token_amount()
+        };
+
+        process_instruction(
+            &mut accounts,
+            metas.clone(),
+            crate::instruction::Swap {
+                amount: 1_000_000,
+                other_amount_threshold: 0,
+                sqrt_price_limit_x64: 0,
+                is_base_input: true,
+            },
+        )
+        .unwrap();
+        // the referral fee of the whole amount is skimmed once before the swap,
+        // which doesn't move tokens yet
+        assert_eq!(token_amount(&accounts, referrer_token_account), 3_000);
+        assert_eq!(token_amount(&accounts, input_token_account), 997_000);
+        // the result reports what moved between the user's token accounts
+        let result = return_data::<SwapResult>();
+        assert_eq!(result.amount_in, 1_000_000);
+        assert_eq!(result.amount_in_consumed, 3_000);
+        assert_eq!(result.amount_out, 0);
+
+        // a base output swap reports no output the swap didn't transfer
+        process_instruction(
+            &mut accounts,
+            metas,
+            crate::instruction::Swap {
+                amount: 1_000,
+                other_amount_threshold: u64::MAX,
+                sqrt_price_limit_x64: 0,
+                is_base_input: false,
+            },
+        )
+        .unwrap();
+        let result = return_data::<SwapResult>();
+        assert_eq!(result.amount_in, 0);
+        assert_eq!(result.amount_out, 0);
+        assert_eq!(token_amount(&accounts, output_token_account), 0);
+    }
 }
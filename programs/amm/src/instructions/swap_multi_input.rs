@@ -0,0 +1,182 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::swap::{
+    check_input_authority, check_input_balance, check_min_swap_amount, check_vault_pair, SwapResult,
+};
+use crate::swap_v2::{exact_internal_v2, SwapSingleV2};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_interface::{Mint, Token2022, TokenAccount},
+};
+
+/// The max number of input tokens swapped in one swap_multi_input
+pub const MAX_SWAP_INPUT_NUM: usize = 4;
+
+#[derive(Accounts)]
+pub struct SwapMultiInput<'info> {
+    /// The user performing the swap
+    pub payer: Signer<'info>,
+
+    /// The token account that receives the output of all swaps
+    #[account(mut)]
+    pub output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The mint of output token
+    #[account(address = output_token_account.mint)]
+    pub output_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// SPL program for token transfers
+    pub token_program: Program<'info, Token>,
+    /// SPL program 2022 for token transfers
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK:
+    #[account(
+        address = spl_memo::id()
+    )]
+    pub memo_program: UncheckedAccount<'info>,
+    // remaining accounts, repeated for each input in the order of amounts_in
+    // amm_config
+    // pool_state
+    // input_token_account
+    // input_token_mint
+    // input_vault
+    // output_vault
+    // observation_state
+    // tickarray_bitmap_extension: must add account if need regardless the sequence
+    // tick_array_account_...
+}
+
+/// Ensures at least one and at most `MAX_SWAP_INPUT_NUM` input tokens are swapped
+pub fn check_swap_input_num(input_num: usize) -> Result<()> {
+    require!(
+        input_num > 0 && input_num <= MAX_SWAP_INPUT_NUM,
+        ErrorCode::InvalidSwapInputNum
+    );
+    Ok(())
+}
+
+pub fn swap_multi_input<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapMultiInput<'info>>,
+    amounts_in: Vec<u64>,
+    amount_out_minimum: u64,
+) -> Result<SwapResult> {
+    check_swap_input_num(amounts_in.len())?;
+    let amount_in_total = amounts_in
+        .iter()
+        .try_fold(0u64, |total, amount_in| total.checked_add(*amount_in))
+        .ok_or(ErrorCode::MaxTokenOverflow)?;
+    let output_token_mint = ctx.accounts.output_token_mint.key();
+    let mut amount_out_total = 0u64;
+    let mut amounts_in = amounts_in.into_iter();
+    let mut accounts: &[AccountInfo] = ctx.remaining_accounts;
+    let mut last_pool_state = None;
+    while !accounts.is_empty() {
+        let mut remaining_accounts = accounts.iter();
+        let account_info = remaining_accounts.next().unwrap();
+        // skip the tick arrays of the previous swap
+        if accounts.len() != ctx.remaining_accounts.len()
+            && account_info.data_len() != AmmConfig::LEN
+        {
+            accounts = remaining_accounts.as_slice();
+            continue;
+        }
+        let amount_in = amounts_in.next().ok_or(ErrorCode::InvalidSwapInputNum)?;
+        let amm_config = Box::new(Account::<AmmConfig>::try_from(account_info)?);
+        let pool_state_loader =
+            AccountLoader::<PoolState>::try_from(remaining_accounts.next().unwrap())?;
+        let input_token_account = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let input_token_mint = Box::new(InterfaceAccount::<Mint>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let input_vault = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let output_vault = Box::new(InterfaceAccount::<TokenAccount>::try_from(
+            remaining_accounts.next().unwrap(),
+        )?);
+        let observation_state =
+            AccountLoader::<ObservationState>::try_from(remaining_accounts.next().unwrap())?;
+
+        {
+            let pool_state = pool_state_loader.load()?;
+            require_keys_eq!(pool_state.observation_key, observation_state.key());
+            require_keys_eq!(pool_state.amm_config, amm_config.key());
+            check_vault_pair(&pool_state, input_vault.mint, output_vault.mint)?;
+            // every swap pays out the same token
+            require_keys_eq!(
+                output_vault.mint,
+                output_token_mint,
+                ErrorCode::InvalidVaultPair
+            );
+            check_min_swap_amount(&pool_state, input_vault.mint, amount_in)?;
+        }
+        require_keys_eq!(input_token_mint.key(), input_token_account.mint);
+        check_input_authority(&input_token_account, ctx.accounts.payer.key())?;
+        check_input_balance(&input_token_account, amount_in)?;
+
+        last_pool_state = Some(pool_state_loader.clone());
+        accounts = remaining_accounts.as_slice();
+        // each swap is settled on its own pool, only the output is summed up
+        let amount_out = exact_internal_v2(
+            &mut SwapSingleV2 {
+                payer: ctx.accounts.payer.clone(),
+                amm_config,
+                input_token_account,
+                pool_state: pool_state_loader,
+                output_token_account: ctx.accounts.output_token_account.clone(),
+                input_vault,
+                output_vault,
+                input_vault_mint: input_token_mint,
+                output_vault_mint: ctx.accounts.output_token_mint.clone(),
+                observation_state,
+                token_program: ctx.accounts.token_program.clone(),
+                token_program_2022: ctx.accounts.token_program_2022.clone(),
+                memo_program: ctx.accounts.memo_program.clone(),
+                referrer_token_account: None,
+            },
+            accounts,
+            amount_in,
+            0,
+            true,
+        )?;
+        amount_out_total = amount_out_total
+            .checked_add(amount_out)
+            .ok_or(ErrorCode::MaxTokenOverflow)?;
+    }
+    // the pool accounts of every input must be given
+    require!(amounts_in.next().is_none(), ErrorCode::InvalidSwapInputNum);
+    #[cfg(feature = "enable-log")]
+    msg!("swap multi input, amount_out_total:{}", amount_out_total);
+    require_gte!(
+        amount_out_total,
+        amount_out_minimum,
+        ErrorCode::TooLittleOutputReceived
+    );
+
+    let last_pool_state = last_pool_state.ok_or(ErrorCode::InvalidSwapInputNum)?;
+    let swap_result = SwapResult::new(&*last_pool_state.load()?, amount_in_total, amount_out_total);
+    Ok(swap_result)
+}
+
+#[cfg(test)]
+mod swap_multi_input_test {
+    use super::*;
+
+    #[test]
+    fn check_swap_input_num_test() {
+        assert_eq!(
+            check_swap_input_num(0).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+        assert!(check_swap_input_num(1).is_ok());
+        assert!(check_swap_input_num(MAX_SWAP_INPUT_NUM).is_ok());
+        assert_eq!(
+            check_swap_input_num(MAX_SWAP_INPUT_NUM + 1).unwrap_err(),
+            ErrorCode::InvalidSwapInputNum.into()
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use crate::error::ErrorCode;
 use crate::states::*;
+use crate::swap::{
+    check_amount_out_minimum, check_input_authority, check_input_balance, check_min_swap_amount,
+    check_vault_pair, SwapResult,
+};
 use crate::swap_v2::{exact_internal_v2, SwapSingleV2};
 use anchor_lang::prelude::*;
 use anchor_spl::{
@@ -36,20 +40,89 @@ pub fn swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseIn<'info>>,
     amount_in: u64,
     amount_out_minimum: u64,
-) -> Result<()> {
+) -> Result<SwapResult> {
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    check_input_balance(&ctx.accounts.input_token_account, amount_in)?;
+    route_base_in(
+        ctx.accounts,
+        ctx.remaining_accounts,
+        amount_in,
+        amount_out_minimum,
+    )
+}
+
+/// Swap `amount_in` through the pools of the route in `route_accounts`, the output of each pool
+/// is the input of the next one
+pub fn route_base_in<'c: 'info, 'info>(
+    router_accounts: &SwapRouterBaseIn<'info>,
+    route_accounts: &'c [AccountInfo<'info>],
+    amount_in: u64,
+    amount_out_minimum: u64,
+) -> Result<SwapResult> {
+    let route_progress = route_base_in_hops(
+        router_accounts,
+        route_accounts,
+        amount_in,
+        amount_out_minimum,
+        u8::MAX,
+    )?;
+    require_gte!(
+        route_progress.amount_in_internal,
+        amount_out_minimum,
+        ErrorCode::TooLittleOutputReceived
+    );
+
+    let last_pool_state = route_progress
+        .last_pool_state
+        .ok_or(ErrorCode::InvalidSwapInputNum)?;
+    let swap_result = SwapResult::new(
+        &*last_pool_state.load()?,
+        amount_in,
+        route_progress.amount_in_internal,
+    );
+    Ok(swap_result)
+}
+
+/// How far `route_base_in_hops` got through a route
+pub struct RouteProgress<'c, 'info> {
+    /// The output of the last hop swapped, the input of the next hop
+    pub amount_in_internal: u64,
+    /// The number of hops swapped
+    pub hops: u8,
+    /// The token account holding `amount_in_internal`
+    pub input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The pool of the last hop swapped
+    pub last_pool_state: Option<AccountLoader<'info, PoolState>>,
+    /// The accounts of the hops not swapped yet, empty once the route is done
+    pub remaining_accounts: &'c [AccountInfo<'info>],
+}
+
+/// Swap `amount_in` through at most `max_hops` pools of the route in `route_accounts`,
+/// stopping before the next hop so the rest of the route can be swapped in another transaction
+pub fn route_base_in_hops<'c: 'info, 'info>(
+    router_accounts: &SwapRouterBaseIn<'info>,
+    route_accounts: &'c [AccountInfo<'info>],
+    amount_in: u64,
+    amount_out_minimum: u64,
+    max_hops: u8,
+) -> Result<RouteProgress<'c, 'info>> {
     let mut amount_in_internal = amount_in;
-    let mut input_token_account = Box::new(ctx.accounts.input_token_account.clone());
-    let mut input_token_mint = Box::new(ctx.accounts.input_token_mint.clone());
-    let mut accounts: &[AccountInfo] = ctx.remaining_accounts;
+    let mut input_token_account = Box::new(router_accounts.input_token_account.clone());
+    let mut input_token_mint = Box::new(router_accounts.input_token_mint.clone());
+    let mut accounts: &[AccountInfo] = route_accounts;
+    let mut last_pool_state = None;
+    let mut hops = 0;
     while !accounts.is_empty() {
         let mut remaining_accounts = accounts.iter();
         let account_info = remaining_accounts.next().unwrap();
-        if accounts.len() != ctx.remaining_accounts.len()
-            && account_info.data_len() != AmmConfig::LEN
-        {
+        if accounts.len() != route_accounts.len() && account_info.data_len() != AmmConfig::LEN {
             accounts = remaining_accounts.as_slice();
             continue;
         }
+        if hops == max_hops {
+            break;
+        }
+        hops += 1;
         let amm_config = Box::new(Account::<AmmConfig>::try_from(account_info)?);
         let pool_state_loader =
             AccountLoader::<PoolState>::try_from(remaining_accounts.next().unwrap())?;
@@ -74,13 +147,17 @@ pub fn swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
             require_keys_eq!(pool_state.observation_key, observation_state.key());
             // check ammConfig account is associate with the pool
             require_keys_eq!(pool_state.amm_config, amm_config.key());
+            check_vault_pair(&pool_state, input_vault.mint, output_vault.mint)?;
+            check_amount_out_minimum(&pool_state, true, amount_out_minimum)?;
+            check_min_swap_amount(&pool_state, input_vault.mint, amount_in_internal)?;
         }
 
         // solana_program::log::sol_log_compute_units();
+        last_pool_state = Some(pool_state_loader.clone());
         accounts = remaining_accounts.as_slice();
         amount_in_internal = exact_internal_v2(
             &mut SwapSingleV2 {
-                payer: ctx.accounts.payer.clone(),
+                payer: router_accounts.payer.clone(),
                 amm_config,
                 input_token_account: input_token_account.clone(),
                 pool_state: pool_state_loader,
@@ -90,9 +167,10 @@ pub fn swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
                 input_vault_mint: input_token_mint.clone(),
                 output_vault_mint: output_token_mint.clone(),
                 observation_state,
-                token_program: ctx.accounts.token_program.clone(),
-                token_program_2022: ctx.accounts.token_program_2022.clone(),
-                memo_program: ctx.accounts.memo_program.clone(),
+                token_program: router_accounts.token_program.clone(),
+                token_program_2022: router_accounts.token_program_2022.clone(),
+                memo_program: router_accounts.memo_program.clone(),
+                referrer_token_account: None,
             },
             accounts,
             amount_in_internal,
@@ -103,11 +181,11 @@ pub fn swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
         input_token_account = output_token_account;
         input_token_mint = output_token_mint;
     }
-    require_gte!(
+    Ok(RouteProgress {
         amount_in_internal,
-        amount_out_minimum,
-        ErrorCode::TooLittleOutputReceived
-    );
-
-    Ok(())
+        hops,
+        input_token_account,
+        last_pool_state,
+        remaining_accounts: accounts,
+    })
 }
@@ -0,0 +1,202 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use crate::swap::{check_input_authority, check_input_balance, SwapResult};
+use crate::swap_router_base_in::{route_base_in_hops, RouteProgress, SwapRouterBaseIn};
+use crate::util::*;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_interface::{Mint, Token2022, TokenAccount},
+};
+
+#[derive(Accounts)]
+pub struct SwapRouterBaseInResumable<'info> {
+    /// The user performing the swap, pays for the route cursor
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The token account that pays input tokens for the swap
+    #[account(mut)]
+    pub input_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The mint of input token
+    #[account(mut)]
+    pub input_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Records where the route stopped, a route the payer left unfinished is replaced
+    #[account(
+        init_if_needed,
+        seeds = [ROUTE_CURSOR_SEED.as_bytes(), payer.key().as_ref()],
+        bump,
+        payer = payer,
+        space = RouteCursorState::LEN
+    )]
+    pub route_cursor: Box<Account<'info, RouteCursorState>>,
+
+    /// SPL program for token transfers
+    pub token_program: Program<'info, Token>,
+    /// SPL program 2022 for token transfers
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK:
+    pub memo_program: UncheckedAccount<'info>,
+
+    /// Program to create the route cursor
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ContinueSwapRouterBaseIn<'info> {
+    /// The user who started the route
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The token account holding the output of the last hop swapped
+    #[account(mut)]
+    pub input_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The mint of the output of the last hop swapped
+    #[account(mut)]
+    pub input_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Where the route stopped, closed to the payer once the route is done
+    #[account(
+        mut,
+        seeds = [ROUTE_CURSOR_SEED.as_bytes(), payer.key().as_ref()],
+        bump = route_cursor.bump,
+    )]
+    pub route_cursor: Box<Account<'info, RouteCursorState>>,
+
+    /// SPL program for token transfers
+    pub token_program: Program<'info, Token>,
+    /// SPL program 2022 for token transfers
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK:
+    pub memo_program: UncheckedAccount<'info>,
+}
+
+/// Swap at most `max_hops` pools of a base input route, the remaining accounts are laid out as
+/// those of `swap_router_base_in`. If the route isn't done, where it stopped is saved in the
+/// route cursor for `continue_swap_router_base_in` and no result is returned.
+pub fn swap_router_base_in_resumable<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseInResumable<'info>>,
+    amount_in: u64,
+    amount_out_minimum: u64,
+    max_hops: u8,
+    deadline: i64,
+) -> Result<Option<SwapResult>> {
+    // the route spans several pools, so it gets the default horizon
+    check_deadline(deadline, 0)?;
+    require_gt!(max_hops, 0, ErrorCode::InvalidSwapInputNum);
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    check_input_balance(&ctx.accounts.input_token_account, amount_in)?;
+
+    let route_progress = route_base_in_hops(
+        &SwapRouterBaseIn {
+            payer: ctx.accounts.payer.clone(),
+            input_token_account: ctx.accounts.input_token_account.clone(),
+            input_token_mint: ctx.accounts.input_token_mint.clone(),
+            token_program: ctx.accounts.token_program.clone(),
+            token_program_2022: ctx.accounts.token_program_2022.clone(),
+            memo_program: ctx.accounts.memo_program.clone(),
+        },
+        ctx.remaining_accounts,
+        amount_in,
+        amount_out_minimum,
+        max_hops,
+    )?;
+
+    let route_cursor = &mut ctx.accounts.route_cursor;
+    route_cursor.bump = ctx.bumps.route_cursor;
+    route_cursor.owner = ctx.accounts.payer.key();
+    route_cursor.hops = 0;
+    route_cursor.amount_in = amount_in;
+    route_cursor.amount_out_minimum = amount_out_minimum;
+    route_cursor.deadline = deadline;
+    finish_or_save_route(
+        route_cursor,
+        &ctx.accounts.payer.to_account_info(),
+        route_progress,
+    )
+}
+
+/// Swap at most `max_hops` more pools of the route saved in the payer's route cursor,
+/// the remaining accounts are those of the hops left to swap
+pub fn continue_swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, ContinueSwapRouterBaseIn<'info>>,
+    max_hops: u8,
+) -> Result<Option<SwapResult>> {
+    require_gt!(max_hops, 0, ErrorCode::InvalidSwapInputNum);
+    let route_cursor = &mut ctx.accounts.route_cursor;
+    route_cursor.check_continue(
+        ctx.accounts.payer.key(),
+        ctx.accounts.input_token_account.key(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    check_input_balance(
+        &ctx.accounts.input_token_account,
+        route_cursor.amount_in_internal,
+    )?;
+
+    let route_progress = route_base_in_hops(
+        &SwapRouterBaseIn {
+            payer: ctx.accounts.payer.clone(),
+            input_token_account: ctx.accounts.input_token_account.clone(),
+            input_token_mint: ctx.accounts.input_token_mint.clone(),
+            token_program: ctx.accounts.token_program.clone(),
+            token_program_2022: ctx.accounts.token_program_2022.clone(),
+            memo_program: ctx.accounts.memo_program.clone(),
+        },
+        ctx.remaining_accounts,
+        route_cursor.amount_in_internal,
+        route_cursor.amount_out_minimum,
+        max_hops,
+    )?;
+    finish_or_save_route(
+        route_cursor,
+        &ctx.accounts.payer.to_account_info(),
+        route_progress,
+    )
+}
+
+/// Checks the slippage of a finished route and closes its cursor to the payer,
+/// or saves where an unfinished route stopped
+fn finish_or_save_route<'info>(
+    route_cursor: &mut Account<'info, RouteCursorState>,
+    payer: &AccountInfo<'info>,
+    route_progress: RouteProgress<'_, 'info>,
+) -> Result<Option<SwapResult>> {
+    route_cursor.hops = route_cursor
+        .hops
+        .checked_add(route_progress.hops)
+        .ok_or(ErrorCode::InvalidSwapInputNum)?;
+    route_cursor.amount_in_internal = route_progress.amount_in_internal;
+    if !route_progress.remaining_accounts.is_empty() {
+        route_cursor.input_token_account = route_progress.input_token_account.key();
+        #[cfg(feature = "enable-log")]
+        msg!(
+            "route stopped after hop:{}, amount_in_internal:{}",
+            route_cursor.hops,
+            route_cursor.amount_in_internal
+        );
+        return Ok(None);
+    }
+
+    require_gte!(
+        route_cursor.amount_in_internal,
+        route_cursor.amount_out_minimum,
+        ErrorCode::TooLittleOutputReceived
+    );
+    let last_pool_state = route_progress
+        .last_pool_state
+        .ok_or(ErrorCode::InvalidSwapInputNum)?;
+    let swap_result = SwapResult::new(
+        &*last_pool_state.load()?,
+        route_cursor.amount_in,
+        route_cursor.amount_in_internal,
+    );
+    route_cursor.close(payer.clone())?;
+    Ok(Some(swap_result))
+}
@@ -0,0 +1,265 @@
+use crate::error::ErrorCode;
+use crate::swap::{
+    check_amount_out_minimum, check_input_authority, check_input_balance, check_min_swap_amount,
+    check_vault_pair, SwapResult,
+};
+use crate::swap_v2::{exact_internal_v2, SwapSingleV2};
+use crate::util::check_deadline;
+use anchor_lang::prelude::*;
+
+/// Calculate the output amount needed to bring `current_output_balance` up to `target_output_balance`
+pub fn output_amount_to_balance(
+    current_output_balance: u64,
+    target_output_balance: u64,
+) -> Result<u64> {
+    require_gt!(
+        target_output_balance,
+        current_output_balance,
+        ErrorCode::TargetBalanceReached
+    );
+    Ok(target_output_balance - current_output_balance)
+}
+
+pub fn swap_to_balance<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+    target_output_balance: u64,
+    amount_in_maximum: u64,
+    sqrt_price_limit_x64: u128,
+    deadline: i64,
+) -> Result<SwapResult> {
+    let amount_out = output_amount_to_balance(
+        ctx.accounts.output_token_account.amount,
+        target_output_balance,
+    )?;
+    {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        check_deadline(deadline, pool_state.max_deadline_horizon)?;
+        check_vault_pair(
+            &pool_state,
+            ctx.accounts.input_vault.mint,
+            ctx.accounts.output_vault.mint,
+        )?;
+        check_amount_out_minimum(&pool_state, false, amount_in_maximum)?;
+        check_min_swap_amount(&pool_state, ctx.accounts.output_vault.mint, amount_out)?;
+    }
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    // the input isn't known before the swap, the account must hold the most it may take
+    check_input_balance(&ctx.accounts.input_token_account, amount_in_maximum)?;
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "swap to balance, current:{}, target:{}, amount_out:{}",
+        ctx.accounts.output_token_account.amount,
+        target_output_balance,
+        amount_out
+    );
+
+    let input_balance_before = ctx.accounts.input_token_account.amount;
+    let output_balance_before = ctx.accounts.output_token_account.amount;
+    exact_internal_v2(
+        ctx.accounts,
+        ctx.remaining_accounts,
+        amount_out,
+        sqrt_price_limit_x64,
+        false,
+    )?;
+    // the amounts are what the swap moved between the user's token accounts
+    ctx.accounts.input_token_account.reload()?;
+    ctx.accounts.output_token_account.reload()?;
+    let amount_in = input_balance_before - ctx.accounts.input_token_account.amount;
+    let amount_out = ctx.accounts.output_token_account.amount - output_balance_before;
+    require_gte!(amount_in_maximum, amount_in, ErrorCode::TooMuchInputPaid);
+    // a swap stopped short by its price limit or the pool's liquidity fails rather than
+    // leaving the account under the target
+    require_gte!(
+        ctx.accounts.output_token_account.amount,
+        target_output_balance,
+        ErrorCode::TargetBalanceNotReached
+    );
+
+    Ok(SwapResult::new(
+        &*ctx.accounts.pool_state.load()?,
+        amount_in,
+        amount_out,
+    ))
+}
+
+#[cfg(test)]
+mod swap_to_balance_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::{build_pool, build_pool_accounts};
+    use crate::states::PoolState;
+    use crate::util::program_test::{
+        process_instruction, program_error, TestAccount, TEST_UNIX_TIMESTAMP,
+    };
+    use crate::util::DEFAULT_MAX_DEADLINE_HORIZON;
+    use anchor_lang::ToAccountMetas;
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_2022::spl_token_2022;
+
+    #[test]
+    fn output_amount_reach_target_test() {
+        let current_output_balance = 1_000_000;
+        let target_output_balance = 1_234_567;
+        let amount_out =
+            output_amount_to_balance(current_output_balance, target_output_balance).unwrap();
+        assert_eq!(amount_out, 234_567);
+        assert_eq!(current_output_balance + amount_out, target_output_balance);
+    }
+
+    #[test]
+    fn output_amount_already_reach_target_test() {
+        assert!(output_amount_to_balance(1_000_000, 1_000_000).is_err());
+        assert!(output_amount_to_balance(1_000_001, 1_000_000).is_err());
+    }
+
+    /// The pool's accounts with the payer's token accounts, the payer holding `input_balance`
+    /// of token_0 to swap for token_1
+    fn build_swap_to_balance_accounts(
+        pool_state: &mut PoolState,
+        input_balance: u64,
+    ) -> (Vec<TestAccount>, crate::accounts::SwapSingleV2) {
+        let mut accounts = build_pool_accounts(pool_state, 1_000_000);
+        let payer = Pubkey::new_unique();
+        let (input_token_account, output_token_account) =
+            (Pubkey::new_unique(), Pubkey::new_unique());
+        accounts.extend([
+            TestAccount::wallet(payer),
+            TestAccount::token_account(
+                input_token_account,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                payer,
+                input_balance,
+            ),
+            TestAccount::token_account(
+                output_token_account,
+                spl_token::id(),
+                pool_state.token_mint_1,
+                payer,
+                0,
+            ),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(spl_token_2022::id()),
+            TestAccount::program(spl_memo::id()),
+            TestAccount::program(crate::id()),
+        ]);
+        let swap_accounts = crate::accounts::SwapSingleV2 {
+            payer,
+            amm_config: pool_state.amm_config,
+            pool_state: pool_state.key(),
+            input_token_account,
+            output_token_account,
+            input_vault: pool_state.token_vault_0,
+            output_vault: pool_state.token_vault_1,
+            observation_state: pool_state.observation_key,
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+            memo_program: spl_memo::id(),
+            input_vault_mint: pool_state.token_mint_0,
+            output_vault_mint: pool_state.token_mint_1,
+            referrer_token_account: None,
+        };
+        (accounts, swap_accounts)
+    }
+
+    #[test]
+    fn swap_to_balance_deadline_test() {
+        let pool_state = build_pool(0, 10, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        let (mut accounts, swap_accounts) = build_swap_to_balance_accounts(pool_state, 1_000_000);
+        let metas = swap_accounts.to_account_metas(Some(true));
+        let swap_to_balance = |deadline| crate::instruction::SwapToBalance {
+            target_output_balance: 1_000,
+            amount_in_maximum: 1_000_000,
+            sqrt_price_limit_x64: 0,
+            deadline,
+        };
+
+        // expired
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                metas.clone(),
+                swap_to_balance(TEST_UNIX_TIMESTAMP - 1)
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::TransactionTooOld)
+        );
+        // further than the pool allows
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                metas.clone(),
+                swap_to_balance(TEST_UNIX_TIMESTAMP + DEFAULT_MAX_DEADLINE_HORIZON as i64 + 1)
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::DeadlineTooFar)
+        );
+        // within the deadline the swap runs, and is checked against the target
+        assert_eq!(
+            process_instruction(
+                &mut accounts,
+                metas,
+                swap_to_balance(TEST_UNIX_TIMESTAMP + 60)
+            )
+            .unwrap_err(),
+            program_error(ErrorCode::TargetBalanceNotReached)
+        );
+    }
+
+    #[test]
+    fn swap_to_balance_test() {
+        let pool_state = build_pool(0, 10, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        let (mut accounts, swap_accounts) = build_swap_to_balance_accounts(pool_state, 1_000_000);
+        let output_token_account = swap_accounts.output_token_account;
+        let swap_to_balance = |amount_in_maximum| crate::instruction::SwapToBalance {
+            target_output_balance: 1_000,
+            amount_in_maximum,
+            sqrt_price_limit_x64: 0,
+            deadline: TEST_UNIX_TIMESTAMP + 60,
+        };
+
+        let metas = swap_accounts.to_account_metas(Some(true));
+        // both vaults of the same token
+        let same_vault_metas = metas
+            .iter()
+            .cloned()
+            .map(|mut meta| {
+                if meta.pubkey == pool_state.token_vault_1 {
+                    meta.pubkey = pool_state.token_vault_0;
+                } else if meta.pubkey == pool_state.token_mint_1 {
+                    meta.pubkey = pool_state.token_mint_0;
+                }
+                meta
+            })
+            .collect();
+        assert_eq!(
+            process_instruction(&mut accounts, same_vault_metas, swap_to_balance(1_000_000))
+                .unwrap_err(),
+            program_error(ErrorCode::InvalidVaultPair)
+        );
+
+        // the input account is checked before the swap to hold the most the swap may take
+        assert_eq!(
+            process_instruction(&mut accounts, metas.clone(), swap_to_balance(1_000_001))
+                .unwrap_err(),
+            program_error(ErrorCode::InsufficientInputBalance)
+        );
+        // the swap loop isn't wired in yet and transfers nothing, the output account is left
+        // short of the target, which fails the swap rather than reporting it done
+        assert_eq!(
+            process_instruction(&mut accounts, metas, swap_to_balance(1_000_000)).unwrap_err(),
+            program_error(ErrorCode::TargetBalanceNotReached)
+        );
+        assert_eq!(
+            accounts
+                .iter()
+                .find(|account| account.key == output_token_account)
+                .unwrap()
+                .token_amount(),
+            0
+        );
+    }
+}
@@ -3,7 +3,11 @@ use std::ops::Deref;
 
 use crate::error::ErrorCode;
 use crate::libraries::tick_math;
-use crate::swap::swap_internal;
+use crate::swap::{
+    check_amount_out_minimum, check_forbidden_tick, check_input_authority, check_input_balance,
+    check_min_swap_amount, check_twap_deviation, check_twap_price_bound, check_vault_pair,
+    get_output_reserve, get_referral_fee, SwapResult, TwapBound, TwapPriceBound,
+};
 use crate::util::*;
 use crate::{states::*, util};
 use anchor_lang::prelude::*;
@@ -68,6 +72,15 @@ pub struct SwapSingleV2<'info> {
         address = output_vault.mint
     )]
     pub output_vault_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The referrer token account for input token, receives the referral fee if given
+    #[account(
+        mut,
+        token::mint = input_vault_mint,
+        constraint = referrer_token_account.to_account_info().owner
+            == input_vault_mint.to_account_info().owner @ ErrorCode::InvalidReferrerAccount,
+    )]
+    pub referrer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     // remaining accounts
     // tickarray_bitmap_extension: must add account if need regardless the sequence
     // tick_array_account_1
@@ -78,42 +91,168 @@ pub struct SwapSingleV2<'info> {
 /// Performs a single exact input/output swap
 /// if is_base_input = true, return vaule is the max_amount_out, otherwise is min_amount_in
 pub fn exact_internal_v2<'c: 'info, 'info>(
-    _ctx: &mut SwapSingleV2<'info>,
+    ctx: &mut SwapSingleV2<'info>,
     _remaining_accounts: &'c [AccountInfo<'info>],
     _amount_specified: u64,
     _sqrt_price_limit_x64: u128,
     _is_base_input: bool,
 ) -> Result<u64> {
+    let pool_state = ctx.pool_state.load()?;
+    pool_state.check_initialized()?;
+    pool_state.check_not_deprecated()?;
     Ok(0)
 }
 
+/// Transfer the referral fee from the swapper to the referrer, nothing without a referrer
+fn transfer_referral_fee(accounts: &SwapSingleV2, referral_fee: u64) -> Result<()> {
+    if let Some(referrer_token_account) = &accounts.referrer_token_account {
+        transfer_from_user_to_pool_vault(
+            &accounts.payer,
+            &accounts.input_token_account,
+            referrer_token_account,
+            Some(accounts.input_vault_mint.clone()),
+            &accounts.token_program,
+            Some(accounts.token_program_2022.to_account_info()),
+            referral_fee,
+        )?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn swap_v2<'a, 'b, 'c: 'info, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
     amount: u64,
     other_amount_threshold: u64,
     sqrt_price_limit_x64: u128,
     is_base_input: bool,
-) -> Result<()> {
-    let amount_result = exact_internal_v2(
+    forbidden_tick: Option<i32>,
+    twap_bound: Option<TwapBound>,
+    post_swap_twap_bound: Option<TwapPriceBound>,
+) -> Result<SwapResult> {
+    let tick_before;
+    {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        tick_before = pool_state.tick_current;
+        check_vault_pair(
+            &pool_state,
+            ctx.accounts.input_vault.mint,
+            ctx.accounts.output_vault.mint,
+        )?;
+        check_amount_out_minimum(&pool_state, is_base_input, other_amount_threshold)?;
+        check_min_swap_amount(
+            &pool_state,
+            if is_base_input {
+                ctx.accounts.input_vault.mint
+            } else {
+                ctx.accounts.output_vault.mint
+            },
+            amount,
+        )?;
+        if let Some(twap_bound) = twap_bound {
+            let twap_price_x64 = ctx.accounts.observation_state.load()?.twap_price_x64(
+                pool_state.observation_index,
+                block_timestamp(),
+                twap_bound.window_seconds,
+            )?;
+            check_twap_deviation(
+                pool_state.sqrt_price_x64,
+                twap_price_x64,
+                twap_bound.max_deviation_bps,
+            )?;
+        }
+    }
+    check_input_authority(&ctx.accounts.input_token_account, ctx.accounts.payer.key())?;
+    // all the tick arrays of swap v2 are in the remaining accounts
+    require!(
+        !ctx.remaining_accounts.is_empty(),
+        ErrorCode::MissingTickArrayAccounts
+    );
+    let referrer_token_mint = ctx
+        .accounts
+        .referrer_token_account
+        .as_deref()
+        .map(|account| account.mint);
+    let mut amount_specified = amount;
+    let mut referral_fee = 0;
+    if is_base_input {
+        check_input_balance(&ctx.accounts.input_token_account, amount)?;
+        // the amount includes the referral fee, it's skimmed before the swap,
+        // which takes exactly the rest
+        referral_fee = get_referral_fee(
+            &ctx.accounts.amm_config,
+            referrer_token_mint,
+            ctx.accounts.input_vault_mint.key(),
+            amount,
+        )?;
+        amount_specified = amount - referral_fee;
+    } else {
+        // the output of swap base output is known up front
+        get_output_reserve(ctx.accounts.output_vault.amount, amount)?;
+    }
+    let input_balance_before = ctx.accounts.input_token_account.amount;
+    let output_balance_before = ctx.accounts.output_token_account.amount;
+    transfer_referral_fee(ctx.accounts, referral_fee)?;
+    exact_internal_v2(
         ctx.accounts,
         ctx.remaining_accounts,
-        amount,
+        amount_specified,
         sqrt_price_limit_x64,
         is_base_input,
     )?;
+    // the amounts are what the swap moved between the user's token accounts, a swap stopped
+    // by the price limit takes less than the amount specified, the input paid includes the
+    // referral fee skimmed for a base input swap
+    ctx.accounts.input_token_account.reload()?;
+    ctx.accounts.output_token_account.reload()?;
+    let amount_paid_in = input_balance_before - ctx.accounts.input_token_account.amount;
+    let amount_out = ctx.accounts.output_token_account.amount - output_balance_before;
+    check_forbidden_tick(
+        tick_before,
+        ctx.accounts.pool_state.load()?.tick_current,
+        forbidden_tick,
+    )?;
+    // the swap has written its observation, so the TWAP includes the price it moved to
+    if let Some(post_swap_twap_bound) = post_swap_twap_bound {
+        let twap_price_x64 = ctx.accounts.observation_state.load()?.twap_price_x64(
+            ctx.accounts.pool_state.load()?.observation_index,
+            block_timestamp(),
+            post_swap_twap_bound.window_seconds,
+        )?;
+        check_twap_price_bound(twap_price_x64, &post_swap_twap_bound)?;
+    }
     if is_base_input {
         require_gte!(
-            amount_result,
+            amount_out,
             other_amount_threshold,
             ErrorCode::TooLittleOutputReceived
         );
     } else {
+        // the input of swap base output is only known after the swap, so is its referral fee
+        referral_fee = get_referral_fee(
+            &ctx.accounts.amm_config,
+            referrer_token_mint,
+            ctx.accounts.input_vault_mint.key(),
+            amount_paid_in,
+        )?;
         require_gte!(
             other_amount_threshold,
-            amount_result,
+            amount_paid_in + referral_fee,
             ErrorCode::TooMuchInputPaid
         );
+        check_input_balance(&ctx.accounts.input_token_account, referral_fee)?;
+        transfer_referral_fee(ctx.accounts, referral_fee)?;
     }
 
-    Ok(())
+    let (amount_in, amount_in_consumed) = if is_base_input {
+        (amount, amount_paid_in)
+    } else {
+        (amount_paid_in + referral_fee, amount_paid_in + referral_fee)
+    };
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let zero_for_one = ctx.accounts.input_vault.mint == pool_state.token_mint_0;
+    Ok(
+        SwapResult::new_partial(&pool_state, amount_in, amount_in_consumed, amount_out)
+            .with_partial_fill_reason(sqrt_price_limit_x64, zero_for_one),
+    )
 }
@@ -13,6 +13,7 @@ pub fn update_reward_infos<'a, 'b, 'c, 'info>(
 ) -> Result<()> {
     let clock = Clock::get()?;
     let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.check_initialized()?;
     let updated_reward_infos = pool_state.update_reward_infos(u64::try_from(clock.unix_timestamp).unwrap())?;
 
     emit!(UpdateRewardInfosEvent {
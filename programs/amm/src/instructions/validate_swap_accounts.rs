@@ -0,0 +1,202 @@
+use crate::states::*;
+use crate::util::AccountLoad;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ValidateSwapAccounts<'info> {
+    /// The pool to swap in
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: The vault to pay the input into, checked against the pool in instruction
+    pub input_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The vault to receive the output from, checked against the pool in instruction
+    pub output_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The observation account to pass to the swap, checked against the pool in instruction
+    pub observation_state: UncheckedAccount<'info>,
+}
+
+/// Which accounts of a prospective swap are consistent with the pool, set as the instruction's
+/// return data. Nothing is enforced, a client reads the report before building the swap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwapAccountsReport {
+    /// Whether all the checks below pass
+    pub is_valid: bool,
+    /// Whether the pool is the program address derived from its config and mints
+    pub pool_address_valid: bool,
+    /// Whether the input and output vaults are the pool's two vaults, in either order
+    pub vaults_valid: bool,
+    /// Whether the input vault is the pool's token_0 vault, the direction the swap would take
+    pub zero_for_one: bool,
+    /// Whether the observation account is the pool's, and the pool's observation index is its
+    /// latest written observation
+    pub observation_valid: bool,
+}
+
+/// Check the accounts of a swap against the pool, `observation_state` is `None` if the
+/// observation account can't be loaded
+pub fn compute_swap_accounts_report(
+    pool_key: Pubkey,
+    pool_state: &PoolState,
+    input_vault: Pubkey,
+    output_vault: Pubkey,
+    observation_key: Pubkey,
+    observation_state: Option<&ObservationState>,
+) -> SwapAccountsReport {
+    let pool_address_valid =
+        Pubkey::create_program_address(&pool_state.seeds(), &crate::id()) == Ok(pool_key);
+    let zero_for_one =
+        input_vault == pool_state.token_vault_0 && output_vault == pool_state.token_vault_1;
+    let vaults_valid = zero_for_one
+        || (input_vault == pool_state.token_vault_1 && output_vault == pool_state.token_vault_0);
+    let observation_valid = observation_key == pool_state.observation_key
+        && observation_state.is_some_and(|observation_state| {
+            observation_state.pool_id == pool_key
+                && observation_state
+                    .check_latest_index(pool_state.observation_index)
+                    .is_ok()
+        });
+    SwapAccountsReport {
+        is_valid: pool_address_valid && vaults_valid && observation_valid,
+        pool_address_valid,
+        vaults_valid,
+        zero_for_one,
+        observation_valid,
+    }
+}
+
+pub fn validate_swap_accounts(ctx: Context<ValidateSwapAccounts>) -> Result<SwapAccountsReport> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let observation_info = ctx.accounts.observation_state.to_account_info();
+    // an account which isn't an observation is reported, not rejected
+    let observation_loader = AccountLoad::<ObservationState>::try_from(&observation_info).ok();
+    let observation_state = match &observation_loader {
+        Some(loader) => loader.load().ok(),
+        None => None,
+    };
+    let report = compute_swap_accounts_report(
+        ctx.accounts.pool_state.key(),
+        &pool_state,
+        ctx.accounts.input_vault.key(),
+        ctx.accounts.output_vault.key(),
+        ctx.accounts.observation_state.key(),
+        observation_state.as_deref(),
+    );
+    #[cfg(feature = "enable-log")]
+    msg!("swap accounts report:{:?}", report);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod validate_swap_accounts_test {
+    use super::*;
+    use crate::libraries::tick_math;
+    use crate::states::pool_test::build_pool;
+
+    #[test]
+    fn compute_swap_accounts_report_test() {
+        let pool_state = build_pool(0, 60, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+        let pool_state = &mut pool_state.borrow_mut();
+        pool_state.token_vault_0 = Pubkey::new_unique();
+        pool_state.token_vault_1 = Pubkey::new_unique();
+        pool_state.observation_key = Pubkey::new_unique();
+        pool_state.observation_index = 1;
+        let pool_key = pool_state.key();
+        let mut observation_state = ObservationState {
+            pool_id: pool_key,
+            ..Default::default()
+        };
+        observation_state.observations[0].block_timestamp = 100;
+        observation_state.observations[1].block_timestamp = 115;
+        let (vault_0, vault_1, observation_key) = (
+            pool_state.token_vault_0,
+            pool_state.token_vault_1,
+            pool_state.observation_key,
+        );
+
+        let report = compute_swap_accounts_report(
+            pool_key,
+            pool_state,
+            vault_0,
+            vault_1,
+            observation_key,
+            Some(&observation_state),
+        );
+        assert_eq!(
+            report,
+            SwapAccountsReport {
+                is_valid: true,
+                pool_address_valid: true,
+                vaults_valid: true,
+                zero_for_one: true,
+                observation_valid: true,
+            }
+        );
+        // the other direction
+        let report = compute_swap_accounts_report(
+            pool_key,
+            pool_state,
+            vault_1,
+            vault_0,
+            observation_key,
+            Some(&observation_state),
+        );
+        assert!(report.is_valid && !report.zero_for_one);
+
+        // a vault of another pool, or the same vault twice
+        for (input_vault, output_vault) in [(Pubkey::new_unique(), vault_1), (vault_0, vault_0)] {
+            let report = compute_swap_accounts_report(
+                pool_key,
+                pool_state,
+                input_vault,
+                output_vault,
+                observation_key,
+                Some(&observation_state),
+            );
+            assert!(!report.is_valid && !report.vaults_valid);
+            assert!(report.pool_address_valid && report.observation_valid);
+        }
+
+        // another pool account with the same fields
+        let report = compute_swap_accounts_report(
+            Pubkey::new_unique(),
+            pool_state,
+            vault_0,
+            vault_1,
+            observation_key,
+            Some(&observation_state),
+        );
+        assert!(!report.is_valid && !report.pool_address_valid);
+
+        // another observation account, an account that isn't one, or a stale index
+        let report = compute_swap_accounts_report(
+            pool_key,
+            pool_state,
+            vault_0,
+            vault_1,
+            Pubkey::new_unique(),
+            Some(&observation_state),
+        );
+        assert!(!report.is_valid && !report.observation_valid);
+        let report = compute_swap_accounts_report(
+            pool_key,
+            pool_state,
+            vault_0,
+            vault_1,
+            observation_key,
+            None,
+        );
+        assert!(!report.observation_valid);
+        pool_state.observation_index = 0;
+        let report = compute_swap_accounts_report(
+            pool_key,
+            pool_state,
+            vault_0,
+            vault_1,
+            observation_key,
+            Some(&observation_state),
+        );
+        assert!(!report.is_valid && !report.observation_valid);
+    }
+}
@@ -71,12 +71,30 @@ pub mod amm_v3 {
     /// * `fund_fee_rate`- The new fund fee rate of amm config, be set when `param` is 2
     /// * `new_owner`- The config's new owner, be set when `param` is 3
     /// * `new_fund_owner`- The config's new fund owner, be set when `param` is 4
-    /// * `param`- The vaule can be 0 | 1 | 2 | 3 | 4, otherwise will report a error
+    /// * `status`- The config's status, 0: enable, 1: disable creating pools, be set when `param` is 5
+    /// * `referral_fee_bps`- The referral fee of swaps in bips, at most 100, be set when `param` is 6
+    /// * `secondary_protocol_fee_bps`- The share of protocol fees to the secondary recipient in bips, be set when `param` is 7
+    /// * `param`- The vaule can be 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7, otherwise will report a error
     ///
     pub fn update_amm_config(ctx: Context<UpdateAmmConfig>, param: u8, value: u32) -> Result<()> {
         instructions::update_amm_config(ctx, param, value)
     }
 
+    /// Sets the owner of the token accounts the secondary share of the config's collected
+    /// protocol fees is sent to, creating the config's recipient account on first use
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `recipient`- The owner of the secondary recipient token accounts
+    ///
+    pub fn set_secondary_protocol_fee_recipient(
+        ctx: Context<SetSecondaryProtocolFeeRecipient>,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        instructions::set_secondary_protocol_fee_recipient(ctx, recipient)
+    }
+
     /// Creates a pool for the given token pair and the initial price
     ///
     /// # Arguments
@@ -92,6 +110,86 @@ pub mod amm_v3 {
         instructions::create_pool(ctx, sqrt_price_x64, open_time)
     }
 
+    /// Creates a pool for the given token pair and the initial price with vaults the caller
+    /// created, each an empty token account of its mint owned by the pool
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `sqrt_price_x64` - the initial sqrt price (amount_token_1 / amount_token_0) of the pool as a Q64.64
+    ///
+    pub fn create_pool_with_vaults(
+        ctx: Context<CreatePoolWithVaults>,
+        sqrt_price_x64: u128,
+        open_time: u64,
+    ) -> Result<()> {
+        instructions::create_pool_with_vaults(ctx, sqrt_price_x64, open_time)
+    }
+
+    /// Creates a pool for the given token pair with the initial price at a tick
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `initial_tick` - the initial tick of the pool, must be a multiple of the tick spacing
+    ///
+    pub fn create_pool_at_tick(
+        ctx: Context<CreatePool>,
+        initial_tick: i32,
+        open_time: u64,
+    ) -> Result<()> {
+        instructions::create_pool_at_tick(ctx, initial_tick, open_time)
+    }
+
+    /// Compute the initial sqrt price and tick of a pool for a human price,
+    /// the result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `price_x64` - The price as token_1 per token_0 in UI units, as a Q64.64
+    /// * `decimals_0` - The decimals of token_0 mint
+    /// * `decimals_1` - The decimals of token_1 mint
+    ///
+    pub fn compute_initial_sqrt_price(
+        ctx: Context<ComputeInitialSqrtPrice>,
+        price_x64: u128,
+        decimals_0: u8,
+        decimals_1: u8,
+    ) -> Result<InitialSqrtPrice> {
+        instructions::compute_initial_sqrt_price(ctx, price_x64, decimals_0, decimals_1)
+    }
+
+    /// Compute the ticks of the range covering a band of human prices, aligned to the
+    /// tick spacing, the result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `price_lower_x64` - The lower price as token_1 per token_0 in UI units, as a Q64.64
+    /// * `price_upper_x64` - The upper price as token_1 per token_0 in UI units, as a Q64.64
+    /// * `decimals_0` - The decimals of token_0 mint
+    /// * `decimals_1` - The decimals of token_1 mint
+    /// * `tick_spacing` - The tick spacing of the pool
+    ///
+    pub fn price_band_to_ticks(
+        ctx: Context<PriceBandToTicks>,
+        price_lower_x64: u128,
+        price_upper_x64: u128,
+        decimals_0: u8,
+        decimals_1: u8,
+        tick_spacing: u16,
+    ) -> Result<PriceBandTicks> {
+        instructions::price_band_to_ticks(
+            ctx,
+            price_lower_x64,
+            price_upper_x64,
+            decimals_0,
+            decimals_1,
+            tick_spacing,
+        )
+    }
+
     /// Update pool status for given vaule
     ///
     /// # Arguments
@@ -103,6 +201,79 @@ pub mod amm_v3 {
         instructions::update_pool_status(ctx, status)
     }
 
+    /// Deprecate a migrated pool, swaps and liquidity adds are rejected while LPs can still
+    /// decrease liquidity and collect
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `successor_pool` - The pool that replaces it, recorded for integrators
+    ///
+    pub fn deprecate_pool(
+        ctx: Context<UpdatePoolStatus>,
+        successor_pool: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::deprecate_pool(ctx, successor_pool)
+    }
+
+    /// Update the seconds liquidity must stay in a position of the pool before it can be
+    /// decreased, to deter liquidity added just in time for a swap and removed right after
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `position_min_duration` - The seconds since the last increase of a position, 0 for no limit
+    ///
+    pub fn update_pool_position_min_duration(
+        ctx: Context<UpdatePoolStatus>,
+        position_min_duration: u64,
+    ) -> Result<()> {
+        instructions::update_pool_position_min_duration(ctx, position_min_duration)
+    }
+
+    /// Update how far in the future the deadline of an instruction on the pool can be,
+    /// so a huge deadline can't switch the protection off
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `max_deadline_horizon` - The seconds from now a deadline can be, 0 for the default
+    ///
+    pub fn update_pool_max_deadline_horizon(
+        ctx: Context<UpdatePoolStatus>,
+        max_deadline_horizon: u64,
+    ) -> Result<()> {
+        instructions::update_pool_max_deadline_horizon(ctx, max_deadline_horizon)
+    }
+
+    /// Return the protocol's share of the pool's trade fees to the LPs, or switch it back.
+    /// While set, the share is added to the fee growth and no protocol fee can be collected.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `protocol_fee_to_lp` - Whether the protocol's share goes to the LPs
+    ///
+    pub fn update_pool_protocol_fee_to_lp(
+        ctx: Context<UpdatePoolStatus>,
+        protocol_fee_to_lp: bool,
+    ) -> Result<()> {
+        instructions::update_pool_protocol_fee_to_lp(ctx, protocol_fee_to_lp)
+    }
+
+    /// Close an observation account which was allocated but never bound to a pool, refund the rent
+    /// to the payer of its creation. The first call, signed by the payer, schedules the close,
+    /// which is done by a call of the admin or the payer once the grace period has passed, unless
+    /// the account was used to create a pool in between
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    ///
+    pub fn close_unused_observation(ctx: Context<CloseUnusedObservation>) -> Result<()> {
+        instructions::close_unused_observation(ctx)
+    }
+
     /// Creates an operation account for the program
     ///
     /// # Arguments
@@ -216,7 +387,40 @@ pub mod amm_v3 {
         )
     }
 
-    /// Collect the protocol fee accrued to the pool
+    /// Cap the total liquidity of the pool, e.g. for a guarded launch.
+    /// Only the pool owner or the admin can set it, and once set it can only be raised.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `max_total_liquidity` - The max sum of the liquidity of all positions, at least the sum
+    ///
+    pub fn set_pool_max_total_liquidity(
+        ctx: Context<SetPoolMaxTotalLiquidity>,
+        max_total_liquidity: u128,
+    ) -> Result<()> {
+        instructions::set_pool_max_total_liquidity(ctx, max_total_liquidity)
+    }
+
+    /// Set the minimum amount of each token of a swap in the pool, to make dust swaps moving
+    /// the oracle and crossing ticks not worth it. Only the pool owner can set it.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `min_swap_amount_0` - The minimum amount of token_0 swapped in or out, 0 for any
+    /// * `min_swap_amount_1` - The minimum amount of token_1 swapped in or out, 0 for any
+    ///
+    pub fn set_pool_min_swap_amount(
+        ctx: Context<SetPoolMinSwapAmount>,
+        min_swap_amount_0: u64,
+        min_swap_amount_1: u64,
+    ) -> Result<()> {
+        instructions::set_pool_min_swap_amount(ctx, min_swap_amount_0, min_swap_amount_1)
+    }
+
+    /// Collect the protocol fee accrued to the pool, the config's `secondary_protocol_fee_bps` of it
+    /// is sent to the token accounts of the recipient set by `set_secondary_protocol_fee_recipient`
     ///
     /// # Arguments
     ///
@@ -325,6 +529,77 @@ pub mod amm_v3 {
         )
     }
 
+    /// Creates the tick arrays and the protocol position of a range in one call,
+    /// so positions can later be opened in it without paying for them
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `tick_lower_index` - The low boundary of market
+    /// * `tick_upper_index` - The upper boundary of market
+    /// * `tick_array_lower_start_index` - The start index of tick array which include tick low
+    /// * `tick_array_upper_start_index` - The start index of tick array which include tick upper
+    ///
+    pub fn init_position_accounts(
+        ctx: Context<InitPositionAccounts>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+    ) -> Result<()> {
+        instructions::init_position_accounts(
+            ctx,
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+        )
+    }
+
+    /// Migrates liquidity withdrawn from a constant product pool into a new position wrapped in a NFT, support Token2022.
+    /// Meant to follow the withdrawal in the same transaction, the most liquidity both amounts can pay for is minted
+    /// and the excess of the imbalanced side stays in the payer's token account, reported in the return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `tick_lower_index` - The low boundary of market
+    /// * `tick_upper_index` - The upper boundary of market
+    /// * `tick_array_lower_start_index` - The start index of tick array which include tick low
+    /// * `tick_array_upper_start_index` - The start index of tick array which include tick upper
+    /// * `amount_0` - The amount of token_0 to import, including transfer fee
+    /// * `amount_1` - The amount of token_1 to import, including transfer fee
+    /// * `amount_0_min` - The minimum amount of token_0 deposited, which serves as a slippage check
+    /// * `amount_1_min` - The minimum amount of token_1 deposited, which serves as a slippage check
+    /// * `deadline` - The unix timestamp after which the import is rejected
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_liquidity<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, OpenPositionV2<'info>>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        amount_0: u64,
+        amount_1: u64,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        deadline: i64,
+    ) -> Result<ImportLiquidityResult> {
+        instructions::import_liquidity(
+            ctx,
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+            amount_0,
+            amount_1,
+            amount_0_min,
+            amount_1_min,
+            deadline,
+        )
+    }
+
     /// Close a position, the nft mint and nft account
     ///
     /// # Arguments
@@ -337,6 +612,74 @@ pub mod amm_v3 {
         instructions::close_position(ctx)
     }
 
+    /// Close the owner's empty positions with their nft mints and nft accounts in one call,
+    /// together with the protocol positions and tick arrays `close_position_complete` would
+    /// close, refunding the rent. Positions not safe to close are skipped, the closed ones
+    /// are emitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts, the position nft mint, nft account, position, pool,
+    /// protocol position and lower and upper tick arrays of each position in the remaining accounts
+    ///
+    pub fn cleanup_accounts<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CleanupAccounts<'info>>,
+    ) -> Result<()> {
+        instructions::cleanup_accounts(ctx)
+    }
+
+    /// Close an empty position with its nft mint and nft account, together with the
+    /// protocol position and tick arrays no other position refers to and the nft owner paid for,
+    /// refunding their rent to the nft owner
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn close_position_complete<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ClosePositionComplete<'info>>,
+    ) -> Result<()> {
+        instructions::close_position_complete(ctx)
+    }
+
+    /// Grow a position account of an older layout to the current one,
+    /// the fields added since then are set to their default
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn migrate_position(ctx: Context<MigratePosition>) -> Result<()> {
+        instructions::migrate_position(ctx)
+    }
+
+    /// Set the initialized flag of a pool created before the flag was added,
+    /// which every instruction of the pool checks
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate_pool(ctx)
+    }
+
+    /// Set or clear the operator of a position, who can increase and decrease its liquidity
+    /// and collect its fees without holding the position NFT, until the NFT is transferred
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `operator` - The operator to approve, none to clear it
+    ///
+    #[access_control(is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account))]
+    pub fn set_position_operator(
+        ctx: Context<SetPositionOperator>,
+        operator: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_position_operator(ctx, operator)
+    }
+
     /// Increases liquidity with a exist position, with amount paid by `payer`
     ///
     /// # Arguments
@@ -346,7 +689,11 @@ pub mod amm_v3 {
     /// * `amount_0_max` - The max amount of token_0 to spend, which serves as a slippage check
     /// * `amount_1_max` - The max amount of token_1 to spend, which serves as a slippage check
     ///
-    #[access_control(is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account))]
+    #[access_control(is_authorized_for_position(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.nft_account,
+        &ctx.accounts.personal_position
+    ))]
     pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidity<'info>>,
         liquidity: u128,
@@ -367,7 +714,11 @@ pub mod amm_v3 {
     /// * `amount_1_max` - The max amount of token_1 to spend, which serves as a slippage check
     /// * `base_flag` - must be specified if liquidity is zero, true: calculate liquidity base amount_0_max otherwise base amount_1_max
     ///
-    #[access_control(is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account))]
+    #[access_control(is_authorized_for_position(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.nft_account,
+        &ctx.accounts.personal_position
+    ))]
     pub fn increase_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidityV2<'info>>,
         liquidity: u128,
@@ -390,7 +741,11 @@ pub mod amm_v3 {
     /// * `amount_0_min` - The minimum amount of token_0 that should be accounted for the burned liquidity
     /// * `amount_1_min` - The minimum amount of token_1 that should be accounted for the burned liquidity
     ///
-    #[access_control(is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account))]
+    #[access_control(is_authorized_for_position(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.nft_account,
+        &ctx.accounts.personal_position
+    ))]
     pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidity<'info>>,
         liquidity: u128,
@@ -409,7 +764,11 @@ pub mod amm_v3 {
     /// * `amount_0_min` - The minimum amount of token_0 that should be accounted for the burned liquidity
     /// * `amount_1_min` - The minimum amount of token_1 that should be accounted for the burned liquidity
     ///
-    #[access_control(is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account))]
+    #[access_control(is_authorized_for_position(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.nft_account,
+        &ctx.accounts.personal_position
+    ))]
     pub fn decrease_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityV2<'info>>,
         liquidity: u128,
@@ -419,6 +778,49 @@ pub mod amm_v3 {
         instructions::decrease_liquidity_v2(ctx, liquidity, amount_0_min, amount_1_min)
     }
 
+    /// Decreases liquidity of a position by enough to free about a target amount of token_0 at
+    /// the current price, or all of its liquidity if it holds less. Support Token2022.
+    /// The amount of token_0 freed, excluding fees, is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` -  The context of accounts
+    /// * `target_amount_0` - The amount of token_0 to withdraw, the amount freed is at most this
+    /// * `amount_1_min` - The minimum amount of token_1 that should be accounted for the burned liquidity
+    /// * `deadline` - The unix timestamp after which the decrease is rejected
+    ///
+    #[access_control(is_authorized_for_position(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.nft_account,
+        &ctx.accounts.personal_position
+    ))]
+    pub fn decrease_liquidity_for_amount_0<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityV2<'info>>,
+        target_amount_0: u64,
+        amount_1_min: u64,
+        deadline: i64,
+    ) -> Result<u64> {
+        instructions::decrease_liquidity_for_amount_0(ctx, target_amount_0, amount_1_min, deadline)
+    }
+
+    /// Adds the fees owed to a position to its liquidity in the same range, without a transfer.
+    /// The fees the liquidity at the current price doesn't take stay owed to the position.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `liquidity_min` - The minimum liquidity to be added, which serves as a slippage check
+    /// * `deadline` - The unix timestamp after which the compound is rejected
+    ///
+    #[access_control(is_authorized_for_token(&ctx.accounts.nft_owner, &ctx.accounts.nft_account))]
+    pub fn compound_position<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CompoundPosition<'info>>,
+        liquidity_min: u128,
+        deadline: i64,
+    ) -> Result<()> {
+        instructions::compound_position(ctx, liquidity_min, deadline)
+    }
+
     /// Swaps one token for as much as possible of another token across a single pool
     ///
     /// # Arguments
@@ -435,7 +837,7 @@ pub mod amm_v3 {
         other_amount_threshold: u64,
         sqrt_price_limit_x64: u128,
         is_base_input: bool,
-    ) -> Result<()> {
+    ) -> Result<SwapResult> {
         instructions::swap(
             ctx,
             amount,
@@ -445,6 +847,62 @@ pub mod amm_v3 {
         )
     }
 
+    /// Swaps as little as possible of one token for `amount_out` of another token across a
+    /// single pool
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_out` - The exact amount of output token to receive
+    /// * `amount_in_maximum` - The most input token to pay, for slippage check, see
+    /// `exact_output_single_bps` to give it relative to the quoted input
+    /// * `deadline` - The unix timestamp after which the swap is rejected
+    /// * `sqrt_price_limit_x64` - The Q64.64 sqrt price √P limit, zero for no limit
+    ///
+    pub fn exact_output_single<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingle<'info>>,
+        amount_out: u64,
+        amount_in_maximum: u64,
+        deadline: i64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<SwapResult> {
+        instructions::exact_output_single(
+            ctx,
+            amount_out,
+            amount_in_maximum,
+            deadline,
+            sqrt_price_limit_x64,
+        )
+    }
+
+    /// Swaps as little as possible of one token for `amount_out` of another token across a
+    /// single pool, with the input cap given relative to the input quoted on chain
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_out` - The exact amount of output token to receive
+    /// * `amount_in_maximum_bps` - The most input token to pay in bips over the input quoted
+    /// for `amount_out` by the pool's current state
+    /// * `deadline` - The unix timestamp after which the swap is rejected
+    /// * `sqrt_price_limit_x64` - The Q64.64 sqrt price √P limit, zero for no limit
+    ///
+    pub fn exact_output_single_bps<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingle<'info>>,
+        amount_out: u64,
+        amount_in_maximum_bps: u16,
+        deadline: i64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<SwapResult> {
+        instructions::exact_output_single_bps(
+            ctx,
+            amount_out,
+            amount_in_maximum_bps,
+            deadline,
+            sqrt_price_limit_x64,
+        )
+    }
+
     /// Swaps one token for as much as possible of another token across a single pool, support token program 2022
     ///
     /// # Arguments
@@ -461,13 +919,52 @@ pub mod amm_v3 {
         other_amount_threshold: u64,
         sqrt_price_limit_x64: u128,
         is_base_input: bool,
-    ) -> Result<()> {
+    ) -> Result<SwapResult> {
         instructions::swap_v2(
             ctx,
             amount,
             other_amount_threshold,
             sqrt_price_limit_x64,
             is_base_input,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Swaps as swap_v2 does, failing when the swap breaks any of the limits set
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount` - Arranged in pairs with other_amount_threshold. (amount_in, amount_out_minimum) or (amount_out, amount_in_maximum)
+    /// * `other_amount_threshold` - For slippage check
+    /// * `sqrt_price_limit` - The Q64.64 sqrt price √P limit. If zero for one, the price cannot
+    /// * `is_base_input` - swap base input or swap base output
+    /// * `forbidden_tick` - If set, the swap fails when the current tick moves across it
+    /// * `twap_bound` - If set, the swap fails when the spot price deviates from the pool's TWAP beyond the bound
+    /// * `post_swap_twap_bound` - If set, the swap fails when the pool's TWAP after the swap is out of the bound
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap_v2_with_limits<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+        forbidden_tick: Option<i32>,
+        twap_bound: Option<TwapBound>,
+        post_swap_twap_bound: Option<TwapPriceBound>,
+    ) -> Result<SwapResult> {
+        instructions::swap_v2(
+            ctx,
+            amount,
+            other_amount_threshold,
+            sqrt_price_limit_x64,
+            is_base_input,
+            forbidden_tick,
+            twap_bound,
+            post_swap_twap_bound,
         )
     }
 
@@ -483,7 +980,416 @@ pub mod amm_v3 {
         ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseIn<'info>>,
         amount_in: u64,
         amount_out_minimum: u64,
-    ) -> Result<()> {
+    ) -> Result<SwapResult> {
         instructions::swap_router_base_in(ctx, amount_in, amount_out_minimum)
     }
+
+    /// Swap across the path provided like `swap_router_base_in`, but through at most `max_hops`
+    /// pools, so a route too long for one transaction's compute budget can be split.
+    /// Where the route stops is saved in the payer's route cursor to be finished by
+    /// `continue_swap_router_base_in`, the swap result is only returned once the route is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_in` - Token amount to be swapped in
+    /// * `amount_out_minimum` - Panic if the route's output amount is below minimum amount. For slippage.
+    /// * `max_hops` - The most pools to swap through in this transaction
+    /// * `deadline` - The route can't be continued after this unix timestamp
+    ///
+    pub fn swap_router_base_in_resumable<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseInResumable<'info>>,
+        amount_in: u64,
+        amount_out_minimum: u64,
+        max_hops: u8,
+        deadline: i64,
+    ) -> Result<Option<SwapResult>> {
+        instructions::swap_router_base_in_resumable(
+            ctx,
+            amount_in,
+            amount_out_minimum,
+            max_hops,
+            deadline,
+        )
+    }
+
+    /// Swap through at most `max_hops` more pools of the route the payer started with
+    /// `swap_router_base_in_resumable`. The remaining accounts are those of the hops left.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `max_hops` - The most pools to swap through in this transaction
+    ///
+    pub fn continue_swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ContinueSwapRouterBaseIn<'info>>,
+        max_hops: u8,
+    ) -> Result<Option<SwapResult>> {
+        instructions::continue_swap_router_base_in(ctx, max_hops)
+    }
+
+    /// Swap as little as possible of a token for `amount_out` of another token across the path
+    /// provided, base output. The input of each pool is quoted from the last pool to the first
+    /// before any token moves, then the pools are swapped from the first.
+    /// The remaining accounts are the accounts of each pool in turn, laid out as the remaining
+    /// accounts of `swap_router_base_in`, a pool can't be passed through twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_out` - The exact amount of output token to receive from the last pool
+    /// * `amount_in_maximum` - The most input token to pay to the first pool, for slippage check
+    /// * `additional_accounts_per_pool` - The number of bitmap extension and tick array
+    /// accounts following the `ROUTE_POOL_ACCOUNT_NUM` accounts of each pool
+    /// * `deadline` - The unix timestamp after which the swap is rejected, within the deadline
+    /// horizon of every pool on the route
+    ///
+    pub fn exact_output<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseOut<'info>>,
+        amount_out: u64,
+        amount_in_maximum: u64,
+        additional_accounts_per_pool: Vec<u8>,
+        deadline: i64,
+    ) -> Result<SwapResult> {
+        instructions::exact_output(
+            ctx,
+            amount_out,
+            amount_in_maximum,
+            additional_accounts_per_pool,
+            deadline,
+        )
+    }
+
+    /// Simulate swapping along each of the candidate routes and execute only the one with
+    /// the most output, base input. The chosen route is emitted.
+    /// The remaining accounts are the accounts of each route in turn, each laid out as
+    /// the remaining accounts of `swap_router_base_in`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_in` - Token amount to be swapped in
+    /// * `amount_out_minimum` - Panic if output amount is below minimum amount. For slippage.
+    /// * `routes` - The number of bitmap extension and tick array accounts of each pool
+    ///
+    pub fn exact_input_best_of<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseIn<'info>>,
+        amount_in: u64,
+        amount_out_minimum: u64,
+        routes: Vec<RouteSpec>,
+    ) -> Result<SwapResult> {
+        instructions::exact_input_best_of(ctx, amount_in, amount_out_minimum, routes)
+    }
+
+    /// Swap several tokens into the same output token, each through its own pool, base input
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amounts_in` - Token amount to be swapped in for each input, at most `MAX_SWAP_INPUT_NUM`
+    /// * `amount_out_minimum` - Panic if the total output amount is below minimum amount. For slippage.
+    ///
+    pub fn swap_multi_input<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapMultiInput<'info>>,
+        amounts_in: Vec<u64>,
+        amount_out_minimum: u64,
+    ) -> Result<SwapResult> {
+        instructions::swap_multi_input(ctx, amounts_in, amount_out_minimum)
+    }
+
+    /// Execute several independent swaps across different pools atomically,
+    /// the whole batch reverts if any swap fails its own slippage check
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts, the accounts of each swap are given in the remaining accounts
+    /// * `swaps` - The amount, slippage threshold and price limit of each swap, at most `MAX_BATCH_SWAP_NUM`
+    ///
+    pub fn batch_swap<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, BatchSwap<'info>>,
+        swaps: Vec<BatchSwapSpec>,
+    ) -> Result<Vec<SwapResult>> {
+        instructions::batch_swap(ctx, swaps)
+    }
+
+    /// Swaps until the output token account holds the target balance, support token program 2022
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `target_output_balance` - The balance of output token account expected after swap, must be greater than the current balance
+    /// * `amount_in_maximum` - The max amount of input token to spend, for slippage check
+    /// * `sqrt_price_limit_x64` - The Q64.64 sqrt price √P limit
+    /// * `deadline` - The unix timestamp after which the swap is rejected
+    ///
+    pub fn swap_to_balance<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+        target_output_balance: u64,
+        amount_in_maximum: u64,
+        sqrt_price_limit_x64: u128,
+        deadline: i64,
+    ) -> Result<SwapResult> {
+        instructions::swap_to_balance(
+            ctx,
+            target_output_balance,
+            amount_in_maximum,
+            sqrt_price_limit_x64,
+            deadline,
+        )
+    }
+
+    /// Read the pool info, include the tick spacing of its fee tier,
+    /// the result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_pool_info(ctx: Context<GetPoolInfo>) -> Result<PoolInfo> {
+        instructions::get_pool_info(ctx)
+    }
+
+    /// Read the facts fixed when the pool created: its config, tokens, tick spacing,
+    /// genesis tick, creator and creation time. The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_pool_immutables(ctx: Context<GetPoolImmutables>) -> Result<PoolImmutables> {
+        instructions::get_pool_immutables(ctx)
+    }
+
+    /// Check that the vaults and the observation account of a prospective swap belong to the
+    /// pool, and the pool is its program address. The report is set as return data, nothing
+    /// is rejected, so it can run in simulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn validate_swap_accounts(
+        ctx: Context<ValidateSwapAccounts>,
+    ) -> Result<SwapAccountsReport> {
+        instructions::validate_swap_accounts(ctx)
+    }
+
+    /// Suggest a default slippage for swapping an amount in the pool, from its fee tier and
+    /// the price impact of the amount at the in range liquidity. Advisory only, the result is
+    /// set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_in` - The amount of input token to swap
+    /// * `zero_for_one` - Whether the input token is token_0
+    ///
+    pub fn get_suggested_slippage(
+        ctx: Context<GetSuggestedSlippage>,
+        amount_in: u64,
+        zero_for_one: bool,
+    ) -> Result<SuggestedSlippage> {
+        instructions::get_suggested_slippage(ctx, amount_in, zero_for_one)
+    }
+
+    /// Read the full amm config, versioned so fields can be appended,
+    /// the result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_amm_config(ctx: Context<GetAmmConfig>) -> Result<AmmConfigInfo> {
+        instructions::get_amm_config(ctx)
+    }
+
+    /// Read the observation index the pool last wrote and how far the ring is written,
+    /// so a client knows the observation account to pass to a swap.
+    /// The result is set as return data and emitted in an event.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_current_observation(
+        ctx: Context<GetCurrentObservation>,
+    ) -> Result<CurrentObservation> {
+        instructions::get_current_observation(ctx)
+    }
+
+    /// Count the initialized tick arrays in a 64 bits word of the pool's default tick array bitmap,
+    /// so clients know how many tick arrays to fetch for a price region.
+    /// The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `word_pos` - The word of the bitmap, 0 to 15, words below 8 hold the negative tick arrays
+    ///
+    pub fn count_initialized_tick_arrays(
+        ctx: Context<CountInitializedTickArrays>,
+        word_pos: u8,
+    ) -> Result<u32> {
+        instructions::count_initialized_tick_arrays(ctx, word_pos)
+    }
+
+    /// Check whether the pool's initialized ticks in the given tick arrays cover a swap,
+    /// and at which tick the swap would stop otherwise, so routers can skip thin pools.
+    /// The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount` - Amount of the swap, the input if `is_base_input`, the output otherwise
+    /// * `zero_for_one` - The direction of the swap, true for token_0 to token_1
+    /// * `is_base_input` - Swap base input or base output
+    ///
+    pub fn get_swap_tick_limit<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, GetSwapTickLimit<'info>>,
+        amount: u64,
+        zero_for_one: bool,
+        is_base_input: bool,
+    ) -> Result<SwapTickLimit> {
+        instructions::get_swap_tick_limit(ctx, amount, zero_for_one, is_base_input)
+    }
+
+    /// Quote a base input swap in a single pool without executing it: the amounts and the
+    /// trade fee it would charge, with the protocol's and fund's shares.
+    /// The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `amount_in` - Token amount to be swapped in
+    /// * `sqrt_price_limit_x64` - The Q64.64 sqrt price √P limit, 0 for no limit
+    /// * `zero_for_one` - The direction of the swap, true for token_0 to token_1
+    ///
+    pub fn quote_exact_input_single<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, QuoteExactInputSingle<'info>>,
+        amount_in: u64,
+        sqrt_price_limit_x64: u128,
+        zero_for_one: bool,
+    ) -> Result<SwapQuote> {
+        instructions::quote_exact_input_single(ctx, amount_in, sqrt_price_limit_x64, zero_for_one)
+    }
+
+    /// Compute the in range liquidity of the pool if its price were at a tick, crossing the
+    /// initialized ticks from the current tick in either direction, for liquidity depth charts.
+    /// The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `tick` - The tick to read the liquidity at
+    ///
+    pub fn get_liquidity_at_tick<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, GetLiquidityAtTick<'info>>,
+        tick: i32,
+    ) -> Result<u128> {
+        instructions::get_liquidity_at_tick(ctx, tick)
+    }
+
+    /// Compute the pool's price and in range liquidity if `liquidity` were minted in a range,
+    /// without changing the pool, so LPs can check a range before adding to it.
+    /// The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `tick_lower` - The low boundary of the range
+    /// * `tick_upper` - The upper boundary of the range
+    /// * `liquidity` - The liquidity to be minted
+    ///
+    pub fn get_liquidity_after_mint(
+        ctx: Context<GetLiquidityAfterMint>,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> Result<LiquidityAfterMint> {
+        instructions::get_liquidity_after_mint(ctx, tick_lower, tick_upper, liquidity)
+    }
+
+    /// Read the fee growth globals, liquidity and current tick of the pool, emitted as an event
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_fee_growth_global(ctx: Context<GetPoolInfo>) -> Result<()> {
+        instructions::get_fee_growth_global(ctx)
+    }
+
+    /// Estimate the annualized fee yield of a position in bps, the result is set as return data.
+    /// Assumes the volume of the lookback window stays constant for a year, and the position
+    /// stays in range with the current share of liquidity.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `lookback_seconds` - The length of the window the fees are sampled in
+    /// * `total_fees_token_0_before` - The pool's `total_fees_token_0` at the start of the window
+    /// * `total_fees_token_1_before` - The pool's `total_fees_token_1` at the start of the window
+    ///
+    pub fn get_position_fee_apr(
+        ctx: Context<GetPositionFeeApr>,
+        lookback_seconds: u64,
+        total_fees_token_0_before: u64,
+        total_fees_token_1_before: u64,
+    ) -> Result<()> {
+        instructions::get_position_fee_apr(
+            ctx,
+            lookback_seconds,
+            total_fees_token_0_before,
+            total_fees_token_1_before,
+        )
+    }
+
+    /// Read the fees owed to a position and the fees it collected over its lifetime,
+    /// the result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_position_fees(ctx: Context<GetPositionFees>) -> Result<PositionFees> {
+        instructions::get_position_fees(ctx)
+    }
+
+    /// Read the protocol position and tick arrays a position would leave empty once its
+    /// liquidity is burned, so clients can batch closing them with `close_position`.
+    /// The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_position_teardown_accounts(
+        ctx: Context<GetPositionTeardownAccounts>,
+    ) -> Result<PositionTeardownAccounts> {
+        instructions::get_position_teardown_accounts(ctx)
+    }
+
+    /// Read a position by the mint of its nft, emitted as an event
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_tokenized_position(ctx: Context<GetTokenizedPosition>) -> Result<()> {
+        instructions::get_tokenized_position(ctx)
+    }
+
+    /// Read a position's fee growth snapshot of its last update together with the current
+    /// fee growth inside its range, so the fees owed can be computed off-chain the same way
+    /// as on-chain. The result is set as return data.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    ///
+    pub fn get_position_fee_growth(
+        ctx: Context<GetPositionFeeGrowth>,
+    ) -> Result<PositionFeeGrowth> {
+        instructions::get_position_fee_growth(ctx)
+    }
 }
@@ -168,7 +168,7 @@ pub fn get_delta_amount_0_unsigned(
     mut sqrt_ratio_b_x64: u128,
     liquidity: u128,
     round_up: bool,
-) -> u64 {
+) -> Result<u64> {
     // sqrt_ratio_a_x64 should hold the smaller value
     if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
         std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
@@ -179,21 +179,21 @@ pub fn get_delta_amount_0_unsigned(
 
     assert!(sqrt_ratio_a_x64 > 0);
 
-    if round_up {
+    // an intermediate product over u128 is taken as an amount over u64
+    let result = if round_up {
         U256::div_rounding_up(
             numerator_1
                 .mul_div_ceil(numerator_2, U256::from(sqrt_ratio_b_x64))
-                .unwrap(),
+                .ok_or(ErrorCode::MaxTokenOverflow)?,
             U256::from(sqrt_ratio_a_x64),
         )
-        .as_u64()
     } else {
-        (numerator_1
+        numerator_1
             .mul_div_floor(numerator_2, U256::from(sqrt_ratio_b_x64))
-            .unwrap()
-            / U256::from(sqrt_ratio_a_x64))
-        .as_u64()
-    }
+            .ok_or(ErrorCode::MaxTokenOverflow)?
+            / U256::from(sqrt_ratio_a_x64)
+    };
+    to_token_amount(result)
 }
 
 /// Gets the delta amount_1 for given liquidity and price range
@@ -203,13 +203,13 @@ pub fn get_delta_amount_1_unsigned(
     mut sqrt_ratio_b_x64: u128,
     liquidity: u128,
     round_up: bool,
-) -> u64 {
+) -> Result<u64> {
     // sqrt_ratio_a_x64 should hold the smaller value
     if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
         std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
     };
 
-    if round_up {
+    let result = if round_up {
         U256::from(liquidity).mul_div_ceil(
             U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64),
             U256::from(fixed_point_64::Q64),
@@ -220,8 +220,14 @@ pub fn get_delta_amount_1_unsigned(
             U256::from(fixed_point_64::Q64),
         )
     }
-    .unwrap()
-    .as_u64()
+    .ok_or(ErrorCode::MaxTokenOverflow)?;
+    to_token_amount(result)
+}
+
+/// Narrows a token amount to u64, reverting rather than truncating if it doesn't fit
+fn to_token_amount(amount: U256) -> Result<u64> {
+    require!(amount <= U256::from(u64::MAX), ErrorCode::MaxTokenOverflow);
+    Ok(amount.as_u64())
 }
 
 /// Helper function to get signed delta amount_0 for given liquidity and price range
@@ -229,22 +235,24 @@ pub fn get_delta_amount_0_signed(
     sqrt_ratio_a_x64: u128,
     sqrt_ratio_b_x64: u128,
     liquidity: i128,
-) -> u64 {
-    if liquidity < 0 {
-        get_delta_amount_0_unsigned(
-            sqrt_ratio_a_x64,
-            sqrt_ratio_b_x64,
-            u128::try_from(-liquidity).unwrap(),
-            false,
-        )
+) -> Result<u64> {
+    // round down when remove liquidity, round up when add liquidity
+    let (liquidity, round_up) = if liquidity < 0 {
+        (u128::try_from(-liquidity).unwrap(), false)
     } else {
-        get_delta_amount_0_unsigned(
-            sqrt_ratio_a_x64,
-            sqrt_ratio_b_x64,
-            u128::try_from(liquidity).unwrap(),
-            true,
-        )
-    }
+        (u128::try_from(liquidity).unwrap(), true)
+    };
+    let amount_0 =
+        get_delta_amount_0_unsigned(sqrt_ratio_a_x64, sqrt_ratio_b_x64, liquidity, round_up)?;
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "delta amount_0, liquidity:{}, round_up:{}, amount_0:{}, amount_0_opposite_rounding:{}",
+        liquidity,
+        round_up,
+        amount_0,
+        get_delta_amount_0_unsigned(sqrt_ratio_a_x64, sqrt_ratio_b_x64, liquidity, !round_up)?
+    );
+    Ok(amount_0)
 }
 
 /// Helper function to get signed delta amount_1 for given liquidity and price range
@@ -252,22 +260,24 @@ pub fn get_delta_amount_1_signed(
     sqrt_ratio_a_x64: u128,
     sqrt_ratio_b_x64: u128,
     liquidity: i128,
-) -> u64 {
-    if liquidity < 0 {
-        get_delta_amount_1_unsigned(
-            sqrt_ratio_a_x64,
-            sqrt_ratio_b_x64,
-            u128::try_from(-liquidity).unwrap(),
-            false,
-        )
+) -> Result<u64> {
+    // round down when remove liquidity, round up when add liquidity
+    let (liquidity, round_up) = if liquidity < 0 {
+        (u128::try_from(-liquidity).unwrap(), false)
     } else {
-        get_delta_amount_1_unsigned(
-            sqrt_ratio_a_x64,
-            sqrt_ratio_b_x64,
-            u128::try_from(liquidity).unwrap(),
-            true,
-        )
-    }
+        (u128::try_from(liquidity).unwrap(), true)
+    };
+    let amount_1 =
+        get_delta_amount_1_unsigned(sqrt_ratio_a_x64, sqrt_ratio_b_x64, liquidity, round_up)?;
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "delta amount_1, liquidity:{}, round_up:{}, amount_1:{}, amount_1_opposite_rounding:{}",
+        liquidity,
+        round_up,
+        amount_1,
+        get_delta_amount_1_unsigned(sqrt_ratio_a_x64, sqrt_ratio_b_x64, liquidity, !round_up)?
+    );
+    Ok(amount_1)
 }
 
 pub fn get_delta_amounts_signed(
@@ -284,31 +294,75 @@ pub fn get_delta_amounts_signed(
             tick_math::get_sqrt_price_at_tick(tick_lower)?,
             tick_math::get_sqrt_price_at_tick(tick_upper)?,
             liquidity_delta,
-        );
+        )?;
     } else if tick_current < tick_upper {
         amount_0 = get_delta_amount_0_signed(
             sqrt_price_x64_current,
             tick_math::get_sqrt_price_at_tick(tick_upper)?,
             liquidity_delta,
-        );
+        )?;
         amount_1 = get_delta_amount_1_signed(
             tick_math::get_sqrt_price_at_tick(tick_lower)?,
             sqrt_price_x64_current,
             liquidity_delta,
-        );
+        )?;
     } else {
         amount_1 = get_delta_amount_1_signed(
             tick_math::get_sqrt_price_at_tick(tick_lower)?,
             tick_math::get_sqrt_price_at_tick(tick_upper)?,
             liquidity_delta,
-        );
+        )?;
     }
     Ok((amount_0, amount_1))
 }
 
+/// Gets the amounts of token_0 and token_1 to deposit for `liquidity` in the range at the price,
+/// rounded up as the pool charges them, e.g. to seed a pool at its initial price.
+/// A range above the price needs only token_0, a range below the price needs only token_1.
+pub fn tokens_for_liquidity_at_price(
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<(u64, u64)> {
+    get_delta_amounts_signed(
+        tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?,
+        sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        i128::try_from(liquidity).map_err(|_| ErrorCode::LiquidityAddValueErr)?,
+    )
+}
+
+/// The max gross liquidity a tick can reference, so the liquidity of the pool can't overflow
+/// even if every usable tick at the `tick_spacing` is referenced to the max
+pub fn get_max_liquidity_per_tick(tick_spacing: u16) -> u128 {
+    let tick_spacing = i32::from(tick_spacing);
+    let min_tick = tick_math::MIN_TICK / tick_spacing * tick_spacing;
+    let max_tick = tick_math::MAX_TICK / tick_spacing * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
 #[cfg(test)]
 mod liquidity_math_test {
     use super::*;
+
+    #[test]
+    fn get_max_liquidity_per_tick_test() {
+        let max_liquidity_1 = get_max_liquidity_per_tick(1);
+        let max_liquidity_10 = get_max_liquidity_per_tick(10);
+        let max_liquidity_60 = get_max_liquidity_per_tick(60);
+        assert_eq!(
+            max_liquidity_1,
+            u128::MAX / (tick_math::MAX_TICK - tick_math::MIN_TICK + 1) as u128
+        );
+        // wider spacing leaves fewer usable ticks, each can hold more
+        assert!(max_liquidity_1 < max_liquidity_10);
+        assert!(max_liquidity_10 < max_liquidity_60);
+        // 443580 / 60 * 2 + 1 usable ticks
+        assert_eq!(max_liquidity_60, u128::MAX / 14787);
+    }
     mod get_amounts_delta_signed {
         use super::*;
 
@@ -321,4 +375,73 @@ mod liquidity_math_test {
             println!("amount0:{}, amount1:{}", amount0, amount1)
         }
     }
+
+    mod tokens_for_liquidity_at_price {
+        use super::*;
+
+        #[test]
+        fn range_above_price_test() {
+            let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+            let (amount_0, amount_1) =
+                tokens_for_liquidity_at_price(sqrt_price_x64, 100, 200, 1_000_000_000).unwrap();
+            assert!(amount_0 > 0);
+            assert_eq!(amount_1, 0);
+        }
+
+        #[test]
+        fn range_below_price_test() {
+            let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+            let (amount_0, amount_1) =
+                tokens_for_liquidity_at_price(sqrt_price_x64, -200, -100, 1_000_000_000).unwrap();
+            assert_eq!(amount_0, 0);
+            assert!(amount_1 > 0);
+        }
+
+        #[test]
+        fn range_around_price_test() {
+            let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+            let liquidity = 1_000_000_000;
+            let (amount_0, amount_1) =
+                tokens_for_liquidity_at_price(sqrt_price_x64, -100, 100, liquidity).unwrap();
+            assert!(amount_0 > 0);
+            assert!(amount_1 > 0);
+            // symmetric around price 1, the amounts only differ by rounding
+            assert!(amount_0.abs_diff(amount_1) <= 1);
+            // the amounts pay for at least the liquidity
+            assert!(
+                get_liquidity_from_amounts(
+                    sqrt_price_x64,
+                    tick_math::get_sqrt_price_at_tick(-100).unwrap(),
+                    tick_math::get_sqrt_price_at_tick(100).unwrap(),
+                    amount_0,
+                    amount_1
+                ) >= liquidity
+            );
+        }
+
+        #[test]
+        fn price_at_range_bounds_test() {
+            let liquidity = 1_000_000_000;
+            // the lower tick is in range
+            let (amount_0, amount_1) = tokens_for_liquidity_at_price(
+                tick_math::get_sqrt_price_at_tick(-100).unwrap(),
+                -100,
+                100,
+                liquidity,
+            )
+            .unwrap();
+            assert!(amount_0 > 0);
+            assert_eq!(amount_1, 0);
+            // the upper tick is out of range
+            let (amount_0, amount_1) = tokens_for_liquidity_at_price(
+                tick_math::get_sqrt_price_at_tick(100).unwrap(),
+                -100,
+                100,
+                liquidity,
+            )
+            .unwrap();
+            assert_eq!(amount_0, 0);
+            assert!(amount_1 > 0);
+        }
+    }
 }
@@ -1,7 +1,9 @@
 use super::full_math::MulDiv;
 use super::liquidity_math;
 use super::sqrt_price_math;
-use crate::states::config::FEE_RATE_DENOMINATOR_VALUE;
+use crate::error::ErrorCode;
+use crate::states::config::{FEE_RATE_DENOMINATOR_VALUE, MAX_TRADE_FEE_RATE};
+use anchor_lang::prelude::*;
 
 /// Result of a swap step
 #[derive(Default, Debug)]
@@ -13,7 +15,9 @@ pub struct SwapStep {
     pub fee_amount: u64,
 }
 
-/// Computes the result of swapping some amount in, or amount out, given the parameters of the swap
+/// Computes the result of swapping some amount in, or amount out, given the parameters of the swap.
+/// Reverts with `MaxTokenOverflow` if the amount the step swaps doesn't fit in u64,
+/// and with `TradeFeeRateTooHigh` rather than charge a fee over `MAX_TRADE_FEE_RATE` of the input.
 pub fn compute_swap_step(
     sqrt_price_current_x64: u128,
     sqrt_price_target_x64: u128,
@@ -22,7 +26,8 @@ pub fn compute_swap_step(
     fee_rate: u32,
     is_base_input: bool,
     zero_for_one: bool,
-) -> SwapStep {
+) -> Result<SwapStep> {
+    require_gte!(MAX_TRADE_FEE_RATE, fee_rate, ErrorCode::TradeFeeRateTooHigh);
     // let exact_in = amount_remaining >= 0;
     let mut swap_step = SwapStep::default();
     if is_base_input {
@@ -34,7 +39,20 @@ pub fn compute_swap_step(
                 u64::from(FEE_RATE_DENOMINATOR_VALUE),
             )
             .unwrap();
-        swap_step.amount_in = if zero_for_one {
+        #[cfg(feature = "enable-log")]
+        msg!(
+            "amount_remaining:{}, amount_remaining_less_fee round down:{}, round up:{}",
+            amount_remaining,
+            amount_remaining_less_fee,
+            amount_remaining
+                .mul_div_ceil(
+                    (FEE_RATE_DENOMINATOR_VALUE - fee_rate).into(),
+                    u64::from(FEE_RATE_DENOMINATOR_VALUE),
+                )
+                .unwrap()
+        );
+        // an amount to the target over u64 can't be paid, the target is out of reach
+        let amount_in_to_target = if zero_for_one {
             liquidity_math::get_delta_amount_0_unsigned(
                 sqrt_price_target_x64,
                 sqrt_price_current_x64,
@@ -48,8 +66,12 @@ pub fn compute_swap_step(
                 liquidity,
                 true,
             )
-        };
-        swap_step.sqrt_price_next_x64 = if amount_remaining_less_fee >= swap_step.amount_in {
+        }
+        .ok();
+        swap_step.amount_in = amount_in_to_target.unwrap_or_default();
+        swap_step.sqrt_price_next_x64 = if amount_in_to_target
+            .is_some_and(|amount_in| amount_remaining_less_fee >= amount_in)
+        {
             sqrt_price_target_x64
         } else {
             sqrt_price_math::get_next_sqrt_price_from_input(
@@ -61,7 +83,8 @@ pub fn compute_swap_step(
         };
     } else {
         // round down amount_out
-        swap_step.amount_out = if zero_for_one {
+        // an amount to the target over u64 is more than any amount_remaining
+        let amount_out_to_target = if zero_for_one {
             liquidity_math::get_delta_amount_1_unsigned(
                 sqrt_price_target_x64,
                 sqrt_price_current_x64,
@@ -75,18 +98,21 @@ pub fn compute_swap_step(
                 liquidity,
                 false,
             )
-        };
-        // In exact output case, amount_remaining is negative
-        swap_step.sqrt_price_next_x64 = if amount_remaining >= swap_step.amount_out {
-            sqrt_price_target_x64
-        } else {
-            sqrt_price_math::get_next_sqrt_price_from_output(
-                sqrt_price_current_x64,
-                liquidity,
-                amount_remaining,
-                zero_for_one,
-            )
         }
+        .ok();
+        swap_step.amount_out = amount_out_to_target.unwrap_or_default();
+        // In exact output case, amount_remaining is negative
+        swap_step.sqrt_price_next_x64 =
+            if amount_out_to_target.is_some_and(|amount_out| amount_remaining >= amount_out) {
+                sqrt_price_target_x64
+            } else {
+                sqrt_price_math::get_next_sqrt_price_from_output(
+                    sqrt_price_current_x64,
+                    liquidity,
+                    amount_remaining,
+                    zero_for_one,
+                )
+            }
     }
 
     // whether we reached the max possible price for the given ticks
@@ -100,7 +126,7 @@ pub fn compute_swap_step(
                 sqrt_price_current_x64,
                 liquidity,
                 true,
-            )
+            )?
         };
         // if max is reached for exact output case, entire amount_out is needed
         if !(max && !is_base_input) {
@@ -109,7 +135,7 @@ pub fn compute_swap_step(
                 sqrt_price_current_x64,
                 liquidity,
                 false,
-            );
+            )?;
         };
     } else {
         if !(max && is_base_input) {
@@ -118,7 +144,7 @@ pub fn compute_swap_step(
                 swap_step.sqrt_price_next_x64,
                 liquidity,
                 true,
-            )
+            )?
         };
         if !(max && !is_base_input) {
             swap_step.amount_out = liquidity_math::get_delta_amount_0_unsigned(
@@ -126,7 +152,7 @@ pub fn compute_swap_step(
                 swap_step.sqrt_price_next_x64,
                 liquidity,
                 false,
-            )
+            )?
         };
     }
 
@@ -144,16 +170,37 @@ pub fn compute_swap_step(
                 .unwrap()
         } else {
             // take pip percentage as fee
-            swap_step
+            let fee_amount = swap_step
                 .amount_in
                 .mul_div_ceil(
                     fee_rate.into(),
                     (FEE_RATE_DENOMINATOR_VALUE - fee_rate).into(),
                 )
-                .unwrap()
+                .unwrap();
+            // the fee is at most half of the input, the amount swapped plus the fee
+            require_gte!(
+                swap_step.amount_in,
+                fee_amount,
+                ErrorCode::TradeFeeRateTooHigh
+            );
+            fee_amount
         };
+    #[cfg(feature = "enable-log")]
+    msg!(
+        "amount_in:{}, amount_out:{}, fee_amount:{}, fee_amount round down:{}",
+        swap_step.amount_in,
+        swap_step.amount_out,
+        swap_step.fee_amount,
+        swap_step
+            .amount_in
+            .mul_div_floor(
+                fee_rate.into(),
+                (FEE_RATE_DENOMINATOR_VALUE - fee_rate).into(),
+            )
+            .unwrap()
+    );
 
-    swap_step
+    Ok(swap_step)
 }
 
 #[cfg(test)]
@@ -163,6 +210,34 @@ mod swap_math_test {
     use super::*;
     use proptest::prelude::*;
 
+    #[test]
+    fn max_trade_fee_rate_test() {
+        let sqrt_price_current_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let sqrt_price_target_x64 = tick_math::get_sqrt_price_at_tick(-10).unwrap();
+        let swap_step = |fee_rate: u32, is_base_input: bool| {
+            compute_swap_step(
+                sqrt_price_current_x64,
+                sqrt_price_target_x64,
+                1 << 40,
+                1_000_000,
+                fee_rate,
+                is_base_input,
+                true,
+            )
+        };
+        for is_base_input in [true, false] {
+            // the ceiling itself charges at most as much fee as is swapped
+            let step = swap_step(MAX_TRADE_FEE_RATE, is_base_input).unwrap();
+            assert!(step.fee_amount > 0 && step.fee_amount <= step.amount_in);
+            for fee_rate in [MAX_TRADE_FEE_RATE + 1, FEE_RATE_DENOMINATOR_VALUE - 1] {
+                assert_eq!(
+                    swap_step(fee_rate, is_base_input).unwrap_err(),
+                    ErrorCode::TradeFeeRateTooHigh.into()
+                );
+            }
+        }
+    }
+
     proptest! {
         #[test]
         fn compute_swap_step_test(
@@ -170,7 +245,7 @@ mod swap_math_test {
             sqrt_price_target_x64 in tick_math::MIN_SQRT_PRICE_X64..tick_math::MAX_SQRT_PRICE_X64,
             liquidity in 1..u32::MAX as u128,
             amount_remaining in 1..u64::MAX,
-            fee_rate in 1..FEE_RATE_DENOMINATOR_VALUE/2,
+            fee_rate in 0..FEE_RATE_DENOMINATOR_VALUE/2,
             is_base_input in proptest::bool::ANY,
         ) {
             prop_assume!(sqrt_price_current_x64 != sqrt_price_target_x64);
@@ -184,7 +259,7 @@ mod swap_math_test {
                 fee_rate,
                 is_base_input,
                 zero_for_one,
-            );
+            ).unwrap();
 
             let amount_in = swap_step.amount_in;
             let amount_out = swap_step.amount_out;
@@ -208,4 +283,47 @@ mod swap_math_test {
             assert!(sqrt_price_next_x64 <= price_upper);
         }
     }
+
+    #[test]
+    fn amount_to_target_overflow_test() {
+        let sqrt_price_current_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let sqrt_price_target_x64 = tick_math::get_sqrt_price_at_tick(-1000).unwrap();
+        // the amounts to the target are several times u64::MAX
+        let liquidity = u64::MAX as u128 * 100;
+        for is_base_input in [true, false] {
+            // the target is out of reach and the whole amount is swapped short of it
+            let swap_step = compute_swap_step(
+                sqrt_price_current_x64,
+                sqrt_price_target_x64,
+                liquidity,
+                i64::MAX as u64,
+                2500,
+                is_base_input,
+                true,
+            )
+            .unwrap();
+            assert_ne!(swap_step.sqrt_price_next_x64, sqrt_price_target_x64);
+            assert!(swap_step.sqrt_price_next_x64 < sqrt_price_current_x64);
+            if is_base_input {
+                assert_eq!(swap_step.amount_in + swap_step.fee_amount, i64::MAX as u64);
+            } else {
+                assert_eq!(swap_step.amount_out, i64::MAX as u64);
+            }
+        }
+
+        // a step to the target paying more than u64::MAX reverts
+        assert_eq!(
+            compute_swap_step(
+                sqrt_price_current_x64,
+                sqrt_price_target_x64,
+                liquidity,
+                u64::MAX,
+                2500,
+                false,
+                true,
+            )
+            .unwrap_err(),
+            crate::error::ErrorCode::MaxTokenOverflow.into()
+        );
+    }
 }
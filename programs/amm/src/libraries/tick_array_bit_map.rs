@@ -28,6 +28,40 @@ pub fn get_bitmap_tick_boundary(tick_array_start_index: i32, tick_spacing: u16)
     }
 }
 
+/// The number of tick arrays tracked by a 64 bits word of the default tick array bitmap
+pub const TICK_ARRAY_BITMAP_WORD_SIZE: i32 = 64;
+
+/// The 64 bits word of the default tick array bitmap tracking the tick array of `tick`,
+/// words below 8 hold the negative tick arrays and the rest the positive ones
+pub fn tick_to_word_pos(tick: i32, tick_spacing: u16) -> Result<u8> {
+    if TickState::check_is_out_of_boundary(tick) {
+        return err!(ErrorCode::InvaildTickIndex);
+    }
+    let tick_array_start_index = TickArrayState::get_array_start_index(tick, tick_spacing);
+    let bit_pos =
+        tick_array_start_index / TickArrayState::tick_count(tick_spacing) + TICK_ARRAY_BITMAP_SIZE;
+    // the tick array is tracked by the bitmap extension
+    require!(
+        (0..TICK_ARRAY_BITMAP_SIZE * 2).contains(&bit_pos),
+        ErrorCode::InvalidTickArrayBoundary
+    );
+    Ok((bit_pos / TICK_ARRAY_BITMAP_WORD_SIZE) as u8)
+}
+
+/// The ticks of the tick arrays tracked by a 64 bits word of the default tick array bitmap,
+/// the lower tick inclusive and the upper tick exclusive, not clamped to the tick boundaries
+pub fn word_pos_to_tick_range(word_pos: u8, tick_spacing: u16) -> Result<(i32, i32)> {
+    let word_num = TICK_ARRAY_BITMAP_SIZE * 2 / TICK_ARRAY_BITMAP_WORD_SIZE;
+    require_gt!(
+        word_num,
+        i32::from(word_pos),
+        ErrorCode::InvalidTickArrayBoundary
+    );
+    let ticks_in_word = TickArrayState::tick_count(tick_spacing) * TICK_ARRAY_BITMAP_WORD_SIZE;
+    let tick_lower = (i32::from(word_pos) - word_num / 2) * ticks_in_word;
+    Ok((tick_lower, tick_lower + ticks_in_word))
+}
+
 pub fn most_significant_bit(x: U1024) -> Option<u16> {
     if x.is_zero() {
         None
@@ -376,6 +410,66 @@ mod test {
         assert!(array_start_index == tick_array_start_index);
     }
 
+    #[test]
+    fn tick_to_word_pos_test() {
+        for tick_spacing in [1, 10, 60] {
+            let ticks_in_word = TickArrayState::tick_count(tick_spacing) * 64;
+            assert_eq!(tick_to_word_pos(0, tick_spacing).unwrap(), 8);
+            assert_eq!(tick_to_word_pos(-1, tick_spacing).unwrap(), 7);
+            assert_eq!(
+                tick_to_word_pos(ticks_in_word - 1, tick_spacing).unwrap(),
+                8
+            );
+            assert_eq!(tick_to_word_pos(ticks_in_word, tick_spacing).unwrap(), 9);
+            assert_eq!(tick_to_word_pos(-ticks_in_word, tick_spacing).unwrap(), 7);
+            assert_eq!(
+                tick_to_word_pos(-ticks_in_word - 1, tick_spacing).unwrap(),
+                6
+            );
+        }
+        // tracked by the bitmap extension
+        assert_eq!(
+            tick_to_word_pos(tick_math::MAX_TICK, 1).unwrap_err(),
+            ErrorCode::InvalidTickArrayBoundary.into()
+        );
+        assert_eq!(
+            tick_to_word_pos(tick_math::MAX_TICK + 1, 60).unwrap_err(),
+            ErrorCode::InvaildTickIndex.into()
+        );
+        // the default bitmap covers the whole tick range in the words around zero
+        assert_eq!(tick_to_word_pos(tick_math::MIN_TICK, 60).unwrap(), 6);
+        assert_eq!(tick_to_word_pos(tick_math::MAX_TICK, 60).unwrap(), 9);
+        assert_eq!(
+            word_pos_to_tick_range(16, 60).unwrap_err(),
+            ErrorCode::InvalidTickArrayBoundary.into()
+        );
+    }
+
+    #[test]
+    fn word_pos_to_tick_range_round_trip_test() {
+        for tick_spacing in [1, 10, 60, 200] {
+            let tick_boundary = max_tick_in_tickarray_bitmap(tick_spacing);
+            let mut tick = tick_math::MIN_TICK.max(-tick_boundary);
+            while tick <= tick_math::MAX_TICK.min(tick_boundary - 1) {
+                let word_pos = tick_to_word_pos(tick, tick_spacing).unwrap();
+                let (tick_lower, tick_upper) =
+                    word_pos_to_tick_range(word_pos, tick_spacing).unwrap();
+                assert!(tick_lower <= tick && tick < tick_upper);
+                // the bounds map back to the word
+                assert_eq!(
+                    tick_to_word_pos(tick_lower.max(tick_math::MIN_TICK), tick_spacing).unwrap(),
+                    word_pos
+                );
+                assert_eq!(
+                    tick_to_word_pos((tick_upper - 1).min(tick_math::MAX_TICK), tick_spacing)
+                        .unwrap(),
+                    word_pos
+                );
+                tick += 997;
+            }
+        }
+    }
+
     #[test]
     fn get_bitmap_tick_boundary_test() {
         let (mut min, mut max) = get_bitmap_tick_boundary(-430080, 1);
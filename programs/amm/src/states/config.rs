@@ -4,6 +4,12 @@ use anchor_lang::prelude::*;
 pub const AMM_CONFIG_SEED: &str = "amm_config";
 
 pub const FEE_RATE_DENOMINATOR_VALUE: u32 = 1_000_000;
+/// A swap never charges a trade fee over half of its input, whatever the fee tier is set to
+pub const MAX_TRADE_FEE_RATE: u32 = FEE_RATE_DENOMINATOR_VALUE / 2;
+
+pub const BIPS_DENOMINATOR_VALUE: u16 = 10_000;
+/// The referral fee can't be more than 1% of the swap input
+pub const MAX_REFERRAL_FEE_BPS: u16 = 100;
 
 /// Holds the current owner of the factory
 #[account]
@@ -22,10 +28,21 @@ pub struct AmmConfig {
     pub tick_spacing: u16,
     /// The fund fee, denominated in hundredths of a bip (10^-6)
     pub fund_fee_rate: u32,
+    /// Whether pools can be created with the config, 0: enable, 1: disable
+    pub status: u8,
+    /// The share of swap input paid to the referrer when one is given, denominated in bips (10^-4),
+    /// it's taken before the swap, so it's not part of the trade fee shared by LPs and protocol
+    pub referral_fee_bps: u16,
     // padding space for upgrade
-    pub padding_u32: u32,
+    pub padding_u8: [u8; 1],
     pub fund_owner: Pubkey,
-    pub padding: [u64; 3],
+    /// The share of collected protocol fees sent to the secondary recipient, denominated in bips (10^-4)
+    pub secondary_protocol_fee_bps: u16,
+    // padding space for upgrade
+    pub padding_u16: [u16; 3],
+    /// The max gross liquidity of a tick, derived from the tick spacing when the config is created
+    /// and copied to its pools, zero for configs created before the cap
+    pub max_liquidity_per_tick: u128,
 }
 
 impl AmmConfig {
@@ -42,6 +59,83 @@ impl AmmConfig {
         );
         Ok(())
     }
+
+    pub fn is_disabled(&self) -> bool {
+        self.status != 0
+    }
+
+    /// Ensures new pools can be created with the config
+    pub fn check_enabled(&self) -> Result<()> {
+        if self.is_disabled() {
+            msg!(
+                "amm config {} is disabled, trade_fee_rate:{}, tick_spacing:{}",
+                self.index,
+                self.trade_fee_rate,
+                self.tick_spacing
+            );
+            return err!(ErrorCode::FeeTierDisabled);
+        }
+        Ok(())
+    }
+
+    /// Split the collected protocol fee `amount` into the primary and secondary recipient's shares,
+    /// the secondary share is rounded down so any remainder goes to the primary recipient
+    pub fn split_protocol_fee(&self, amount: u64) -> (u64, u64) {
+        let secondary_amount = (u128::from(amount)
+            * u128::from(self.secondary_protocol_fee_bps)
+            / u128::from(BIPS_DENOMINATOR_VALUE)) as u64;
+        (amount - secondary_amount, secondary_amount)
+    }
+
+    /// The protocol's share of a swap step's trade fee `fee_amount`.
+    /// It's rounded down so the rounding dust always accrues to the LPs, and the protocol
+    /// never takes more than its exact share however many swaps the fees are split over
+    pub fn protocol_fee(&self, fee_amount: u64) -> u64 {
+        (u128::from(fee_amount) * u128::from(self.protocol_fee_rate)
+            / u128::from(FEE_RATE_DENOMINATOR_VALUE)) as u64
+    }
+
+    /// The fund's share of a swap step's trade fee `fee_amount`, rounded down in favor of the LPs
+    pub fn fund_fee(&self, fee_amount: u64) -> u64 {
+        (u128::from(fee_amount) * u128::from(self.fund_fee_rate)
+            / u128::from(FEE_RATE_DENOMINATOR_VALUE)) as u64
+    }
+
+    /// Split a swap step's trade fee `fee_amount` into the LPs', protocol's and fund's shares.
+    /// The protocol and fund shares round down and the LPs get the rest,
+    /// so the shares always sum up to the fee.
+    pub fn split_trade_fee(&self, fee_amount: u64) -> (u64, u64, u64) {
+        let protocol_fee = self.protocol_fee(fee_amount);
+        let fund_fee = self.fund_fee(fee_amount);
+        (fee_amount - protocol_fee - fund_fee, protocol_fee, fund_fee)
+    }
+
+    /// The referral fee for the swap input `amount`, rounded down
+    pub fn referral_fee(&self, amount: u64) -> u64 {
+        (u128::from(amount) * u128::from(self.referral_fee_bps)
+            / u128::from(BIPS_DENOMINATOR_VALUE)) as u64
+    }
+}
+
+pub const SECONDARY_FEE_RECIPIENT_SEED: &str = "secondary_fee_recipient";
+
+/// The recipient of an amm config's secondary protocol fee share.
+/// It's kept in its own account as `AmmConfig` has no padding left for it.
+#[account]
+#[derive(Default, Debug)]
+pub struct SecondaryFeeRecipientState {
+    /// Bump to identify PDA
+    pub bump: u8,
+    /// The amm config the recipient is set for
+    pub amm_config: Pubkey,
+    /// The owner of the token accounts the secondary share of collected protocol fees is sent to
+    pub recipient: Pubkey,
+    // padding space for upgrade
+    pub padding: [u64; 8],
+}
+
+impl SecondaryFeeRecipientState {
+    pub const LEN: usize = 8 + 1 + 32 + 32 + 64;
 }
 
 /// Emitted when create or update a config
@@ -56,4 +150,118 @@ pub struct ConfigChangeEvent {
     pub tick_spacing: u16,
     pub fund_fee_rate: u32,
     pub fund_owner: Pubkey,
+    pub referral_fee_bps: u16,
+    pub secondary_protocol_fee_bps: u16,
+}
+
+#[cfg(test)]
+mod config_test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn create_pool_on_disabled_config_test() {
+        let mut amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            tick_spacing: 60,
+            ..Default::default()
+        };
+        assert!(amm_config.check_enabled().is_ok());
+
+        amm_config.status = 1;
+        assert_eq!(
+            amm_config.check_enabled().unwrap_err(),
+            ErrorCode::FeeTierDisabled.into()
+        );
+    }
+
+    #[test]
+    fn referral_fee_test() {
+        let mut amm_config = AmmConfig::default();
+        // no referral fee by default
+        assert_eq!(amm_config.referral_fee(1_000_000), 0);
+
+        amm_config.referral_fee_bps = 25;
+        assert_eq!(amm_config.referral_fee(1_000_000), 2_500);
+        // rounded down in favor of the swapper
+        assert_eq!(amm_config.referral_fee(399), 0);
+
+        amm_config.referral_fee_bps = MAX_REFERRAL_FEE_BPS;
+        assert_eq!(amm_config.referral_fee(u64::MAX), u64::MAX / 100);
+    }
+
+    #[test]
+    fn split_protocol_fee_test() {
+        let mut amm_config = AmmConfig::default();
+        // all to the primary recipient by default
+        assert_eq!(amm_config.split_protocol_fee(1_000_001), (1_000_001, 0));
+
+        amm_config.secondary_protocol_fee_bps = 2_500;
+        assert_eq!(amm_config.split_protocol_fee(1_000_000), (750_000, 250_000));
+        // rounding favors the primary recipient
+        assert_eq!(amm_config.split_protocol_fee(3), (3, 0));
+        assert_eq!(amm_config.split_protocol_fee(1_000_003), (750_003, 250_000));
+
+        amm_config.secondary_protocol_fee_bps = BIPS_DENOMINATOR_VALUE;
+        assert_eq!(amm_config.split_protocol_fee(u64::MAX), (0, u64::MAX));
+    }
+
+    #[test]
+    fn protocol_fee_rounding_test() {
+        let mut amm_config = AmmConfig::default();
+        assert_eq!(amm_config.protocol_fee(1_000_000), 0);
+
+        amm_config.protocol_fee_rate = 120_000;
+        amm_config.fund_fee_rate = 40_000;
+        assert_eq!(amm_config.protocol_fee(1_000_000), 120_000);
+        assert_eq!(amm_config.fund_fee(1_000_000), 40_000);
+        // the dust is left to the LPs
+        assert_eq!(amm_config.protocol_fee(8), 0);
+        assert_eq!(amm_config.protocol_fee(9), 1);
+        assert_eq!(amm_config.fund_fee(24), 0);
+
+        amm_config.protocol_fee_rate = FEE_RATE_DENOMINATOR_VALUE;
+        assert_eq!(amm_config.protocol_fee(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn split_trade_fee_test() {
+        let amm_config = AmmConfig {
+            protocol_fee_rate: 120_000,
+            fund_fee_rate: 40_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            amm_config.split_trade_fee(1_000_000),
+            (840_000, 120_000, 40_000)
+        );
+        // both shares round down to zero
+        assert_eq!(amm_config.split_trade_fee(1), (1, 0, 0));
+        assert_eq!(amm_config.split_trade_fee(0), (0, 0, 0));
+    }
+
+    proptest! {
+        #[test]
+        fn protocol_fee_never_exceeds_exact_share_test(
+            protocol_fee_rate in 0..=FEE_RATE_DENOMINATOR_VALUE,
+            fee_amounts in proptest::collection::vec(0..u32::MAX as u64, 1..200),
+        ) {
+            let amm_config = AmmConfig {
+                protocol_fee_rate,
+                ..Default::default()
+            };
+
+            let mut total_fee = 0u128;
+            let mut total_protocol_fee = 0u128;
+            for fee_amount in fee_amounts {
+                total_fee += u128::from(fee_amount);
+                total_protocol_fee += u128::from(amm_config.protocol_fee(fee_amount));
+            }
+            // total_protocol_fee <= total_fee * protocol_fee_rate / FEE_RATE_DENOMINATOR_VALUE
+            prop_assert!(
+                total_protocol_fee * u128::from(FEE_RATE_DENOMINATOR_VALUE)
+                    <= total_fee * u128::from(protocol_fee_rate)
+            );
+        }
+    }
 }
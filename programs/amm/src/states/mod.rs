@@ -4,6 +4,7 @@ pub mod oracle;
 pub mod personal_position;
 pub mod pool;
 pub mod protocol_position;
+pub mod route_cursor;
 pub mod tick_array;
 pub mod tickarray_bitmap_extension;
 
@@ -13,5 +14,6 @@ pub use oracle::*;
 pub use personal_position::*;
 pub use pool::*;
 pub use protocol_position::*;
+pub use route_cursor::*;
 pub use tick_array::*;
 pub use tickarray_bitmap_extension::*;
@@ -1,3 +1,4 @@
+use crate::error::ErrorCode;
 use crate::libraries::{big_num::U128, fixed_point_64, full_math::MulDiv};
 use crate::Result;
 use anchor_lang::error::ErrorCode as anchorErrorCode;
@@ -12,7 +13,12 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// Seed to derive account address and signature
 pub const OBSERVATION_SEED: &str = "observation";
 // Number of ObservationState element
+// The ring buffer is allocated at full size when the pool is created and can't grow or shrink,
+// so unlike a cardinality tracked in state, the usable slots never fall below the written ones.
 pub const OBSERVATION_NUM: usize = 1000;
+/// An unused observation account can only be closed this many seconds after its close was
+/// scheduled, so its creator still has time to create the pool with it
+pub const UNUSED_OBSERVATION_CLOSE_GRACE_PERIOD: i64 = 7 * 24 * 60 * 60;
 
 /// The element of observations in ObservationState
 #[zero_copy(unsafe)]
@@ -30,6 +36,29 @@ pub struct Observation {
 }
 impl Observation {
     pub const LEN: usize = 4 + 16 + 16 + 16;
+
+    /// The time weighted average price from an older observation to this one, Q64.64.
+    /// Both the cumulative and the truncated timestamp may have wrapped in between,
+    /// the wrapping deltas are exact as long as they didn't wrap more than once.
+    pub fn average_price_x64(&self, older: &Observation) -> Result<u128> {
+        let delta_time = self.block_timestamp.wrapping_sub(older.block_timestamp);
+        require_gt!(delta_time, 0);
+        let delta_cumulative = self
+            .cumulative_time_price_x64
+            .wrapping_sub(older.cumulative_time_price_x64);
+        Ok(delta_cumulative / u128::from(delta_time))
+    }
+
+    /// The price of the observation, Q64.64
+    pub fn price_x64(&self) -> u128 {
+        U128::from(self.sqrt_price_x64)
+            .mul_div_floor(
+                U128::from(self.sqrt_price_x64),
+                U128::from(fixed_point_64::Q64),
+            )
+            .unwrap()
+            .as_u128()
+    }
 }
 
 #[account(zero_copy(unsafe))]
@@ -59,6 +88,9 @@ impl Default for ObservationState {
 
 impl ObservationState {
     pub const LEN: usize = 8 + 1 + 32 + (Observation::LEN * OBSERVATION_NUM) + 16 * 5;
+    /// Where the padding starts in the account data, an unused account scheduled to be closed
+    /// keeps the payer to refund there, followed by the timestamp the close was scheduled at
+    const PADDING_OFFSET: usize = Self::LEN - 16 * 5;
 
     fn discriminator() -> [u8; 8] {
         [122, 174, 197, 53, 129, 9, 165, 132]
@@ -92,11 +124,119 @@ impl ObservationState {
         }))
     }
 
-    pub fn initialize(account_info: &AccountInfo, pool_id: Pubkey) -> Result<()> {
+    /// Check whether the account was created for an observation but never bound to a pool,
+    /// e.g. the pool creation failed after it was allocated off-chain.
+    /// An initialized observation always belongs to a live pool and can't be closed.
+    pub fn is_unused(account_info: &AccountInfo) -> Result<bool> {
+        if account_info.owner != &crate::id() || account_info.data_len() != ObservationState::LEN {
+            return Ok(false);
+        }
+        let data = account_info.try_borrow_data()?;
+        let disc_bytes = array_ref![data, 0, 8];
+        Ok(u64::from_le_bytes(*disc_bytes) == 0)
+    }
+
+    /// The payer to refund and the timestamp the close of an unused account was scheduled at,
+    /// none if it's not scheduled
+    pub fn scheduled_close(account_info: &AccountInfo) -> Result<Option<(Pubkey, i64)>> {
+        let data = account_info.try_borrow_data()?;
+        let payer = Pubkey::new_from_array(*array_ref![data, Self::PADDING_OFFSET, 32]);
+        if payer == Pubkey::default() {
+            return Ok(None);
+        }
+        let scheduled_at = i64::from_le_bytes(*array_ref![data, Self::PADDING_OFFSET + 32, 8]);
+        Ok(Some((payer, scheduled_at)))
+    }
+
+    /// Schedule the close of an unused account at `now`, its rent will be refunded to `payer`
+    pub fn schedule_close(account_info: &AccountInfo, payer: Pubkey, now: i64) -> Result<()> {
+        require_keys_neq!(payer, Pubkey::default());
+        let mut data = account_info.try_borrow_mut_data()?;
+        data[Self::PADDING_OFFSET..Self::PADDING_OFFSET + 32].copy_from_slice(payer.as_ref());
+        data[Self::PADDING_OFFSET + 32..Self::PADDING_OFFSET + 40]
+            .copy_from_slice(&now.to_le_bytes());
+        Ok(())
+    }
+
+    /// Binds the observation to its pool and writes the genesis observation at the pool's
+    /// creation, so the cumulative starts from zero at the initial price rather than at the
+    /// first swap, and the first TWAP averages over the time the pool actually had that price
+    pub fn initialize(
+        account_info: &AccountInfo,
+        pool_id: Pubkey,
+        block_timestamp: u32,
+        sqrt_price_x64: u128,
+    ) -> Result<()> {
         let observation_state = &mut Self::load_init_mut(account_info)?;
         require_eq!(observation_state.initialized, false);
         require_keys_eq!(observation_state.pool_id, Pubkey::default());
         observation_state.pool_id = pool_id;
+        // clears a close scheduled while the account was unused
+        observation_state.padding = [0; 5];
+        // the pool starts writing at index 0, the update duration doesn't apply to the first write
+        observation_state.update_check(block_timestamp, sqrt_price_x64, 0, 0)?;
+        Ok(())
+    }
+
+    /// The number of observations that have been written, reaches `OBSERVATION_NUM` once the ring wraps
+    pub fn cardinality(&self) -> u16 {
+        self.observations
+            .iter()
+            .filter(|observation| observation.block_timestamp != 0)
+            .count() as u16
+    }
+
+    /// The time weighted average price over the `window` seconds before `now`, Q64.64.
+    /// It's averaged from the latest observation back to the newest one at least `window` old,
+    /// or the oldest one if the ring doesn't go back that far.
+    /// If no observation was written within the window, it's the price of the latest one.
+    pub fn twap_price_x64(&self, observation_index: u16, now: u32, window: u32) -> Result<u128> {
+        let latest = self.observations[observation_index as usize];
+        let mut older = latest;
+        let mut index = observation_index as usize;
+        while now.wrapping_sub(older.block_timestamp) < window {
+            index = if index == 0 {
+                OBSERVATION_NUM - 1
+            } else {
+                index - 1
+            };
+            let observation = self.observations[index];
+            if index == observation_index as usize || observation.block_timestamp == 0 {
+                break;
+            }
+            older = observation;
+        }
+        if older.block_timestamp == latest.block_timestamp {
+            return Ok(latest.price_x64());
+        }
+        latest.average_price_x64(&older)
+    }
+
+    /// Ensures `observation_index` is the most recently written observation: the one after it in
+    /// the ring is either never written or older. Writing after a stale index would overwrite newer
+    /// observations and extend an outdated cumulative, corrupting the oracle.
+    pub fn check_latest_index(&self, observation_index: u16) -> Result<()> {
+        let next_index = (observation_index as usize + 1) % OBSERVATION_NUM;
+        let next_block_timestamp = self.observations[next_index].block_timestamp;
+        require!(
+            next_block_timestamp == 0
+                || next_block_timestamp
+                    < self.observations[observation_index as usize].block_timestamp,
+            ErrorCode::StaleObservationIndex
+        );
+        Ok(())
+    }
+
+    /// Ensures an observation can be written at `observation_index`. The written observations are
+    /// a prefix of the ring, so the index must be within the cardinality, or the slot right after it
+    /// to grow the ring by one. Writing further would leave a gap the TWAP reads as the ring's end.
+    pub fn check_write_index(&self, observation_index: u16) -> Result<()> {
+        let index = observation_index as usize;
+        require!(
+            index < OBSERVATION_NUM
+                && (index == 0 || self.observations[index - 1].block_timestamp != 0),
+            ErrorCode::ObservationIndexOutOfRange
+        );
         Ok(())
     }
 
@@ -128,6 +268,15 @@ impl ObservationState {
             self.observations[observation_index as usize].cumulative_time_price_x64 = 0;
             Ok(Some(observation_index))
         } else {
+            let next_observation_index = if observation_index as usize == OBSERVATION_NUM - 1 {
+                0
+            } else {
+                observation_index
+                    .checked_add(1)
+                    .ok_or(ErrorCode::ObservationIndexOutOfRange)?
+            };
+            self.check_write_index(next_observation_index)?;
+            self.check_latest_index(observation_index)?;
             let observation = self.observations[observation_index as usize];
             let delta_time = block_timestamp.saturating_sub(observation.block_timestamp);
             if delta_time < observation_update_duration
@@ -135,16 +284,12 @@ impl ObservationState {
             {
                 return Ok(None);
             }
-            let cur_price_x64 = U128::from(sqrt_price_x64)
-                .mul_div_floor(U128::from(sqrt_price_x64), U128::from(fixed_point_64::Q64))
-                .unwrap()
-                .as_u128();
-            let delta_price_x64 = cur_price_x64.checked_mul(delta_time.into()).unwrap();
-            let next_observation_index = if observation_index as usize == OBSERVATION_NUM - 1 {
-                0
-            } else {
-                observation_index + 1
-            };
+            // the previous observation's price is the one the pool held since it was written,
+            // the new price only starts accruing from this observation on
+            let delta_price_x64 = observation
+                .price_x64()
+                .checked_mul(delta_time.into())
+                .unwrap();
             self.observations[next_observation_index as usize].block_timestamp = block_timestamp;
             self.observations[next_observation_index as usize].sqrt_price_x64 = sqrt_price_x64;
             // cumulative_time_price_x64 may be flipped because of 'observation.cumulative_time_price_x64 + delta_price_x64' is larger than std::u128::MAX;
@@ -159,6 +304,27 @@ impl ObservationState {
     }
 }
 
+/// Emitted when read where the pool's next observation is written
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct CurrentObservationEvent {
+    /// The pool to read
+    #[index]
+    pub pool_state: Pubkey,
+
+    /// The observation account of the pool
+    pub observation_key: Pubkey,
+
+    /// The most recently written index of the observations
+    pub observation_index: u16,
+
+    /// The number of observations that have been written
+    pub observation_cardinality: u16,
+
+    /// The number of observations the ring holds before it wraps
+    pub observation_cardinality_next: u16,
+}
+
 /// Returns the block timestamp truncated to 32 bits, i.e. mod 2**32
 ///
 pub fn block_timestamp() -> u32 {
@@ -176,8 +342,9 @@ pub fn block_timestamp_mock() -> u64 {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::libraries::{big_num::U256, get_sqrt_price_at_tick};
+    use crate::libraries::{big_num::U256, get_sqrt_price_at_tick, get_tick_at_sqrt_price};
     use crate::states::pool::OBSERVATION_UPDATE_DURATION_DEFAULT;
+    use std::convert::identity;
     #[test]
     fn test_update_check_init() {
         let block_timestamp = 1647424834 as u32;
@@ -369,11 +536,9 @@ mod test {
         {
             assert!(false)
         }
-        let cur_price_x64 = U128::from(sqrt_price_x64)
-            .mul_div_floor(U128::from(sqrt_price_x64), U128::from(fixed_point_64::Q64))
-            .unwrap()
-            .as_u128();
-        let delta_price_x64 = cur_price_x64.checked_mul(delta_time.into()).unwrap();
+        // the previous price accrues over the time since the previous observation
+        let last_price_x64 = observation.price_x64();
+        let delta_price_x64 = last_price_x64.checked_mul(delta_time.into()).unwrap();
         let expected = observation.cumulative_time_price_x64 + delta_price_x64;
 
         observation_index = next_observation_index.unwrap();
@@ -444,11 +609,9 @@ mod test {
         {
             assert!(false)
         }
-        let cur_price_x64 = U128::from(sqrt_price_x64)
-            .mul_div_floor(U128::from(sqrt_price_x64), U128::from(fixed_point_64::Q64))
-            .unwrap()
-            .as_u128();
-        let delta_price_x64 = cur_price_x64.checked_mul(delta_time.into()).unwrap();
+        // the previous price accrues over the time since the previous observation
+        let last_price_x64 = observation.price_x64();
+        let delta_price_x64 = last_price_x64.checked_mul(delta_time.into()).unwrap();
         let expected = observation
             .cumulative_time_price_x64
             .wrapping_add(delta_price_x64);
@@ -490,4 +653,368 @@ mod test {
                 == expected
         );
     }
+
+    #[test]
+    fn test_is_unused() {
+        let key = Pubkey::new_unique();
+        let owner = crate::id();
+        let mut lamports = 0;
+        let mut data = vec![0u8; ObservationState::LEN];
+        {
+            let account_info = AccountInfo::new(
+                &key,
+                false,
+                true,
+                &mut lamports,
+                data.as_mut_slice(),
+                &owner,
+                false,
+                0,
+            );
+            assert!(ObservationState::is_unused(&account_info).unwrap());
+            let sqrt_price_x64 = get_sqrt_price_at_tick(0).unwrap();
+            ObservationState::initialize(&account_info, Pubkey::new_unique(), 100, sqrt_price_x64)
+                .unwrap();
+            assert!(!ObservationState::is_unused(&account_info).unwrap());
+            // the genesis observation is written at the creation
+            let observation_loader =
+                crate::util::AccountLoad::<ObservationState>::try_from(&account_info).unwrap();
+            let observation_state = observation_loader.load().unwrap();
+            assert_eq!(observation_state.cardinality(), 1);
+            assert_eq!({ observation_state.observations[0].block_timestamp }, 100);
+        }
+
+        let other_owner = Pubkey::new_unique();
+        let mut data = vec![0u8; ObservationState::LEN];
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            data.as_mut_slice(),
+            &other_owner,
+            false,
+            0,
+        );
+        assert!(!ObservationState::is_unused(&account_info).unwrap());
+    }
+
+    #[test]
+    fn test_cardinality_never_decreases_across_wrap() {
+        let mut block_timestamp = block_timestamp_mock() as u32;
+        let mut observation_index = 0u16;
+        let observation_update_duration = OBSERVATION_UPDATE_DURATION_DEFAULT as u32;
+        let mut observation_state = ObservationState::default();
+        assert_eq!(observation_state.cardinality(), 0);
+
+        let mut cardinality_before = 0;
+        for i in 0..OBSERVATION_NUM + 10 {
+            let sqrt_price_x64 = get_sqrt_price_at_tick((i % 2) as i32).unwrap();
+            if let Some(next_observation_index) = observation_state
+                .update_check(
+                    block_timestamp,
+                    sqrt_price_x64,
+                    observation_index,
+                    observation_update_duration,
+                )
+                .unwrap()
+            {
+                observation_index = next_observation_index;
+            }
+            assert!((observation_index as usize) < OBSERVATION_NUM);
+            let cardinality = observation_state.cardinality();
+            assert!(cardinality >= cardinality_before);
+            assert_eq!(
+                cardinality as usize,
+                usize::min(i + 1, OBSERVATION_NUM),
+                "one slot is written on each update until the ring is full"
+            );
+            cardinality_before = cardinality;
+            block_timestamp += observation_update_duration;
+        }
+        // the ring has wrapped, the index restarts from the beginning
+        assert_eq!(observation_index as usize, 9);
+    }
+
+    #[test]
+    fn test_check_write_index_at_wrap() {
+        let observation_update_duration = OBSERVATION_UPDATE_DURATION_DEFAULT as u32;
+        let mut observation_state = ObservationState::default();
+        let mut block_timestamp = 100;
+        let mut observation_index = 0;
+        for i in 0..OBSERVATION_NUM {
+            observation_index = observation_state
+                .update_check(
+                    block_timestamp,
+                    get_sqrt_price_at_tick((i % 2) as i32).unwrap(),
+                    observation_index,
+                    observation_update_duration,
+                )
+                .unwrap()
+                .unwrap();
+            block_timestamp += observation_update_duration;
+        }
+        // the ring is full, the last slot is the latest and the next write wraps to the start
+        assert_eq!(observation_index as usize, OBSERVATION_NUM - 1);
+        assert_eq!(observation_state.cardinality() as usize, OBSERVATION_NUM);
+        let next_observation_index = observation_state
+            .update_check(
+                block_timestamp,
+                get_sqrt_price_at_tick(2).unwrap(),
+                observation_index,
+                observation_update_duration,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(next_observation_index, 0);
+        assert_eq!(
+            identity(observation_state.observations[0].block_timestamp),
+            block_timestamp
+        );
+        assert_eq!(
+            observation_state
+                .check_write_index(OBSERVATION_NUM as u16)
+                .unwrap_err(),
+            ErrorCode::ObservationIndexOutOfRange.into()
+        );
+
+        // a ring written up to index 4 can only grow into index 5
+        let mut observation_state = ObservationState {
+            initialized: true,
+            ..Default::default()
+        };
+        for index in 0..5 {
+            observation_state.observations[index].block_timestamp = 100 + index as u32;
+        }
+        assert!(observation_state.check_write_index(5).is_ok());
+        assert_eq!(
+            observation_state.check_write_index(6).unwrap_err(),
+            ErrorCode::ObservationIndexOutOfRange.into()
+        );
+        // an index past the written observations is rejected before any write,
+        // as is one past the end of the ring
+        assert_eq!(
+            observation_state
+                .update_check(200, get_sqrt_price_at_tick(1).unwrap(), 7, 0)
+                .unwrap_err(),
+            ErrorCode::ObservationIndexOutOfRange.into()
+        );
+        assert_eq!(
+            observation_state
+                .update_check(200, get_sqrt_price_at_tick(1).unwrap(), 1005, 0)
+                .unwrap_err(),
+            ErrorCode::ObservationIndexOutOfRange.into()
+        );
+        // the largest index doesn't overflow stepping to the next one
+        assert_eq!(
+            observation_state
+                .update_check(200, get_sqrt_price_at_tick(1).unwrap(), u16::MAX, 0)
+                .unwrap_err(),
+            ErrorCode::ObservationIndexOutOfRange.into()
+        );
+    }
+
+    #[test]
+    fn test_average_price_across_cumulative_wrap() {
+        let tick = 1000;
+        let block_timestamp = 1647424834_u32;
+        let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+        let price_x64 = U128::from(sqrt_price_x64)
+            .mul_div_floor(U128::from(sqrt_price_x64), U128::from(fixed_point_64::Q64))
+            .unwrap()
+            .as_u128();
+        let mut observation_state = ObservationState {
+            initialized: true,
+            ..Default::default()
+        };
+        // the cumulative is going to wrap on the next update
+        observation_state.observations[0] = Observation {
+            block_timestamp,
+            sqrt_price_x64,
+            cumulative_time_price_x64: u128::MAX - price_x64 * 3,
+            padding: 0,
+        };
+        let next_observation_index = observation_state
+            .update_check(
+                block_timestamp + 15,
+                get_sqrt_price_at_tick(tick + 1).unwrap(),
+                0,
+                15,
+            )
+            .unwrap()
+            .unwrap();
+        let older = observation_state.observations[0];
+        let newer = observation_state.observations[next_observation_index as usize];
+        assert!(
+            identity(newer.cumulative_time_price_x64) < identity(older.cumulative_time_price_x64)
+        );
+
+        let average_price_x64 = newer.average_price_x64(&older).unwrap();
+        assert_eq!(average_price_x64, price_x64);
+        // the average still maps back to the tick
+        let average_sqrt_price_x64 = (U256::from(average_price_x64) << 64).integer_sqrt();
+        assert_eq!(
+            get_tick_at_sqrt_price(average_sqrt_price_x64.as_u128()).unwrap(),
+            tick - 1,
+            "flooring the price lands just below the tick"
+        );
+    }
+
+    #[test]
+    fn test_average_price_across_timestamp_wrap() {
+        let price_x64 = fixed_point_64::Q64 * 3;
+        let older = Observation {
+            block_timestamp: u32::MAX - 4,
+            cumulative_time_price_x64: price_x64,
+            ..Default::default()
+        };
+        // 10 seconds later the truncated timestamp has wrapped
+        let newer = Observation {
+            block_timestamp: 5,
+            cumulative_time_price_x64: price_x64 * 11,
+            ..Default::default()
+        };
+        assert_eq!(newer.average_price_x64(&older).unwrap(), price_x64);
+
+        // no time elapsed
+        assert!(older.average_price_x64(&older).is_err());
+    }
+
+    #[test]
+    fn test_twap_price_after_first_swap() {
+        let creation_timestamp = 1_700_000_000;
+        let observation_update_duration = u32::from(OBSERVATION_UPDATE_DURATION_DEFAULT);
+        let mut observation_state = ObservationState::default();
+        // the genesis observation written when the pool is created
+        assert_eq!(
+            observation_state
+                .update_check(creation_timestamp, get_sqrt_price_at_tick(0).unwrap(), 0, 0)
+                .unwrap(),
+            Some(0)
+        );
+        assert_eq!(observation_state.cardinality(), 1);
+        assert_eq!(
+            identity(observation_state.observations[0].cumulative_time_price_x64),
+            0
+        );
+
+        // a swap shortly after the creation, once the update duration passed
+        let swap_timestamp = creation_timestamp + observation_update_duration;
+        let sqrt_price_x64 = get_sqrt_price_at_tick(100).unwrap();
+        let observation_index = observation_state
+            .update_check(
+                swap_timestamp,
+                sqrt_price_x64,
+                0,
+                observation_update_duration,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(observation_index, 1);
+
+        // the pool held the genesis price until the swap, the TWAP up to the swap is that price,
+        // not the post-swap one, and no spurious delta from a zero baseline
+        let genesis_price_x64 = observation_state.observations[0].price_x64();
+        let swap_price_x64 = observation_state.observations[1].price_x64();
+        assert!(genesis_price_x64.abs_diff(fixed_point_64::Q64) <= 1);
+        assert!(swap_price_x64 > genesis_price_x64);
+        for window in [1, observation_update_duration, 3600] {
+            let twap_price_x64 = observation_state
+                .twap_price_x64(observation_index, swap_timestamp, window)
+                .unwrap();
+            assert_eq!(twap_price_x64, genesis_price_x64);
+        }
+    }
+
+    #[test]
+    fn test_twap_price() {
+        let mut observation_state = ObservationState::default();
+        let price_0_x64 = fixed_point_64::Q64;
+        let price_1_x64 = fixed_point_64::Q64 * 4;
+        let mut write =
+            |index: usize, block_timestamp: u32, sqrt_price_x64: u128, delta_cumulative: u128| {
+                let cumulative_time_price_x64 = if index == 0 {
+                    0
+                } else {
+                    observation_state.observations[index - 1].cumulative_time_price_x64
+                } + delta_cumulative;
+                observation_state.observations[index] = Observation {
+                    block_timestamp,
+                    sqrt_price_x64,
+                    cumulative_time_price_x64,
+                    padding: 0,
+                };
+            };
+        // price 1 until 1_100, then price 4 until 1_200
+        write(0, 1_000, fixed_point_64::Q64, 0);
+        write(1, 1_100, fixed_point_64::Q64, price_0_x64 * 100);
+        write(2, 1_200, fixed_point_64::Q64 * 2, price_1_x64 * 100);
+
+        // the last 100 seconds
+        assert_eq!(
+            observation_state.twap_price_x64(2, 1_200, 100).unwrap(),
+            price_1_x64
+        );
+        // the last 200 seconds
+        assert_eq!(
+            observation_state.twap_price_x64(2, 1_200, 200).unwrap(),
+            (price_0_x64 + price_1_x64) / 2
+        );
+        // the ring doesn't go back 1_000 seconds, averaged since the oldest observation
+        assert_eq!(
+            observation_state.twap_price_x64(2, 1_200, 1_000).unwrap(),
+            (price_0_x64 + price_1_x64) / 2
+        );
+        // nothing written within the window, the latest price
+        assert_eq!(
+            observation_state.twap_price_x64(2, 1_500, 100).unwrap(),
+            price_1_x64
+        );
+    }
+
+    #[test]
+    fn test_update_check_stale_index() {
+        let mut block_timestamp = 1647424834_u32;
+        let observation_update_duration = OBSERVATION_UPDATE_DURATION_DEFAULT as u32;
+        let mut observation_state = ObservationState::default();
+        let mut observation_index = 0u16;
+        for tick in [0, 10, 20] {
+            observation_index = observation_state
+                .update_check(
+                    block_timestamp,
+                    get_sqrt_price_at_tick(tick).unwrap(),
+                    observation_index,
+                    observation_update_duration,
+                )
+                .unwrap()
+                .unwrap();
+            block_timestamp += observation_update_duration;
+        }
+        assert_eq!(observation_index, 2);
+        assert!(observation_state.check_latest_index(2).is_ok());
+
+        // an older index would overwrite the latest observation
+        assert_eq!(
+            observation_state
+                .update_check(
+                    block_timestamp,
+                    get_sqrt_price_at_tick(30).unwrap(),
+                    1,
+                    observation_update_duration,
+                )
+                .unwrap_err(),
+            ErrorCode::StaleObservationIndex.into()
+        );
+
+        // in a wrapped ring, the latest observation is followed by the oldest one
+        observation_state.observations[3].block_timestamp = 1_000;
+        observation_state.observations[OBSERVATION_NUM - 1].block_timestamp = 1_100;
+        assert!(observation_state.check_latest_index(2).is_ok());
+        assert_eq!(
+            observation_state
+                .check_latest_index((OBSERVATION_NUM - 1) as u16)
+                .unwrap_err(),
+            ErrorCode::StaleObservationIndex.into()
+        );
+    }
 }
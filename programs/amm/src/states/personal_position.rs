@@ -1,6 +1,9 @@
+use crate::error::ErrorCode;
 use crate::libraries::{big_num::U256, fixed_point_64, full_math::MulDiv};
 use crate::pool::REWARD_NUM;
+use crate::states::{ProtocolPositionState, POSITION_SEED};
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 
 #[account]
 #[derive(Default, Debug)]
@@ -37,8 +40,31 @@ pub struct PersonalPositionState {
 
     // Position reward info
     pub reward_infos: [PositionRewardInfo; REWARD_NUM],
+
+    /// The total fees of token_0 collected by the position owner over the position's lifetime
+    /// Fees compounded into the position's liquidity aren't counted
+    pub fees_collected_0: u64,
+
+    /// The total fees of token_1 collected by the position owner over the position's lifetime
+    /// Fees compounded into the position's liquidity aren't counted
+    pub fees_collected_1: u64,
+
+    /// The block timestamp of the last time liquidity was added to the position
+    pub last_increase_timestamp: u64,
+
+    /// The `operator_approval` of the operator the NFT holder approved to manage the position's
+    /// liquidity and fees and of that holder, zero for none. Hashing the two together fits the
+    /// approval in the padding, and the operator only acts while the NFT stays with the
+    /// approving holder, a transfer revokes it.
+    pub operator_approval: [u8; 32],
+
     // Unused bytes for future upgrades.
-    pub padding: [u64; 8],
+    pub padding: [u64; 1],
+}
+
+/// The approval of `operator` by `approver`, the holder of the position NFT
+pub fn operator_approval(operator: Pubkey, approver: Pubkey) -> [u8; 32] {
+    hashv(&[operator.as_ref(), approver.as_ref()]).to_bytes()
 }
 
 impl PersonalPositionState {
@@ -79,6 +105,78 @@ impl PersonalPositionState {
         }
         Ok(())
     }
+
+    /// Whether the position holds no liquidity and owes no fees or rewards, so it can be closed
+    pub fn is_empty(&self) -> bool {
+        self.liquidity == 0
+            && self.token_fees_owed_0 == 0
+            && self.token_fees_owed_1 == 0
+            && self
+                .reward_infos
+                .iter()
+                .all(|reward_info| reward_info.reward_amount_owed == 0)
+    }
+
+    /// Ensures the liquidity has been in the position for at least `min_duration` seconds
+    /// since it was last increased, to deter liquidity added just in time for a swap
+    pub fn check_unlocked(&self, now: u64, min_duration: u64) -> Result<()> {
+        require_gte!(
+            now.saturating_sub(self.last_increase_timestamp),
+            min_duration,
+            ErrorCode::PositionLocked
+        );
+        Ok(())
+    }
+
+    /// Move the owed fees to the lifetime collected counters, returns the fees to transfer
+    pub fn collect_fees(&mut self) -> (u64, u64) {
+        let fees_owed = (self.token_fees_owed_0, self.token_fees_owed_1);
+        self.fees_collected_0 = self.fees_collected_0.saturating_add(fees_owed.0);
+        self.fees_collected_1 = self.fees_collected_1.saturating_add(fees_owed.1);
+        self.token_fees_owed_0 = 0;
+        self.token_fees_owed_1 = 0;
+        fees_owed
+    }
+
+    /// Undo `collect_fees` for the fees that couldn't be transferred, they stay owed
+    pub fn keep_fees_owed(&mut self, fees_unpaid_0: u64, fees_unpaid_1: u64) {
+        self.fees_collected_0 = self.fees_collected_0.saturating_sub(fees_unpaid_0);
+        self.fees_collected_1 = self.fees_collected_1.saturating_sub(fees_unpaid_1);
+        self.token_fees_owed_0 = self.token_fees_owed_0.checked_add(fees_unpaid_0).unwrap();
+        self.token_fees_owed_1 = self.token_fees_owed_1.checked_add(fees_unpaid_1).unwrap();
+    }
+
+    /// Deserialize a position account written with an older, shorter layout.
+    /// The fields appended to the layout since then are read as zero, their default.
+    pub fn try_deserialize_legacy(data: &[u8]) -> Result<Self> {
+        require_gte!(Self::LEN, data.len(), ErrorCode::InvalidPosition);
+        let mut data = data.to_vec();
+        data.resize(Self::LEN, 0);
+        Self::try_deserialize(&mut data.as_slice())
+    }
+
+    /// Ensures the position has been opened in the pool, and the protocol position covers the same range
+    pub fn check_position(
+        &self,
+        pool_id: Pubkey,
+        protocol_position: &ProtocolPositionState,
+    ) -> Result<()> {
+        require!(
+            self.nft_mint != Pubkey::default()
+                && self.pool_id == pool_id
+                && self.tick_lower_index < self.tick_upper_index
+                && protocol_position.pool_id == pool_id
+                && protocol_position.tick_lower_index == self.tick_lower_index
+                && protocol_position.tick_upper_index == self.tick_upper_index,
+            ErrorCode::InvalidPosition
+        );
+        Ok(())
+    }
+}
+
+/// The address and bump of the position tokenized by `nft_mint`, derived as `open_position` creates it
+pub fn tokenized_position_pda(nft_mint: Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSITION_SEED.as_bytes(), nft_mint.as_ref()], &crate::id())
 }
 
 #[derive(Copy, Clone, AnchorSerialize, AnchorDeserialize, Default, Debug, PartialEq)]
@@ -92,6 +190,21 @@ impl PositionRewardInfo {
     pub const LEN: usize = 16 + 8;
 }
 
+/// Emitted when the holder of a position NFT sets or clears the position's operator
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct PositionOperatorEvent {
+    /// The mint of the position nft
+    #[index]
+    pub position_nft_mint: Pubkey,
+
+    /// The holder of the position nft, who set the operator
+    pub nft_owner: Pubkey,
+
+    /// The operator of the position, default if it was cleared
+    pub operator: Pubkey,
+}
+
 /// Emitted when create a new position
 #[event]
 #[cfg_attr(feature = "client", derive(Debug))]
@@ -131,6 +244,92 @@ pub struct CreatePersonalPositionEvent {
 }
 
 /// Emitted when liquidity is increased.
+/// Emitted when a user's empty positions are closed in one call
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct CleanupPositionsEvent {
+    /// The owner of the positions, who received the rent
+    #[index]
+    pub nft_owner: Pubkey,
+
+    /// The nft mints of the closed positions, positions not safe to close are skipped
+    pub closed_position_nft_mints: Vec<Pubkey>,
+
+    /// The protocol positions and tick arrays closed with the positions, those still
+    /// referenced by other positions or paid for by someone else are kept
+    pub closed_accounts: Vec<Pubkey>,
+}
+
+/// Emitted when a position is closed together with the accounts only it referenced
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct PositionCleanupEvent {
+    /// The owner of the position, who received the rent
+    #[index]
+    pub nft_owner: Pubkey,
+
+    /// The nft mint of the closed position
+    pub position_nft_mint: Pubkey,
+
+    /// The protocol position and tick arrays closed with the position, those still
+    /// referenced by other liquidity or paid for by someone else are kept
+    pub closed_accounts: Vec<Pubkey>,
+}
+
+/// Emitted when a position is read by its nft mint
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct TokenizedPositionEvent {
+    /// The mint of the position nft
+    #[index]
+    pub position_nft_mint: Pubkey,
+
+    /// The position account, derived from the nft mint
+    pub personal_position: Pubkey,
+
+    /// The pool of the position
+    pub pool_id: Pubkey,
+
+    /// The lower tick of the position
+    pub tick_lower_index: i32,
+
+    /// The upper tick of the position
+    pub tick_upper_index: i32,
+
+    /// The liquidity of the position
+    pub liquidity: u128,
+
+    /// The token_0 fees owed to the position, as of its last update
+    pub token_fees_owed_0: u64,
+
+    /// The token_1 fees owed to the position, as of its last update
+    pub token_fees_owed_1: u64,
+}
+
+/// Emitted when the fees owed to a position are added to its liquidity
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct CompoundPositionEvent {
+    /// The ID of the token for which the fees were compounded
+    #[index]
+    pub position_nft_mint: Pubkey,
+
+    /// The amount by which liquidity for the NFT position was increased
+    pub liquidity: u128,
+
+    /// The amount of the token_0 fees that was added as liquidity
+    pub amount_0: u64,
+
+    /// The amount of the token_1 fees that was added as liquidity
+    pub amount_1: u64,
+
+    /// The token_0 fees still owed to the position
+    pub token_fees_owed_0: u64,
+
+    /// The token_1 fees still owed to the position
+    pub token_fees_owed_1: u64,
+}
+
 #[event]
 #[cfg_attr(feature = "client", derive(Debug))]
 pub struct IncreaseLiquidityEvent {
@@ -230,3 +429,261 @@ pub struct UpdateRewardInfosEvent {
     /// Reward info
     pub reward_growth_global_x64: [u128; REWARD_NUM],
 }
+
+#[cfg(test)]
+mod personal_position_test {
+    use super::*;
+    use crate::states::{protocol_position_pda, POOL_SEED};
+
+    fn build_positions(
+        pool_id: Pubkey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> (PersonalPositionState, ProtocolPositionState) {
+        let personal_position = PersonalPositionState {
+            nft_mint: Pubkey::new_unique(),
+            pool_id,
+            tick_lower_index,
+            tick_upper_index,
+            ..Default::default()
+        };
+        let protocol_position = ProtocolPositionState {
+            pool_id,
+            tick_lower_index,
+            tick_upper_index,
+            ..Default::default()
+        };
+        (personal_position, protocol_position)
+    }
+
+    #[test]
+    fn tokenized_position_pda_test() {
+        let nft_mint = Pubkey::new_unique();
+        let (personal_position, bump) = tokenized_position_pda(nft_mint);
+        // the seeds of the personal position in OpenPosition
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[POSITION_SEED.as_bytes(), nft_mint.as_ref(), &[bump]],
+                &crate::id(),
+            )
+            .unwrap(),
+            personal_position
+        );
+        assert_ne!(
+            tokenized_position_pda(Pubkey::new_unique()).0,
+            personal_position
+        );
+    }
+
+    #[test]
+    fn check_position_test() {
+        let pool_id = Pubkey::new_unique();
+        let (personal_position, protocol_position) = build_positions(pool_id, -100, 100);
+        assert!(personal_position
+            .check_position(pool_id, &protocol_position)
+            .is_ok());
+
+        // another pool
+        assert_eq!(
+            personal_position
+                .check_position(Pubkey::new_unique(), &protocol_position)
+                .unwrap_err(),
+            ErrorCode::InvalidPosition.into()
+        );
+
+        // protocol position of another range
+        let (_, other_protocol_position) = build_positions(pool_id, -100, 200);
+        assert_eq!(
+            personal_position
+                .check_position(pool_id, &other_protocol_position)
+                .unwrap_err(),
+            ErrorCode::InvalidPosition.into()
+        );
+    }
+
+    #[test]
+    fn same_range_in_pools_of_same_mints_test() {
+        // two fee tiers of the same mint pair
+        let (token_mint_0, token_mint_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool_ids = [Pubkey::new_unique(), Pubkey::new_unique()].map(|amm_config| {
+            Pubkey::find_program_address(
+                &[
+                    POOL_SEED.as_bytes(),
+                    amm_config.as_ref(),
+                    token_mint_0.as_ref(),
+                    token_mint_1.as_ref(),
+                ],
+                &crate::id(),
+            )
+            .0
+        });
+        assert_ne!(pool_ids[0], pool_ids[1]);
+        assert_ne!(
+            protocol_position_pda(pool_ids[0], -100, 100).0,
+            protocol_position_pda(pool_ids[1], -100, 100).0
+        );
+
+        // the same range in each pool is a position of its own
+        let (personal_position_0, mut protocol_position_0) =
+            build_positions(pool_ids[0], -100, 100);
+        let (personal_position_1, protocol_position_1) = build_positions(pool_ids[1], -100, 100);
+        assert_ne!(
+            tokenized_position_pda(personal_position_0.nft_mint).0,
+            tokenized_position_pda(personal_position_1.nft_mint).0
+        );
+        protocol_position_0
+            .update(-100, 100, 1_000, 0, 0, [0; REWARD_NUM])
+            .unwrap();
+        assert_eq!(protocol_position_0.liquidity, 1_000);
+        assert_eq!(protocol_position_1.liquidity, 0);
+
+        // and neither pool's position passes for the other's
+        assert!(personal_position_0
+            .check_position(pool_ids[0], &protocol_position_1)
+            .is_err());
+        assert!(personal_position_1
+            .check_position(pool_ids[1], &protocol_position_0)
+            .is_err());
+    }
+
+    #[test]
+    fn is_empty_test() {
+        let mut personal_position = PersonalPositionState::default();
+        assert!(personal_position.is_empty());
+
+        personal_position.liquidity = 1;
+        assert!(!personal_position.is_empty());
+        personal_position.liquidity = 0;
+        personal_position.token_fees_owed_1 = 1;
+        assert!(!personal_position.is_empty());
+        personal_position.token_fees_owed_1 = 0;
+        personal_position.reward_infos[2].reward_amount_owed = 1;
+        assert!(!personal_position.is_empty());
+
+        // the lifetime counters don't keep it open
+        personal_position.reward_infos[2].reward_amount_owed = 0;
+        personal_position.fees_collected_0 = 1_000;
+        assert!(personal_position.is_empty());
+    }
+
+    #[test]
+    fn check_unlocked_test() {
+        let mut personal_position = PersonalPositionState {
+            last_increase_timestamp: 1_000,
+            ..Default::default()
+        };
+        // no cooldown by default
+        assert!(personal_position.check_unlocked(1_000, 0).is_ok());
+
+        assert_eq!(
+            personal_position.check_unlocked(1_059, 60).unwrap_err(),
+            ErrorCode::PositionLocked.into()
+        );
+        assert!(personal_position.check_unlocked(1_060, 60).is_ok());
+
+        // a position never increased since the cooldown was added
+        personal_position.last_increase_timestamp = 0;
+        assert!(personal_position.check_unlocked(1_000, 60).is_ok());
+    }
+
+    #[test]
+    fn check_unlocked_after_mint_test() {
+        // minted just in time for a swap, then burned in the same second
+        let now = 1_700_000_000;
+        let mut personal_position = PersonalPositionState {
+            liquidity: 1_000_000,
+            last_increase_timestamp: now,
+            ..Default::default()
+        };
+        assert_eq!(
+            personal_position.check_unlocked(now, 30).unwrap_err(),
+            ErrorCode::PositionLocked.into()
+        );
+        // the default zero cooldown keeps the burn allowed
+        assert!(personal_position.check_unlocked(now, 0).is_ok());
+
+        // an increase restarts the cooldown
+        personal_position.last_increase_timestamp = now + 20;
+        assert_eq!(
+            personal_position.check_unlocked(now + 30, 30).unwrap_err(),
+            ErrorCode::PositionLocked.into()
+        );
+        assert!(personal_position.check_unlocked(now + 50, 30).is_ok());
+    }
+
+    #[test]
+    fn collect_fees_test() {
+        let mut personal_position = PersonalPositionState {
+            token_fees_owed_0: 100,
+            token_fees_owed_1: 200,
+            ..Default::default()
+        };
+        assert_eq!(personal_position.collect_fees(), (100, 200));
+        assert_eq!(personal_position.token_fees_owed_0, 0);
+        assert_eq!(personal_position.token_fees_owed_1, 0);
+
+        // nothing owed, the counters are kept
+        assert_eq!(personal_position.collect_fees(), (0, 0));
+        personal_position.token_fees_owed_0 = 50;
+        assert_eq!(personal_position.collect_fees(), (50, 0));
+        assert_eq!(personal_position.fees_collected_0, 150);
+        assert_eq!(personal_position.fees_collected_1, 200);
+
+        // saturates rather than overflows
+        personal_position.token_fees_owed_1 = u64::MAX;
+        personal_position.collect_fees();
+        assert_eq!(personal_position.fees_collected_1, u64::MAX);
+    }
+
+    #[test]
+    fn try_deserialize_legacy_test() {
+        let pool_id = Pubkey::new_unique();
+        let (mut personal_position, _) = build_positions(pool_id, -100, 100);
+        personal_position.liquidity = 1_000_000;
+        personal_position.token_fees_owed_1 = 500;
+        personal_position.reward_infos[REWARD_NUM - 1].reward_amount_owed = 20;
+        let mut data = Vec::new();
+        personal_position.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), PersonalPositionState::LEN);
+
+        // an older layout without the last reward info and the padding
+        let legacy_len = PersonalPositionState::LEN - PositionRewardInfo::LEN - 64;
+        let migrated = PersonalPositionState::try_deserialize_legacy(&data[..legacy_len]).unwrap();
+        assert_eq!(migrated.nft_mint, personal_position.nft_mint);
+        assert_eq!(migrated.pool_id, pool_id);
+        assert_eq!(migrated.liquidity, 1_000_000);
+        assert_eq!(migrated.token_fees_owed_1, 500);
+        assert_eq!(
+            migrated.reward_infos[REWARD_NUM - 1],
+            PositionRewardInfo::default()
+        );
+        assert_eq!(migrated.operator_approval, [0; 32]);
+        assert_eq!(migrated.padding, [0; 1]);
+
+        // the current layout reads as is
+        let current = PersonalPositionState::try_deserialize_legacy(&data).unwrap();
+        assert_eq!(current.reward_infos, personal_position.reward_infos);
+
+        // longer than the current layout
+        data.push(0);
+        assert_eq!(
+            PersonalPositionState::try_deserialize_legacy(&data).unwrap_err(),
+            ErrorCode::InvalidPosition.into()
+        );
+        // not a position account
+        assert!(PersonalPositionState::try_deserialize_legacy(&[0u8; 64]).is_err());
+    }
+
+    #[test]
+    fn check_uninitialized_position_test() {
+        let pool_id = Pubkey::new_unique();
+        let (_, protocol_position) = build_positions(pool_id, -100, 100);
+        let personal_position = PersonalPositionState::default();
+        assert_eq!(
+            personal_position
+                .check_position(pool_id, &protocol_position)
+                .unwrap_err(),
+            ErrorCode::InvalidPosition.into()
+        );
+    }
+}
@@ -39,6 +39,7 @@ pub enum PoolStatusBitIndex {
     CollectFee,
     CollectReward,
     Swap,
+    SwapWithoutOutputMinimum,
 }
 
 #[derive(PartialEq, Eq)]
@@ -77,7 +78,8 @@ pub struct PoolState {
     pub mint_decimals_0: u8,
     pub mint_decimals_1: u8,
 
-    /// The minimum number of ticks between initialized ticks
+    /// The minimum number of ticks between initialized ticks,
+    /// copied from amm_config when the pool created and never changes
     pub tick_spacing: u16,
     /// The currently in range liquidity available to the pool.
     pub liquidity: u128,
@@ -111,9 +113,17 @@ pub struct PoolState {
     /// bit2, 1: disable collect fee, 0: normal
     /// bit3, 1: disable collect reward, 0: normal
     /// bit4, 1: disable swap, 0: normal
+    /// bit5, 1: disable swap base input with zero amount_out_minimum, 0: normal
     pub status: u8,
+    /// Set when the pool is created and initialized, a zeroed pool account can't be used
+    pub initialized: bool,
+    /// Set when the pool is migrated, swaps and liquidity adds are rejected but LPs can still exit
+    pub deprecated: bool,
+    /// Set when governance returns the protocol's share of the trade fees to the LPs,
+    /// the share is then added to the fee growth and no protocol fees accrue
+    pub protocol_fee_to_lp: bool,
     /// Leave blank for future use
-    pub padding: [u8; 7],
+    pub padding: [u8; 4],
 
     pub reward_infos: [RewardInfo; REWARD_NUM],
 
@@ -133,8 +143,44 @@ pub struct PoolState {
     // The timestamp allowed for swap in the pool.
     pub open_time: u64,
 
+    /// The tick of the initial price when the pool created, never changes
+    pub genesis_tick: i32,
+
+    // Unused bytes for future upgrades.
+    pub padding_u32: u32,
+
+    /// Swaps specifying a smaller amount of token_0 are rejected, zero allows any amount
+    pub min_swap_amount_0: u64,
+    /// Swaps specifying a smaller amount of token_1 are rejected, zero allows any amount
+    pub min_swap_amount_1: u64,
+
+    /// The seconds liquidity must stay in a position before it can be decreased, zero for no limit
+    pub position_min_duration: u64,
+
+    /// The max gross liquidity of a tick, copied from amm_config when the pool created, zero for no limit
+    pub max_liquidity_per_tick: u128,
+
+    /// The pool that replaces a deprecated pool, default if it has none
+    pub successor_pool: Pubkey,
+
+    /// The furthest in the future, in seconds, the deadline of an instruction on the pool can be,
+    /// zero for `DEFAULT_MAX_DEADLINE_HORIZON`
+    pub max_deadline_horizon: u64,
+
+    /// The sum of the liquidity of all positions, only counted since the field was added and
+    /// seeded with the in range liquidity when a cap is set
+    pub total_liquidity: u128,
+    /// Liquidity adds taking `total_liquidity` above it are rejected, zero until a cap is set
+    pub max_total_liquidity: u128,
+
+    /// The creator of the pool, unlike `owner` never changes. Default for pools created before
+    /// the field was added.
+    pub creator: Pubkey,
+    /// The block timestamp when the pool created, zero for pools created before the field was added
+    pub created_at: u64,
+
     // Unused bytes for future upgrades.
-    pub padding1: [u64; 25],
+    pub padding1: [u64; 5],
     pub padding2: [u64; 32],
 }
 
@@ -190,6 +236,7 @@ impl PoolState {
         token_mint_0: &InterfaceAccount<Mint>,
         token_mint_1: &InterfaceAccount<Mint>,
         observation_state_key: Pubkey,
+        created_at: u64,
     ) -> Result<()> {
         self.bump = [bump];
         self.amm_config = amm_config.key();
@@ -216,7 +263,10 @@ impl PoolState {
         self.swap_in_amount_token_1 = 0;
         self.swap_out_amount_token_0 = 0;
         self.status = 0;
-        self.padding = [0; 7];
+        self.initialized = true;
+        self.deprecated = false;
+        self.protocol_fee_to_lp = false;
+        self.padding = [0; 4];
         self.tick_array_bitmap = [0; 16];
         self.total_fees_token_0 = 0;
         self.total_fees_claimed_token_0 = 0;
@@ -225,7 +275,19 @@ impl PoolState {
         self.fund_fees_token_0 = 0;
         self.fund_fees_token_1 = 0;
         self.open_time = open_time;
-        self.padding1 = [0; 25];
+        self.genesis_tick = tick;
+        self.padding_u32 = 0;
+        self.min_swap_amount_0 = 0;
+        self.min_swap_amount_1 = 0;
+        self.position_min_duration = 0;
+        self.max_liquidity_per_tick = amm_config.max_liquidity_per_tick;
+        self.successor_pool = Pubkey::default();
+        self.max_deadline_horizon = 0;
+        self.total_liquidity = 0;
+        self.max_total_liquidity = 0;
+        self.creator = pool_creator;
+        self.created_at = created_at;
+        self.padding1 = [0; 5];
         self.padding2 = [0; 32];
         self.observation_key = observation_state_key;
 
@@ -468,6 +530,16 @@ impl PoolState {
         }
     }
 
+    /// The number of initialized tick arrays in a 64 bits word of the default bitmap,
+    /// words below 8 hold the negative tick arrays and the rest the positive ones
+    pub fn initialized_tick_array_count(&self, word_pos: u8) -> Result<u32> {
+        let tick_array_bitmap = self.tick_array_bitmap;
+        let word = tick_array_bitmap
+            .get(usize::from(word_pos))
+            .ok_or(ErrorCode::InvalidTickArrayBoundary)?;
+        Ok(word.count_ones())
+    }
+
     pub fn get_first_initialized_tick_array(
         &self,
         tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
@@ -524,6 +596,10 @@ impl PoolState {
                 return Ok(Some(start_index));
             }
             last_tick_array_start_index = start_index;
+            // the default bitmap covers the whole tick range for large tick spacings
+            if !(tick_math::MIN_TICK..=tick_math::MAX_TICK).contains(&last_tick_array_start_index) {
+                return Ok(None);
+            }
 
             if tickarray_bitmap_extension.is_none() {
                 return err!(ErrorCode::MissingTickArrayBitmapExtensionAccount);
@@ -549,6 +625,35 @@ impl PoolState {
         }
     }
 
+    /// Ensures the gross liquidity of a tick is within the cap of the pool's fee tier
+    pub fn check_tick_liquidity(&self, liquidity_gross: u128) -> Result<()> {
+        let max_liquidity_per_tick = self.max_liquidity_per_tick;
+        if max_liquidity_per_tick != 0 {
+            require_gte!(
+                max_liquidity_per_tick,
+                liquidity_gross,
+                ErrorCode::TickLiquidityOverflow
+            );
+        }
+        Ok(())
+    }
+
+    /// Ensures the token vaults are the pool's, so liquidity can't be credited for a deposit
+    /// into another account, nor be withdrawn from one
+    pub fn check_token_vaults(&self, token_vault_0: Pubkey, token_vault_1: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            token_vault_0,
+            self.token_vault_0,
+            ErrorCode::InvalidTokenVault
+        );
+        require_keys_eq!(
+            token_vault_1,
+            self.token_vault_1,
+            ErrorCode::InvalidTokenVault
+        );
+        Ok(())
+    }
+
     pub fn set_status(&mut self, status: u8) {
         self.status = status
     }
@@ -569,6 +674,84 @@ impl PoolState {
         self.status.bitand(status) == 0
     }
 
+    /// Ensures the pool was set up by `create_pool`, a zeroed pool account has no price to trade at.
+    /// Pools created before the flag was added must be migrated by `migrate_pool` first.
+    pub fn check_initialized(&self) -> Result<()> {
+        require!(self.initialized, ErrorCode::PoolNotInitialized);
+        Ok(())
+    }
+
+    /// Sets the flag of a pool created before it was added, such a pool has had a price since
+    /// its creation while a zeroed pool account has none
+    pub fn migrate_initialized(&mut self) -> Result<()> {
+        require!(self.sqrt_price_x64 != 0, ErrorCode::PoolNotInitialized);
+        self.initialized = true;
+        Ok(())
+    }
+
+    /// The protocol fees that can be collected, none while the protocol's share goes to the LPs.
+    /// Fees accrued before the share was redirected stay owed until it is switched back.
+    pub fn collectable_protocol_fees(&self) -> (u64, u64) {
+        if self.protocol_fee_to_lp {
+            (0, 0)
+        } else {
+            (self.protocol_fees_token_0, self.protocol_fees_token_1)
+        }
+    }
+
+    /// Adds a position's liquidity delta to `total_liquidity`, an increase must stay within
+    /// `max_total_liquidity`. A decrease saturates at zero, as the liquidity of positions
+    /// opened before the total was counted is missing from it.
+    pub fn update_total_liquidity(&mut self, liquidity_delta: i128) -> Result<()> {
+        if liquidity_delta < 0 {
+            self.total_liquidity = self
+                .total_liquidity
+                .saturating_sub(liquidity_delta.unsigned_abs());
+            return Ok(());
+        }
+        let total_liquidity = self
+            .total_liquidity
+            .checked_add(liquidity_delta.unsigned_abs())
+            .ok_or(ErrorCode::PoolLiquidityCapReached)?;
+        if self.max_total_liquidity > 0 {
+            require_gte!(
+                self.max_total_liquidity,
+                total_liquidity,
+                ErrorCode::PoolLiquidityCapReached
+            );
+        }
+        self.total_liquidity = total_liquidity;
+        Ok(())
+    }
+
+    /// Sets `max_total_liquidity`, it can only be raised and never below `total_liquidity`.
+    /// The positions opened before the total was counted are missing from it, so the total is
+    /// first raised to the in range liquidity, which those positions hold at least.
+    pub fn set_max_total_liquidity(&mut self, max_total_liquidity: u128) -> Result<()> {
+        self.total_liquidity = self.total_liquidity.max(self.liquidity);
+        require!(
+            max_total_liquidity > 0
+                && max_total_liquidity >= self.max_total_liquidity
+                && max_total_liquidity >= self.total_liquidity,
+            ErrorCode::PoolLiquidityCapLowered
+        );
+        self.max_total_liquidity = max_total_liquidity;
+        Ok(())
+    }
+
+    /// Marks the pool deprecated, pointing integrators to `successor_pool` if it has one
+    pub fn deprecate(&mut self, successor_pool: Option<Pubkey>) {
+        self.deprecated = true;
+        self.successor_pool = successor_pool.unwrap_or_default();
+    }
+
+    /// Ensures the pool isn't deprecated, only swaps and liquidity adds check it
+    /// so the LPs of a deprecated pool can still decrease liquidity and collect
+    pub fn check_not_deprecated(&self) -> Result<()> {
+        require!(!self.deprecated, ErrorCode::PoolDeprecated);
+        Ok(())
+    }
+
     pub fn is_overflow_default_tickarray_bitmap(&self, tick_array_start_indexs: Vec<i32>) -> bool {
         let (max_tick_boundary, min_tick_boundary) = self.tick_range();
         for tick_index in tick_array_start_indexs {
@@ -591,7 +774,9 @@ impl PoolState {
             max_tick_boundary = tick_math::MAX_TICK
         }
         if min_tick_boundary < tick_math::MIN_TICK {
-            min_tick_boundary = tick_math::MIN_TICK
+            // the tick array holding MIN_TICK starts below it, and is still tracked by the default bitmap
+            min_tick_boundary =
+                TickArrayState::get_array_start_index(tick_math::MIN_TICK, self.tick_spacing)
         }
         (max_tick_boundary, min_tick_boundary)
     }
@@ -678,7 +863,8 @@ pub struct PoolCreatedEvent {
     #[index]
     pub token_mint_1: Pubkey,
 
-    /// The minimum number of ticks between initialized ticks
+    /// The minimum number of ticks between initialized ticks,
+    /// copied from amm_config when the pool created and never changes
     pub tick_spacing: u16,
 
     /// The address of the created pool
@@ -715,6 +901,53 @@ pub struct CollectProtocolFeeEvent {
 
     /// The amount of token_0 protocol fees that is withdrawn
     pub amount_1: u64,
+
+    /// The part of amount_0 sent to the secondary recipient
+    pub secondary_amount_0: u64,
+
+    /// The part of amount_1 sent to the secondary recipient
+    pub secondary_amount_1: u64,
+}
+
+/// Emitted when a collection is owed more than the pool vault holds, which indicates an accounting bug
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct VaultShortfallEvent {
+    /// The pool of the vault
+    #[index]
+    pub pool_state: Pubkey,
+
+    /// The vault that can't cover the collection
+    pub token_vault: Pubkey,
+
+    /// The amount owed to the collector
+    pub amount_owed: u64,
+
+    /// The vault balance, which is what is actually transferred
+    pub vault_balance: u64,
+}
+
+/// Emitted when a pool is deprecated and points integrators to its replacement
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct PoolDeprecatedEvent {
+    /// The deprecated pool
+    #[index]
+    pub pool_state: Pubkey,
+
+    /// The pool that replaces it, default if it has none
+    pub successor_pool: Pubkey,
+}
+
+/// Emitted when the protocol's share of a pool's trade fees is redirected to or back from the LPs
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct ProtocolFeeToLpEvent {
+    #[index]
+    pub pool_state: Pubkey,
+
+    /// Whether the protocol's share now goes to the LPs
+    pub protocol_fee_to_lp: bool,
 }
 
 /// Emitted by when a swap is performed for a pool
@@ -764,6 +997,24 @@ pub struct SwapEvent {
     pub tick: i32,
 }
 
+/// Emitted when exact_input_best_of picks the route to execute
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct RouteSelectedEvent {
+    /// The user performing the swap
+    #[index]
+    pub payer: Pubkey,
+
+    /// The index of the chosen route in the candidate routes
+    pub route_index: u8,
+
+    /// The amount of input token swapped
+    pub amount_in: u64,
+
+    /// The simulated output of the chosen route, the most of the candidate routes
+    pub amount_out: u64,
+}
+
 /// Emitted pool liquidity change when increase and decrease liquidity
 #[event]
 #[cfg_attr(feature = "client", derive(Debug))]
@@ -788,6 +1039,57 @@ pub struct LiquidityChangeEvent {
     pub liquidity_after: u128,
 }
 
+/// Emitted when read the fee growth globals of a pool, sample it twice to derive
+/// the fees earned per unit of liquidity per second
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct FeeGrowthGlobalEvent {
+    /// The pool to read
+    #[index]
+    pub pool_state: Pubkey,
+
+    /// The fee growth of token_0 as a Q64.64 number
+    pub fee_growth_global_0_x64: u128,
+
+    /// The fee growth of token_1 as a Q64.64 number
+    pub fee_growth_global_1_x64: u128,
+
+    /// The currently in range liquidity
+    pub liquidity: u128,
+
+    /// The current tick of the pool
+    pub tick_current: i32,
+
+    /// The block timestamp of the read
+    pub timestamp: u64,
+}
+
+/// Emitted when a swap crosses an initialized tick
+///
+/// Position keys are not included, a tick only records the net and gross liquidity referenced
+/// to it, not which positions reference it. Keeping a position list per tick would cost an
+/// account read for every position on each crossing, so keepers should match `tick` against the
+/// `tick_lower_index` and `tick_upper_index` of positions they track off-chain.
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct TickCrossEvent {
+    /// The pool for swap
+    #[index]
+    pub pool_state: Pubkey,
+
+    /// The tick which is crossed
+    pub tick: i32,
+
+    /// The net liquidity applied to the pool when crossed, already signed by the swap direction
+    pub liquidity_net: i128,
+
+    /// The liquidity of the pool after crossing
+    pub liquidity_after: u128,
+
+    /// The direction of swap
+    pub zero_for_one: bool,
+}
+
 // /// Emitted when price move in a swap step
 // #[event]
 // #[cfg_attr(feature = "client", derive(Debug))]
@@ -821,6 +1123,9 @@ pub struct LiquidityChangeEvent {
 #[cfg(test)]
 pub mod pool_test {
     use super::*;
+    use crate::states::tick_array_test::{build_tick, build_tick_array_with_tick_states};
+    use crate::util::program_test::TestAccount;
+    use anchor_spl::token::spl_token;
     use std::cell::RefCell;
 
     pub fn build_pool(
@@ -830,6 +1135,7 @@ pub mod pool_test {
         liquidity: u128,
     ) -> RefCell<PoolState> {
         let mut new_pool = PoolState::default();
+        new_pool.initialized = true;
         new_pool.tick_current = tick_current;
         new_pool.tick_spacing = tick_spacing;
         new_pool.sqrt_price_x64 = sqrt_price_x64;
@@ -853,6 +1159,75 @@ pub mod pool_test {
         RefCell::new(new_pool)
     }
 
+    /// A pool at `tick_current` with tick spacing 60 whose only `liquidity` is in [60, 3540),
+    /// and its only initialized tick array, [0, 3600), holding the range's ticks
+    pub fn build_swap_pool(
+        tick_current: i32,
+        liquidity: u128,
+    ) -> (RefCell<PoolState>, RefCell<TickArrayState>) {
+        let tick_spacing = 60;
+        let pool_state = build_pool(
+            tick_current,
+            tick_spacing,
+            tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+            liquidity,
+        );
+        pool_state
+            .borrow_mut()
+            .flip_tick_array_bit(None, 0)
+            .unwrap();
+        let tick_array = build_tick_array_with_tick_states(
+            Pubkey::default(),
+            0,
+            tick_spacing,
+            vec![
+                build_tick(60, liquidity, liquidity as i128).take(),
+                build_tick(3540, liquidity, -(liquidity as i128)).take(),
+            ],
+        );
+        (pool_state, tick_array)
+    }
+
+    /// The accounts `process_instruction` runs the pool's instructions against: the pool at its
+    /// address, its config, observation, spl token mints and vaults holding `vault_amount` each
+    pub fn build_pool_accounts(pool_state: &mut PoolState, vault_amount: u64) -> Vec<TestAccount> {
+        let pool_id = pool_state.key();
+        pool_state.token_vault_0 = Pubkey::new_unique();
+        pool_state.token_vault_1 = Pubkey::new_unique();
+        pool_state.observation_key = Pubkey::new_unique();
+        pool_state.mint_decimals_0 = 6;
+        pool_state.mint_decimals_1 = 6;
+        let amm_config = AmmConfig {
+            tick_spacing: pool_state.tick_spacing,
+            ..Default::default()
+        };
+        let observation_state = ObservationState {
+            pool_id,
+            ..Default::default()
+        };
+        vec![
+            TestAccount::account(pool_state.amm_config, &amm_config, AmmConfig::LEN),
+            TestAccount::zero_copy(pool_id, pool_state),
+            TestAccount::zero_copy(pool_state.observation_key, &observation_state),
+            TestAccount::mint(pool_state.token_mint_0, spl_token::id(), 6),
+            TestAccount::mint(pool_state.token_mint_1, spl_token::id(), 6),
+            TestAccount::token_account(
+                pool_state.token_vault_0,
+                spl_token::id(),
+                pool_state.token_mint_0,
+                pool_id,
+                vault_amount,
+            ),
+            TestAccount::token_account(
+                pool_state.token_vault_1,
+                spl_token::id(),
+                pool_state.token_mint_1,
+                pool_id,
+                vault_amount,
+            ),
+        ]
+    }
+
     mod tick_array_bitmap_test {
 
         use super::*;
@@ -934,6 +1309,75 @@ pub mod pool_test {
         }
     }
 
+    mod boundary_tick_array_bitmap_test {
+        use super::*;
+
+        #[test]
+        fn flip_boundary_tick_array_bit_test() {
+            let tick_spacing = 60;
+            let mut pool_state = PoolState {
+                tick_spacing,
+                ..Default::default()
+            };
+            // the tick arrays of the spacing aligned MIN_TICK and MAX_TICK
+            let max_usable_tick =
+                tick_math::MAX_TICK / i32::from(tick_spacing) * i32::from(tick_spacing);
+            let upper_start_index =
+                TickArrayState::get_array_start_index(max_usable_tick, tick_spacing);
+            let lower_start_index =
+                TickArrayState::get_array_start_index(-max_usable_tick, tick_spacing);
+            assert!(!pool_state
+                .is_overflow_default_tickarray_bitmap(vec![lower_start_index, upper_start_index]));
+
+            pool_state
+                .flip_tick_array_bit(None, upper_start_index)
+                .unwrap();
+            pool_state
+                .flip_tick_array_bit(None, lower_start_index)
+                .unwrap();
+            assert!(U1024(pool_state.tick_array_bitmap)
+                .bit(pool_state.get_tick_array_offset(upper_start_index).unwrap()));
+            assert!(U1024(pool_state.tick_array_bitmap)
+                .bit(pool_state.get_tick_array_offset(lower_start_index).unwrap()));
+
+            // swaps find the boundary tick arrays in both directions
+            assert_eq!(
+                pool_state
+                    .next_initialized_tick_array_start_index(&None, 0, false)
+                    .unwrap(),
+                Some(upper_start_index)
+            );
+            assert_eq!(
+                pool_state
+                    .next_initialized_tick_array_start_index(&None, 0, true)
+                    .unwrap(),
+                Some(lower_start_index)
+            );
+            // and nothing beyond them
+            assert_eq!(
+                pool_state
+                    .next_initialized_tick_array_start_index(&None, upper_start_index, false)
+                    .unwrap(),
+                None
+            );
+            assert_eq!(
+                pool_state
+                    .next_initialized_tick_array_start_index(&None, lower_start_index, true)
+                    .unwrap(),
+                None
+            );
+
+            // burning the last position unsets the bits
+            pool_state
+                .flip_tick_array_bit(None, upper_start_index)
+                .unwrap();
+            pool_state
+                .flip_tick_array_bit(None, lower_start_index)
+                .unwrap();
+            assert!(U1024(pool_state.tick_array_bitmap).is_zero());
+        }
+    }
+
     mod pool_status_test {
         use super::*;
 
@@ -994,6 +1438,261 @@ pub mod pool_test {
         }
     }
 
+    mod deprecate_pool_test {
+        use super::*;
+
+        #[test]
+        fn deprecate_test() {
+            let pool_state_ref =
+                build_pool(0, 60, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+            let pool_state = &mut pool_state_ref.borrow_mut();
+            assert!(pool_state.check_not_deprecated().is_ok());
+
+            let successor_pool = Pubkey::new_unique();
+            pool_state.deprecate(Some(successor_pool));
+            assert_eq!(
+                pool_state.check_not_deprecated().unwrap_err(),
+                ErrorCode::PoolDeprecated.into()
+            );
+            assert_eq!({ pool_state.successor_pool }, successor_pool);
+            // the status bits are untouched, so decrease liquidity and collect still pass
+            assert!(pool_state.get_status_by_bit(PoolStatusBitIndex::DecreaseLiquidity));
+            assert!(pool_state.get_status_by_bit(PoolStatusBitIndex::CollectFee));
+
+            pool_state.deprecate(None);
+            assert_eq!({ pool_state.successor_pool }, Pubkey::default());
+        }
+    }
+
+    mod max_total_liquidity_test {
+        use super::*;
+
+        #[test]
+        fn update_total_liquidity_test() {
+            let pool_state_ref =
+                build_pool(0, 60, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+            let pool_state = &mut pool_state_ref.borrow_mut();
+            // unlimited by default
+            pool_state.update_total_liquidity(i128::MAX).unwrap();
+            pool_state.update_total_liquidity(-i128::MAX).unwrap();
+            assert_eq!({ pool_state.total_liquidity }, 0);
+
+            pool_state.set_max_total_liquidity(1_000).unwrap();
+            pool_state.update_total_liquidity(600).unwrap();
+            pool_state.update_total_liquidity(400).unwrap();
+            assert_eq!(
+                pool_state.update_total_liquidity(1).unwrap_err(),
+                ErrorCode::PoolLiquidityCapReached.into()
+            );
+            assert_eq!({ pool_state.total_liquidity }, 1_000);
+            // removing liquidity makes room again, and never underflows
+            pool_state.update_total_liquidity(-100).unwrap();
+            pool_state.update_total_liquidity(100).unwrap();
+            pool_state.update_total_liquidity(-2_000).unwrap();
+            assert_eq!({ pool_state.total_liquidity }, 0);
+        }
+
+        #[test]
+        fn set_max_total_liquidity_test() {
+            let pool_state_ref =
+                build_pool(0, 60, tick_math::get_sqrt_price_at_tick(0).unwrap(), 0);
+            let pool_state = &mut pool_state_ref.borrow_mut();
+            pool_state.set_max_total_liquidity(1_000).unwrap();
+            assert_eq!(
+                pool_state.set_max_total_liquidity(999).unwrap_err(),
+                ErrorCode::PoolLiquidityCapLowered.into()
+            );
+            pool_state.set_max_total_liquidity(2_000).unwrap();
+            assert_eq!({ pool_state.max_total_liquidity }, 2_000);
+            // the cap can't be lifted, so neither lowered by lifting it first
+            assert_eq!(
+                pool_state.set_max_total_liquidity(0).unwrap_err(),
+                ErrorCode::PoolLiquidityCapLowered.into()
+            );
+            assert_eq!({ pool_state.max_total_liquidity }, 2_000);
+        }
+
+        #[test]
+        fn set_max_total_liquidity_below_total_test() {
+            // a pool whose positions were opened before the total was counted
+            let pool_state_ref =
+                build_pool(0, 60, tick_math::get_sqrt_price_at_tick(0).unwrap(), 5_000);
+            let pool_state = &mut pool_state_ref.borrow_mut();
+            assert_eq!({ pool_state.total_liquidity }, 0);
+            assert_eq!(
+                pool_state.set_max_total_liquidity(4_999).unwrap_err(),
+                ErrorCode::PoolLiquidityCapLowered.into()
+            );
+            // the total is seeded with the in range liquidity
+            assert_eq!({ pool_state.total_liquidity }, 5_000);
+            pool_state.set_max_total_liquidity(5_000).unwrap();
+            assert_eq!(
+                pool_state.update_total_liquidity(1).unwrap_err(),
+                ErrorCode::PoolLiquidityCapReached.into()
+            );
+        }
+    }
+
+    mod check_token_vaults_test {
+        use super::*;
+
+        #[test]
+        fn check_token_vaults() {
+            let pool_state = PoolState {
+                token_vault_0: Pubkey::new_unique(),
+                token_vault_1: Pubkey::new_unique(),
+                ..Default::default()
+            };
+            assert!(pool_state
+                .check_token_vaults(pool_state.token_vault_0, pool_state.token_vault_1)
+                .is_ok());
+
+            let other_vault = Pubkey::new_unique();
+            assert_eq!(
+                pool_state
+                    .check_token_vaults(other_vault, pool_state.token_vault_1)
+                    .unwrap_err(),
+                ErrorCode::InvalidTokenVault.into()
+            );
+            assert_eq!(
+                pool_state
+                    .check_token_vaults(pool_state.token_vault_0, other_vault)
+                    .unwrap_err(),
+                ErrorCode::InvalidTokenVault.into()
+            );
+            // swapped vaults
+            assert_eq!(
+                pool_state
+                    .check_token_vaults(pool_state.token_vault_1, pool_state.token_vault_0)
+                    .unwrap_err(),
+                ErrorCode::InvalidTokenVault.into()
+            );
+        }
+    }
+
+    mod initialized_tick_array_count_test {
+        use super::*;
+
+        #[test]
+        fn initialized_tick_array_count_test() {
+            let mut pool_state = PoolState {
+                tick_spacing: 10,
+                ..Default::default()
+            };
+            let tick_array_span = TICK_ARRAY_SIZE * 10;
+            for start_index in [
+                -tick_array_span * 2,
+                0,
+                tick_array_span,
+                tick_array_span * 63,
+                tick_array_span * 64,
+            ] {
+                pool_state.flip_tick_array_bit(None, start_index).unwrap();
+            }
+            // the tick arrays right below zero end the word 7
+            assert_eq!(pool_state.initialized_tick_array_count(7).unwrap(), 1);
+            assert_eq!(pool_state.initialized_tick_array_count(8).unwrap(), 3);
+            assert_eq!(pool_state.initialized_tick_array_count(9).unwrap(), 1);
+            assert_eq!(pool_state.initialized_tick_array_count(0).unwrap(), 0);
+
+            // flipped back
+            pool_state.flip_tick_array_bit(None, 0).unwrap();
+            assert_eq!(pool_state.initialized_tick_array_count(8).unwrap(), 2);
+
+            assert_eq!(
+                pool_state.initialized_tick_array_count(16).unwrap_err(),
+                ErrorCode::InvalidTickArrayBoundary.into()
+            );
+        }
+    }
+
+    mod check_tick_liquidity_test {
+        use super::*;
+        use crate::libraries::liquidity_math;
+
+        #[test]
+        fn check_tick_liquidity_test() {
+            let mut pool_state = PoolState::default();
+            // pools created before the cap have none
+            assert!(pool_state.check_tick_liquidity(u128::MAX).is_ok());
+
+            let amm_config_1 = AmmConfig {
+                max_liquidity_per_tick: liquidity_math::get_max_liquidity_per_tick(1),
+                ..Default::default()
+            };
+            let amm_config_60 = AmmConfig {
+                max_liquidity_per_tick: liquidity_math::get_max_liquidity_per_tick(60),
+                ..Default::default()
+            };
+
+            pool_state.max_liquidity_per_tick = amm_config_60.max_liquidity_per_tick;
+            assert!(pool_state
+                .check_tick_liquidity(amm_config_60.max_liquidity_per_tick)
+                .is_ok());
+            assert_eq!(
+                pool_state
+                    .check_tick_liquidity(amm_config_60.max_liquidity_per_tick + 1)
+                    .unwrap_err(),
+                ErrorCode::TickLiquidityOverflow.into()
+            );
+
+            // the narrower tier caps lower
+            pool_state.max_liquidity_per_tick = amm_config_1.max_liquidity_per_tick;
+            assert_eq!(
+                pool_state
+                    .check_tick_liquidity(amm_config_60.max_liquidity_per_tick)
+                    .unwrap_err(),
+                ErrorCode::TickLiquidityOverflow.into()
+            );
+        }
+    }
+
+    mod pool_check_reset_test {
+        use super::*;
+        use std::convert::identity;
+
+        #[test]
+        fn reset_keep_tick_spacing() {
+            let pool_state_ref = build_pool(
+                1000,
+                10,
+                tick_math::get_sqrt_price_at_tick(1000).unwrap(),
+                100,
+            );
+            let pool_state = &mut pool_state_ref.borrow_mut();
+            let new_tick = -2000;
+            pool_state
+                .pool_check_reset(
+                    tick_math::get_sqrt_price_at_tick(new_tick).unwrap(),
+                    new_tick,
+                )
+                .unwrap();
+            assert_eq!(identity(pool_state.tick_current), new_tick);
+            assert_eq!(identity(pool_state.tick_spacing), 10);
+        }
+
+        #[test]
+        fn reset_keep_genesis_tick() {
+            let pool_state_ref = build_pool(
+                1000,
+                10,
+                tick_math::get_sqrt_price_at_tick(1000).unwrap(),
+                100,
+            );
+            let pool_state = &mut pool_state_ref.borrow_mut();
+            pool_state.genesis_tick = 1000;
+            let new_tick = -2000;
+            pool_state
+                .pool_check_reset(
+                    tick_math::get_sqrt_price_at_tick(new_tick).unwrap(),
+                    new_tick,
+                )
+                .unwrap();
+            assert_eq!(identity(pool_state.tick_current), new_tick);
+            assert_eq!(identity(pool_state.genesis_tick), 1000);
+        }
+    }
+
     mod update_reward_infos_test {
         use super::*;
         use anchor_lang::prelude::Pubkey;
@@ -43,12 +43,31 @@ pub struct ProtocolPositionState {
 
     /// The reward growth per unit of liquidity as of the last update to liquidity
     pub reward_growth_inside: [u128; REWARD_NUM], // 24
+
+    /// The account that paid for the protocol position's creation, default for ones created
+    /// before it's recorded
+    pub payer: Pubkey,
+
+    /// The number of personal positions of the range not closed yet, zero for protocol positions
+    /// created before it's recorded. A position closed without its protocol position stays
+    /// counted, which only keeps the protocol position open.
+    pub position_count: u64,
     // Unused bytes for future upgrades.
-    pub padding: [u64; 8],
+    pub padding: [u64; 3],
 }
 
 impl ProtocolPositionState {
-    pub const LEN: usize = 8 + 1 + 32 + 4 + 4 + 16 + 16 + 16 + 8 + 8 + 16 * REWARD_NUM + 64;
+    pub const LEN: usize =
+        8 + 1 + 32 + 4 + 4 + 16 + 16 + 16 + 8 + 8 + 16 * REWARD_NUM + 32 + 8 + 24;
+
+    /// Count a personal position of the range opened or closed
+    pub fn update_position_count(&mut self, add: bool) {
+        if add {
+            self.position_count += 1;
+        } else {
+            self.position_count = self.position_count.saturating_sub(1);
+        }
+    }
 
     pub fn update(
         &mut self,
@@ -112,3 +131,39 @@ impl ProtocolPositionState {
         self.reward_growth_inside = reward_growths_inside;
     }
 }
+
+/// The address and bump of the protocol position of a range, derived as `open_position` creates it.
+/// The pool key is part of the seeds, so pools of the same mints in other fee tiers don't collide.
+pub fn protocol_position_pda(
+    pool_id: Pubkey,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            POSITION_SEED.as_bytes(),
+            pool_id.as_ref(),
+            &tick_lower_index.to_be_bytes(),
+            &tick_upper_index.to_be_bytes(),
+        ],
+        &crate::id(),
+    )
+}
+
+#[cfg(test)]
+pub mod protocol_position_test {
+    use super::*;
+
+    pub fn build_protocol_position(
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        liquidity: u128,
+    ) -> ProtocolPositionState {
+        ProtocolPositionState {
+            tick_lower_index,
+            tick_upper_index,
+            liquidity,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::error::ErrorCode;
+use crate::util::check_deadline_at;
+use anchor_lang::prelude::*;
+
+pub const ROUTE_CURSOR_SEED: &str = "route_cursor";
+
+/// Where a base input multi-hop swap stopped, so the payer can continue it in another transaction.
+/// The account is derived from the payer, who alone can continue the route, one route at a time.
+#[account]
+#[derive(Default, Debug)]
+pub struct RouteCursorState {
+    /// Bump to identify PDA
+    pub bump: u8,
+
+    /// The payer of the route
+    pub owner: Pubkey,
+
+    /// The number of hops swapped so far
+    pub hops: u8,
+
+    /// The token account holding the output of the last hop swapped, the input of the next hop
+    pub input_token_account: Pubkey,
+
+    /// The amount of input token paid for the whole route
+    pub amount_in: u64,
+
+    /// The output of the last hop swapped, the input of the next hop
+    pub amount_in_internal: u64,
+
+    /// The minimum output of the whole route
+    pub amount_out_minimum: u64,
+
+    /// The unix timestamp after which the route can't be continued
+    pub deadline: i64,
+}
+
+impl RouteCursorState {
+    pub const LEN: usize = 8 + 1 + 32 + 1 + 32 + 8 + 8 + 8 + 8;
+
+    /// Ensures the route is continued by its payer from the token account it stopped at,
+    /// before its deadline
+    pub fn check_continue(
+        &self,
+        owner: Pubkey,
+        input_token_account: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            self.owner == owner && self.input_token_account == input_token_account,
+            ErrorCode::InvalidRouteCursor
+        );
+        check_deadline_at(now, self.deadline, 0)
+    }
+}
+
+#[cfg(test)]
+mod route_cursor_test {
+    use super::*;
+
+    #[test]
+    fn check_continue_test() {
+        let owner = Pubkey::new_unique();
+        let input_token_account = Pubkey::new_unique();
+        let deadline = 1_700_000_000;
+        let route_cursor = RouteCursorState {
+            owner,
+            hops: 1,
+            input_token_account,
+            amount_in: 1_000_000,
+            amount_in_internal: 999_000,
+            deadline,
+            ..Default::default()
+        };
+        assert!(route_cursor
+            .check_continue(owner, input_token_account, deadline)
+            .is_ok());
+
+        // someone else's route, or from another token account
+        assert_eq!(
+            route_cursor
+                .check_continue(Pubkey::new_unique(), input_token_account, deadline)
+                .unwrap_err(),
+            ErrorCode::InvalidRouteCursor.into()
+        );
+        assert_eq!(
+            route_cursor
+                .check_continue(owner, Pubkey::new_unique(), deadline)
+                .unwrap_err(),
+            ErrorCode::InvalidRouteCursor.into()
+        );
+        // past the deadline
+        assert_eq!(
+            route_cursor
+                .check_continue(owner, input_token_account, deadline + 1)
+                .unwrap_err(),
+            ErrorCode::TransactionTooOld.into()
+        );
+    }
+}
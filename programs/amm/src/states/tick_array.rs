@@ -20,12 +20,17 @@ pub struct TickArrayState {
     pub start_tick_index: i32,
     pub ticks: [TickState; TICK_ARRAY_SIZE_USIZE],
     pub initialized_tick_count: u8,
+    /// The account that paid for the tick array's creation, default for ones created before it's recorded
+    pub payer: Pubkey,
+    /// The number of personal positions not closed yet with a tick in the tick array, zero for ones
+    /// created before it's recorded
+    pub position_count: u32,
     // Unused bytes for future upgrades.
-    pub padding: [u8; 115],
+    pub padding: [u8; 79],
 }
 
 impl TickArrayState {
-    pub const LEN: usize = 8 + 32 + 4 + TickState::LEN * TICK_ARRAY_SIZE_USIZE + 1 + 115;
+    pub const LEN: usize = 8 + 32 + 4 + TickState::LEN * TICK_ARRAY_SIZE_USIZE + 1 + 32 + 4 + 79;
 
     pub fn key(&self) -> Pubkey {
         Pubkey::find_program_address(
@@ -52,6 +57,7 @@ impl TickArrayState {
             ErrorCode::InvaildTickIndex
         );
 
+        let payer_key = payer.key();
         let tick_array_state = if tick_array_account_info.owner == &system_program::ID {
             let (expect_pda_address, bump) = Pubkey::find_program_address(
                 &[
@@ -86,6 +92,7 @@ impl TickArrayState {
                     tick_spacing,
                     pool_state_loader.key(),
                 )?;
+                tick_array_account.payer = payer_key;
             }
             tick_array_state_loader
         } else {
@@ -118,6 +125,20 @@ impl TickArrayState {
         Ok(())
     }
 
+    /// Count a personal position opened or closed with a tick in the tick array
+    pub fn update_position_count(&mut self, add: bool) {
+        if add {
+            self.position_count += 1;
+        } else {
+            self.position_count = self.position_count.saturating_sub(1);
+        }
+    }
+
+    pub fn get_tick_state(&self, tick_index: i32, tick_spacing: u16) -> Result<&TickState> {
+        let offset_in_array = self.get_tick_offset_in_array(tick_index, tick_spacing)?;
+        Ok(&self.ticks[offset_in_array])
+    }
+
     pub fn get_tick_state_mut(
         &mut self,
         tick_index: i32,
@@ -138,6 +159,35 @@ impl TickArrayState {
         Ok(())
     }
 
+    /// Update both boundary ticks of a position which fall in this tick array, e.g. a position one tick spacing wide,
+    /// so the tick array is loaded once and the pool bitmap is looked up at most once.
+    /// Return whether the tick array bit in the pool bitmap need to be flipped.
+    pub fn update_position_tick_states(
+        &mut self,
+        tick_spacing: u16,
+        tick_lower_state: TickState,
+        tick_upper_state: TickState,
+        flip_tick_lower: bool,
+        flip_tick_upper: bool,
+        add: bool,
+    ) -> Result<bool> {
+        self.update_tick_state(tick_lower_state.tick, tick_spacing, tick_lower_state)?;
+        self.update_tick_state(tick_upper_state.tick, tick_spacing, tick_upper_state)?;
+
+        let before_init_tick_count = self.initialized_tick_count;
+        if flip_tick_lower {
+            self.update_initialized_tick_count(add)?;
+        }
+        if flip_tick_upper {
+            self.update_initialized_tick_count(add)?;
+        }
+        Ok(if add {
+            before_init_tick_count == 0 && self.initialized_tick_count != 0
+        } else {
+            before_init_tick_count != 0 && self.initialized_tick_count == 0
+        })
+    }
+
     /// Get tick's offset in current tick array, tick must be include in tick array， otherwise throw an error
     fn get_tick_offset_in_array(self, tick_index: i32, tick_spacing: u16) -> Result<usize> {
         let start_tick_index = TickArrayState::get_array_start_index(tick_index, tick_spacing);
@@ -258,7 +308,9 @@ impl Default for TickArrayState {
             ticks: [TickState::default(); TICK_ARRAY_SIZE_USIZE],
             start_tick_index: 0,
             initialized_tick_count: 0,
-            padding: [0; 115],
+            payer: Pubkey::default(),
+            position_count: 0,
+            padding: [0; 79],
         }
     }
 }
@@ -683,6 +735,63 @@ pub mod tick_array_test {
             );
         }
 
+        #[test]
+        fn update_position_tick_states_cross_check_test() {
+            let tick_spacing = 10;
+            let tick_lower = build_tick(100, 500, 500).into_inner();
+            let tick_upper = build_tick(110, 500, -500).into_inner();
+            for init_tick_count in [0u8, 1, 2, 5] {
+                for (flip_tick_lower, flip_tick_upper) in
+                    [(false, false), (true, false), (false, true), (true, true)]
+                {
+                    for add in [true, false] {
+                        let flip_count = flip_tick_lower as u8 + flip_tick_upper as u8;
+                        if !add && init_tick_count < flip_count {
+                            continue;
+                        }
+                        // the two tick arrays path of open_position and decrease_liquidity,
+                        // with both arrays the same one: each tick updated and counted by itself
+                        let general_ref = build_tick_array(0, tick_spacing, vec![3, 20]);
+                        let mut general = general_ref.borrow_mut();
+                        general.initialized_tick_count = init_tick_count;
+                        general.update_tick_state(100, tick_spacing, tick_lower).unwrap();
+                        general.update_tick_state(110, tick_spacing, tick_upper).unwrap();
+                        let mut general_bit_flip_count = 0;
+                        for flip in [flip_tick_lower, flip_tick_upper] {
+                            if flip {
+                                let before_init_tick_count = general.initialized_tick_count;
+                                general.update_initialized_tick_count(add).unwrap();
+                                if (add && before_init_tick_count == 0)
+                                    || (!add && general.initialized_tick_count == 0)
+                                {
+                                    general_bit_flip_count += 1;
+                                }
+                            }
+                        }
+
+                        let single_ref = build_tick_array(0, tick_spacing, vec![3, 20]);
+                        let mut single = single_ref.borrow_mut();
+                        single.initialized_tick_count = init_tick_count;
+                        let flip_bit = single
+                            .update_position_tick_states(
+                                tick_spacing,
+                                tick_lower,
+                                tick_upper,
+                                flip_tick_lower,
+                                flip_tick_upper,
+                                add,
+                            )
+                            .unwrap();
+
+                        assert!(general_bit_flip_count <= 1);
+                        assert_eq!(flip_bit, general_bit_flip_count == 1);
+                        // the whole tick array ends the same, ticks and counts alike
+                        assert_eq!(bytemuck::bytes_of(&*single), bytemuck::bytes_of(&*general));
+                    }
+                }
+            }
+        }
+
         #[test]
         fn first_initialized_tick_test() {
             let tick_spacing = 15;
@@ -776,6 +885,181 @@ pub mod tick_array_test {
         }
     }
 
+    mod boundary_tick_test {
+        use super::*;
+        use std::convert::identity;
+
+        const TICK_SPACING: u16 = 60;
+        // the usable boundary ticks, aligned to tick spacing
+        const MAX_USABLE_TICK: i32 =
+            tick_math::MAX_TICK / TICK_SPACING as i32 * TICK_SPACING as i32;
+        const MIN_USABLE_TICK: i32 = -MAX_USABLE_TICK;
+
+        #[test]
+        fn boundary_tick_array_start_index_test() {
+            assert_eq!(MAX_USABLE_TICK, 443580);
+            let upper_start_index =
+                TickArrayState::get_array_start_index(MAX_USABLE_TICK, TICK_SPACING);
+            assert_eq!(upper_start_index, 442800);
+            assert!(TickArrayState::check_is_valid_start_index(
+                upper_start_index,
+                TICK_SPACING
+            ));
+            check_tick_array_start_index(upper_start_index, MAX_USABLE_TICK, TICK_SPACING).unwrap();
+
+            // the tick array of the lower boundary starts beyond MIN_TICK
+            let lower_start_index =
+                TickArrayState::get_array_start_index(MIN_USABLE_TICK, TICK_SPACING);
+            assert_eq!(lower_start_index, -446400);
+            assert!(lower_start_index < tick_math::MIN_TICK);
+            assert!(TickArrayState::check_is_valid_start_index(
+                lower_start_index,
+                TICK_SPACING
+            ));
+            check_tick_array_start_index(lower_start_index, MIN_USABLE_TICK, TICK_SPACING).unwrap();
+
+            // one spacing beyond the boundary is rejected
+            assert_eq!(
+                check_tick_array_start_index(
+                    upper_start_index,
+                    MAX_USABLE_TICK + i32::from(TICK_SPACING),
+                    TICK_SPACING
+                )
+                .unwrap_err(),
+                error!(ErrorCode::TickUpperOverflow)
+            );
+            assert_eq!(
+                check_tick_array_start_index(
+                    lower_start_index,
+                    MIN_USABLE_TICK - i32::from(TICK_SPACING),
+                    TICK_SPACING
+                )
+                .unwrap_err(),
+                error!(ErrorCode::TickLowerOverflow)
+            );
+        }
+
+        #[test]
+        fn mint_and_burn_boundary_position_test() {
+            let reward_infos = [RewardInfo::default(); REWARD_NUM];
+            let liquidity = 1_000_000i128;
+            let mut tick_lower = TickState::default();
+            tick_lower
+                .initialize(MIN_USABLE_TICK, TICK_SPACING)
+                .unwrap();
+            let mut tick_upper = TickState::default();
+            tick_upper
+                .initialize(MAX_USABLE_TICK, TICK_SPACING)
+                .unwrap();
+
+            // mint
+            assert!(tick_lower
+                .update(0, liquidity, 0, 0, false, &reward_infos)
+                .unwrap());
+            assert!(tick_upper
+                .update(0, liquidity, 0, 0, true, &reward_infos)
+                .unwrap());
+            assert_eq!(identity(tick_lower.liquidity_net), liquidity);
+            assert_eq!(identity(tick_upper.liquidity_net), -liquidity);
+
+            // the boundary ticks sit in their tick arrays
+            let upper_array_ref = build_tick_array_with_tick_states(
+                Pubkey::default(),
+                TickArrayState::get_array_start_index(MAX_USABLE_TICK, TICK_SPACING),
+                TICK_SPACING,
+                vec![tick_upper],
+            );
+            let lower_array_ref = build_tick_array_with_tick_states(
+                Pubkey::default(),
+                TickArrayState::get_array_start_index(MIN_USABLE_TICK, TICK_SPACING),
+                TICK_SPACING,
+                vec![tick_lower],
+            );
+            assert_eq!(
+                identity(
+                    upper_array_ref
+                        .borrow_mut()
+                        .get_tick_state_mut(MAX_USABLE_TICK, TICK_SPACING)
+                        .unwrap()
+                        .liquidity_gross
+                ),
+                liquidity as u128
+            );
+            assert_eq!(
+                identity(
+                    lower_array_ref
+                        .borrow_mut()
+                        .get_tick_state_mut(MIN_USABLE_TICK, TICK_SPACING)
+                        .unwrap()
+                        .liquidity_gross
+                ),
+                liquidity as u128
+            );
+
+            // burn
+            assert!(tick_lower
+                .update(0, -liquidity, 0, 0, false, &reward_infos)
+                .unwrap());
+            assert!(tick_upper
+                .update(0, -liquidity, 0, 0, true, &reward_infos)
+                .unwrap());
+            assert_eq!(identity(tick_lower.liquidity_net), 0);
+            assert_eq!(identity(tick_upper.liquidity_net), 0);
+            assert!(!tick_lower.is_initialized());
+            assert!(!tick_upper.is_initialized());
+        }
+
+        #[test]
+        fn cross_boundary_tick_test() {
+            let reward_infos = [RewardInfo::default(); REWARD_NUM];
+            let liquidity = 1_000_000i128;
+
+            // price moves up to the upper boundary
+            let upper_start_index =
+                TickArrayState::get_array_start_index(MAX_USABLE_TICK, TICK_SPACING);
+            let upper_array_ref = build_tick_array_with_tick_states(
+                Pubkey::default(),
+                upper_start_index,
+                TICK_SPACING,
+                vec![build_tick(MAX_USABLE_TICK, liquidity as u128, -liquidity).into_inner()],
+            );
+            let upper_array = upper_array_ref.borrow();
+            let next_tick = upper_array
+                .next_initialized_tick(MAX_USABLE_TICK - 1, TICK_SPACING, false)
+                .unwrap()
+                .unwrap();
+            assert_eq!(identity(next_tick.tick), MAX_USABLE_TICK);
+            assert_eq!(next_tick.cross(0, 0, &reward_infos), -liquidity);
+            // nothing left to cross above the boundary
+            assert!(upper_array
+                .next_initialized_tick(MAX_USABLE_TICK, TICK_SPACING, false)
+                .unwrap()
+                .is_none());
+
+            // price moves down to the lower boundary
+            let lower_start_index =
+                TickArrayState::get_array_start_index(MIN_USABLE_TICK, TICK_SPACING);
+            let lower_array_ref = build_tick_array_with_tick_states(
+                Pubkey::default(),
+                lower_start_index,
+                TICK_SPACING,
+                vec![build_tick(MIN_USABLE_TICK, liquidity as u128, liquidity).into_inner()],
+            );
+            let lower_array = lower_array_ref.borrow();
+            let next_tick = lower_array
+                .next_initialized_tick(MIN_USABLE_TICK + 1, TICK_SPACING, true)
+                .unwrap()
+                .unwrap();
+            assert_eq!(identity(next_tick.tick), MIN_USABLE_TICK);
+            // liquidity_net is subtracted when crossing from right to left
+            assert_eq!(-next_tick.cross(0, 0, &reward_infos), -liquidity);
+            assert!(lower_array
+                .next_initialized_tick(MIN_USABLE_TICK - 1, TICK_SPACING, true)
+                .unwrap()
+                .is_none());
+        }
+    }
+
     mod get_fee_growth_inside_test {
         use super::*;
         use crate::states::{
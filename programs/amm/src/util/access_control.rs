@@ -1,8 +1,11 @@
 use crate::error::ErrorCode;
+use crate::states::{operator_approval, PersonalPositionState};
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::TokenAccount;
 
-/// Ensures that the signer is the owner or a delgated authority for the position NFT
+/// Ensures that the signer is the holder of the position NFT.
+/// A delegated authority of the token account is not accepted, since the liquidity and fees
+/// of the position can be sent to any recipient account.
 ///
 /// # Arguments
 ///
@@ -13,9 +16,183 @@ pub fn is_authorized_for_token<'info>(
     signer: &Signer<'info>,
     token_account: &Box<InterfaceAccount<'info, TokenAccount>>,
 ) -> Result<()> {
+    check_token_holder(token_account.amount, token_account.owner, signer.key())
+}
+
+/// Holder check of `is_authorized_for_token` on the token account's amount and owner
+pub fn check_token_holder(amount: u64, owner: Pubkey, signer: Pubkey) -> Result<()> {
+    require!(amount == 1 && owner == signer, ErrorCode::NotApproved);
+    Ok(())
+}
+
+/// Ensures that the signer is the holder of the position NFT, or the operator the holder
+/// approved for the position, who can manage its liquidity and fees without holding the NFT.
+///
+/// # Arguments
+///
+/// * `signer` - The signer address
+/// * `token_account` - The token account holding the position NFT
+/// * `personal_position` - The position the NFT is for
+///
+pub fn is_authorized_for_position<'info>(
+    signer: &Signer<'info>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    personal_position: &PersonalPositionState,
+) -> Result<()> {
+    check_position_authority(
+        token_account.amount,
+        token_account.owner,
+        personal_position.operator_approval,
+        signer.key(),
+    )
+}
+
+/// Authority check of `is_authorized_for_position`, the operator acts for the account holding
+/// the NFT only while it's held by the holder who approved the operator
+pub fn check_position_authority(
+    amount: u64,
+    owner: Pubkey,
+    approval: [u8; 32],
+    signer: Pubkey,
+) -> Result<()> {
+    if signer != owner && approval != [0; 32] {
+        require!(
+            amount == 1 && approval == operator_approval(signer, owner),
+            ErrorCode::NotApproved
+        );
+        return Ok(());
+    }
+    check_token_holder(amount, owner, signer)
+}
+
+/// The furthest in the future a deadline can be when the pool doesn't set its own horizon
+pub const DEFAULT_MAX_DEADLINE_HORIZON: u64 = 30 * 24 * 60 * 60;
+
+/// Ensures the transaction is executed no later than `deadline`.
+/// The deadline is inclusive, it passes when the block timestamp equals `deadline`
+/// and fails with `TransactionTooOld` from `deadline + 1`.
+/// A deadline more than `max_deadline_horizon` seconds away fails with `DeadlineTooFar`,
+/// so a huge deadline can't switch the protection off.
+///
+/// # Arguments
+///
+/// * `deadline` - The unix timestamp after which the transaction is rejected
+/// * `max_deadline_horizon` - The furthest the deadline can be, 0 for the default
+///
+pub fn check_deadline(deadline: i64, max_deadline_horizon: u64) -> Result<()> {
+    check_deadline_at(Clock::get()?.unix_timestamp, deadline, max_deadline_horizon)
+}
+
+/// Deadline check against the given `now`, see `check_deadline`
+pub fn check_deadline_at(now: i64, deadline: i64, max_deadline_horizon: u64) -> Result<()> {
+    require_gte!(deadline, now, ErrorCode::TransactionTooOld);
+    let max_deadline_horizon = if max_deadline_horizon == 0 {
+        DEFAULT_MAX_DEADLINE_HORIZON
+    } else {
+        max_deadline_horizon
+    };
     require!(
-        token_account.amount == 1 && token_account.owner == signer.key(),
-        ErrorCode::NotApproved
+        deadline.abs_diff(now) <= max_deadline_horizon,
+        ErrorCode::DeadlineTooFar
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod access_control_test {
+    use super::*;
+
+    #[test]
+    fn check_deadline_boundary_test() {
+        let deadline = 1_700_000_000;
+        assert!(check_deadline_at(deadline - 1, deadline, 0).is_ok());
+        assert!(check_deadline_at(deadline, deadline, 0).is_ok());
+        assert_eq!(
+            check_deadline_at(deadline + 1, deadline, 0).unwrap_err(),
+            ErrorCode::TransactionTooOld.into()
+        );
+    }
+
+    #[test]
+    fn check_deadline_horizon_test() {
+        let now = 1_700_000_000;
+        // the default horizon leaves room for any normal deadline
+        let horizon = DEFAULT_MAX_DEADLINE_HORIZON as i64;
+        assert!(check_deadline_at(now, now + horizon, 0).is_ok());
+        assert_eq!(
+            check_deadline_at(now, now + horizon + 1, 0).unwrap_err(),
+            ErrorCode::DeadlineTooFar.into()
+        );
+        assert_eq!(
+            check_deadline_at(now, i64::MAX, 0).unwrap_err(),
+            ErrorCode::DeadlineTooFar.into()
+        );
+
+        // a horizon set by the pool
+        assert!(check_deadline_at(now, now + 60, 60).is_ok());
+        assert_eq!(
+            check_deadline_at(now, now + 61, 60).unwrap_err(),
+            ErrorCode::DeadlineTooFar.into()
+        );
+        // an expired deadline is still too old first
+        assert_eq!(
+            check_deadline_at(now, now - 1, 60).unwrap_err(),
+            ErrorCode::TransactionTooOld.into()
+        );
+    }
+
+    #[test]
+    fn check_token_holder_test() {
+        let holder = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        assert!(check_token_holder(1, holder, holder).is_ok());
+        // a delegate of the holder's token account can't act on the position
+        assert_eq!(
+            check_token_holder(1, holder, delegate).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+        // the token account doesn't hold the position NFT
+        assert_eq!(
+            check_token_holder(0, holder, holder).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+    }
+
+    #[test]
+    fn check_position_authority_test() {
+        let holder = Pubkey::new_unique();
+        let operator = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let none = [0; 32];
+        let approval = operator_approval(operator, holder);
+        // without an operator only the holder is authorized
+        assert!(check_position_authority(1, holder, none, holder).is_ok());
+        assert_eq!(
+            check_position_authority(1, holder, none, operator).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+        // the holder keeps its authority alongside the operator
+        assert!(check_position_authority(1, holder, approval, holder).is_ok());
+        assert!(check_position_authority(1, holder, approval, operator).is_ok());
+        assert_eq!(
+            check_position_authority(1, holder, approval, other).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+        // the operator must pass the token account holding the NFT
+        assert_eq!(
+            check_position_authority(0, holder, approval, operator).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+        // the NFT moved away from the holder who approved the operator
+        assert_eq!(
+            check_position_authority(1, other, approval, operator).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+        assert!(check_position_authority(1, other, approval, other).is_ok());
+        // the default key is never an operator
+        assert_eq!(
+            check_position_authority(1, holder, none, Pubkey::default()).unwrap_err(),
+            ErrorCode::NotApproved.into()
+        );
+    }
+}
@@ -9,3 +9,6 @@ pub use access_control::*;
 
 pub mod account_load;
 pub use account_load::*;
+
+#[cfg(test)]
+pub mod program_test;
@@ -0,0 +1,343 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    clock::Clock,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_utils::limited_deserialize,
+    rent::Rent,
+    system_instruction::SystemInstruction,
+    system_program, sysvar,
+};
+use anchor_lang::{Discriminator, InstructionData, ZeroCopy};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use std::cell::RefCell;
+use std::sync::Once;
+
+/// The block timestamp every instruction run by `process_instruction` sees
+pub const TEST_UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+thread_local! {
+    /// The return data set by the last instruction `process_instruction` ran on the thread
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Processes the CPIs of an instruction under test: the token programs run their processors
+/// and the system program creates and funds accounts, against the accounts the caller passed
+struct ProgramTestStubs;
+
+impl SyscallStubs for ProgramTestStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &crate::id()))
+            .collect::<std::result::Result<Vec<Pubkey>, _>>()?;
+        let mut accounts = Vec::with_capacity(instruction.accounts.len());
+        for meta in instruction.accounts.iter() {
+            let mut account_info = account_infos
+                .iter()
+                .find(|account_info| *account_info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?
+                .clone();
+            if meta.is_signer && !account_info.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            account_info.is_signer = meta.is_signer;
+            account_info.is_writable = meta.is_writable;
+            accounts.push(account_info);
+        }
+        if instruction.program_id == spl_token::id() {
+            spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &accounts,
+                &instruction.data,
+            )
+        } else if instruction.program_id == spl_token_2022::id() {
+            spl_token_2022::processor::Processor::process(
+                &instruction.program_id,
+                &accounts,
+                &instruction.data,
+            )
+        } else if instruction.program_id == system_program::id() {
+            process_system_instruction(&accounts, &instruction.data)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Clock) = Clock {
+                unix_timestamp: TEST_UNIX_TIMESTAMP,
+                ..Clock::default()
+            };
+        }
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        SUCCESS
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = data.to_vec());
+    }
+}
+
+/// The system instructions the program invokes, creating an account and funding one
+fn process_system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    match limited_deserialize(data, 1024).map_err(|_| ProgramError::InvalidInstructionData)? {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            if to.lamports() != 0 || to.data_len() as u64 != space {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            **from.try_borrow_mut_lamports()? -= lamports;
+            **to.try_borrow_mut_lamports()? += lamports;
+            to.assign(&owner);
+            Ok(())
+        }
+        SystemInstruction::Transfer { lamports } => {
+            **accounts[0].try_borrow_mut_lamports()? -= lamports;
+            **accounts[1].try_borrow_mut_lamports()? += lamports;
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// An account an instruction under test runs against, its state is kept between instructions
+#[derive(Clone, Debug)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    /// A rent exempt account holding `data`
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self {
+            key,
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+        }
+    }
+
+    /// A wallet of the system program, e.g. a payer
+    pub fn wallet(key: Pubkey) -> Self {
+        Self {
+            lamports: 10_000_000_000,
+            ..Self::new(key, system_program::id(), vec![])
+        }
+    }
+
+    /// An account not created yet, with room for `space` bytes once it is
+    pub fn uncreated(key: Pubkey, space: usize) -> Self {
+        Self {
+            lamports: 0,
+            ..Self::new(key, system_program::id(), vec![0; space])
+        }
+    }
+
+    /// The executable account of a program
+    pub fn program(program_id: Pubkey) -> Self {
+        Self {
+            executable: true,
+            ..Self::new(program_id, Pubkey::default(), vec![])
+        }
+    }
+
+    /// The rent sysvar account, holding the same `Rent` the syscall stub returns
+    pub fn rent_sysvar() -> Self {
+        let rent = Rent::default();
+        let mut data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        data.push(rent.burn_percent);
+        Self::new(sysvar::rent::id(), sysvar::id(), data)
+    }
+
+    /// An account of the program serialized by anchor
+    pub fn account<T: AccountSerialize>(key: Pubkey, state: &T, space: usize) -> Self {
+        let mut data = Vec::with_capacity(space);
+        state.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        Self::new(key, crate::id(), data)
+    }
+
+    /// A zero copy account of the program
+    pub fn zero_copy<T: ZeroCopy + Discriminator>(key: Pubkey, state: &T) -> Self {
+        let mut data = T::discriminator().to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(state));
+        Self::new(key, crate::id(), data)
+    }
+
+    /// A mint of `token_program` without extensions
+    pub fn mint(key: Pubkey, token_program: Pubkey, decimals: u8) -> Self {
+        let mint = spl_token::state::Mint {
+            mint_authority: Some(Pubkey::new_unique()).into(),
+            supply: u64::MAX / 2,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        Self::new(key, token_program, data)
+    }
+
+    /// A token account of `token_program` without extensions
+    pub fn token_account(
+        key: Pubkey,
+        token_program: Pubkey,
+        mint: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+    ) -> Self {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        Self::new(key, token_program, data)
+    }
+
+    /// The balance of a token account
+    pub fn token_amount(&self) -> u64 {
+        spl_token::state::Account::unpack_from_slice(&self.data[..spl_token::state::Account::LEN])
+            .unwrap()
+            .amount
+    }
+
+    /// Deserializes an account of the program serialized by anchor
+    pub fn state<T: AccountDeserialize>(&self) -> T {
+        T::try_deserialize(&mut self.data.as_slice()).unwrap()
+    }
+
+    /// Reads a zero copy account of the program
+    pub fn load<T: ZeroCopy>(&self) -> T {
+        *bytemuck::from_bytes(&self.data[8..8 + std::mem::size_of::<T>()])
+    }
+}
+
+/// An account as the runtime serializes it for the program: the original data length before
+/// the key, the data length before the data and room to grow after it, so `AccountInfo::realloc`
+/// can resize the data in place
+struct SerializedAccount {
+    key: Vec<u64>,
+    data: Vec<u64>,
+}
+
+impl SerializedAccount {
+    fn new(account: &TestAccount) -> Self {
+        let mut key = vec![0u64; 5];
+        let key_bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut key);
+        key_bytes[4..8].copy_from_slice(&(account.data.len() as u32).to_le_bytes());
+        key_bytes[8..].copy_from_slice(account.key.as_ref());
+        let mut data =
+            vec![0u64; (8 + account.data.len() + MAX_PERMITTED_DATA_INCREASE).div_ceil(8)];
+        let data_bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut data);
+        data_bytes[..8].copy_from_slice(&(account.data.len() as u64).to_le_bytes());
+        data_bytes[8..8 + account.data.len()].copy_from_slice(&account.data);
+        Self { key, data }
+    }
+}
+
+/// Runs `instruction` through the program's entry as the runtime would, the accounts of
+/// `metas` are looked up by key in `accounts` and keep their state after the instruction,
+/// even when it fails. The runtime would roll a failed instruction back, so a test asserting
+/// the state after a failure checks that nothing was moved before it.
+pub fn process_instruction(
+    accounts: &mut [TestAccount],
+    metas: Vec<AccountMeta>,
+    instruction: impl InstructionData,
+) -> ProgramResult {
+    static SET_STUBS: Once = Once::new();
+    SET_STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(ProgramTestStubs));
+    });
+    RETURN_DATA.with(|return_data| return_data.borrow_mut().clear());
+
+    let mut serialized_accounts = accounts
+        .iter()
+        .map(SerializedAccount::new)
+        .collect::<Vec<SerializedAccount>>();
+    // the account infos borrow the accounts until the instruction is done
+    let (result, data_lens) = {
+        let account_infos = accounts
+            .iter_mut()
+            .zip(serialized_accounts.iter_mut())
+            .map(|(account, serialized)| {
+                let data_len = account.data.len();
+                AccountInfo::new(
+                    bytemuck::from_bytes(&bytemuck::cast_slice::<u64, u8>(&serialized.key)[8..]),
+                    false,
+                    false,
+                    &mut account.lamports,
+                    &mut bytemuck::cast_slice_mut::<u64, u8>(&mut serialized.data)[8..8 + data_len],
+                    &account.owner,
+                    account.executable,
+                    0,
+                )
+            })
+            .collect::<Vec<AccountInfo>>();
+        let mut instruction_accounts = Vec::with_capacity(metas.len());
+        for meta in metas {
+            let mut account_info = account_infos
+                .iter()
+                .find(|account_info| *account_info.key == meta.pubkey)
+                .unwrap_or_else(|| panic!("missing test account {}", meta.pubkey))
+                .clone();
+            account_info.is_signer = meta.is_signer;
+            account_info.is_writable = meta.is_writable;
+            instruction_accounts.push(account_info);
+        }
+        let result = crate::entry(&crate::id(), &instruction_accounts, &instruction.data());
+        let data_lens = account_infos
+            .iter()
+            .map(|account_info| account_info.data_len())
+            .collect::<Vec<usize>>();
+        (result, data_lens)
+    };
+    for ((account, serialized), data_len) in accounts
+        .iter_mut()
+        .zip(serialized_accounts.iter())
+        .zip(data_lens)
+    {
+        account.data = bytemuck::cast_slice::<u64, u8>(&serialized.data)[8..8 + data_len].to_vec();
+    }
+    result
+}
+
+/// The return data of the last instruction `process_instruction` ran on the thread
+pub fn return_data<T: AnchorDeserialize>() -> T {
+    RETURN_DATA.with(|return_data| T::try_from_slice(&return_data.borrow()).unwrap())
+}
+
+/// The error the program's entry returns for `error`
+pub fn program_error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
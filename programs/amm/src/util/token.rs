@@ -131,7 +131,7 @@ pub fn close_spl_account<'a, 'b, 'c, 'info>(
     owner: &AccountInfo<'info>,
     destination: &AccountInfo<'info>,
     close_account: &InterfaceAccount<'info, TokenAccount>,
-    token_program: &Program<'info, Token>,
+    token_program: &impl ToAccountInfo<'info>,
     // token_program_2022: &Program<'info, Token2022>,
     signers_seeds: &[&[&[u8]]],
 ) -> Result<()> {
@@ -156,7 +156,7 @@ pub fn burn<'a, 'b, 'c, 'info>(
     owner: &Signer<'info>,
     mint: &InterfaceAccount<'info, Mint>,
     burn_account: &InterfaceAccount<'info, TokenAccount>,
-    token_program: &Program<'info, Token>,
+    token_program: &impl ToAccountInfo<'info>,
     // token_program_2022: &Program<'info, Token2022>,
     signers_seeds: &[&[&[u8]]],
     amount: u64,
@@ -180,6 +180,39 @@ pub fn burn<'a, 'b, 'c, 'info>(
     )
 }
 
+/// Bound the `amount` collected from a pool vault by the vault `balance`,
+/// returns the amount to transfer and the shortfall the vault can't cover
+pub fn clamp_to_vault_balance(amount: u64, balance: u64) -> (u64, u64) {
+    let transfer_amount = amount.min(balance);
+    (transfer_amount, amount - transfer_amount)
+}
+
+/// Clamp the `amount` collected from `token_vault` to its balance.
+/// The owed amounts should never exceed the vault, so a shortfall means an accounting bug,
+/// it's reported with a `VaultShortfallEvent` instead of failing in the token program.
+pub fn clamp_collect_amount(
+    pool_state: Pubkey,
+    token_vault: &InterfaceAccount<TokenAccount>,
+    amount: u64,
+) -> u64 {
+    let (transfer_amount, shortfall) = clamp_to_vault_balance(amount, token_vault.amount);
+    if shortfall > 0 {
+        msg!(
+            "vault {} can't cover the collected amount {}, shortfall:{}",
+            token_vault.key(),
+            amount,
+            shortfall
+        );
+        emit!(VaultShortfallEvent {
+            pool_state,
+            token_vault: token_vault.key(),
+            amount_owed: amount,
+            vault_balance: token_vault.amount,
+        });
+    }
+    transfer_amount
+}
+
 /// Calculate the fee for output amount
 pub fn get_transfer_inverse_fee(
     mint_account: Box<InterfaceAccount<Mint>>,
@@ -253,3 +286,17 @@ pub fn is_supported_mint(mint_account: &InterfaceAccount<Mint>) -> Result<bool>
     }
     Ok(true)
 }
+
+#[cfg(test)]
+mod token_test {
+    use super::*;
+
+    #[test]
+    fn clamp_to_vault_balance_test() {
+        assert_eq!(clamp_to_vault_balance(100, 1_000), (100, 0));
+        assert_eq!(clamp_to_vault_balance(1_000, 1_000), (1_000, 0));
+        // owed more than the vault holds, only the balance is transferred
+        assert_eq!(clamp_to_vault_balance(1_500, 1_000), (1_000, 500));
+        assert_eq!(clamp_to_vault_balance(u64::MAX, 0), (0, u64::MAX));
+    }
+}